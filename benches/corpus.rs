@@ -0,0 +1,47 @@
+//! Explores each scenario of the bench corpus and plans to its targets, as
+//! `candyworks bench` does, printing the median, fastest and slowest of
+//! the runs for each. `cargo bench -- custom` measures only the scenarios
+//! whose name holds `custom`.
+
+use std::{
+    env,
+    time::{Duration, Instant},
+};
+
+use candyworks::{bench::CORPUS, scenario::Scenario};
+
+const RUNS: usize = 20;
+
+fn main() {
+    // Cargo passes `--bench` besides the filter.
+    let filter = env::args().skip(1).find(|arg| !arg.starts_with('-'));
+    for (name, input) in CORPUS {
+        if filter
+            .as_ref()
+            .is_some_and(|filter| !name.contains(filter.as_str()))
+        {
+            continue;
+        }
+        let scenario = Scenario::parse(input).unwrap();
+        let problem = scenario.problem().unwrap();
+        let mut times = (0..RUNS)
+            .map(|_| {
+                let mut candy_works = problem.unexplored();
+                let started = Instant::now();
+                candy_works.explore();
+                for target in &scenario.targets {
+                    candy_works.find_optimal_route(*target);
+                }
+                started.elapsed()
+            })
+            .collect::<Vec<Duration>>();
+        times.sort();
+        println!(
+            "{:<12}median {:>10.3?}  fastest {:>10.3?}  slowest {:>10.3?}",
+            name,
+            times[RUNS / 2],
+            times[0],
+            times[RUNS - 1]
+        );
+    }
+}
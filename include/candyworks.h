@@ -0,0 +1,56 @@
+/*
+ * C bindings to the candyworks planner, from `cargo build --release
+ * --features ffi`, which builds libcandyworks to link against. See
+ * src/ffi.rs for how they are used.
+ *
+ * Functions taking text read it as the scenario file writes it, return 0
+ * on success and -1 on failure, and candyworks_last_error says what
+ * failed until the next call setting up or solving the solver, which
+ * clears it first.
+ */
+
+#ifndef CANDYWORKS_H
+#define CANDYWORKS_H
+
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct CandyWorksSolver CandyWorksSolver;
+
+/* A solver capped at `cap` candies, to be freed with candyworks_free. */
+CandyWorksSolver *candyworks_new(uint32_t cap);
+
+/* Sets the inventory to trade from, such as "3e 3w". */
+int candyworks_set_inventory(CandyWorksSolver *solver, const char *candies);
+
+/* Offers a trade, such as "1w -> 2e", besides the standard trades. */
+int candyworks_add_trade(CandyWorksSolver *solver, const char *trade);
+
+/* Sets the candies to hold at the end of the route, such as "5e". */
+int candyworks_set_target(CandyWorksSolver *solver, const char *target);
+
+/*
+ * The route to the target as the CLI prints it, to be freed with
+ * candyworks_free_string; NULL if there is none or the problem is not set
+ * up.
+ */
+char *candyworks_solve(CandyWorksSolver *solver);
+
+/*
+ * Why the last call setting up or solving the solver failed, or NULL if it
+ * succeeded; owned by the solver.
+ */
+const char *candyworks_last_error(const CandyWorksSolver *solver);
+
+void candyworks_free_string(char *route);
+
+void candyworks_free(CandyWorksSolver *solver);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+
+use crate::{Candies, CandyWorks, Trade};
+
+/// Recommends one trade at a time towards a fixed target, for bots that
+/// make a trade, look at their inventory and ask again. Plans are cached
+/// by the inventories along them, so following the advice explores only
+/// once; an inventory off every known plan is planned from afresh.
+pub struct Advisor {
+    problem: CandyWorks,
+    target: Candies,
+    next: HashMap<Candies, Option<Trade>>,
+}
+
+impl Advisor {
+    /// Advises towards `target` under the caps and trades of `problem`,
+    /// from whatever inventory is asked about.
+    pub fn new(problem: &CandyWorks, target: Candies) -> Advisor {
+        Advisor {
+            problem: problem.unexplored(),
+            target,
+            next: HashMap::new(),
+        }
+    }
+
+    /// The problem advised on, unexplored.
+    pub fn problem(&self) -> &CandyWorks {
+        &self.problem
+    }
+
+    /// The trade to make next from `current`, or `None` once the target is
+    /// held or cannot be reached.
+    pub fn advise(&mut self, current: Candies) -> Option<Trade> {
+        if let Some(&next) = self.next.get(&current) {
+            return next;
+        }
+        let mut search = self.problem.unexplored();
+        search.candies = current;
+        search.explore();
+        let Some(route) = search.find_optimal_route(self.target) else {
+            self.next.insert(current, None);
+            return None;
+        };
+        let mut candies = current;
+        for trade in route.trades() {
+            self.next.insert(candies, Some(*trade));
+            candies = candies.trade(trade).unwrap();
+        }
+        self.next.insert(candies, None);
+        route.trades().first().copied()
+    }
+
+    /// Notes that `trade` was made, so plans made from then on keep to the
+    /// uses it has left; plans made before are dropped if it is limited.
+    pub fn made(&mut self, trade: &Trade) {
+        if self.problem.use_limits.limit(trade).is_some() {
+            self.problem.use_limits = self.problem.use_limits.less(&[*trade]);
+            self.next.clear();
+        }
+    }
+
+    /// Whether `current` already holds the target.
+    pub fn done(&self, current: &Candies) -> bool {
+        current.contains(&self.target)
+    }
+}
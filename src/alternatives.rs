@@ -0,0 +1,199 @@
+use std::{collections::HashMap, fmt::Write};
+
+use crate::{effort::EffortModel, Candies, CandyWorks, Trade};
+
+/// Routes looked at before giving up on finding more strategies, since
+/// the number of shortest routes grows exponentially with their length.
+const ROUTE_LIMIT: usize = 10_000;
+
+pub struct Alternative {
+    pub route: Vec<Trade>,
+    /// How many orderings of the same trades were found, including `route`.
+    pub orderings: usize,
+}
+
+struct Search<'a> {
+    candy_works: &'a CandyWorks,
+    index: HashMap<Candies, usize>,
+    depths: Vec<usize>,
+    /// Indices of the distinct trades, so a custom trade that repeats a
+    /// standard one does not double every route.
+    trades: Vec<usize>,
+    groups: Vec<(Vec<usize>, Alternative)>,
+    visited: usize,
+    limit: usize,
+    effort: Option<&'a EffortModel>,
+}
+
+impl Search<'_> {
+    /// Walks every shortest route back from `candies` to a root, one layer
+    /// of the breadth-first graph at a time. `reversed` holds the trades
+    /// taken after `candies`, last trade first.
+    fn walk(&mut self, candies: Candies, reversed: &mut Vec<usize>) {
+        if self.visited >= ROUTE_LIMIT {
+            return;
+        }
+        let depth = self.depths[self.index[&candies]];
+        if depth == 0 {
+            self.visited += 1;
+            self.record(reversed);
+            return;
+        }
+        for n in 0..self.trades.len() {
+            let t = self.trades[n];
+            let Some(before) = candies.untrade(&self.candy_works.trades[t]) else {
+                continue;
+            };
+            if self
+                .index
+                .get(&before)
+                .is_some_and(|&i| self.depths[i] + 1 == depth)
+            {
+                reversed.push(t);
+                self.walk(before, reversed);
+                reversed.pop();
+            }
+        }
+    }
+
+    fn record(&mut self, reversed: &[usize]) {
+        let mut key = reversed.to_vec();
+        key.sort_unstable();
+        let trades = &self.candy_works.trades;
+        let route = || {
+            reversed
+                .iter()
+                .rev()
+                .map(|&t| trades[t])
+                .collect::<Vec<_>>()
+        };
+        // Every ordering of the same trades makes each as often.
+        if !self.candy_works.use_limits.allow(&route()) {
+            return;
+        }
+        if let Some((_, alternative)) = self.groups.iter_mut().find(|(k, _)| *k == key) {
+            alternative.orderings += 1;
+            if let Some(model) = self.effort {
+                let route = route();
+                if model.effort(&route) < model.effort(&alternative.route) {
+                    alternative.route = route;
+                }
+            }
+        } else if self.groups.len() < self.limit {
+            let alternative = Alternative {
+                route: route(),
+                orderings: 1,
+            };
+            self.groups.push((key, alternative));
+        }
+    }
+}
+
+impl CandyWorks {
+    /// Lists up to `limit` genuinely different routes to the target: the
+    /// shortest routes to every state with the most candies that contains
+    /// it. Routes that only reorder the same trades count as one strategy,
+    /// represented by the first ordering found, or with an effort model by
+    /// the ordering needing the least effort, and are then ranked by it.
+    /// Routes breaking the use limits are left out.
+    pub fn alternative_routes(
+        &self,
+        target: Candies,
+        limit: usize,
+        effort: Option<&EffortModel>,
+    ) -> Vec<Alternative> {
+        let Some(best) = self.find_optimal_combination(target) else {
+            return Vec::new();
+        };
+        let best = self.combinations.state(best).total();
+        let mut search = Search {
+            candy_works: self,
+            index: self
+                .combinations
+                .states()
+                .enumerate()
+                .map(|(i, candies)| (candies, i))
+                .collect(),
+            depths: self.depths(),
+            trades: (0..self.trades.len())
+                .filter(|&t| !self.trades[..t].contains(&self.trades[t]))
+                .collect(),
+            groups: Vec::new(),
+            visited: 0,
+            // Ranking by effort needs every strategy before keeping the best.
+            limit: if effort.is_some() { usize::MAX } else { limit },
+            effort,
+        };
+        let mut goals = self
+            .combinations
+            .states()
+            .enumerate()
+            .filter(|(_, candies)| candies.contains(&target) && candies.total() == best)
+            .map(|(i, candies)| (search.depths[i], candies))
+            .collect::<Vec<_>>();
+        goals.sort_by_key(|(depth, _)| *depth);
+        for (_, goal) in goals {
+            search.walk(goal, &mut Vec::new());
+        }
+        let mut alternatives = search
+            .groups
+            .into_iter()
+            .map(|(_, alternative)| alternative)
+            .collect::<Vec<_>>();
+        if let Some(model) = effort {
+            // Shorter routes still come first, as without a model.
+            alternatives.sort_by(|a, b| {
+                (a.route.len(), model.effort(&a.route))
+                    .partial_cmp(&(b.route.len(), model.effort(&b.route)))
+                    .unwrap()
+            });
+            alternatives.truncate(limit);
+        }
+        alternatives
+    }
+
+    /// The strategies of `alternative_routes` as text, numbered, each with
+    /// how many other orders its trades work in and its route shown with
+    /// the `reserved` candies added back; empty if there are none.
+    pub fn format_alternatives(
+        &self,
+        target: Candies,
+        limit: usize,
+        effort: Option<&EffortModel>,
+        reserved: Candies,
+    ) -> String {
+        let mut out = String::new();
+        let alternatives = self.alternative_routes(target, limit, effort);
+        if alternatives.is_empty() {
+            return out;
+        }
+        writeln!(out, "Strategies:").unwrap();
+        for (n, alternative) in alternatives.iter().enumerate() {
+            match alternative.orderings {
+                1 => writeln!(out, "{}.", n + 1),
+                2 => writeln!(
+                    out,
+                    "{}. (the same trades also work in 1 other order)",
+                    n + 1
+                ),
+                orderings => writeln!(
+                    out,
+                    "{}. (the same trades also work in {} other orders)",
+                    n + 1,
+                    orderings - 1
+                ),
+            }
+            .unwrap();
+            out.push_str(&self.format_route(self.candies.with(&reserved), &alternative.route));
+            if let Some(model) = effort {
+                writeln!(
+                    out,
+                    "Estimated effort: {}",
+                    model.effort(&alternative.route)
+                )
+                .unwrap();
+            }
+        }
+        out
+    }
+}
@@ -0,0 +1,213 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+use clap::ValueEnum;
+
+use crate::{count::Count, kinds, objective::Objective, route::Route, Candies, CandyWorks, Trade};
+
+/// Which way the routes to the targets are searched.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Strategy {
+    /// Backward when the forward search would be refused as too large,
+    /// forward otherwise.
+    #[default]
+    Auto,
+    /// Explore every state reachable from the inventory, then pick the
+    /// routes among them.
+    Forward,
+    /// Search back from each target, without exploring.
+    Backward,
+}
+
+/// What has to be held before `trade` to hold `demand` after it: the
+/// give side, and whatever of `demand` the receive side does not cover.
+fn before(demand: &Candies, trade: &Trade) -> Candies {
+    let mut needed = trade.give;
+    for i in 0..kinds::count() {
+        let uncovered = demand.get_by_index(i) - trade.receive.get_by_index(i);
+        needed.add_by_index(i, uncovered.max(Count::ZERO));
+    }
+    needed
+}
+
+/// The candies `trade` loses, negative if it gains some.
+fn loss(trade: &Trade) -> i64 {
+    i64::from(trade.give.total().get()) - i64::from(trade.receive.total().get())
+}
+
+impl CandyWorks {
+    /// The route `find_optimal_route` finds once the graph is explored,
+    /// as good by the objective though ties may break differently, found
+    /// searching backward from `target` instead: each trade is undone from
+    /// what has to be held after it, keeping only trades that receive
+    /// some of it, until what has to be held is in the inventory. When the
+    /// target is small, what has to be held stays small, so far fewer
+    /// states are searched than the cap allows.
+    ///
+    /// The demands are searched cheapest first, by trades then candies
+    /// lost for the fewest trades and the other way round for the most
+    /// candies, the tie-breaks `find_optimal_route` makes. `Ok(None)` if no
+    /// route reaches the target within the cap, and `Err` with the reason
+    /// when the backward search cannot answer for certain and the graph
+    /// has to be explored: weighted objectives, trades gaining candies
+    /// when losing the fewest is searched for, and routes only found to
+    /// pass the cap on the way or to break the use limits.
+    pub fn backward_route(&self, target: Candies) -> Result<Option<Route>, String> {
+        let by_trades = match &self.objective {
+            Objective::MinTrades => true,
+            Objective::MaxLeftoverTotal => false,
+            Objective::MaxWeightedValue(_) => {
+                return Err("the backward search does not weigh candies".to_string())
+            }
+        };
+        if !by_trades {
+            if let Some(trade) = self.trades.iter().find(|trade| loss(trade) < 0) {
+                return Err(format!(
+                    "trade `{}` gains candies, which the backward search cannot rank",
+                    trade
+                ));
+            }
+        }
+        if self.candies.contains(&target) {
+            return Ok(Some(Route::empty(self.candies)));
+        }
+        if !self.fits(&target) {
+            return Ok(None);
+        }
+        let cost = |trades: usize, lost: i64| {
+            if by_trades {
+                (trades as i64, lost)
+            } else {
+                (lost, trades as i64)
+            }
+        };
+        // What each demand was first given at the least cost, and the trade
+        // and the demand after it that gave it.
+        let mut best = HashMap::from([(target, (cost(0, 0), None::<(Trade, Candies)>))]);
+        let mut queue = BinaryHeap::from([Reverse((cost(0, 0), 0_usize, 0_i64, target))]);
+        while let Some(Reverse((at, trades, lost, demand))) = queue.pop() {
+            if best.get(&demand).is_some_and(|(known, _)| *known < at) {
+                continue;
+            }
+            if self.candies.contains(&demand) {
+                let mut route = Vec::new();
+                let mut current = demand;
+                while let Some((_, Some((trade, after)))) = best.get(&current).copied() {
+                    route.push(trade);
+                    current = after;
+                }
+                let mut candies = self.candies;
+                for trade in &route {
+                    candies = candies.trade(trade).unwrap();
+                    if !self.fits(&candies) {
+                        return Err("the route found passes the cap on the way".to_string());
+                    }
+                }
+                if !self.use_limits.allow(&route) {
+                    return Err(
+                        "the route found makes a trade more often than its uses allow".to_string(),
+                    );
+                }
+                return Ok(Some(Route::new(self.candies, route).unwrap()));
+            }
+            for trade in &self.trades {
+                let needed = before(&demand, trade);
+                if needed == trade.give.with(&demand) || !self.fits(&needed) {
+                    // The trade receives none of what is needed, or what it
+                    // needs is more than the cap allows holding.
+                    continue;
+                }
+                let next = (trades + 1, lost + loss(trade));
+                let next_cost = cost(next.0, next.1);
+                if best
+                    .get(&needed)
+                    .is_some_and(|(known, _)| *known <= next_cost)
+                {
+                    continue;
+                }
+                best.insert(needed, (next_cost, Some((*trade, demand))));
+                queue.push(Reverse((next_cost, next.0, next.1, needed)));
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{candies, trade};
+
+    fn problem(objective: Objective, cap: u32) -> CandyWorks {
+        CandyWorks::builder(candies("6e 4w"))
+            .cap(cap)
+            .trade(trade("2e -> 1m"))
+            .trade(trade("2w -> 1m"))
+            .trade(trade("1e -> 1w"))
+            .trade(trade("1w -> 1e"))
+            .trade(trade("2c -> 1f"))
+            .objective(objective)
+            .build()
+            .unwrap()
+    }
+
+    const TARGETS: [&str; 6] = ["2m", "4m", "1c 1m", "1f", "1c 1f 1m", "1w 3m"];
+
+    /// The length and the candies left by the route forward search finds
+    /// and by the one backward search finds.
+    fn both_ways(objective: Objective, cap: u32, target: &str) -> [Option<(usize, Count)>; 2] {
+        let mut forward = problem(objective.clone(), cap);
+        forward.explore();
+        let found = forward.find_optimal_route(candies(target));
+        let backward = problem(objective, cap)
+            .backward_route(candies(target))
+            .unwrap();
+        if let Some(route) = &backward {
+            assert!(route.end().contains(&candies(target)));
+        }
+        [found, backward].map(|route| route.map(|route| (route.len(), route.end().total())))
+    }
+
+    #[test]
+    fn fewest_trades_like_forward() {
+        for target in TARGETS {
+            let [forward, backward] = both_ways(Objective::MinTrades, 20, target);
+            assert_eq!(forward, backward, "target {}", target);
+        }
+    }
+
+    #[test]
+    fn most_candies_like_forward() {
+        for target in TARGETS {
+            let [forward, backward] = both_ways(Objective::MaxLeftoverTotal, 20, target);
+            assert_eq!(
+                forward.map(|(_, left)| left),
+                backward.map(|(_, left)| left),
+                "target {}",
+                target
+            );
+        }
+    }
+
+    #[test]
+    fn unreachable_like_forward() {
+        for (target, cap) in [("6m", 10), ("11e", 10), ("30m", 20)] {
+            let [forward, backward] = both_ways(Objective::MinTrades, cap, target);
+            assert_eq!(forward, None, "target {}", target);
+            assert_eq!(backward, None, "target {}", target);
+        }
+    }
+
+    #[test]
+    fn refuses_what_it_cannot_rank() {
+        let weighted = problem(Objective::MaxWeightedValue(candies("1m")), 20);
+        assert!(weighted.backward_route(candies("1m")).is_err());
+        let gaining = CandyWorks::builder(candies("2e"))
+            .trade(trade("1e -> 1m 1w"))
+            .build()
+            .unwrap();
+        assert!(gaining.backward_route(candies("1m")).is_err());
+    }
+}
@@ -0,0 +1,255 @@
+use std::{
+    fs,
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    format_route_with, guard, metrics,
+    notes::Notes,
+    preset::Preset,
+    scenario::Scenario,
+    solve::{Planned, Planner},
+    Candies, Exhausted,
+};
+
+/// Resource ceilings applied to each scenario separately, and whether the
+/// scenarios offer the standard trades of `preset` besides their own.
+#[derive(Clone)]
+pub struct Limits {
+    pub time: Option<Duration>,
+    pub memory_mb: Option<u64>,
+    pub standard_trades: bool,
+    pub preset: Preset,
+}
+
+impl Limits {
+    /// The number of states that fit in the memory limit.
+    fn max_states(&self) -> Option<usize> {
+        self.memory_mb
+            .map(|mb| (mb * 1024 * 1024) as usize / guard::state_bytes())
+    }
+}
+
+struct Outcome {
+    rows: Vec<String>,
+    error: Option<String>,
+}
+
+/// `value` as a field of the summary, quoted as RFC 4180 has it when it
+/// holds a comma, quote or line break.
+fn field(value: &str) -> String {
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// A line of the summary holding `fields`, in the order of its header.
+fn row(fields: &[&str]) -> String {
+    fields
+        .iter()
+        .map(|value| field(value))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn failure(name: &str, message: String) -> Outcome {
+    Outcome {
+        rows: vec![row(&[name, "", "", "", "", "", "", &message])],
+        error: Some(message),
+    }
+}
+
+fn solve(path: &Path, out: &Path, limits: &Limits) -> Outcome {
+    let name = path.file_stem().unwrap().to_string_lossy().to_string();
+    let scenario = match Scenario::load_with(path, &limits.preset) {
+        Ok(scenario) => Scenario {
+            standard_trades: limits.standard_trades,
+            ..scenario
+        },
+        Err(err) => return failure(&name, err.to_string()),
+    };
+    let t = Instant::now();
+    let mut candy_works = match scenario.problem() {
+        Ok(candy_works) => candy_works,
+        Err(err) => return failure(&name, err.to_string()),
+    };
+    let inventory = candy_works.candies;
+    let deadline = limits.time.map(|limit| t + limit);
+    let explored = candy_works.explore_bounded(&[inventory], deadline, limits.max_states(), None);
+    let message = match explored {
+        Ok(()) => None,
+        Err(Exhausted::Time) => Some("time limit exceeded"),
+        Err(Exhausted::States) => Some("memory limit exceeded"),
+        Err(Exhausted::Interrupted) => Some("interrupted"),
+    };
+    if let Some(message) = message {
+        let _ = fs::write(
+            out.join(format!("{}.txt", name)),
+            format!("Scenario: {}\nAborted: {}\n", path.display(), message),
+        );
+        return failure(&name, message.to_string());
+    }
+    let millis = t.elapsed().as_millis();
+    let states = candy_works.combinations.len();
+    metrics::record_states(states);
+    metrics::record_graph_bytes(candy_works.combinations.memory_bytes());
+
+    let mut result = format!(
+        "Scenario: {}\nInventory: {}\nCap: {}\nStates explored: {}\n",
+        path.display(),
+        scenario.inventory.display(true),
+        scenario.cap,
+        states
+    );
+    if scenario.reserved != Candies::none() {
+        result.push_str(&format!("Reserved: {}\n", scenario.reserved.display(true)));
+    }
+    let planner = Planner::new(&scenario);
+    let mut rows = Vec::new();
+    for target in &scenario.targets {
+        result.push_str(&format!("\nTarget: {}\n", target.display(true)));
+        let Planned {
+            route,
+            itinerary,
+            notes,
+        } = planner.plan(&candy_works, *target, None);
+        for note in notes {
+            result.push_str(&format!("{}\n", note));
+        }
+        match route {
+            Some(route) => {
+                metrics::record_route(route.len());
+                let start = candy_works.candies.with(&scenario.reserved);
+                let end = start.trade_all(route.trades()).unwrap();
+                result.push_str(&format_route_with(
+                    start,
+                    route.trades(),
+                    &candy_works.use_limits,
+                    &Notes::new(&scenario),
+                ));
+                if !planner.costs().is_empty() {
+                    result.push_str(&format!(
+                        "Total cost: {}\n",
+                        planner.costs().total(route.trades())
+                    ));
+                }
+                if let Some((_, time)) = itinerary {
+                    result.push_str(&format!("Estimated time: {}\n", time));
+                }
+                rows.push(row(&[
+                    &name,
+                    &target.compact(),
+                    "true",
+                    &route.len().to_string(),
+                    &end.total().to_string(),
+                    &states.to_string(),
+                    &millis.to_string(),
+                    "",
+                ]));
+            }
+            None => {
+                result.push_str("No route found\n");
+                rows.push(row(&[
+                    &name,
+                    &target.compact(),
+                    "false",
+                    "",
+                    "",
+                    &states.to_string(),
+                    &millis.to_string(),
+                    "",
+                ]));
+            }
+        }
+    }
+    if scenario.targets.is_empty() {
+        let (states, millis) = (states.to_string(), millis.to_string());
+        rows.push(row(&[&name, "", "", "", "", &states, &millis, ""]));
+    }
+    let error = fs::write(out.join(format!("{}.txt", name)), result)
+        .err()
+        .map(|err| err.to_string());
+    Outcome { rows, error }
+}
+
+pub fn run(dir: &Path, out: &Path, jobs: usize, limits: Limits) -> Result<(), String> {
+    let mut paths = fs::read_dir(dir)
+        .map_err(|err| format!("{}: {}", dir.display(), err))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "candy"))
+        .collect::<Vec<PathBuf>>();
+    paths.sort();
+    fs::create_dir_all(out).map_err(|err| format!("{}: {}", out.display(), err))?;
+
+    let queue = Mutex::new(paths.iter().enumerate());
+    let outcomes = Mutex::new(Vec::new());
+    thread::scope(|scope| {
+        for _ in 0..jobs.max(1) {
+            scope.spawn(|| loop {
+                let Some((index, path)) = queue.lock().unwrap().next() else {
+                    break;
+                };
+                // A panicking solve only fails its own scenario.
+                let outcome = panic::catch_unwind(AssertUnwindSafe(|| solve(path, out, &limits)))
+                    .unwrap_or_else(|_| {
+                        let name = path.file_stem().unwrap().to_string_lossy();
+                        failure(&name, "solver panicked".to_string())
+                    });
+                outcomes.lock().unwrap().push((index, outcome));
+            });
+        }
+    });
+    let mut outcomes = outcomes.into_inner().unwrap();
+    outcomes.sort_by_key(|(index, _)| *index);
+
+    let mut summary =
+        "scenario,target,reachable,trades,final_total,states,milliseconds,error\n".to_string();
+    let mut failures = 0;
+    for (index, outcome) in &outcomes {
+        for row in &outcome.rows {
+            summary.push_str(row);
+            summary.push('\n');
+        }
+        if let Some(error) = &outcome.error {
+            failures += 1;
+            eprintln!("{}: {}", paths[*index].display(), error);
+        }
+    }
+    let summary_path = out.join("summary.csv");
+    fs::write(&summary_path, summary).map_err(|err| err.to_string())?;
+    println!(
+        "Processed {} scenarios ({} failed), summary written to {}",
+        paths.len(),
+        failures,
+        summary_path.display()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields_are_quoted_only_when_needed() {
+        assert_eq!(field("plain"), "plain");
+        assert_eq!(field("line 3: a, b"), "\"line 3: a, b\"");
+        assert_eq!(field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(field("two\nlines"), "\"two\nlines\"");
+    }
+
+    #[test]
+    fn failures_keep_their_commas() {
+        let outcome = failure("odd, name", "line 2: expected `a`, `b`".to_string());
+        assert_eq!(
+            outcome.rows,
+            ["\"odd, name\",,,,,,,\"line 2: expected `a`, `b`\""]
+        );
+    }
+}
@@ -0,0 +1,236 @@
+use std::{
+    fs,
+    path::Path,
+    thread,
+    time::{Duration, Instant},
+};
+
+use clap::ValueEnum;
+
+use crate::{guard, metrics, scenario, scenario::Scenario, sweep::Param, CandyWorks};
+
+/// Scenarios measured by `bench` and `cargo bench`, from small to the size
+/// where exploring starts to take a noticeable fraction of a second.
+pub const CORPUS: &[(&str, &str)] = &[
+    ("demo", scenario::DEMO),
+    (
+        "standard",
+        "inventory = 10e 10w 10c 5f 5m\ncap = 40\ntarget = 4m\n",
+    ),
+    (
+        "custom",
+        "inventory = 8e 8w 8c 8f\ncap = 36\ntrade = 2e 1w -> 1m\ntrade = 1m -> 2c\n\
+         trade = 3c -> 1f 1e\ntarget = 3m 2f\n",
+    ),
+    ("wide", "inventory = 20e 20w 20c\ncap = 60\ntarget = 5m\n"),
+];
+
+/// Explores every scenario of the corpus `runs` times and returns the
+/// states of each, how many remain when dominated states are skipped, and
+/// the best throughput, in states per second.
+fn measure(runs: usize) -> Vec<(&'static str, usize, usize, f64)> {
+    CORPUS
+        .iter()
+        .map(|(name, input)| {
+            let scenario = Scenario::parse(input)
+                .unwrap_or_else(|err| unreachable!("bench scenario {} is invalid: {}", name, err));
+            let problem = scenario
+                .problem()
+                .unwrap_or_else(|err| unreachable!("bench scenario {} is invalid: {}", name, err));
+            let mut best = 0.0_f64;
+            let mut states = 0;
+            for _ in 0..runs.max(1) {
+                let mut candy_works = problem.unexplored();
+                let started = Instant::now();
+                candy_works.explore();
+                for target in &scenario.targets {
+                    candy_works.find_optimal_route(*target);
+                }
+                let seconds = started.elapsed().as_secs_f64();
+                states = candy_works.combinations.len();
+                best = best.max(states as f64 / seconds);
+            }
+            let mut pruned = problem.unexplored();
+            pruned.prune_dominated = true;
+            pruned.explore();
+            (*name, states, pruned.combinations.len(), best)
+        })
+        .collect()
+}
+
+fn load_baseline(path: &Path) -> Result<Vec<(String, f64)>, String> {
+    let input = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(number, line)| {
+            let (name, rate) = line
+                .split_once(' ')
+                .and_then(|(name, rate)| Some((name, rate.trim().parse::<f64>().ok()?)))
+                .ok_or_else(|| format!("line {}: expected `name states_per_second`", number + 1))?;
+            Ok((name.to_string(), rate))
+        })
+        .collect()
+}
+
+/// Runs the corpus and prints the throughput of each scenario, with the
+/// states left to explore by `--prune-dominated`. Against a
+/// `baseline`, a scenario more than `tolerance` percent slower is reported
+/// as a regression; returns whether there were none.
+pub fn run(
+    runs: usize,
+    baseline: Option<&Path>,
+    save: Option<&Path>,
+    tolerance: f64,
+) -> Result<bool, String> {
+    let baseline = baseline.map(load_baseline).transpose()?;
+    let results = measure(runs);
+    let mut ok = true;
+    println!(
+        "{:<12}{:>10}{:>10}{:>16}{:>12}",
+        "scenario", "states", "pruned", "states/s", "change"
+    );
+    for (name, states, pruned, rate) in &results {
+        let previous = baseline
+            .as_ref()
+            .and_then(|baseline| baseline.iter().find(|(n, _)| n == name))
+            .map(|(_, rate)| *rate);
+        let (change, flag) = match previous {
+            Some(previous) => {
+                let change = (rate / previous - 1.0) * 100.0;
+                let regressed = change < -tolerance;
+                ok &= !regressed;
+                (
+                    format!("{:+.1}%", change),
+                    if regressed { "  regression" } else { "" },
+                )
+            }
+            None => ("-".to_string(), ""),
+        };
+        println!(
+            "{:<12}{:>10}{:>10}{:>16.0}{:>12}{}",
+            name, states, pruned, rate, change, flag
+        );
+    }
+    if let Some(path) = save {
+        let out = results
+            .iter()
+            .map(|(name, _, _, rate)| format!("{} {:.0}\n", name, rate))
+            .collect::<String>();
+        fs::write(path, out).map_err(|err| err.to_string())?;
+        println!("Baseline saved to {}", path.display());
+    }
+    Ok(ok)
+}
+
+/// A way of exploring compared by `bench --caps`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Explorer {
+    /// Breadth first, keeping every state.
+    Plain,
+    /// Skipping states dominated by one already explored.
+    Pruned,
+    /// With the visited set fronted by a Bloom filter.
+    Bloom,
+    /// One level at a time on every available thread.
+    Parallel,
+    /// Counting the states level by level without keeping the graph.
+    Streaming,
+}
+
+/// The caps written as for `sweep`, a range such as `10..40:10` or a list
+/// such as `10,20,40`.
+pub fn caps(input: &str) -> Result<Vec<u32>, String> {
+    format!("cap={}", input)
+        .parse::<Param>()?
+        .values()
+        .iter()
+        .map(|cap| {
+            cap.parse::<u32>()
+                .map_err(|_| format!("`{}` is not a cap, expected a whole number", cap))
+        })
+        .collect()
+}
+
+/// Explores `candy_works` once with `explorer`, returning the states it
+/// counted and the memory the graph holds, `None` when none is kept.
+fn explore_with(mut candy_works: CandyWorks, explorer: Explorer) -> (usize, Option<usize>) {
+    match explorer {
+        Explorer::Plain => candy_works.explore(),
+        Explorer::Pruned => {
+            candy_works.prune_dominated = true;
+            candy_works.explore();
+        }
+        Explorer::Bloom => {
+            candy_works.prefilter = true;
+            candy_works.explore();
+        }
+        Explorer::Parallel => {
+            let threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+            candy_works.explore_parallel(threads);
+        }
+        Explorer::Streaming => return (candy_works.streaming_statistics().combinations, None),
+    }
+    (
+        candy_works.combinations.len(),
+        Some(candy_works.combinations.memory_bytes()),
+    )
+}
+
+/// Explores the problem of `scenario` at every one of `caps` with each of
+/// `explorers`, `runs` times, and prints a row for each: the states
+/// explored, the median time, the throughput and the memory of the graph,
+/// then the peak memory of the whole run. Caps `guard` refuses are left
+/// out unless `force`.
+pub fn sweep(
+    scenario: &Scenario,
+    caps: &[u32],
+    explorers: &[Explorer],
+    runs: usize,
+    force: bool,
+) -> Result<(), String> {
+    let problem = scenario.problem().map_err(|err| err.to_string())?;
+    let reserved = scenario.reserved.total().get() as u32;
+    println!(
+        "{:>6}  {:<10}{:>12}{:>12}{:>14}{:>12}",
+        "cap", "explorer", "states", "median ms", "states/s", "graph KB"
+    );
+    for &cap in caps {
+        if !force {
+            if let Err(err) = guard::check(cap.into()) {
+                println!("{:>6}  skipped: {}", cap, err);
+                continue;
+            }
+        }
+        for &explorer in explorers {
+            let mut times = Vec::new();
+            let mut found = (0, None);
+            for _ in 0..runs.max(1) {
+                let mut candy_works = problem.unexplored();
+                candy_works.max_candies = cap.saturating_sub(reserved);
+                let started = Instant::now();
+                found = explore_with(candy_works, explorer);
+                times.push(started.elapsed());
+            }
+            times.sort();
+            let median = times[times.len() / 2].max(Duration::from_nanos(1));
+            let (states, bytes) = found;
+            println!(
+                "{:>6}  {:<10}{:>12}{:>12.1}{:>14.0}{:>12}",
+                cap,
+                explorer
+                    .to_possible_value()
+                    .map_or(String::new(), |value| value.get_name().to_string()),
+                states,
+                median.as_secs_f64() * 1000.0,
+                states as f64 / median.as_secs_f64(),
+                bytes.map_or("-".to_string(), |bytes| bytes.div_ceil(1024).to_string())
+            );
+        }
+    }
+    if let Some(kb) = metrics::peak_memory_kb() {
+        println!("Peak memory of the run: {} KB", kb);
+    }
+    Ok(())
+}
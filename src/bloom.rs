@@ -0,0 +1,111 @@
+use std::collections::HashSet;
+
+use crate::{kinds, Candies};
+
+const HASHES: usize = 3;
+/// Filter size as a multiple of the states stored; about 10 bits per state
+/// keeps false positives near 2% with three hashes.
+const BITS_PER_STATE: usize = 10;
+
+struct Bloom {
+    bits: Vec<u64>,
+}
+
+impl Bloom {
+    fn with_capacity(states: usize) -> Self {
+        let words = (states * BITS_PER_STATE).div_ceil(64).next_power_of_two();
+        Bloom {
+            bits: vec![0; words],
+        }
+    }
+
+    fn capacity(&self) -> usize {
+        self.bits.len() * 64 / BITS_PER_STATE
+    }
+
+    fn positions(&self, candies: &Candies) -> [usize; HASHES] {
+        let mut hash = 0xcbf2_9ce4_8422_2325u64;
+        for i in 0..kinds::count() {
+            hash =
+                (hash ^ candies.get_by_index(i).get() as u32 as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+        let h1 = hash;
+        let h2 = hash.rotate_left(32) | 1;
+        let mask = (self.bits.len() * 64 - 1) as u64;
+        std::array::from_fn(|i| (h1.wrapping_add((i as u64).wrapping_mul(h2)) & mask) as usize)
+    }
+
+    fn insert(&mut self, candies: &Candies) {
+        for bit in self.positions(candies) {
+            self.bits[bit / 64] |= 1 << (bit % 64);
+        }
+    }
+
+    fn may_contain(&self, candies: &Candies) -> bool {
+        self.positions(candies)
+            .into_iter()
+            .all(|bit| self.bits[bit / 64] & (1 << (bit % 64)) != 0)
+    }
+}
+
+/// The set of states seen during an exploration, optionally fronted by a
+/// Bloom filter. A state the filter has never seen is certainly new, so the
+/// exact set is only probed for the few the filter is unsure about. States
+/// are kept by their IDs, a quarter of their size, and only the ones
+/// without an ID in full.
+pub struct Visited {
+    exact: HashSet<u64>,
+    wide: HashSet<Candies>,
+    filter: Option<Bloom>,
+}
+
+impl Visited {
+    pub fn new(prefilter: bool) -> Self {
+        Visited {
+            exact: HashSet::new(),
+            wide: HashSet::new(),
+            filter: prefilter.then(|| Bloom::with_capacity(1 << 12)),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.exact.len() + self.wide.len()
+    }
+
+    fn contains(&self, candies: &Candies) -> bool {
+        match candies.id() {
+            Some(id) => self.exact.contains(&id),
+            None => self.wide.contains(candies),
+        }
+    }
+
+    fn insert_exact(&mut self, candies: Candies) -> bool {
+        match candies.id() {
+            Some(id) => self.exact.insert(id),
+            None => self.wide.insert(candies),
+        }
+    }
+
+    /// Adds a state, returning whether it was not seen before.
+    pub fn insert(&mut self, candies: Candies) -> bool {
+        let Some(filter) = &self.filter else {
+            return self.insert_exact(candies);
+        };
+        if filter.may_contain(&candies) && self.contains(&candies) {
+            return false;
+        }
+        if self.len() >= filter.capacity() {
+            let mut grown = Bloom::with_capacity(filter.capacity() * 2);
+            for id in &self.exact {
+                grown.insert(&Candies::from_id(*id).unwrap());
+            }
+            for known in &self.wide {
+                grown.insert(known);
+            }
+            self.filter = Some(grown);
+        }
+        self.filter.as_mut().unwrap().insert(&candies);
+        self.insert_exact(candies);
+        true
+    }
+}
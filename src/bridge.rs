@@ -0,0 +1,121 @@
+use std::{collections::HashSet, fmt::Write};
+
+use crate::{count::Count, display_side, kinds, starts, Candies, CandyWorks, Trade};
+
+/// The most candies a suggested trade gives and receives together.
+const MAX_SIZE: i32 = 6;
+
+/// Trades of `size` candies in all, giving and receiving different kinds,
+/// the least generous first.
+fn trades_of_size(size: i32) -> Vec<Trade> {
+    let sides = starts::all_within(size as u32 - 1);
+    let mut trades = Vec::new();
+    for give in sides.iter().filter(|side| side.total() > Count::ZERO) {
+        for receive in sides
+            .iter()
+            .filter(|side| side.total().get() == size - give.total().get())
+        {
+            let disjoint = (0..kinds::count()).all(|i| {
+                give.get_by_index(i) == Count::ZERO || receive.get_by_index(i) == Count::ZERO
+            });
+            if disjoint {
+                trades.push(Trade {
+                    give: *give,
+                    receive: *receive,
+                });
+            }
+        }
+    }
+    trades.sort_by_key(|trade| trade.receive.total() - trade.give.total());
+    trades
+}
+
+/// The smallest trades not already offered that, made once, connect a
+/// state reachable from the start to one from which `target` can be
+/// reached.
+fn bridges(
+    candy_works: &CandyWorks,
+    reachable: &HashSet<Candies>,
+    reaching: &HashSet<Candies>,
+) -> Vec<Trade> {
+    for size in 2..=MAX_SIZE {
+        let found = trades_of_size(size)
+            .into_iter()
+            .filter(|trade| !candy_works.trades.contains(trade))
+            .filter(|trade| {
+                reachable.iter().any(|from| {
+                    from.trade(trade)
+                        .is_some_and(|to| candy_works.fits(&to) && reaching.contains(&to))
+                })
+            })
+            .collect::<Vec<_>>();
+        if !found.is_empty() {
+            return found;
+        }
+    }
+    Vec::new()
+}
+
+/// The smallest trades that would make `target` reachable when the trades
+/// offered cannot, up to `limit` of them and the route the first one opens,
+/// as text.
+pub fn suggest(candy_works: &CandyWorks, target: Candies, limit: usize) -> String {
+    let mut out = String::new();
+    let reachable = candy_works.reachable_set();
+    if reachable.iter().any(|candies| candies.contains(&target)) {
+        writeln!(out, "({}) can already be reached", display_side(&target)).unwrap();
+        return out;
+    }
+    let cap = candy_works.max_candies;
+    let inventories = starts::inventories_within(cap);
+    if inventories > starts::MAX_INVENTORIES {
+        writeln!(
+            out,
+            "The cap of {} allows {} inventories, too many to check; try a lower cap",
+            cap, inventories
+        )
+        .unwrap();
+        return out;
+    }
+    let reaching = starts::reaching(candy_works, target);
+    let found = bridges(candy_works, &reachable, &reaching);
+    let Some(first) = found.first() else {
+        writeln!(
+            out,
+            "No trade of up to {} candies would make ({}) reachable",
+            MAX_SIZE,
+            display_side(&target)
+        )
+        .unwrap();
+        return out;
+    };
+    writeln!(
+        out,
+        "{} trade{} of {} candies would make ({}) reachable:",
+        found.len(),
+        if found.len() == 1 { "" } else { "s" },
+        first.give.total() + first.receive.total(),
+        display_side(&target)
+    )
+    .unwrap();
+    for trade in found.iter().take(limit) {
+        writeln!(
+            out,
+            "  {} -> {}",
+            display_side(&trade.give),
+            display_side(&trade.receive)
+        )
+        .unwrap();
+    }
+    if found.len() > limit {
+        writeln!(out, "  ... and {} more", found.len() - limit).unwrap();
+    }
+    let mut bridged = candy_works.unexplored();
+    bridged.trades.push(*first);
+    bridged.explore();
+    if let Some(route) = bridged.find_optimal_route(target) {
+        writeln!(out, "With the first, a route would be:").unwrap();
+        out.push_str(&candy_works.format_route(candy_works.candies, route.trades()));
+    }
+    out
+}
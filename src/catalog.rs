@@ -0,0 +1,282 @@
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use crate::{count::Counts, names::CandyNames, player, scenario::Scenario, template, trades_csv};
+
+/// Where a trade in effect comes from, from the least to the most binding.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Layer {
+    /// The catalog everyone on the machine shares, which candyworks only
+    /// ever reads.
+    Shared,
+    /// The additions of the `--player`.
+    Player,
+    /// The trades written in the scenario file.
+    Scenario,
+    /// The `--catalog` files of this run.
+    Session,
+}
+
+impl fmt::Display for Layer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Layer::Shared => "shared",
+            Layer::Player => "player",
+            Layer::Scenario => "scenario",
+            Layer::Session => "session",
+        })
+    }
+}
+
+/// The give side and location of a trade, which a later layer overrides
+/// it by.
+type Key = (Counts<i64>, Option<String>);
+
+/// A `trade` or `drop` line of one layer.
+#[derive(Clone)]
+pub struct Entry {
+    pub layer: Layer,
+    /// The file and line it was read from.
+    pub source: String,
+    /// What follows `trade =` or `drop =`.
+    pub value: String,
+    pub drop: bool,
+    key: Key,
+    /// The line of a scenario trade, counting from 0.
+    line: Option<usize>,
+}
+
+/// A trade that a later layer took out of effect.
+pub struct Replaced {
+    pub trade: Entry,
+    /// The `trade` that replaced it or the `drop` that took it out.
+    pub by: Entry,
+}
+
+/// The trades in effect once the layers are merged.
+pub struct Merged {
+    /// The scenario with its overridden trade lines blanked and the
+    /// catalog trades in effect appended.
+    pub input: String,
+    /// The trades in effect, from the lowest layer up.
+    pub trades: Vec<Entry>,
+    pub replaced: Vec<Replaced>,
+    /// How many lines of `input` the scenario has; the catalog trades
+    /// follow in the order of `trades`.
+    lines: usize,
+}
+
+impl Merged {
+    /// Where line `number` of `input` was read from, for the catalog
+    /// trades appended to the scenario.
+    pub fn origin(&self, number: usize) -> Option<&Entry> {
+        let appended = number.checked_sub(self.lines + 1)?;
+        self.trades
+            .iter()
+            .filter(|entry| entry.layer != Layer::Scenario)
+            .nth(appended)
+    }
+}
+
+/// The trade catalogs layered under and over scenario files: the shared
+/// one, the player's and the `--catalog` files of the run, in that order
+/// with the scenario between the player's and the run's.
+///
+/// A catalog holds `trade = ...` lines written as in a scenario, and
+/// `drop = 3e @ market` lines. A trade replaces every trade of the layers
+/// below with the same give side and location; `drop` takes them out
+/// without a replacement. Trades of the same layer never replace each
+/// other, so one vendor can still offer several trades for `3e`.
+#[derive(Default, PartialEq)]
+///
+/// The `--trades-csv` files of the run are layered last, each row read as
+/// a `trade` line by `trades_csv::catalog`.
+pub struct Catalogs {
+    shared: Option<PathBuf>,
+    player: Option<PathBuf>,
+    session: Vec<PathBuf>,
+    spreadsheets: Vec<PathBuf>,
+    /// Whether the scenarios leave out the standard trades, the run's
+    /// trade lists already holding every trade on offer; see
+    /// `Scenario::standard_trades`.
+    pub without_standard: bool,
+}
+
+impl Catalogs {
+    /// The catalogs for a run with `--catalog` files `session` and
+    /// `--trades-csv` files `spreadsheets`; the shared and player catalogs
+    /// are optional, the run's have to exist.
+    pub fn new(player: Option<PathBuf>, session: &[PathBuf], spreadsheets: &[PathBuf]) -> Catalogs {
+        Catalogs {
+            shared: Some(shared()),
+            player,
+            session: session.to_vec(),
+            spreadsheets: spreadsheets.to_vec(),
+            without_standard: false,
+        }
+    }
+
+    /// Layers the catalogs around the scenario `input`, read from the file
+    /// named `source`, or from no file if it is empty.
+    pub fn merge(&self, input: &str, source: &str) -> Result<Merged, String> {
+        // Catalog trades are written with the scenario's names and aliases.
+        let names = Scenario::parse(input)
+            .map(|scenario| scenario.names)
+            .unwrap_or_else(|_| CandyNames::builtin());
+        let mut layers = Vec::new();
+        for (layer, path) in [(Layer::Shared, &self.shared), (Layer::Player, &self.player)] {
+            if let Some(text) = path.as_deref().map(read_optional).transpose()?.flatten() {
+                let source = path.as_ref().unwrap().display().to_string();
+                layers.push(entries(&text, &source, layer, &names)?);
+            }
+        }
+        layers.push(entries(input, source, Layer::Scenario, &names)?);
+        for path in &self.session {
+            let text = crate::replay::read_to_string(path)
+                .map_err(|err| format!("{}: {}", path.display(), err))?;
+            let source = path.display().to_string();
+            layers.push(entries(&text, &source, Layer::Session, &names)?);
+        }
+        for path in &self.spreadsheets {
+            let source = path.display().to_string();
+            let text = crate::replay::read_to_string(path)
+                .map_err(|err| err.to_string())
+                .and_then(|text| trades_csv::catalog(&text, &names))
+                .map_err(|err| format!("{}: {}", source, err))?;
+            layers.push(entries(&text, &source, Layer::Session, &names)?);
+        }
+        let mut trades: Vec<Entry> = Vec::new();
+        let mut replaced = Vec::new();
+        for layer in layers {
+            for by in &layer {
+                let (gone, kept) = trades.into_iter().partition(|old| old.key == by.key);
+                trades = kept;
+                replaced.extend(gone.into_iter().map(|trade: Entry| Replaced {
+                    trade,
+                    by: by.clone(),
+                }));
+            }
+            trades.extend(layer.into_iter().filter(|entry| !entry.drop));
+        }
+        // Overridden scenario trades are blanked rather than removed, so
+        // errors still point at the lines as written.
+        let blanked = replaced
+            .iter()
+            .filter_map(|replaced| replaced.trade.line)
+            .collect::<Vec<_>>();
+        let mut merged = String::new();
+        for (number, line) in input.lines().enumerate() {
+            if !blanked.contains(&number) {
+                merged.push_str(line);
+            }
+            merged.push('\n');
+        }
+        for entry in trades.iter().filter(|entry| entry.layer != Layer::Scenario) {
+            merged.push_str(&format!("trade = {}\n", entry.value));
+        }
+        Ok(Merged {
+            input: merged,
+            trades,
+            replaced,
+            lines: input.lines().count(),
+        })
+    }
+}
+
+/// The catalog shared by everyone planning on this machine.
+pub fn shared() -> PathBuf {
+    player::home().join("catalog.candy")
+}
+
+/// The catalog at `path`, or `None` if there is none.
+fn read_optional(path: &Path) -> Result<Option<String>, String> {
+    match crate::replay::read_to_string(path) {
+        Ok(text) => Ok(Some(text)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(format!("{}: {}", path.display(), err)),
+    }
+}
+
+/// The `trade` lines of a scenario, or the `trade` and `drop` lines of a
+/// catalog. Scenario lines that do not read are left for the scenario to
+/// report.
+fn entries(
+    text: &str,
+    source: &str,
+    layer: Layer,
+    names: &CandyNames,
+) -> Result<Vec<Entry>, String> {
+    let mut entries = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (drop, value, key) = match read_line(line, layer, names) {
+            Ok(Some(entry)) => entry,
+            Ok(None) => continue,
+            Err(_) if layer == Layer::Scenario => continue,
+            Err(err) => return Err(format!("{}: line {}: {}", source, number + 1, err)),
+        };
+        entries.push(Entry {
+            layer,
+            source: if source.is_empty() {
+                format!("line {}", number + 1)
+            } else {
+                format!("{}:{}", source, number + 1)
+            },
+            value,
+            drop,
+            key,
+            line: (layer == Layer::Scenario).then_some(number),
+        });
+    }
+    Ok(entries)
+}
+
+/// Whether `line` drops trades, what follows its `=` and the key it
+/// overrides by; `None` for the other keys of a scenario.
+fn read_line(
+    line: &str,
+    layer: Layer,
+    names: &CandyNames,
+) -> Result<Option<(bool, String, Key)>, String> {
+    let line = template::active().expand(line)?;
+    let (key, value) = line.split_once('=').unwrap_or((&line, ""));
+    let value = value.trim();
+    let drop = match key.trim() {
+        "trade" => false,
+        "drop" if layer != Layer::Scenario => true,
+        _ if layer == Layer::Scenario => return Ok(None),
+        _ => return Err("catalogs only hold `trade` and `drop` lines".to_string()),
+    };
+    let (trade, location) = match value.split_once('@') {
+        Some((trade, location)) => (trade, Some(location.trim().to_string())),
+        None => (value, None),
+    };
+    let trade = trade.split(';').next().unwrap();
+    let give = match trade.split_once("->") {
+        Some(_) if drop => return Err("expected `drop = 3e` or `drop = 3e @ market`".to_string()),
+        Some((give, _)) => give,
+        None if drop => trade,
+        None => return Err("invalid trade: missing `->` between give and receive".to_string()),
+    };
+    let give = Counts::<i64>::parse_with(give, names)
+        .map_err(|err| format!("invalid candies: {}", err))?;
+    Ok(Some((drop, value.to_string(), (give, location))))
+}
+
+static ACTIVE: OnceLock<Catalogs> = OnceLock::new();
+
+/// Sets the catalogs scenario files are layered with for the rest of the
+/// run, failing once other catalogs are in use.
+pub fn configure(catalogs: Catalogs) -> Result<(), String> {
+    crate::configure_once(&ACTIVE, catalogs, "the trade catalogs are")
+}
+
+pub fn active() -> &'static Catalogs {
+    ACTIVE.get_or_init(Catalogs::default)
+}
@@ -0,0 +1,156 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Write},
+};
+
+use crate::{count::Count, display_side, kinds, scenario::Scenario, Candies, Trade};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    Floor,
+    Ceil,
+}
+
+/// A tax of a share of every candy given, rounded to whole candies one
+/// kind at a time, as in `10% ceil`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tax {
+    /// The share in hundredths of a percent, so `12.5%` is 1250.
+    basis_points: u32,
+    rounding: Rounding,
+}
+
+impl Tax {
+    pub fn none() -> Tax {
+        Tax {
+            basis_points: 0,
+            rounding: Rounding::Floor,
+        }
+    }
+
+    /// Reads `10%`, `12.5% floor` or `none`; without a rounding rule the
+    /// tax is rounded up, as vendors do.
+    pub fn parse(input: &str) -> Result<Tax, String> {
+        let expected = "expected a tax such as `10% ceil` or `12.5% floor`";
+        let mut words = input.split_whitespace();
+        let rate = words.next().ok_or_else(|| expected.to_string())?;
+        if rate == "none" && words.next().is_none() {
+            return Ok(Tax::none());
+        }
+        let rate = rate.strip_suffix('%').ok_or_else(|| expected.to_string())?;
+        let (whole, fraction) = rate.split_once('.').unwrap_or((rate, ""));
+        if fraction.len() > 2 || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+            return Err("a tax has at most two decimals".to_string());
+        }
+        let basis_points = whole
+            .parse::<u32>()
+            .ok()
+            .and_then(|whole| whole.checked_mul(100))
+            .and_then(|whole| whole.checked_add(format!("{:0<2}", fraction).parse().unwrap()))
+            .ok_or_else(|| expected.to_string())?;
+        let rounding = match words.next() {
+            None | Some("ceil") => Rounding::Ceil,
+            Some("floor") => Rounding::Floor,
+            Some(other) => {
+                return Err(format!(
+                    "unknown rounding `{}`, expected `floor` or `ceil`",
+                    other
+                ))
+            }
+        };
+        if words.next().is_some() {
+            return Err(expected.to_string());
+        }
+        Ok(Tax {
+            basis_points,
+            rounding,
+        })
+    }
+
+    /// The tax owed on giving `give`.
+    pub fn on(&self, give: &Candies) -> Candies {
+        let mut owed = Candies::none();
+        for i in 0..kinds::count() {
+            let taxed = give.get_by_index(i).get() as u64 * self.basis_points as u64;
+            let whole = match self.rounding {
+                Rounding::Floor => taxed / 10_000,
+                Rounding::Ceil => taxed.div_ceil(10_000),
+            };
+            owed.add_by_index(i, Count::new(whole as i32));
+        }
+        owed
+    }
+}
+
+impl fmt::Display for Tax {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if *self == Tax::none() {
+            return write!(f, "none");
+        }
+        write!(f, "{}", self.basis_points / 100)?;
+        match self.basis_points % 100 {
+            0 => {}
+            hundredths if hundredths % 10 == 0 => write!(f, ".{}", hundredths / 10)?,
+            hundredths => write!(f, ".{:02}", hundredths)?,
+        }
+        let rounding = match self.rounding {
+            Rounding::Floor => "floor",
+            Rounding::Ceil => "ceil",
+        };
+        write!(f, "% {}", rounding)
+    }
+}
+
+/// What vendors take on top of the scenario's trades, keyed by the trades
+/// as searched, which already give it.
+pub struct Charges {
+    /// The fee and the tax of every charged trade.
+    charged: HashMap<Trade, (Candies, Candies)>,
+}
+
+impl Charges {
+    pub fn new(scenario: &Scenario) -> Charges {
+        let mut charged = HashMap::new();
+        for (n, trade) in scenario.trades.iter().enumerate() {
+            let (fee, tax) = (scenario.fee_of(n), scenario.taxed[n]);
+            if fee != Candies::none() || tax != Candies::none() {
+                charged.entry(*trade).or_insert((fee, tax));
+            }
+        }
+        Charges { charged }
+    }
+
+    /// The fees and taxes paid along `route` as text, a line each for
+    /// those paid at all.
+    pub fn paid(&self, route: &[Trade]) -> String {
+        let charges = route
+            .iter()
+            .filter_map(|trade| self.charged.get(trade))
+            .collect::<Vec<_>>();
+        paid("Fees", charges.iter().map(|(fee, _)| fee))
+            + &paid("Tax", charges.iter().map(|(_, tax)| tax))
+    }
+}
+
+fn paid<'a>(what: &str, paid: impl Iterator<Item = &'a Candies>) -> String {
+    let mut out = String::new();
+    let paid = paid
+        .filter(|paid| **paid != Candies::none())
+        .collect::<Vec<_>>();
+    if paid.is_empty() {
+        return out;
+    }
+    let total = paid
+        .iter()
+        .fold(Candies::none(), |total, paid| total.with(paid));
+    writeln!(
+        out,
+        "{} paid: {} over {} trade{}",
+        what,
+        display_side(&total),
+        paid.len(),
+        if paid.len() == 1 { "" } else { "s" }
+    )
+    .unwrap();
+    out
+}
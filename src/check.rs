@@ -0,0 +1,281 @@
+use std::{fs, path::Path};
+
+use crate::{
+    count::Count,
+    error::CandyWorksError,
+    kinds::MAX_KINDS,
+    preset::Preset,
+    scenario::{key_lines, Scenario, ScenarioError},
+    template,
+    wide::{self, WideScenario},
+    Candies, Trade,
+};
+
+enum Severity {
+    Error,
+    Warning,
+}
+
+struct Diagnostic {
+    line: Option<usize>,
+    severity: Severity,
+    code: &'static str,
+    message: String,
+}
+
+fn net(trades: &[&Trade]) -> [i32; MAX_KINDS] {
+    let mut net = [0; MAX_KINDS];
+    for trade in trades {
+        for (i, value) in net.iter_mut().enumerate() {
+            *value += (trade.receive.get_by_index(i) - trade.give.get_by_index(i)).get();
+        }
+    }
+    net
+}
+
+fn is_gain(net: &[i32]) -> bool {
+    net.iter().all(|&v| v >= 0) && net.iter().any(|&v| v > 0)
+}
+
+fn syntax(err: ScenarioError) -> Vec<Diagnostic> {
+    vec![Diagnostic {
+        line: err.line,
+        severity: Severity::Error,
+        code: "syntax",
+        message: err.message,
+    }]
+}
+
+fn diagnose(input: &str, preset: &Preset) -> Vec<Diagnostic> {
+    // `count = i64` scenarios hold too little besides their trades to
+    // have more than their syntax to check.
+    if wide::is_wide(input) {
+        return WideScenario::<i64>::parse_with(input, template::active(), preset)
+            .err()
+            .map_or_else(Vec::new, syntax);
+    }
+    let scenario = match Scenario::parse_with(input, template::active(), preset) {
+        Ok(scenario) => scenario,
+        Err(err) => return syntax(err),
+    };
+    let mut diagnostics = Vec::new();
+    let mut push = |line, severity, code, message| {
+        diagnostics.push(Diagnostic {
+            line,
+            severity,
+            code,
+            message,
+        })
+    };
+    let cap = Count::new(scenario.cap as i32);
+    let inventory_line = key_lines(input, "inventory").first().copied();
+    let trade_lines = key_lines(input, "trade");
+    let target_lines = key_lines(input, "target");
+
+    if scenario.inventory.total() > cap {
+        push(
+            inventory_line,
+            Severity::Error,
+            "over-cap",
+            format!(
+                "inventory holds {} candies but the cap is {}",
+                scenario.inventory.total(),
+                cap
+            ),
+        );
+    }
+
+    if let Some(kind_caps) = &scenario.kind_caps {
+        if !kind_caps.allows(&scenario.inventory) {
+            push(
+                inventory_line,
+                Severity::Error,
+                "over-kind-cap",
+                format!(
+                    "inventory holds more of a candy than kind_cap {} allows",
+                    kind_caps
+                ),
+            );
+        }
+    }
+
+    if !scenario.inventory.contains(&scenario.reserved) {
+        push(
+            key_lines(input, "reserve").first().copied(),
+            Severity::Error,
+            "over-reserved",
+            format!(
+                "reserving ({}) but the inventory only has ({})",
+                scenario.reserved.display(false).trim(),
+                scenario.inventory.display(false).trim()
+            ),
+        );
+    }
+
+    let standard = scenario.standard();
+    for (n, trade) in scenario.trades.iter().enumerate() {
+        let line = trade_lines.get(n).copied();
+        let location = &scenario.locations[n];
+        if let Some(first) = (0..n).find(|&first| {
+            scenario.trades[first] == *trade && scenario.locations[first] == *location
+        }) {
+            push(
+                line,
+                Severity::Warning,
+                "duplicate-trade",
+                format!(
+                    "trade `{}` is already defined on line {}",
+                    trade, trade_lines[first]
+                ),
+            );
+        } else if location.is_none() && standard.contains(trade) {
+            push(
+                line,
+                Severity::Warning,
+                "standard-trade",
+                format!("trade `{}` is already offered as a standard trade", trade),
+            );
+        }
+        if trade.give == trade.receive {
+            push(
+                line,
+                Severity::Warning,
+                "no-op-trade",
+                "trade gives and receives the same candies".to_string(),
+            );
+        } else if trade.give.total() > cap || trade.receive.total() > cap {
+            push(
+                line,
+                Severity::Warning,
+                "never-applicable",
+                format!("trade `{}` can never be used under cap {}", trade, cap),
+            );
+        } else if is_gain(&net(&[trade])) {
+            push(
+                line,
+                Severity::Warning,
+                "arbitrage",
+                format!("trade `{}` produces candies without a cost", trade),
+            );
+        }
+    }
+    for (n, a) in scenario.trades.iter().enumerate() {
+        let others = scenario.trades[n + 1..].iter().chain(&standard);
+        for b in others {
+            if a.give != a.receive && !is_gain(&net(&[a])) && is_gain(&net(&[a, b])) {
+                push(
+                    trade_lines.get(n).copied(),
+                    Severity::Warning,
+                    "arbitrage",
+                    format!("trades `{}` and `{}` together produce free candies", a, b),
+                );
+            }
+        }
+    }
+
+    for (n, target) in scenario.targets.iter().enumerate() {
+        let line = target_lines.get(n).copied();
+        if target.total() > cap {
+            push(
+                line,
+                Severity::Warning,
+                "target-over-cap",
+                format!(
+                    "target needs {} candies but the cap is {}",
+                    target.total(),
+                    cap
+                ),
+            );
+        } else if scenario
+            .kind_caps
+            .is_some_and(|kind_caps| !kind_caps.allows(target))
+        {
+            push(
+                line,
+                Severity::Warning,
+                "target-over-kind-cap",
+                "target needs more of a candy than kind_cap allows".to_string(),
+            );
+        } else if scenario.inventory.contains(target) && *target != Candies::none() {
+            push(
+                line,
+                Severity::Warning,
+                "target-satisfied",
+                "target is already in the inventory".to_string(),
+            );
+        }
+    }
+    diagnostics
+}
+
+pub fn check(path: &Path, preset: &Preset) -> bool {
+    let input = match fs::read_to_string(path) {
+        Ok(input) => input,
+        Err(err) => {
+            println!("{}: error[io]: {}", path.display(), err);
+            return false;
+        }
+    };
+    let diagnostics = diagnose(&input, preset);
+    let mut ok = true;
+    for diagnostic in &diagnostics {
+        let severity = match diagnostic.severity {
+            Severity::Error => {
+                ok = false;
+                "error"
+            }
+            Severity::Warning => "warning",
+        };
+        let location = match diagnostic.line {
+            Some(line) => format!("{}:{}", path.display(), line),
+            None => path.display().to_string(),
+        };
+        println!(
+            "{}: {}[{}]: {}",
+            location, severity, diagnostic.code, diagnostic.message
+        );
+    }
+    ok
+}
+
+/// Writes the scenario at `path` back in canonical form, or with `check`
+/// only reports whether it is in it. Returns whether it was in canonical
+/// form or could be written so.
+pub fn format(path: &Path, check: bool) -> bool {
+    let original = fs::read_to_string(path).unwrap_or_default();
+    // Formatting writes the values back, which would lose the variables
+    // that were in their place.
+    if original.contains("${") {
+        eprintln!(
+            "{} uses variables, which formatting would replace by their values",
+            path.display()
+        );
+        return false;
+    }
+    // Catalog trades stay in their own files.
+    let loaded = if wide::is_wide(&original) {
+        WideScenario::<i64>::parse(&original).map(|scenario| scenario.format())
+    } else {
+        Scenario::load(path).map(|scenario| scenario.format())
+    };
+    let formatted = match loaded {
+        Ok(formatted) => formatted,
+        Err(err) => {
+            eprintln!("Could not load scenario {}: {}", path.display(), err);
+            return false;
+        }
+    };
+    if original == formatted {
+        return true;
+    }
+    if check {
+        eprintln!("{} is not in canonical form", path.display());
+        return false;
+    }
+    if let Err(err) = fs::write(path, formatted) {
+        eprintln!("Could not write {}", CandyWorksError::file(path, err));
+        return false;
+    }
+    println!("Formatted {}", path.display());
+    true
+}
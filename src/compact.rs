@@ -0,0 +1,55 @@
+use crate::{storage::Combinations, Candies, CandyWorks};
+
+/// The explored states no other state holds at least as many of every
+/// candy as, found by checking each against the ones kept so far from the
+/// fullest down: a state can only be beaten by one at least as full.
+pub fn pareto_optimal(states: &[Candies]) -> Vec<bool> {
+    let mut order = (0..states.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| std::cmp::Reverse(states[i].total()));
+    let mut optimal = vec![false; states.len()];
+    let mut kept: Vec<Candies> = Vec::new();
+    for i in order {
+        if !kept.iter().any(|best| best.contains(&states[i])) {
+            kept.push(states[i]);
+            optimal[i] = true;
+        }
+    }
+    optimal
+}
+
+impl CandyWorks {
+    /// Drops the explored states that are not on the route to a
+    /// Pareto-optimal state or to one holding any of `targets`, keeping
+    /// those routes as they are. Every best route a target can be answered
+    /// with ends in a Pareto-optimal state, so routing loses nothing.
+    /// Returns how many states were dropped.
+    pub fn compact(&mut self, targets: &[Candies]) -> usize {
+        let states = self.combinations.states().collect::<Vec<_>>();
+        let mut keep = pareto_optimal(&states);
+        for (i, candies) in states.iter().enumerate() {
+            keep[i] |= targets.iter().any(|target| candies.contains(target));
+        }
+        // Parents are stored before their children, so walking backwards
+        // marks every ancestor of a kept state before it is reached.
+        for i in (0..states.len()).rev() {
+            if keep[i] {
+                if let Some((parent, _)) = self.combinations.parent(i) {
+                    keep[parent] = true;
+                }
+            }
+        }
+        let mut renumbered = vec![usize::MAX; states.len()];
+        let mut compacted = Combinations::new();
+        for i in (0..states.len()).filter(|&i| keep[i]) {
+            renumbered[i] = compacted.len();
+            let parent = self
+                .combinations
+                .parent(i)
+                .map(|(parent, trade)| (renumbered[parent], trade));
+            compacted.push((states[i], parent));
+        }
+        let dropped = states.len() - compacted.len();
+        self.combinations = compacted;
+        dropped
+    }
+}
@@ -0,0 +1,86 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Write,
+};
+
+use crate::{display, route::Route, uses::Ranking, Candies, CandyWorks};
+
+/// Reads a state given by its ID, as printed in reports, or as candies
+/// such as `3e 2w`.
+pub fn parse_state(input: &str) -> Result<Candies, String> {
+    match input.trim().parse::<u64>() {
+        Ok(id) => Candies::from_id(id).ok_or_else(|| format!("no state has ID {}", id)),
+        Err(_) => input.parse::<Candies>().map_err(|err| err.to_string()),
+    }
+}
+
+impl CandyWorks {
+    /// The fewest trades turning `from` into exactly `to` without going
+    /// over the cap, searched afresh rather than in the explored graph,
+    /// which only holds routes from the start, and keeping to the use
+    /// limits.
+    pub fn shortest_route(&self, from: Candies, to: Candies) -> Option<Route> {
+        let mut previous = HashMap::from([(from, None)]);
+        let mut queue = VecDeque::from([from]);
+        while let Some(candies) = queue.pop_front() {
+            if candies == to {
+                let mut route = Vec::new();
+                let mut current = candies;
+                while let Some(Some((trade, before))) = previous.get(&current).copied() {
+                    route.push(trade);
+                    current = before;
+                }
+                route.reverse();
+                return self.keep_to_uses(
+                    Route::new(from, route).unwrap(),
+                    |candies| *candies == to,
+                    Ranking::FewestTrades,
+                );
+            }
+            for trade in &self.trades {
+                if let Some(after) = candies.trade(trade) {
+                    if self.fits(&after) && !previous.contains_key(&after) {
+                        previous.insert(after, Some((*trade, candies)));
+                        queue.push_back(after);
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+/// How `a` and `b` differ candy by candy, whether one holds at
+/// least as many of every candy as the other, and the shortest route from
+/// each to the other, as text.
+pub fn compare(candy_works: &CandyWorks, a: Candies, b: Candies) -> String {
+    let mut out = String::new();
+    writeln!(out, "{}", display::labelled_change_table(&a, &b, "a", "b")).unwrap();
+    if a == b {
+        writeln!(out, "The states are the same").unwrap();
+        return out;
+    }
+    match (a.contains(&b), b.contains(&a)) {
+        (true, _) => writeln!(
+            out,
+            "a dominates: it holds at least as many of every candy as b"
+        )
+        .unwrap(),
+        (_, true) => writeln!(
+            out,
+            "b dominates: it holds at least as many of every candy as a"
+        )
+        .unwrap(),
+        _ => writeln!(out, "Neither dominates: each holds more of some candy").unwrap(),
+    }
+    for (from, to, name) in [(a, b, "a to b"), (b, a, "b to a")] {
+        match candy_works.shortest_route(from, to) {
+            Some(route) => {
+                writeln!(out, "Shortest route from {}, {} trades:", name, route.len()).unwrap();
+                out.push_str(&candy_works.format_route(from, route.trades()));
+            }
+            None => writeln!(out, "No route from {}", name).unwrap(),
+        }
+    }
+    out
+}
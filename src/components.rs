@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+
+use crate::{count::Count, Candies, CandyWorks};
+
+pub struct Components {
+    pub count: usize,
+    pub largest: usize,
+}
+
+impl CandyWorks {
+    /// The explored states one trade away from `state`.
+    fn successors(&self, index: &HashMap<Candies, usize>, state: usize) -> Vec<usize> {
+        let candies = self.combinations.state(state);
+        self.trades
+            .iter()
+            .filter_map(|trade| candies.trade(trade))
+            .filter(|candies| self.fits(candies))
+            .filter_map(|candies| index.get(&candies).copied())
+            .collect()
+    }
+
+    /// Numbers the strongly connected components of the explored states,
+    /// sets of states that can all be traded into each other, returning
+    /// each state's component. A component is only numbered after every
+    /// component reachable from it, so the sinks come first. The graph only
+    /// keeps each state's first parent, so the trades between states are
+    /// applied again to find every edge.
+    fn component_ids(&self, index: &HashMap<Candies, usize>) -> Vec<usize> {
+        // Tarjan's algorithm, with an explicit stack of (state, successors,
+        // next successor) frames instead of recursion.
+        const UNVISITED: usize = usize::MAX;
+        let len = self.combinations.len();
+        let mut order = vec![UNVISITED; len];
+        let mut low = vec![0; len];
+        let mut on_stack = vec![false; len];
+        let mut ids = vec![0; len];
+        let mut stack = Vec::new();
+        let mut visited = 0;
+        let mut components = 0;
+        for root in 0..len {
+            if order[root] != UNVISITED {
+                continue;
+            }
+            let mut frames = vec![(root, self.successors(index, root), 0)];
+            order[root] = visited;
+            low[root] = visited;
+            visited += 1;
+            stack.push(root);
+            on_stack[root] = true;
+            while let Some((state, next, position)) = frames.last_mut() {
+                let state = *state;
+                if let Some(&successor) = next.get(*position) {
+                    *position += 1;
+                    if order[successor] == UNVISITED {
+                        order[successor] = visited;
+                        low[successor] = visited;
+                        visited += 1;
+                        stack.push(successor);
+                        on_stack[successor] = true;
+                        frames.push((successor, self.successors(index, successor), 0));
+                    } else if on_stack[successor] {
+                        low[state] = low[state].min(order[successor]);
+                    }
+                    continue;
+                }
+                frames.pop();
+                if let Some((parent, _, _)) = frames.last() {
+                    low[*parent] = low[*parent].min(low[state]);
+                }
+                if low[state] == order[state] {
+                    while let Some(member) = stack.pop() {
+                        on_stack[member] = false;
+                        ids[member] = components;
+                        if member == state {
+                            break;
+                        }
+                    }
+                    components += 1;
+                }
+            }
+        }
+        ids
+    }
+
+    fn state_index(&self) -> HashMap<Candies, usize> {
+        self.combinations
+            .states()
+            .enumerate()
+            .map(|(i, candies)| (candies, i))
+            .collect()
+    }
+
+    pub fn components(&self) -> Components {
+        let ids = self.component_ids(&self.state_index());
+        let mut sizes = vec![0; ids.iter().max().map_or(0, |max| max + 1)];
+        for id in ids {
+            sizes[id] += 1;
+        }
+        Components {
+            count: sizes.len(),
+            largest: sizes.into_iter().max().unwrap_or(0),
+        }
+    }
+
+    /// For every explored state, the most candies of any state holding
+    /// `target` that can be traded to from it, if there is one.
+    pub fn best_reachable(&self, target: Candies) -> Vec<Option<Count>> {
+        let index = self.state_index();
+        let ids = self.component_ids(&index);
+        let count = ids.iter().max().map_or(0, |max| max + 1);
+        let mut members = vec![Vec::new(); count];
+        for (state, &id) in ids.iter().enumerate() {
+            members[id].push(state);
+        }
+        // Components reachable from one are numbered before it, so theirs
+        // are final by the time it is reached.
+        let mut best = vec![None; count];
+        for id in 0..count {
+            let mut value: Option<Count> = None;
+            for &state in &members[id] {
+                let candies = self.combinations.state(state);
+                if candies.contains(&target) {
+                    value = value.max(Some(candies.total()));
+                }
+                for successor in self.successors(&index, state) {
+                    if ids[successor] != id {
+                        value = value.max(best[ids[successor]]);
+                    }
+                }
+            }
+            best[id] = value;
+        }
+        ids.into_iter().map(|id| best[id]).collect()
+    }
+}
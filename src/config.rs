@@ -0,0 +1,118 @@
+use std::path::Path;
+
+use crate::{
+    import::{self, At},
+    json,
+};
+
+/// The keys a `--config` file may have.
+const KEYS: [&str; 6] = [
+    "inventory",
+    "max_candies",
+    "max_per_kind",
+    "trades",
+    "target",
+    "targets",
+];
+
+/// Reads a `--config` file: the scenario of a run written as JSON, for
+/// setups planned with again and again.
+///
+/// ```text
+/// {
+///   "inventory": {"eggs": 6},          required, candy names to counts
+///   "max_candies": 20,                 optional cap, 20 if left out
+///   "max_per_kind": {"eggs": 10},      optional, or one number for all
+///   "trades": [                        optional
+///     {"give": {"worms": 2},           required, at least one candy
+///      "receive": {"meats": 1},        required, at least one candy
+///      "location": "market",           optional
+///      "note": "after quest 3",        optional
+///      "max_uses": 2,                  optional, times it can be made
+///      "cost": 5}                      optional, besides the candies
+///   ],
+///   "target": {"meats": 2},            optional
+///   "targets": [{"cakes": 1}]          optional, more targets
+/// }
+/// ```
+///
+/// The file becomes the lines of a scenario, each with the path of the
+/// entry it was read from, such as `$.trades[2]`, so that a line the
+/// scenario refuses can be blamed on its entry.
+pub fn read(path: &Path) -> Result<Vec<(String, String)>, String> {
+    let input = crate::replay::read_to_string(path).map_err(|err| err.to_string())?;
+    let document = json::parse(&input)?;
+    let root = At {
+        value: &document,
+        path: "$".to_string(),
+    };
+    root.object(&KEYS)?;
+    let mut lines = vec![(
+        format!("version = {}", crate::scenario::VERSION),
+        root.path.clone(),
+    )];
+    if let Some(cap) = root.get("max_candies") {
+        let path = cap.path.clone();
+        lines.extend(import::cap(Some(cap))?.map(|line| (line, path)));
+    }
+    if let Some(caps) = root.get("max_per_kind") {
+        let most = match caps.value {
+            json::Value::Number(_) => caps.count()?.to_string(),
+            _ => import::named_counts(&caps)?.compact(),
+        };
+        lines.push((format!("kind_cap = {}", most), caps.path));
+    }
+    let inventory = root.require("inventory")?;
+    lines.push((
+        format!(
+            "inventory = {}",
+            import::named_counts(&inventory)?.compact()
+        ),
+        inventory.path,
+    ));
+    for trade in root
+        .get("trades")
+        .map(|at| at.array())
+        .transpose()?
+        .unwrap_or_default()
+    {
+        trade.object(&["give", "receive", "location", "note", "max_uses", "cost"])?;
+        let give = trade.require("give")?;
+        let receive = trade.require("receive")?;
+        let mut line = import::trade_line(
+            import::side(import::named_counts(&give)?, &give)?,
+            import::side(import::named_counts(&receive)?, &receive)?,
+            None,
+            trade.get("note").map(|at| at.text()).transpose()?,
+        );
+        if let Some(uses) = trade.get("max_uses") {
+            line.push_str(&format!("; uses {}", uses.count()?));
+        }
+        if let Some(cost) = trade.get("cost") {
+            let cost = cost
+                .value
+                .as_f64()
+                .ok_or_else(|| cost.error("expected a number"))?;
+            line.push_str(&format!("; cost {}", cost));
+        }
+        if let Some(location) = trade.get("location").map(|at| at.text()).transpose()? {
+            if !location.is_empty() {
+                line.push_str(&format!(" @ {}", location));
+            }
+        }
+        lines.push((line, trade.path));
+    }
+    let targets = root
+        .get("targets")
+        .map(|at| at.array())
+        .transpose()?
+        .unwrap_or_default();
+    for target in root.get("target").into_iter().chain(targets) {
+        let line = format!(
+            "target = {}",
+            import::side(import::named_counts(&target)?, &target)?
+        );
+        lines.push((line, target.path));
+    }
+    Ok(lines)
+}
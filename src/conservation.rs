@@ -0,0 +1,314 @@
+use std::collections::HashSet;
+
+use crate::{
+    kinds::{self, MAX_KINDS},
+    names, Candies, Trade,
+};
+
+const N: usize = MAX_KINDS;
+
+/// Moduli searched for laws that only hold up to a remainder, such as the
+/// total staying odd. Primes, so each modulus has a plain basis of laws.
+const MODULI: [i64; 4] = [2, 3, 5, 7];
+
+/// A weighted sum of the candy counts that no trade changes, exactly or,
+/// with a modulus, up to its remainder.
+pub struct Law {
+    weights: [i64; N],
+    modulus: Option<i64>,
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn delta(trade: &Trade) -> [i64; N] {
+    std::array::from_fn(|i| {
+        (trade.receive.get_by_index(i) - trade.give.get_by_index(i)).get() as i64
+    })
+}
+
+impl Law {
+    fn reduce(&self, value: i64) -> i64 {
+        self.modulus.map_or(value, |m| value.rem_euclid(m))
+    }
+
+    fn value(&self, candies: &Candies) -> i64 {
+        self.reduce(
+            (0..kinds::count())
+                .map(|i| self.weights[i] * candies.get_by_index(i).get() as i64)
+                .sum(),
+        )
+    }
+
+    fn sum(&self) -> String {
+        let names = names::active();
+        let mut out = String::new();
+        for (i, &weight) in self.weights.iter().enumerate().filter(|(_, w)| **w != 0) {
+            let sign = if weight < 0 { "- " } else { "+ " };
+            if !out.is_empty() {
+                out.push(' ');
+                out.push_str(sign);
+            } else if weight < 0 {
+                out.push('-');
+            }
+            if weight.abs() != 1 {
+                out.push_str(&format!("{}·", weight.abs()));
+            }
+            out.push_str(names.plural(i));
+        }
+        out
+    }
+
+    /// The law as it applies to `start`, e.g. "eggs + 2·worms is always 14".
+    pub fn describe(&self, start: &Candies) -> String {
+        let value = self.value(start);
+        match self.modulus {
+            None => format!("{} is always {}", self.sum(), value),
+            Some(2) => format!(
+                "{} is always {}",
+                self.sum(),
+                if value == 0 { "even" } else { "odd" }
+            ),
+            Some(m) => format!(
+                "{} always leaves {} when divided by {}",
+                self.sum(),
+                value,
+                m
+            ),
+        }
+    }
+
+    /// Whether no inventory holding `target` within `cap` candies has the
+    /// value `start` has, so the target can never be reached.
+    fn rules_out(&self, start: &Candies, target: &Candies, cap: u32) -> bool {
+        let slack = cap as i64 - target.total().get() as i64;
+        if slack < 0 {
+            return false;
+        }
+        // The values of the candies that could be held besides the target,
+        // growing one candy at a time until the slack is used or nothing
+        // new turns up.
+        let mut values = HashSet::from([0]);
+        let mut last = vec![0];
+        for _ in 0..slack {
+            last = last
+                .iter()
+                .flat_map(|value| self.weights.iter().map(move |w| self.reduce(value + w)))
+                .filter(|value| values.insert(*value))
+                .collect();
+            if last.is_empty() {
+                break;
+            }
+        }
+        let needed = self.value(start) - self.value(target);
+        !values.contains(&self.reduce(needed))
+    }
+}
+
+/// Integer weights whose sum no trade changes: a basis of the null space
+/// of the trades' changes, each scaled to whole numbers.
+fn exact_laws(deltas: &[[i64; N]]) -> Vec<Law> {
+    let mut rows = deltas.to_vec();
+    let mut pivots = Vec::new();
+    for column in 0..kinds::count() {
+        let Some(found) = (pivots.len()..rows.len()).find(|&r| rows[r][column] != 0) else {
+            continue;
+        };
+        rows.swap(pivots.len(), found);
+        let pivot = rows[pivots.len()];
+        for (r, row) in rows.iter_mut().enumerate() {
+            if r != pivots.len() && row[column] != 0 {
+                let factor = row[column];
+                for i in 0..kinds::count() {
+                    row[i] = row[i] * pivot[column] - pivot[i] * factor;
+                }
+                let divisor = row.iter().fold(0, |g, &v| gcd(g, v));
+                if divisor > 1 {
+                    row.iter_mut().for_each(|v| *v /= divisor);
+                }
+            }
+        }
+        pivots.push(column);
+    }
+    (0..kinds::count())
+        .filter(|column| !pivots.contains(column))
+        .map(|free| {
+            let scale = pivots
+                .iter()
+                .enumerate()
+                .fold(1, |l, (r, &c)| l / gcd(l, rows[r][c]) * rows[r][c].abs());
+            let mut weights = [0; N];
+            weights[free] = scale;
+            for (r, &c) in pivots.iter().enumerate() {
+                weights[c] = -rows[r][free] * scale / rows[r][c];
+            }
+            let divisor = weights.iter().fold(0, |g, &v| gcd(g, v));
+            let sign = weights.iter().find(|&&w| w != 0).map_or(1, |w| w.signum());
+            Law {
+                weights: weights.map(|w| sign * w / divisor),
+                modulus: None,
+            }
+        })
+        .collect()
+}
+
+/// The inverse of `value` modulo the prime `m`, as `value^(m - 2)`.
+fn inverse(value: i64, m: i64) -> i64 {
+    (0..m - 2).fold(1, |power, _| power * value % m)
+}
+
+/// `weights` scaled modulo the prime `m` so the first that is not zero is
+/// 1, which keeps the laws found for the same sum alike.
+fn monic(weights: [i64; N], m: i64) -> [i64; N] {
+    let first = weights.iter().find(|&&w| w != 0).map_or(1, |&w| w);
+    let scale = inverse(first, m);
+    weights.map(|w| w * scale % m)
+}
+
+/// Brings `rows` to reduced row echelon form modulo the prime `m`,
+/// dropping the rows left all zero, and returns the pivot column of each
+/// row kept.
+fn echelon(rows: &mut Vec<[i64; N]>, m: i64) -> Vec<usize> {
+    let count = kinds::count();
+    for row in rows.iter_mut() {
+        row.iter_mut().for_each(|v| *v = v.rem_euclid(m));
+    }
+    let mut pivots = Vec::new();
+    for column in 0..count {
+        let Some(found) = (pivots.len()..rows.len()).find(|&r| rows[r][column] != 0) else {
+            continue;
+        };
+        rows.swap(pivots.len(), found);
+        let pivot = monic(rows[pivots.len()], m);
+        rows[pivots.len()] = pivot;
+        for (r, row) in rows.iter_mut().enumerate() {
+            if r != pivots.len() && row[column] != 0 {
+                let factor = row[column];
+                for i in 0..count {
+                    row[i] = (row[i] - factor * pivot[i]).rem_euclid(m);
+                }
+            }
+        }
+        pivots.push(column);
+    }
+    rows.truncate(pivots.len());
+    pivots
+}
+
+/// Laws modulo the prime `m` that do not follow from `exact` ones: a basis
+/// of the null space of the trades' changes over the integers modulo `m`,
+/// found by Gaussian elimination, less what the exact laws already span.
+fn modular_laws(deltas: &[[i64; N]], exact: &[Law], m: i64) -> Vec<Law> {
+    let mut rows = deltas.to_vec();
+    let pivots = echelon(&mut rows, m);
+    let mut span = exact.iter().map(|law| law.weights).collect::<Vec<_>>();
+    echelon(&mut span, m);
+    let mut laws = Vec::new();
+    for free in (0..kinds::count()).filter(|column| !pivots.contains(column)) {
+        let mut weights = [0; N];
+        weights[free] = 1;
+        for (row, &column) in rows.iter().zip(&pivots) {
+            weights[column] = (m - row[free]) % m;
+        }
+        let mut grown = span.clone();
+        grown.push(weights);
+        if echelon(&mut grown, m).len() > span.len() {
+            span = grown;
+            laws.push(Law {
+                weights: monic(weights, m),
+                modulus: Some(m),
+            });
+        }
+    }
+    laws
+}
+
+/// Every independent law the trades obey, exact ones first.
+pub fn laws(trades: &[Trade]) -> Vec<Law> {
+    let deltas = trades.iter().map(delta).collect::<Vec<_>>();
+    let exact = exact_laws(&deltas);
+    let modular = MODULI
+        .iter()
+        .flat_map(|&m| modular_laws(&deltas, &exact, m))
+        .collect::<Vec<_>>();
+    exact.into_iter().chain(modular).collect()
+}
+
+/// A law showing that `target` cannot be reached from `start` within
+/// `cap`, unless the start already holds it.
+pub fn ruling_out<'a>(
+    laws: &'a [Law],
+    start: &Candies,
+    target: &Candies,
+    cap: u32,
+) -> Option<&'a Law> {
+    if start.contains(target) {
+        return None;
+    }
+    laws.iter().find(|law| law.rules_out(start, target, cap))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{candies, trade};
+
+    fn described(trades: &[&str], start: &str) -> Vec<String> {
+        let trades = trades.iter().map(|input| trade(input)).collect::<Vec<_>>();
+        laws(&trades)
+            .iter()
+            .map(|law| law.describe(&candies(start)))
+            .collect()
+    }
+
+    #[test]
+    fn finds_what_no_trade_changes() {
+        let laws = described(&["2e -> 1c", "1c -> 2e"], "4e 1c 2w");
+        assert!(laws.contains(&"eggs + 2·cakes is always 6".to_string()));
+        assert!(laws.contains(&"worms is always 2".to_string()));
+        let laws = described(&["1e -> 3w", "1w -> 3e"], "1e");
+        assert!(laws.contains(&"eggs + worms is always odd".to_string()));
+        assert!(!laws.iter().any(|law| law.starts_with("eggs is")));
+    }
+
+    #[test]
+    fn finds_laws_up_to_a_remainder() {
+        let laws = described(&["1e -> 4w", "1w -> 4e"], "2e");
+        assert!(laws.contains(&"eggs + worms always leaves 2 when divided by 3".to_string()));
+        assert!(laws.contains(&"eggs + 4·worms always leaves 2 when divided by 5".to_string()));
+        assert!(!laws
+            .iter()
+            .any(|law| law.ends_with("even") || law.ends_with("odd")));
+    }
+
+    #[test]
+    fn every_law_holds_for_every_trade() {
+        let trades = ["1e -> 4w", "1w -> 4e", "5c -> 2m 1f", "3m -> 1c 1w"].map(trade);
+        let laws = laws(&trades);
+        assert!(laws.iter().any(|law| law.modulus.is_some()));
+        let start = candies("9e 9w 9c 9f 9m");
+        for law in &laws {
+            for trade in &trades {
+                let after = start.trade(trade).unwrap();
+                assert_eq!(law.value(&after), law.value(&start), "{}", law.sum());
+            }
+        }
+    }
+
+    #[test]
+    fn rules_out_only_what_cannot_be_reached() {
+        let cake = laws(&[trade("2e -> 1c")]);
+        let law = ruling_out(&cake, &candies("1e"), &candies("1c"), 20).unwrap();
+        assert_eq!(law.describe(&candies("1e")), "eggs + 2·cakes is always 1");
+        assert!(ruling_out(&cake, &candies("2e"), &candies("1c"), 20).is_none());
+        assert!(ruling_out(&cake, &candies("1c"), &candies("1c"), 0).is_none());
+        let parity = laws(&[trade("1e -> 3w"), trade("1w -> 3e")]);
+        assert!(ruling_out(&parity, &candies("1e"), &candies("2e"), 2).is_some());
+        assert!(ruling_out(&parity, &candies("1e"), &candies("2e"), 3).is_none());
+    }
+}
@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+
+use crate::{count::Count, display_side, kinds, Candies, CandyWorks, Trade};
+
+/// One step of a plan shared between players, who are counted from 0 in
+/// the order they were named.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Step {
+    /// Player `by` makes `trade` out of their own candies.
+    Trade { by: usize, trade: Trade },
+    /// Player `from` hands `candies` to player `to`, besides losing the
+    /// transfer fee.
+    Transfer {
+        from: usize,
+        to: usize,
+        candies: Candies,
+    },
+}
+
+/// How a search for a shared plan ended.
+pub enum Outcome {
+    /// The steps, made in order, leave the target with its player.
+    Found(Vec<Step>),
+    /// No plan exists within the cap of every bag.
+    Unreachable,
+    /// It gave up after exploring this many states.
+    Stopped(usize),
+}
+
+/// Players pooling their candies: each makes the trades of `candy_works`
+/// out of their own bag, which every player holds to the cap and kind
+/// caps of `candy_works`, and any player can hand candies of one kind to
+/// another, losing `fee` on top each time.
+pub struct Cooperation<'a> {
+    pub candy_works: &'a CandyWorks,
+    pub names: Vec<String>,
+    pub inventories: Vec<Candies>,
+    pub fee: Candies,
+}
+
+impl Cooperation<'_> {
+    /// The steps to and the bags after each step from `bags`.
+    fn moves(&self, bags: &[Candies]) -> Vec<(Step, Vec<Candies>)> {
+        let mut moves = Vec::new();
+        for (by, bag) in bags.iter().enumerate() {
+            for trade in &self.candy_works.trades {
+                if let Some(after) = bag
+                    .trade(trade)
+                    .filter(|after| self.candy_works.fits(after))
+                {
+                    let mut next = bags.to_vec();
+                    next[by] = after;
+                    moves.push((Step::Trade { by, trade: *trade }, next));
+                }
+            }
+            for to in (0..bags.len()).filter(|&to| to != by) {
+                for i in 0..kinds::count() {
+                    for count in 1..=bag.get_by_index(i).get() {
+                        let mut candies = Candies::none();
+                        candies.add_by_index(i, Count::new(count));
+                        let Some(left) = bag.without(&candies.with(&self.fee)) else {
+                            break;
+                        };
+                        let received = bags[to].with(&candies);
+                        if !self.candy_works.fits(&received) {
+                            break;
+                        }
+                        let mut next = bags.to_vec();
+                        next[by] = left;
+                        next[to] = received;
+                        moves.push((
+                            Step::Transfer {
+                                from: by,
+                                to,
+                                candies,
+                            },
+                            next,
+                        ));
+                    }
+                }
+            }
+        }
+        moves
+    }
+
+    /// The plan in the fewest steps leaving player `receiver` holding
+    /// `target`, searched breadth first over what every player holds; of
+    /// plans as short, the one leaving the most candies in all wins.
+    /// Gives up once more than `max_states` states are stored.
+    pub fn plan(&self, receiver: usize, target: Candies, max_states: usize) -> Outcome {
+        let start = self.inventories.clone();
+        let mut previous: HashMap<Vec<Candies>, Option<(Step, Vec<Candies>)>> =
+            HashMap::from([(start.clone(), None)]);
+        let mut level = vec![start];
+        while !level.is_empty() {
+            let best = level
+                .iter()
+                .filter(|bags| bags[receiver].contains(&target))
+                .max_by_key(|bags| bags.iter().map(Candies::total).sum::<Count>());
+            if let Some(end) = best {
+                let mut steps = Vec::new();
+                let mut current = end.clone();
+                while let Some(Some((step, before))) = previous.get(&current).cloned() {
+                    steps.push(step);
+                    current = before;
+                }
+                steps.reverse();
+                return Outcome::Found(steps);
+            }
+            let mut next = Vec::new();
+            for bags in &level {
+                for (step, after) in self.moves(bags) {
+                    if previous.contains_key(&after) {
+                        continue;
+                    }
+                    if previous.len() >= max_states {
+                        return Outcome::Stopped(previous.len());
+                    }
+                    previous.insert(after.clone(), Some((step, bags.clone())));
+                    next.push(after);
+                }
+            }
+            level = next;
+        }
+        Outcome::Unreachable
+    }
+
+    /// Prints the plan for `receiver` to hold `target`, each step with the
+    /// player making it, then what every player ends with.
+    pub fn print(&self, receiver: usize, target: Candies, max_states: usize) -> bool {
+        let steps = match self.plan(receiver, target, max_states) {
+            Outcome::Found(steps) => steps,
+            Outcome::Unreachable => return false,
+            Outcome::Stopped(states) => {
+                println!(
+                    "Stopped after {} states without a plan; raise --max-states to search on",
+                    states
+                );
+                return false;
+            }
+        };
+        let width = self.names.iter().map(String::len).max().unwrap_or(0);
+        let mut bags = self.inventories.clone();
+        for (n, step) in steps.iter().enumerate() {
+            match step {
+                Step::Trade { by, trade } => {
+                    println!(
+                        "{:>3}. {:<width$}  {} -> {}",
+                        n + 1,
+                        self.names[*by],
+                        display_side(&trade.give),
+                        display_side(&trade.receive),
+                        width = width
+                    );
+                    bags[*by] = bags[*by].trade(trade).unwrap();
+                }
+                Step::Transfer { from, to, candies } => {
+                    println!(
+                        "{:>3}. {:<width$}  gives {} {}{}",
+                        n + 1,
+                        self.names[*from],
+                        self.names[*to],
+                        display_side(candies),
+                        if self.fee == Candies::none() {
+                            String::new()
+                        } else {
+                            format!(", paying {}", display_side(&self.fee))
+                        },
+                        width = width
+                    );
+                    bags[*from] = bags[*from].without(&candies.with(&self.fee)).unwrap();
+                    bags[*to] = bags[*to].with(candies);
+                }
+            }
+        }
+        let made = |player: usize| {
+            steps
+                .iter()
+                .filter(|step| match step {
+                    Step::Trade { by, .. } => *by == player,
+                    Step::Transfer { from, .. } => *from == player,
+                })
+                .count()
+        };
+        println!(
+            "{} step{}, leaving ({}) with {}",
+            steps.len(),
+            if steps.len() == 1 { "" } else { "s" },
+            display_side(&target),
+            self.names[receiver]
+        );
+        for (player, bag) in bags.iter().enumerate() {
+            let steps = made(player);
+            println!(
+                "  {:<width$}  ({}) after {} step{}",
+                self.names[player],
+                bag.display(true),
+                steps,
+                if steps == 1 { "" } else { "s" },
+                width = width
+            );
+        }
+        true
+    }
+}
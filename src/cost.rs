@@ -0,0 +1,99 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+use crate::{route::Route, travel::Cost, uses::Ranking, Candies, CandyWorks, Trade};
+
+/// What trades cost besides their candies, as in `3e -> 1m; cost 5`, in
+/// coins, minutes or whatever unit the scenario's costs share.
+#[derive(Default)]
+pub struct TradeCosts {
+    costs: HashMap<Trade, f64>,
+}
+
+impl TradeCosts {
+    /// Collects the costs; `costs` runs parallel to `trades`, as in a
+    /// scenario. A trade written more than once costs the least of its
+    /// lines, and one written without a cost, or a standard trade, nothing.
+    pub fn new(trades: &[Trade], costs: &[Option<f64>]) -> TradeCosts {
+        let mut known = HashMap::new();
+        for (trade, cost) in trades.iter().zip(costs) {
+            let cost = cost.unwrap_or(0.0);
+            known
+                .entry(*trade)
+                .and_modify(|known: &mut f64| *known = known.min(cost))
+                .or_insert(cost);
+        }
+        known.retain(|_, cost| *cost > 0.0);
+        TradeCosts { costs: known }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.costs.is_empty()
+    }
+
+    pub fn of(&self, trade: &Trade) -> f64 {
+        self.costs.get(trade).copied().unwrap_or(0.0)
+    }
+
+    pub fn total(&self, trades: &[Trade]) -> f64 {
+        trades.iter().map(|trade| self.of(trade)).sum()
+    }
+}
+
+impl CandyWorks {
+    /// The route to `target` costing the least, searched over the explored
+    /// states with Dijkstra's algorithm; of routes costing as much, the one
+    /// in the fewest trades. Standard trades cost nothing, so the cheapest
+    /// route may well be longer than the one leaving the most candies. The
+    /// route keeps to the use limits, as `find_optimal_route`'s does.
+    pub fn cheapest_route(&self, costs: &TradeCosts, target: Candies) -> Option<Route> {
+        let index = self
+            .combinations
+            .states()
+            .enumerate()
+            .map(|(i, candies)| (candies, i))
+            .collect::<HashMap<_, _>>();
+        let start = *index.get(&self.candies)?;
+        let mut best = HashMap::from([(start, (0.0, 0))]);
+        let mut previous = HashMap::<usize, (usize, Trade)>::new();
+        let mut heap = BinaryHeap::from([Reverse((Cost(0.0), 0, start))]);
+        while let Some(Reverse((Cost(cost), length, state))) = heap.pop() {
+            if best
+                .get(&state)
+                .is_some_and(|&known| known < (cost, length))
+            {
+                continue;
+            }
+            let candies = self.combinations.state(state);
+            if candies.contains(&target) {
+                let mut trades = Vec::new();
+                let mut current = state;
+                while let Some(&(before, trade)) = previous.get(&current) {
+                    trades.push(trade);
+                    current = before;
+                }
+                trades.reverse();
+                let route = Route::new(self.candies, trades).unwrap();
+                return self.keep_to_uses(
+                    route.found_in(self.exploration()),
+                    |candies| candies.contains(&target),
+                    Ranking::Cheapest(costs),
+                );
+            }
+            for trade in &self.trades {
+                let Some(&next) = candies.trade(trade).and_then(|next| index.get(&next)) else {
+                    continue;
+                };
+                let reached = (cost + costs.of(trade), length + 1);
+                if best.get(&next).is_none_or(|&known| reached < known) {
+                    best.insert(next, reached);
+                    previous.insert(next, (state, *trade));
+                    heap.push(Reverse((Cost(reached.0), reached.1, next)));
+                }
+            }
+        }
+        None
+    }
+}
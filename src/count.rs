@@ -0,0 +1,400 @@
+use std::{
+    fmt::{self, Debug, Display},
+    hash::Hash,
+    iter::Sum,
+    num::ParseIntError,
+    ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign},
+    str::FromStr,
+};
+
+use crate::{
+    kinds::{self, MAX_KINDS},
+    names::{CandyNames, Lookup},
+    Candies, ParseCandiesError,
+};
+
+/// An integer type candy counts can be kept in.
+pub trait Integer: Copy + Ord + Hash + Debug + Display + FromStr {
+    const ZERO: Self;
+    const MAX: Self;
+    fn checked_add(self, other: Self) -> Option<Self>;
+    fn checked_sub(self, other: Self) -> Option<Self>;
+    fn checked_mul(self, other: Self) -> Option<Self>;
+    fn from_i64(value: i64) -> Option<Self>;
+}
+
+macro_rules! integer {
+    ($($t:ty),*) => {$(
+        impl Integer for $t {
+            const ZERO: $t = 0;
+            const MAX: $t = <$t>::MAX;
+            fn checked_add(self, other: $t) -> Option<$t> {
+                <$t>::checked_add(self, other)
+            }
+            fn checked_sub(self, other: $t) -> Option<$t> {
+                <$t>::checked_sub(self, other)
+            }
+            fn checked_mul(self, other: $t) -> Option<$t> {
+                <$t>::checked_mul(self, other)
+            }
+            fn from_i64(value: i64) -> Option<$t> {
+                <$t>::try_from(value).ok()
+            }
+        }
+    )*};
+}
+
+integer!(i32, i64);
+
+/// A number of candies, as `Candies` hands its counts out and takes them
+/// in. Counts add to and subtract from counts and multiply by a plain
+/// number of times, but are never taken for a candy index, a cap or a
+/// cost without saying so with `Count::new` or `get`. Written and read as
+/// the bare number, so route files and JSON output stay as they were.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Count(i32);
+
+impl Count {
+    pub const ZERO: Count = Count(0);
+
+    pub const fn new(count: i32) -> Count {
+        Count(count)
+    }
+
+    pub const fn get(self) -> i32 {
+        self.0
+    }
+}
+
+impl From<i32> for Count {
+    fn from(count: i32) -> Count {
+        Count(count)
+    }
+}
+
+impl From<Count> for i32 {
+    fn from(count: Count) -> i32 {
+        count.0
+    }
+}
+
+impl From<Count> for i64 {
+    fn from(count: Count) -> i64 {
+        count.0.into()
+    }
+}
+
+impl Add for Count {
+    type Output = Count;
+
+    fn add(self, other: Count) -> Count {
+        Count(self.0 + other.0)
+    }
+}
+
+impl Sub for Count {
+    type Output = Count;
+
+    fn sub(self, other: Count) -> Count {
+        Count(self.0 - other.0)
+    }
+}
+
+impl Neg for Count {
+    type Output = Count;
+
+    fn neg(self) -> Count {
+        Count(-self.0)
+    }
+}
+
+/// `times` lots of a count, as a trade made several times gives.
+impl Mul<i32> for Count {
+    type Output = Count;
+
+    fn mul(self, times: i32) -> Count {
+        Count(self.0 * times)
+    }
+}
+
+impl AddAssign for Count {
+    fn add_assign(&mut self, other: Count) {
+        self.0 += other.0;
+    }
+}
+
+impl SubAssign for Count {
+    fn sub_assign(&mut self, other: Count) {
+        self.0 -= other.0;
+    }
+}
+
+impl Sum for Count {
+    fn sum<I: Iterator<Item = Count>>(counts: I) -> Count {
+        counts.fold(Count::ZERO, Add::add)
+    }
+}
+
+impl Display for Count {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl FromStr for Count {
+    type Err = ParseIntError;
+
+    fn from_str(input: &str) -> Result<Count, ParseIntError> {
+        input.parse().map(Count)
+    }
+}
+
+/// The integer type a scenario keeps its counts in, as in `count = i64`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Width {
+    #[default]
+    I32,
+    I64,
+}
+
+impl Width {
+    pub fn parse(value: &str) -> Result<Width, String> {
+        match value {
+            "i32" => Ok(Width::I32),
+            "i64" => Ok(Width::I64),
+            _ => Err(format!(
+                "unknown count type `{}`, expected `i32` or `i64`",
+                value
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Width {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Width::I32 => write!(f, "i32"),
+            Width::I64 => write!(f, "i64"),
+        }
+    }
+}
+
+/// What to do with a count too large for its type, as in
+/// `overflow = saturate`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Overflow {
+    /// Refuse the scenario, or the trade while exploring.
+    #[default]
+    Error,
+    /// Keep the largest count the type holds.
+    Saturate,
+}
+
+impl Overflow {
+    pub fn parse(value: &str) -> Result<Overflow, String> {
+        match value {
+            "error" => Ok(Overflow::Error),
+            "saturate" => Ok(Overflow::Saturate),
+            _ => Err(format!(
+                "unknown overflow policy `{}`, expected `error` or `saturate`",
+                value
+            )),
+        }
+    }
+
+    /// The result of a checked operation, `None` meaning it overflowed.
+    pub fn apply<C: Integer>(self, result: Option<C>) -> Option<C> {
+        match self {
+            Overflow::Error => result,
+            Overflow::Saturate => Some(result.unwrap_or(C::MAX)),
+        }
+    }
+}
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Overflow::Error => write!(f, "error"),
+            Overflow::Saturate => write!(f, "saturate"),
+        }
+    }
+}
+
+/// Candy counts in `kinds` order kept in any `Integer`. `Candies` is the
+/// `i32` kind the explorer is tuned for; these are what scenarios are read
+/// into and what `count = i64` scenarios are planned with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Counts<C>(pub [C; MAX_KINDS]);
+
+impl<C: Integer> Counts<C> {
+    pub fn none() -> Counts<C> {
+        Counts([C::ZERO; MAX_KINDS])
+    }
+
+    /// Parses the same forms as `Candies::parse_with`.
+    pub fn parse_with(input: &str, names: &CandyNames) -> Result<Counts<C>, ParseCandiesError> {
+        let input = input.trim();
+        let mut counts = Counts::none();
+        if input == "none" {
+            return Ok(counts);
+        }
+        for part in input.split(',') {
+            let part = part.trim();
+            let parsed = match parse_compact(part) {
+                Ok(compact) => compact,
+                Err(err) => parse_named(part, names).unwrap_or(Err(err))?,
+            };
+            counts = counts
+                .with(&parsed, Overflow::Error)
+                .ok_or_else(|| ParseCandiesError(format!("`{}` is too large", input)))?;
+        }
+        Ok(counts)
+    }
+
+    pub fn total(&self, overflow: Overflow) -> Option<C> {
+        self.0.iter().try_fold(C::ZERO, |total, &count| {
+            overflow.apply(total.checked_add(count))
+        })
+    }
+
+    pub fn contains(&self, other: &Counts<C>) -> bool {
+        self.0.iter().zip(&other.0).all(|(have, want)| have >= want)
+    }
+
+    pub fn with(&self, other: &Counts<C>, overflow: Overflow) -> Option<Counts<C>> {
+        let mut counts = *self;
+        for (count, more) in counts.0.iter_mut().zip(&other.0) {
+            *count = overflow.apply(count.checked_add(*more))?;
+        }
+        Some(counts)
+    }
+
+    pub fn times(&self, times: C, overflow: Overflow) -> Option<Counts<C>> {
+        let mut counts = *self;
+        for count in &mut counts.0 {
+            *count = overflow.apply(count.checked_mul(times))?;
+        }
+        Some(counts)
+    }
+
+    /// The counts after giving `give` and receiving `receive`; `None` if
+    /// they hold less than `give`, or if a count overflows under
+    /// `Overflow::Error`.
+    pub fn trade(
+        &self,
+        give: &Counts<C>,
+        receive: &Counts<C>,
+        overflow: Overflow,
+    ) -> Option<Counts<C>> {
+        let mut counts = *self;
+        for (i, count) in counts.0.iter_mut().enumerate() {
+            *count = overflow.apply(count.checked_sub(give.0[i])?.checked_add(receive.0[i]))?;
+            if *count < C::ZERO {
+                return None;
+            }
+        }
+        Some(counts)
+    }
+
+    /// The same counts in another type, as `overflow` allows.
+    pub fn narrow<D: Integer>(&self, overflow: Overflow) -> Option<Counts<D>>
+    where
+        C: Into<i64>,
+    {
+        let mut counts = Counts::none();
+        for (count, wide) in counts.0.iter_mut().zip(&self.0) {
+            *count = overflow.apply(D::from_i64((*wide).into()))?;
+        }
+        Some(counts)
+    }
+
+    pub fn compact(&self) -> String {
+        let parts = kinds::active()
+            .letters()
+            .zip(&self.0)
+            .filter(|(_, &count)| count != C::ZERO)
+            .map(|(c, count)| format!("{}{}", count, c))
+            .collect::<Vec<_>>();
+        if parts.is_empty() {
+            "none".to_string()
+        } else {
+            parts.join(" ")
+        }
+    }
+}
+
+impl From<Counts<i32>> for Candies {
+    fn from(counts: Counts<i32>) -> Candies {
+        Candies { counts: counts.0 }
+    }
+}
+
+impl Candies {
+    pub fn counts(&self) -> Counts<i32> {
+        Counts(self.counts)
+    }
+}
+
+/// The compact form: `3e2w`, `3e 2w` or `eee`.
+fn parse_compact<C: Integer>(input: &str) -> Result<Counts<C>, ParseCandiesError> {
+    let mut counts = Counts::<C>::none();
+    let mut number = String::new();
+    for c in input.to_lowercase().chars() {
+        let index = match c {
+            '0'..='9' => {
+                number.push(c);
+                continue;
+            }
+            ' ' => continue,
+            c => kinds::active()
+                .position(c)
+                .ok_or_else(|| ParseCandiesError(format!("unknown candy letter `{}`", c)))?,
+        };
+        let too_large = || ParseCandiesError(format!("count `{}` is too large", number));
+        let count = if number.is_empty() {
+            C::from_i64(1).unwrap()
+        } else {
+            number.parse().map_err(|_| too_large())?
+        };
+        counts.0[index] = counts.0[index].checked_add(count).ok_or_else(too_large)?;
+        number.clear();
+    }
+    if number.is_empty() {
+        Ok(counts)
+    } else {
+        Err(ParseCandiesError(format!(
+            "count `{}` is not followed by a candy",
+            number
+        )))
+    }
+}
+
+/// The display form of one candy, as in `3 eggs`; `None` if `part` is not
+/// a count followed by a name.
+fn parse_named<C: Integer>(
+    part: &str,
+    names: &CandyNames,
+) -> Option<Result<Counts<C>, ParseCandiesError>> {
+    let (count, name) = part.split_once(' ')?;
+    let count = count.parse::<C>().ok()?;
+    Some(match names.lookup(name) {
+        Lookup::Exact(index) | Lookup::Close(index) => {
+            let mut counts = Counts::none();
+            counts.0[index] = count;
+            Ok(counts)
+        }
+        Lookup::Ambiguous(indices) => Err(ParseCandiesError(format!(
+            "`{}` could be {}",
+            name.trim(),
+            indices
+                .iter()
+                .map(|&i| names.plural(i))
+                .collect::<Vec<_>>()
+                .join(" or ")
+        ))),
+        Lookup::Unknown => Err(ParseCandiesError(format!(
+            "unknown candy `{}`",
+            name.trim()
+        ))),
+    })
+}
@@ -0,0 +1,86 @@
+use std::{fs, io, path::Path};
+
+use crate::{query::Filter, CandyWorks};
+
+/// Named filters kept in a file of `name = filter` lines, for checking the
+/// same things on every graph.
+#[derive(Default)]
+pub struct SavedQueries {
+    entries: Vec<(String, String)>,
+}
+
+impl SavedQueries {
+    /// Reads queries written by `save`; a missing file has none.
+    pub fn load(path: &Path) -> Result<SavedQueries, String> {
+        let input = match fs::read_to_string(path) {
+            Ok(input) => input,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(SavedQueries::default()),
+            Err(err) => return Err(err.to_string()),
+        };
+        let mut queries = SavedQueries::default();
+        for (number, line) in input.lines().enumerate() {
+            if line.trim().is_empty() || line.trim_start().starts_with('#') {
+                continue;
+            }
+            let (name, filter) = line
+                .split_once('=')
+                .filter(|(name, _)| !name.trim().is_empty())
+                .ok_or_else(|| format!("line {}: expected `name = filter`", number + 1))?;
+            queries.insert(name.trim(), filter.trim());
+        }
+        Ok(queries)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut out = String::new();
+        for (name, filter) in &self.entries {
+            out.push_str(&format!("{} = {}\n", name, filter));
+        }
+        fs::write(path, out)
+    }
+
+    /// Adds a query, replacing any with the same name.
+    pub fn insert(&mut self, name: &str, filter: &str) {
+        match self.entries.iter_mut().find(|(known, _)| known == name) {
+            Some(entry) => entry.1 = filter.to_string(),
+            None => self.entries.push((name.to_string(), filter.to_string())),
+        }
+    }
+}
+
+/// Runs every saved query against the explored states and prints one line
+/// each: how many states match, their share and the fewest trades any of
+/// them is reached in.
+pub fn print(candy_works: &CandyWorks, queries: &SavedQueries) {
+    if queries.entries.is_empty() {
+        println!("No saved queries");
+        return;
+    }
+    let total = candy_works.combinations.len();
+    let depths = candy_works.depths();
+    println!("Dashboard of {} states:", total);
+    let width = queries
+        .entries
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .max()
+        .unwrap();
+    for (name, filter) in &queries.entries {
+        let summary = match Filter::parse(filter).and_then(|filter| candy_works.matching(&filter)) {
+            Ok(matching) => {
+                let nearest = matching.iter().map(|&i| depths[i]).min();
+                format!(
+                    "{:>8} states {:>6.1}%{}",
+                    matching.len(),
+                    100.0 * matching.len() as f64 / total as f64,
+                    nearest.map_or(String::new(), |depth| format!(
+                        ", nearest after {} trades",
+                        depth
+                    ))
+                )
+            }
+            Err(err) => format!("invalid filter: {}", err),
+        };
+        println!("  {:<width$}  {}", name, summary, width = width);
+    }
+}
@@ -0,0 +1,128 @@
+use clap::ValueEnum;
+
+use crate::{display_side, notes::Notes, repeat, Trade};
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum TreeFormat {
+    Text,
+    Json,
+}
+
+/// The routes to several targets merged into one plan: trades everyone
+/// makes, then a choice between groups of targets, each a plan of its own.
+pub struct DecisionTree {
+    trades: Vec<Trade>,
+    /// Targets in hand once the trades are made.
+    reached: Vec<String>,
+    choices: Vec<DecisionTree>,
+}
+
+impl DecisionTree {
+    /// Merges `routes`, each labelled with its target, at their common
+    /// prefixes.
+    pub fn new(routes: &[(String, Vec<Trade>)]) -> DecisionTree {
+        let shared = routes
+            .iter()
+            .map(|(_, route)| route.as_slice())
+            .reduce(|a, b| &a[..a.iter().zip(b).take_while(|(x, y)| x == y).count()])
+            .unwrap_or_default();
+        let mut reached = Vec::new();
+        let mut groups: Vec<Vec<(String, Vec<Trade>)>> = Vec::new();
+        for (label, route) in routes {
+            let rest = &route[shared.len()..];
+            match rest.first() {
+                None => reached.push(label.clone()),
+                Some(first) => {
+                    let entry = (label.clone(), rest.to_vec());
+                    match groups.iter_mut().find(|group| group[0].1[0] == *first) {
+                        Some(group) => group.push(entry),
+                        None => groups.push(vec![entry]),
+                    }
+                }
+            }
+        }
+        DecisionTree {
+            trades: shared.to_vec(),
+            reached,
+            choices: groups
+                .iter()
+                .map(|group| DecisionTree::new(group))
+                .collect(),
+        }
+    }
+
+    /// Every target the plan leads to from here.
+    fn targets(&self) -> Vec<&str> {
+        let mut targets = self.reached.iter().map(String::as_str).collect::<Vec<_>>();
+        for choice in &self.choices {
+            targets.extend(choice.targets());
+        }
+        targets
+    }
+
+    /// The plan written in `format`, with the trades' notes in `notes`.
+    pub fn render(&self, format: TreeFormat, notes: &Notes) -> String {
+        match format {
+            TreeFormat::Text => self.text(0, notes),
+            TreeFormat::Json => format!("{}\n", self.json(notes)),
+        }
+    }
+
+    fn text(&self, depth: usize, notes: &Notes) -> String {
+        let indent = "    ".repeat(depth);
+        let mut out = String::new();
+        for (trade, times) in repeat::runs(&repeat::expand(&self.trades)) {
+            out.push_str(&format!(
+                "{}{} -> {}{}{}\n",
+                indent,
+                display_side(&trade.give),
+                display_side(&trade.receive),
+                repeat::suffix(times),
+                notes.suffix(&trade)
+            ));
+        }
+        for target in &self.reached {
+            out.push_str(&format!("{}({}) is in hand\n", indent, target));
+        }
+        for choice in &self.choices {
+            let targets = choice
+                .targets()
+                .iter()
+                .map(|target| format!("({})", target))
+                .collect::<Vec<_>>();
+            out.push_str(&format!("{}For {}:\n", indent, targets.join(" or ")));
+            out.push_str(&choice.text(depth + 1, notes));
+        }
+        out
+    }
+
+    fn json(&self, notes: &Notes) -> String {
+        let quote = |text: &str| format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""));
+        let trades = repeat::runs(&repeat::expand(&self.trades))
+            .iter()
+            .map(|(trade, times)| {
+                format!(
+                    "{{\"give\":{},\"receive\":{},\"times\":{}{}}}",
+                    quote(&trade.give.compact()),
+                    quote(&trade.receive.compact()),
+                    times,
+                    notes
+                        .get(trade)
+                        .map_or(String::new(), |note| format!(",\"note\":{}", quote(note)))
+                )
+            })
+            .collect::<Vec<_>>();
+        let reached = self.reached.iter().map(|t| quote(t)).collect::<Vec<_>>();
+        let choices = self
+            .choices
+            .iter()
+            .map(|choice| choice.json(notes))
+            .collect::<Vec<_>>();
+        format!(
+            "{{\"trades\":[{}],\"reached\":[{}],\"choices\":[{}]}}",
+            trades.join(","),
+            reached.join(","),
+            choices.join(",")
+        )
+    }
+}
@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+
+use crate::{storage::Combinations, CandyWorks, Trade};
+
+pub fn report(candy_works: &CandyWorks, trade: Trade, limit: usize) {
+    let old_depths = candy_works
+        .combinations
+        .states()
+        .zip(candy_works.depths())
+        .collect::<HashMap<_, _>>();
+
+    let mut trades = vec![trade];
+    trades.extend(candy_works.trades.iter().copied());
+    let mut extended = CandyWorks {
+        candies: candy_works.candies,
+        max_candies: candy_works.max_candies,
+        kind_caps: candy_works.kind_caps,
+        trades,
+        use_limits: candy_works.use_limits.clone(),
+        notes: candy_works.notes.clone(),
+        combinations: Combinations::new(),
+        prefilter: candy_works.prefilter,
+        prune_dominated: candy_works.prune_dominated,
+        frontier: candy_works.frontier.clone(),
+        objective: candy_works.objective.clone(),
+        timings: candy_works.timings.clone(),
+        exploration: None,
+    };
+    extended.explore_from(&candy_works.roots(), None);
+
+    let mut reachable = Vec::new();
+    let mut shortened = Vec::new();
+    for (candies, depth) in extended.combinations.states().zip(extended.depths()) {
+        match old_depths.get(&candies) {
+            None => reachable.push((candies, depth)),
+            Some(&old) if depth < old => shortened.push((candies, old, depth)),
+            Some(_) => (),
+        }
+    }
+
+    println!("New trade: {}", trade);
+    println!("Newly reachable states: {}", reachable.len());
+    for (candies, depth) in reachable.iter().take(limit) {
+        println!(
+            "  #{} ({}) in {} trades",
+            candies.id_label(),
+            candies.display(true),
+            depth
+        );
+    }
+    println!("Routes shortened: {}", shortened.len());
+    for (candies, old, new) in shortened.iter().take(limit) {
+        println!(
+            "  #{} ({}) {} -> {} trades",
+            candies.id_label(),
+            candies.display(true),
+            old,
+            new
+        );
+    }
+}
@@ -0,0 +1,136 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+};
+
+use crate::{kinds, route::Route, uses::Ranking, Candies, CandyWorks, Trade};
+
+/// A lower bound on the trades from `candies` to holding `target`: no trade
+/// adds more of a candy than `most` says, so a kind short by d needs at
+/// least d / most trades. `None` when a kind is short that no trade adds.
+fn trades_needed(candies: &Candies, target: &Candies, most: &[i32]) -> Option<usize> {
+    let mut needed = 0;
+    for (i, &most) in most.iter().enumerate() {
+        let short = (target.get_by_index(i) - candies.get_by_index(i)).get();
+        if short > 0 {
+            if most == 0 {
+                return None;
+            }
+            needed = needed.max((short as usize).div_ceil(most as usize));
+        }
+    }
+    Some(needed)
+}
+
+impl CandyWorks {
+    /// The route to `target` in the fewest trades, searched from the start
+    /// without exploring first: A* over the states within the cap, guided by
+    /// how many trades the missing candies need at least. It stops at the
+    /// first state holding the target, so a reachable target near the start
+    /// is found after a glimpse of the graph, while an unreachable one still
+    /// visits every state. Of routes as short, the one reached first trying
+    /// the trades in their order wins, whatever `objective` says. A route
+    /// breaking the use limits is searched for again keeping to them.
+    pub fn find_route_direct(&self, target: Candies) -> Option<Route> {
+        let most = (0..kinds::count())
+            .map(|i| {
+                self.trades
+                    .iter()
+                    .map(|trade| (trade.receive.get_by_index(i) - trade.give.get_by_index(i)).get())
+                    .max()
+                    .unwrap_or(0)
+                    .max(0)
+            })
+            .collect::<Vec<_>>();
+        let mut reached: Vec<(Candies, Option<(usize, Trade)>)> = vec![(self.candies, None)];
+        let mut trades_to = HashMap::from([(self.candies, 0)]);
+        let mut open = BinaryHeap::new();
+        if let Some(needed) = trades_needed(&self.candies, &target, &most) {
+            open.push(Reverse((needed, 0)));
+        }
+        let mut expanded = HashSet::new();
+        while let Some(Reverse((_, index))) = open.pop() {
+            let (candies, _) = reached[index];
+            if !expanded.insert(candies) {
+                continue;
+            }
+            if candies.contains(&target) {
+                let mut trades = Vec::new();
+                let mut current = index;
+                while let Some((parent, trade)) = reached[current].1 {
+                    trades.push(trade);
+                    current = parent;
+                }
+                trades.reverse();
+                return self.keep_to_uses(
+                    Route::new(self.candies, trades).unwrap(),
+                    |candies| candies.contains(&target),
+                    Ranking::FewestTrades,
+                );
+            }
+            let length = trades_to[&candies] + 1;
+            for trade in &self.trades {
+                let Some(next) = candies.trade(trade) else {
+                    continue;
+                };
+                if !self.fits(&next) || trades_to.get(&next).is_some_and(|&known| known <= length) {
+                    continue;
+                }
+                let Some(needed) = trades_needed(&next, &target, &most) else {
+                    continue;
+                };
+                trades_to.insert(next, length);
+                reached.push((next, Some((index, *trade))));
+                open.push(Reverse((length + needed, reached.len() - 1)));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        objective::Objective,
+        test_support::{candies, trade},
+        CandyWorks,
+    };
+
+    fn problem(objective: Objective) -> CandyWorks {
+        CandyWorks::builder(candies("6e 3w"))
+            .cap(12)
+            .trade(trade("2e -> 1c"))
+            .trade(trade("1c 1w -> 1m"))
+            .trade(trade("1w -> 1e"))
+            .standard_trades(false)
+            .objective(objective)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn takes_as_few_trades_as_exploring() {
+        let mut explored = problem(Objective::MinTrades);
+        explored.explore();
+        for target in ["2m", "3c", "1m 2c", "8e"] {
+            let target = candies(target);
+            let direct = problem(Objective::MinTrades)
+                .find_route_direct(target)
+                .unwrap();
+            assert!(direct.end().contains(&target));
+            assert_eq!(
+                direct.len(),
+                explored.find_optimal_route(target).unwrap().len()
+            );
+        }
+    }
+
+    #[test]
+    fn finds_nothing_past_the_cap_or_the_trades() {
+        let candy_works = problem(Objective::default());
+        assert!(candy_works.find_route_direct(candies("13e")).is_none());
+        assert!(candy_works.find_route_direct(candies("1f")).is_none());
+        let start = candy_works.find_route_direct(candies("6e")).unwrap();
+        assert!(start.trades().is_empty());
+    }
+}
@@ -0,0 +1,206 @@
+use std::{io::IsTerminal, str::FromStr, sync::OnceLock};
+
+use clap::ValueEnum;
+
+use crate::{count::Count, kinds, names, Candies, Trade};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum CandyOrder {
+    #[default]
+    Default,
+    /// Highest count first, ties in the default order
+    Count,
+    /// Alphabetical by the displayed name
+    Name,
+    /// Candy indices in the given order; candies not listed follow in the
+    /// default order
+    Custom(Vec<usize>),
+}
+
+impl FromStr for CandyOrder {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<CandyOrder, String> {
+        match input {
+            "default" => Ok(CandyOrder::Default),
+            "count" => Ok(CandyOrder::Count),
+            "name" => Ok(CandyOrder::Name),
+            letters => {
+                let mut order = Vec::new();
+                for c in letters.to_lowercase().chars() {
+                    let index = kinds::active()
+                        .position(c)
+                        .ok_or_else(|| format!("unknown candy letter `{}`", c))?;
+                    if order.contains(&index) {
+                        return Err(format!("candy letter `{}` is repeated", c));
+                    }
+                    order.push(index);
+                }
+                Ok(CandyOrder::Custom(order))
+            }
+        }
+    }
+}
+
+/// When output may use terminal colors.
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub enum ColorChoice {
+    /// Only on a terminal, and not when `NO_COLOR` is set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Auto => {
+                std::env::var_os("NO_COLOR").is_none() && std::io::stdout().is_terminal()
+            }
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DisplayOptions {
+    pub order: CandyOrder,
+    pub include_zeros: bool,
+    pub color: bool,
+}
+
+impl Candies {
+    fn ordered_indices(&self, order: &CandyOrder) -> Vec<usize> {
+        let names = names::active();
+        let mut indices = (0..kinds::count()).collect::<Vec<_>>();
+        match order {
+            CandyOrder::Default => (),
+            CandyOrder::Count => indices.sort_by_key(|&i| -self.get_by_index(i)),
+            CandyOrder::Name => indices.sort_by_key(|&i| names.plural(i).to_lowercase()),
+            CandyOrder::Custom(order) => {
+                indices.sort_by_key(|i| order.iter().position(|o| o == i).unwrap_or(order.len()))
+            }
+        }
+        indices
+    }
+
+    pub fn display_with(&self, options: &DisplayOptions) -> String {
+        let names = names::active();
+        let indices = self.ordered_indices(&options.order);
+        indices
+            .into_iter()
+            .map(|i| (self.get_by_index(i), names.singular(i), names.plural(i)))
+            .filter(|(count, _, _)| options.include_zeros || *count != Count::ZERO)
+            .map(|(count, s, p)| format!("{:2} {}", count, if count.get() == 1 { s } else { p }))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+impl Candies {
+    /// Name and count of each candy in display order, honoring the active
+    /// display options; zero counts are always included.
+    pub fn rows(&self) -> Vec<(&'static str, Count)> {
+        let names = names::active();
+        let options = active();
+        self.ordered_indices(&options.order)
+            .into_iter()
+            .map(|i| (names.plural(i), self.get_by_index(i)))
+            .collect()
+    }
+
+    /// One candy per line with names left-aligned and counts right-aligned.
+    pub fn to_table(self) -> String {
+        let rows = self.rows();
+        let width = rows.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+        rows.iter()
+            .map(|(name, count)| format!("{:<width$} {:>3}", name, count, width = width))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// How a route changes the inventory from `start` to `end`: the count of
+/// each candy before and after and the difference, gains in green and
+/// losses in red when colors are on. Candies absent at both ends are left
+/// out unless zeros are shown.
+pub fn change_table(start: &Candies, end: &Candies) -> String {
+    labelled_change_table(start, end, "start", "end")
+}
+
+/// `change_table` with its count columns headed `from` and `to`, for
+/// comparing two states that are not the two ends of a route.
+pub fn labelled_change_table(start: &Candies, end: &Candies, from: &str, to: &str) -> String {
+    let options = active();
+    let rows = start
+        .rows()
+        .into_iter()
+        .zip(end.rows())
+        .filter(|((_, before), (_, after))| {
+            options.include_zeros || *before != Count::ZERO || *after != Count::ZERO
+        })
+        .map(|((name, before), (_, after))| (name, before, after))
+        .collect::<Vec<_>>();
+    let width = rows
+        .iter()
+        .map(|(name, ..)| name.len())
+        .chain(["candy".len()])
+        .max()
+        .unwrap();
+    let mut out = format!(
+        "{:<width$} {:>6} {:>6} {:>6}",
+        "candy",
+        from,
+        to,
+        "change",
+        width = width
+    );
+    for (name, before, after) in rows {
+        let change = (after - before).get();
+        let cell = match change {
+            0 => format!("{:>6}", 0),
+            _ => format!("{:>+6}", change),
+        };
+        let cell = match (options.color, change.signum()) {
+            (true, 1) => format!("\x1b[32m{}\x1b[0m", cell),
+            (true, -1) => format!("\x1b[31m{}\x1b[0m", cell),
+            _ => cell,
+        };
+        out.push_str(&format!(
+            "\n{:<width$} {:>6} {:>6} {}",
+            name,
+            before,
+            after,
+            cell,
+            width = width
+        ));
+    }
+    out
+}
+
+impl Trade {
+    /// The trade with its give side padded to `width` characters, so the
+    /// arrows of consecutive trades line up.
+    pub fn to_aligned_string(self, width: usize) -> String {
+        format!(
+            "{:>width$} -> {}",
+            self.give.to_string(),
+            self.receive,
+            width = width
+        )
+    }
+}
+
+static ACTIVE: OnceLock<DisplayOptions> = OnceLock::new();
+
+/// Sets the options used by `Display` for the rest of the run, failing
+/// once other options are in use.
+pub fn configure(options: DisplayOptions) -> Result<(), String> {
+    crate::configure_once(&ACTIVE, options, "the display options are")
+}
+
+pub fn active() -> &'static DisplayOptions {
+    ACTIVE.get_or_init(DisplayOptions::default)
+}
@@ -0,0 +1,75 @@
+use crate::{kind_caps::KindCaps, Candies, Trade};
+
+/// The states some known state dominates, holding at least as many of every
+/// candy, for exploring without them. Whatever trades a dominated state can
+/// make, its dominator can make too and keeps as many of every candy after,
+/// so when no trade receives more candies than it gives, every route from
+/// the dominated state is matched by one from the dominator within the cap.
+///
+/// Only the maximal states covered so far are kept, none holding at least
+/// as many of every candy as another, so memory grows with that antichain
+/// rather than with everything below it. Testing or covering a state
+/// compares it with each of them.
+#[derive(Default)]
+pub struct Dominated {
+    maximal: Vec<Candies>,
+}
+
+impl Dominated {
+    pub fn new() -> Self {
+        Dominated::default()
+    }
+
+    /// Whether a covered state holds at least as many of every candy as
+    /// `candies`, which includes `candies` having been covered itself.
+    pub fn covers(&self, candies: &Candies) -> bool {
+        self.maximal.iter().any(|maximal| maximal.contains(candies))
+    }
+
+    /// Records `candies` and, with it, every state it dominates.
+    pub fn cover(&mut self, candies: Candies) {
+        if self.covers(&candies) {
+            return;
+        }
+        self.maximal.retain(|maximal| !candies.contains(maximal));
+        self.maximal.push(candies);
+    }
+}
+
+/// Whether skipping dominated states loses no route under `trades`: none of
+/// them receives more candies than it gives, so a dominator never passes
+/// the cap where the state it dominates stays within it, and no kind is
+/// capped, since holding more of a kind can pass its cap.
+pub fn exact_for(trades: &[Trade], kind_caps: Option<&KindCaps>) -> bool {
+    kind_caps.is_none()
+        && trades
+            .iter()
+            .all(|trade| trade.receive.total() <= trade.give.total())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::candies;
+
+    #[test]
+    fn covers_what_a_covered_state_holds() {
+        let mut dominated = Dominated::new();
+        dominated.cover(candies("2e 1m"));
+        assert!(dominated.covers(&candies("2e 1m")));
+        assert!(dominated.covers(&candies("1e")));
+        assert!(!dominated.covers(&candies("3e")));
+        assert!(!dominated.covers(&candies("1w")));
+    }
+
+    #[test]
+    fn keeps_only_the_maximal_states() {
+        let mut dominated = Dominated::new();
+        dominated.cover(candies("1e"));
+        dominated.cover(candies("1m"));
+        dominated.cover(candies("1e"));
+        assert_eq!(dominated.maximal.len(), 2);
+        dominated.cover(candies("2e 1m"));
+        assert_eq!(dominated.maximal, [candies("2e 1m")]);
+    }
+}
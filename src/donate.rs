@@ -0,0 +1,59 @@
+use std::fmt::Write;
+
+use crate::{kinds, names, Candies, CandyWorks};
+
+/// Finds a candy by its letter or by any of its names.
+pub fn parse_candy(input: &str) -> Option<usize> {
+    let input = input.trim();
+    kinds::active()
+        .letters()
+        .position(|l| input.len() == 1 && input.starts_with(l))
+        .or_else(|| names::active().find(input))
+}
+
+impl CandyWorks {
+    /// The explored state holding the most of one candy, reached in the
+    /// fewest trades when several hold as many.
+    pub fn most_of(&self, candy: usize) -> Option<usize> {
+        let depths = self.depths();
+        self.combinations
+            .states()
+            .enumerate()
+            .max_by_key(|(i, candies)| {
+                (
+                    candies.get_by_index(candy),
+                    std::cmp::Reverse(depths[*i]),
+                    std::cmp::Reverse(*i),
+                )
+            })
+            .map(|(i, _)| i)
+    }
+}
+
+/// The route converting the whole explored inventory into as many of
+/// `candy` as possible as text, with the reserved candies shown but left
+/// alone.
+pub fn plan(candy_works: &CandyWorks, candy: usize, reserved: Candies) -> String {
+    let mut out = String::new();
+    let Some(index) = candy_works.most_of(candy) else {
+        writeln!(out, "No combinations found").unwrap();
+        return out;
+    };
+    let route = candy_works.route_to(index);
+    out.push_str(&candy_works.format_route(candy_works.candies.with(&reserved), &route));
+    let donation = candy_works.combinations.state(index).get_by_index(candy);
+    let names = names::active();
+    writeln!(
+        out,
+        "Donation: {} {} after {} trades",
+        donation,
+        if donation.get() == 1 {
+            names.singular(candy)
+        } else {
+            names.plural(candy)
+        },
+        route.len()
+    )
+    .unwrap();
+    out
+}
@@ -0,0 +1,89 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use crate::{Candies, CandyWorks};
+
+/// Which explored states `CandyWorks::export_dot` draws.
+#[derive(Clone, Debug, Default)]
+pub struct DotOptions {
+    /// Only states at most this many trades from their start.
+    pub max_depth: Option<usize>,
+    /// Only states holding these candies and the states on the way to them.
+    pub target: Option<Candies>,
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl CandyWorks {
+    /// Writes the graph `export_dot` draws to the file `out`, or to
+    /// standard output without one.
+    pub fn write_dot(&self, out: Option<&Path>, options: &DotOptions) -> io::Result<()> {
+        match out {
+            Some(out) => {
+                let mut writer = io::BufWriter::new(fs::File::create(out)?);
+                self.export_dot(&mut writer, options)?;
+                writer.flush()
+            }
+            None => self.export_dot(&mut io::stdout().lock(), options),
+        }
+    }
+
+    /// Writes the explored states as a Graphviz graph: one node per state,
+    /// labelled with its candies, and an edge from the state each was first
+    /// reached from, labelled with the trade. States left out by `options`
+    /// are left out with their edges.
+    pub fn export_dot(&self, writer: &mut impl Write, options: &DotOptions) -> io::Result<()> {
+        let depths = self.depths();
+        let mut kept = depths
+            .iter()
+            .map(|&depth| options.max_depth.is_none_or(|max_depth| depth <= max_depth))
+            .collect::<Vec<_>>();
+        if let Some(target) = options.target {
+            // Parents come before their children, so marking backwards from
+            // the last state reaches every state on the way to a target.
+            let mut on_route = vec![false; kept.len()];
+            for index in (0..kept.len()).rev() {
+                if kept[index] && self.combinations.state(index).contains(&target) {
+                    on_route[index] = true;
+                }
+                if on_route[index] {
+                    if let Some((parent, _)) = self.combinations.parent(index) {
+                        on_route[parent] = true;
+                    }
+                }
+            }
+            kept = on_route;
+        }
+        writeln!(writer, "digraph candyworks {{")?;
+        for (index, (candies, parent)) in self.combinations.iter().enumerate() {
+            if !kept[index] {
+                continue;
+            }
+            writeln!(
+                writer,
+                "  {} [label={}];",
+                quote(&candies.id_label()),
+                quote(&candies.compact())
+            )?;
+            if let Some((parent, trade)) = parent {
+                writeln!(
+                    writer,
+                    "  {} -> {} [label={}];",
+                    quote(&self.combinations.state(parent).id_label()),
+                    quote(&candies.id_label()),
+                    quote(&format!(
+                        "{} -> {}",
+                        trade.give.compact(),
+                        trade.receive.compact()
+                    ))
+                )?;
+            }
+        }
+        writeln!(writer, "}}")
+    }
+}
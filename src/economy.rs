@@ -0,0 +1,301 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::{
+    conservation,
+    count::Count,
+    display_side,
+    kinds::{self, MAX_KINDS},
+    names, Candies, CandyWorks, Trade,
+};
+
+const N: usize = MAX_KINDS;
+
+/// The most extra candies a combination of other trades may need to stand
+/// in for a trade, and the most trades it may take.
+const MAX_EXTRA: i32 = 3;
+const MAX_STEPS: usize = 6;
+
+/// The best number of `j` obtained per `i` given up, by chaining trades
+/// that take a single kind of candy. Trades taking several kinds cannot
+/// be priced per candy and are left out.
+fn conversion_rates(trades: &[Trade]) -> [[f64; N]; N] {
+    let mut rates = [[0.0_f64; N]; N];
+    for trade in trades {
+        let gives = (0..kinds::count())
+            .filter(|&i| trade.give.get_by_index(i) > Count::ZERO)
+            .collect::<Vec<_>>();
+        let [i] = gives[..] else {
+            continue;
+        };
+        for (j, rate) in rates[i].iter_mut().enumerate() {
+            let received = trade.receive.get_by_index(j) - trade.give.get_by_index(j);
+            *rate = rate.max(received.get() as f64 / trade.give.get_by_index(i).get() as f64);
+        }
+    }
+    for k in 0..kinds::count() {
+        for i in 0..kinds::count() {
+            for j in 0..kinds::count() {
+                rates[i][j] = rates[i][j].max(rates[i][k] * rates[k][j]);
+            }
+        }
+    }
+    // A loop returning more than it takes can be repeated up to the cap,
+    // so any conversion passing through it has no fixed best rate.
+    let rounds = rates;
+    for k in (0..kinds::count()).filter(|&k| rounds[k][k] > 1.0) {
+        for i in 0..kinds::count() {
+            for j in 0..kinds::count() {
+                if rounds[i][k] > 0.0 && rounds[k][j] > 0.0 {
+                    rates[i][j] = f64::INFINITY;
+                }
+            }
+        }
+    }
+    rates
+}
+
+/// Whether `b` is always at least as good as `a`: it asks for no more of
+/// any candy and gives no less of any.
+fn dominates(b: &Trade, a: &Trade) -> bool {
+    b != a && a.give.contains(&b.give) && b.receive.contains(&a.receive)
+}
+
+/// The combination of up to `MAX_STEPS` of `others` turning `start`
+/// within the cap into an inventory holding `wanted` that falls least
+/// short of `baseline`, with what it falls short by; it may only fall
+/// short in candies `start` holds.
+fn composition(
+    others: &[Trade],
+    cap: u32,
+    start: Candies,
+    wanted: &Candies,
+    baseline: &Candies,
+) -> Option<(Candies, Vec<Trade>)> {
+    let mut previous = HashMap::from([(start, None)]);
+    let mut queue = VecDeque::from([(start, 0)]);
+    let mut best: Option<(Candies, Candies)> = None;
+    while let Some((candies, steps)) = queue.pop_front() {
+        if steps > 0 && candies.contains(wanted) {
+            let mut short = Candies::none();
+            for i in 0..kinds::count() {
+                short.add_by_index(
+                    i,
+                    (baseline.get_by_index(i) - candies.get_by_index(i)).max(Count::ZERO),
+                );
+            }
+            let paid = (0..kinds::count()).all(|i| {
+                short.get_by_index(i) == Count::ZERO || start.get_by_index(i) > Count::ZERO
+            });
+            if paid && best.is_none_or(|(least, _)| short.total() < least.total()) {
+                best = Some((short, candies));
+            }
+        }
+        if steps == MAX_STEPS {
+            continue;
+        }
+        for trade in others {
+            if let Some(next) = candies.trade(trade) {
+                if next.total() <= Count::new(cap as i32) && !previous.contains_key(&next) {
+                    previous.insert(next, Some((*trade, candies)));
+                    queue.push_back((next, steps + 1));
+                }
+            }
+        }
+    }
+    let (short, end) = best?;
+    let mut route = Vec::new();
+    let mut current = end;
+    while let Some(Some((trade, before))) = previous.get(&current).copied() {
+        route.push(trade);
+        current = before;
+    }
+    route.reverse();
+    Some((short, route))
+}
+
+/// Every inventory of exactly `total` candies of the `kinds` given.
+fn extras(total: i32, kinds: &[usize]) -> Vec<Candies> {
+    let mut all = vec![Candies::none()];
+    for (n, &i) in kinds.iter().enumerate() {
+        all = all
+            .into_iter()
+            .flat_map(|candies| {
+                let left = total - candies.total().get();
+                let counts = if n == kinds.len() - 1 {
+                    left..=left
+                } else {
+                    0..=left
+                };
+                counts.map(move |count| {
+                    let mut more = candies;
+                    more.add_by_index(i, Count::new(count));
+                    more
+                })
+            })
+            .collect();
+    }
+    all
+}
+
+/// How `trade` can be made from the other trades at the least extra cost:
+/// starting from its give side and up to `MAX_EXTRA` more of the candies
+/// it gives, the trades ending up with its receive side and how many more
+/// of those candies they use up than the trade itself.
+fn reproduction(candy_works: &CandyWorks, trade: &Trade) -> Option<(Candies, Vec<Trade>)> {
+    let others = candy_works
+        .trades
+        .iter()
+        .filter(|other| *other != trade)
+        .copied()
+        .collect::<Vec<_>>();
+    let given = (0..kinds::count())
+        .filter(|&i| trade.give.get_by_index(i) > Count::ZERO)
+        .collect::<Vec<_>>();
+    let mut best: Option<(Candies, Vec<Trade>)> = None;
+    for total in 0..=MAX_EXTRA {
+        for extra in extras(total, &given) {
+            let found = composition(
+                &others,
+                candy_works.max_candies,
+                trade.give.with(&extra),
+                &trade.receive,
+                &trade.receive.with(&extra),
+            );
+            if let Some((cost, route)) = found {
+                let better = best.as_ref().is_none_or(|(least, shortest)| {
+                    (cost.total(), route.len()) < (least.total(), shortest.len())
+                });
+                if better && cost.total().get() <= MAX_EXTRA {
+                    best = Some((cost, route));
+                }
+            }
+        }
+        if best
+            .as_ref()
+            .is_some_and(|(cost, _)| cost.total().get() <= total)
+        {
+            break;
+        }
+    }
+    best
+}
+
+/// Characterizes the trades without a target: what each candy converts
+/// into at best, loops that multiply candies, sums of candies no trade
+/// changes, trades that another trade beats outright or a few others make
+/// just as well, and candies the inventory can never turn into.
+/// `candy_works` is expected to be explored from the inventory.
+pub fn report(candy_works: &CandyWorks, custom_trades: &[Trade]) {
+    let names = names::active();
+    let rates = conversion_rates(&candy_works.trades);
+    println!("Best conversion rates (row candies into column candies):");
+    print!("{:>10}", "");
+    for j in 0..kinds::count() {
+        print!("{:>10}", names.plural(j));
+    }
+    println!();
+    for (i, row) in rates.iter().take(kinds::count()).enumerate() {
+        print!("{:>10}", names.plural(i));
+        for (j, rate) in row.iter().take(kinds::count()).enumerate() {
+            if i == j || *rate == 0.0 {
+                print!("{:>10}", "-");
+            } else if rate.is_infinite() {
+                print!("{:>10}", "unbounded");
+            } else {
+                print!("{:>10.3}", rate);
+            }
+        }
+        println!();
+    }
+
+    println!("\nArbitrage:");
+    let loops = (0..kinds::count())
+        .filter(|&i| rates[i][i].is_infinite())
+        .collect::<Vec<_>>();
+    if loops.is_empty() {
+        println!("  none, no chain of trades returns more of a candy than it took");
+    }
+    for i in loops {
+        println!(
+            "  trading {} around in a loop returns more than were given",
+            names.plural(i)
+        );
+    }
+
+    println!("\nConservation laws:");
+    let laws = conservation::laws(&candy_works.trades);
+    if laws.is_empty() {
+        println!("  none, no weighted sum of the candies is kept by every trade");
+    }
+    for law in &laws {
+        println!("  {}", law.describe(&candy_works.candies));
+    }
+
+    println!("\nDominated trades:");
+    let mut dominated = 0;
+    for a in custom_trades {
+        if let Some(b) = candy_works.trades.iter().find(|b| dominates(b, a)) {
+            println!("  `{}` is never better than `{}`", a, b);
+            dominated += 1;
+        }
+    }
+    if dominated == 0 {
+        println!("  none");
+    }
+
+    println!("\nRedundant trades:");
+    let mut redundant = 0;
+    for trade in custom_trades {
+        let Some((cost, route)) = reproduction(candy_works, trade) else {
+            continue;
+        };
+        let steps = route
+            .iter()
+            .map(|step| {
+                format!(
+                    "{} -> {}",
+                    display_side(&step.give),
+                    display_side(&step.receive)
+                )
+            })
+            .collect::<Vec<_>>();
+        println!(
+            "  `{}` can be made with {} other trade{}{}: {}",
+            trade,
+            route.len(),
+            if route.len() == 1 { "" } else { "s" },
+            if cost == Candies::none() {
+                String::new()
+            } else {
+                format!(" at a cost of {} more", display_side(&cost))
+            },
+            steps.join(", then ")
+        );
+        redundant += 1;
+    }
+    if redundant == 0 {
+        println!(
+            "  none, no trade can be made with up to {} other trades at a cost of {} candies or less",
+            MAX_STEPS, MAX_EXTRA
+        );
+    }
+
+    println!("\nUnreachable candies:");
+    let unreachable = (0..kinds::count())
+        .filter(|&i| {
+            candy_works
+                .combinations
+                .states()
+                .all(|candies| candies.get_by_index(i) == Count::ZERO)
+        })
+        .collect::<Vec<_>>();
+    if unreachable.is_empty() {
+        println!("  none, the inventory can get some of every candy");
+    }
+    for i in unreachable {
+        println!(
+            "  no {} can ever be obtained from the inventory",
+            names.plural(i)
+        );
+    }
+}
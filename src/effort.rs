@@ -0,0 +1,56 @@
+use std::str::FromStr;
+
+use crate::Trade;
+
+/// Estimates the real-world work a route takes, to choose between routes
+/// that are otherwise equally good.
+#[derive(Clone, Debug)]
+pub struct EffortModel {
+    /// Clicks spent on every trade.
+    pub trade: f64,
+    /// Extra clicks whenever a trade differs from the one before it, for
+    /// picking a different offer.
+    pub switch: f64,
+}
+
+impl Default for EffortModel {
+    fn default() -> Self {
+        EffortModel {
+            trade: 1.0,
+            switch: 2.0,
+        }
+    }
+}
+
+impl FromStr for EffortModel {
+    type Err = String;
+
+    /// Parses `trade=1,switch=2`; omitted costs keep their default.
+    fn from_str(input: &str) -> Result<EffortModel, String> {
+        let mut model = EffortModel::default();
+        for part in input.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| format!("expected `key=value`, found `{}`", part))?;
+            let value = value
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|value| *value >= 0.0)
+                .ok_or_else(|| format!("`{}` must be a non-negative number", key.trim()))?;
+            match key.trim() {
+                "trade" => model.trade = value,
+                "switch" => model.switch = value,
+                key => return Err(format!("unknown effort cost `{}`", key)),
+            }
+        }
+        Ok(model)
+    }
+}
+
+impl EffortModel {
+    pub fn effort(&self, route: &[Trade]) -> f64 {
+        let switches = route.windows(2).filter(|pair| pair[0] != pair[1]).count();
+        self.trade * route.len() as f64 + self.switch * switches as f64
+    }
+}
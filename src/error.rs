@@ -0,0 +1,269 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    count::Count,
+    display_side, kinds,
+    notes::Notes,
+    route::{Route, RouteError},
+    scenario::{Scenario, ScenarioError},
+    BuildError, Candies, CandyWorks, ParseCandiesError,
+};
+
+/// Why an operation on a problem failed, for programs embedding the crate
+/// that would rather handle the failure than have it panic or print.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CandyWorksError {
+    /// Candies, a trade or a scenario could not be read.
+    Parse(String),
+    /// The problem cannot be explored as given.
+    Build(BuildError),
+    /// The reserved candies are more than the inventory holds.
+    Reserve {
+        reserved: Candies,
+        inventory: Candies,
+    },
+    /// The target holds more candies than the cap lets any state hold.
+    OverCap { target: Candies, cap: u32 },
+    /// The target holds more of the kind at index `kind` than its own cap
+    /// lets any state hold.
+    OverKindCap {
+        target: Candies,
+        kind: usize,
+        cap: Count,
+    },
+    /// A route was asked of a graph not explored yet.
+    Unexplored,
+    /// No explored state holds the target.
+    Unreachable(Candies),
+    /// A route no longer replays from the inventory.
+    Route(RouteError),
+    /// The file at `path` could not be read or written.
+    File { path: PathBuf, reason: String },
+}
+
+impl CandyWorksError {
+    /// Why the file at `path` could not be read or written.
+    pub fn file(path: &Path, reason: impl fmt::Display) -> CandyWorksError {
+        CandyWorksError::File {
+            path: path.to_path_buf(),
+            reason: reason.to_string(),
+        }
+    }
+
+    /// The exit status the CLI ends with: 3 for a target without a route,
+    /// as `explore` and `route` exit with, and 1 for every other error.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CandyWorksError::OverCap { .. }
+            | CandyWorksError::OverKindCap { .. }
+            | CandyWorksError::Unreachable(_) => 3,
+            _ => 1,
+        }
+    }
+}
+
+impl fmt::Display for CandyWorksError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CandyWorksError::Parse(err) => write!(f, "{}", err),
+            CandyWorksError::Build(err) => write!(f, "{}", err),
+            CandyWorksError::Reserve {
+                reserved,
+                inventory,
+            } => write!(
+                f,
+                "cannot reserve ({}), the inventory only has ({})",
+                display_side(reserved),
+                display_side(inventory)
+            ),
+            CandyWorksError::OverCap { target, cap } => write!(
+                f,
+                "({}) holds more than the cap of {} allows",
+                display_side(target),
+                cap
+            ),
+            CandyWorksError::OverKindCap { target, kind, cap } => write!(
+                f,
+                "({}) holds more {} than their cap of {} allows",
+                display_side(target),
+                kinds::active().get(*kind).plural,
+                cap
+            ),
+            CandyWorksError::Unexplored => write!(f, "the graph has not been explored"),
+            CandyWorksError::Unreachable(target) => {
+                write!(f, "no route reaches ({})", display_side(target))
+            }
+            CandyWorksError::Route(err) => write!(f, "{}", err),
+            CandyWorksError::File { path, reason } => {
+                write!(f, "{}: {}", path.display(), reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CandyWorksError {}
+
+impl From<ParseCandiesError> for CandyWorksError {
+    fn from(err: ParseCandiesError) -> CandyWorksError {
+        CandyWorksError::Parse(err.to_string())
+    }
+}
+
+impl From<ScenarioError> for CandyWorksError {
+    fn from(err: ScenarioError) -> CandyWorksError {
+        CandyWorksError::Parse(err.to_string())
+    }
+}
+
+impl From<BuildError> for CandyWorksError {
+    fn from(err: BuildError) -> CandyWorksError {
+        CandyWorksError::Build(err)
+    }
+}
+
+impl From<RouteError> for CandyWorksError {
+    fn from(err: RouteError) -> CandyWorksError {
+        CandyWorksError::Route(err)
+    }
+}
+
+impl CandyWorks {
+    /// The route `find_optimal_route` finds, or why there is none: the
+    /// graph is not explored, the target cannot fit, or no explored state
+    /// holds it.
+    pub fn try_route(&self, target: Candies) -> Result<Route, CandyWorksError> {
+        if let Some(err) = self.cap_error(target) {
+            return Err(err);
+        }
+        if self.combinations.is_empty() {
+            return Err(CandyWorksError::Unexplored);
+        }
+        self.find_optimal_route(target)
+            .ok_or(CandyWorksError::Unreachable(target))
+    }
+}
+
+impl CandyWorks {
+    /// Why no state can hold `target`, by whichever check of `fits` it
+    /// fails: the cap on the total first, then the caps of the kinds.
+    /// `None` if it fits.
+    pub fn cap_error(&self, target: Candies) -> Option<CandyWorksError> {
+        if target.total() > Count::new(self.max_candies as i32) {
+            return Some(CandyWorksError::OverCap {
+                target,
+                cap: self.max_candies,
+            });
+        }
+        let kind_caps = self.kind_caps.as_ref()?;
+        let kind = kind_caps.exceeded(&target)?;
+        Some(CandyWorksError::OverKindCap {
+            target,
+            kind,
+            cap: kind_caps.limit(kind).unwrap(),
+        })
+    }
+}
+
+impl Scenario {
+    /// The problem the scenario describes, checked by `CandyWorks::builder`,
+    /// with the reserved candies set aside and the cap lowered by as much,
+    /// as the CLI plans.
+    pub fn problem(&self) -> Result<CandyWorks, CandyWorksError> {
+        let inventory = self
+            .inventory
+            .without(&self.reserved)
+            .ok_or(CandyWorksError::Reserve {
+                reserved: self.reserved,
+                inventory: self.inventory,
+            })?;
+        let mut builder = CandyWorks::builder(inventory)
+            .cap(self.cap.saturating_sub(self.reserved.total().get() as u32))
+            .trades(self.trades.iter().copied())
+            .preset(self.preset.clone())
+            .standard_trades(self.standard_trades)
+            .use_limits(self.use_limits())
+            .notes(Notes::new(self));
+        if let Some(kind_caps) = &self.kind_caps {
+            builder = builder.kind_caps(kind_caps.less(&self.reserved));
+        }
+        Ok(builder.build()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kind_caps::KindCaps;
+    use crate::preset::Preset;
+    use crate::test_support::{candies, trade};
+
+    #[test]
+    fn cap_error_names_the_cap_that_blocks() {
+        let mut candy_works = CandyWorks::builder(candies("3e 3w"))
+            .cap(6)
+            .kind_caps(KindCaps::of(&candies("4e")).unwrap())
+            .build()
+            .unwrap();
+        assert_eq!(candy_works.cap_error(candies("4e 2w")), None);
+        assert_eq!(
+            candy_works.cap_error(candies("5e")),
+            Some(CandyWorksError::OverKindCap {
+                target: candies("5e"),
+                kind: 0,
+                cap: Count::new(4)
+            })
+        );
+        assert_eq!(
+            candy_works.cap_error(candies("7w")),
+            Some(CandyWorksError::OverCap {
+                target: candies("7w"),
+                cap: 6
+            })
+        );
+        candy_works.explore();
+        assert_eq!(
+            candy_works
+                .try_route(candies("5e"))
+                .unwrap_err()
+                .to_string(),
+            "(5 eggs) holds more eggs than their cap of 4 allows"
+        );
+    }
+
+    #[test]
+    fn problems_leave_out_the_standard_trades_on_their_own() {
+        let mut scenario =
+            Scenario::parse("cap = 10\ninventory = 3e\ntrade = 1e -> 1m\ntarget = 1m\n").unwrap();
+        let offered = scenario.problem().unwrap();
+        scenario.standard_trades = false;
+        let own = scenario.problem().unwrap();
+        assert_eq!(own.trades, scenario.trades);
+        assert!(offered.trades.len() > own.trades.len());
+        assert_eq!(scenario.problem().unwrap().trades, own.trades);
+        scenario.standard_trades = true;
+        assert_eq!(scenario.problem().unwrap().trades, offered.trades);
+    }
+
+    #[test]
+    fn problems_offer_the_standard_trades_of_their_preset() {
+        let mut scenario = Scenario::parse("cap = 10\ninventory = 4e\ntarget = 2w\n").unwrap();
+        let reaches = |scenario: &Scenario| {
+            let mut problem = scenario.problem().unwrap();
+            problem.explore();
+            problem.try_route(candies("2w")).is_ok()
+        };
+        assert!(!reaches(&scenario));
+        scenario.preset = Preset {
+            ratio: 2,
+            specials: vec![trade("1e -> 1c")],
+            ..Preset::builtin()
+        };
+        let problem = scenario.problem().unwrap();
+        assert!(problem.trades.contains(&trade("2e -> 1w")));
+        assert_eq!(problem.trades.last(), Some(&trade("1e -> 1c")));
+        assert!(reaches(&scenario));
+    }
+}
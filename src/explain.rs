@@ -0,0 +1,86 @@
+use std::fmt::Write;
+
+use crate::{count::Count, kinds, Candies, CandyWorks};
+
+fn distance(a: &Candies, b: &Candies) -> i32 {
+    (0..kinds::count())
+        .map(|i| (a.get_by_index(i) - b.get_by_index(i)).get().abs())
+        .sum()
+}
+
+fn difference(expected: &Candies, actual: &Candies) -> String {
+    let mut more = Candies::none();
+    let mut fewer = Candies::none();
+    for i in 0..kinds::count() {
+        let delta = actual.get_by_index(i) - expected.get_by_index(i);
+        if delta > Count::ZERO {
+            more.add_by_index(i, delta);
+        } else {
+            fewer.add_by_index(i, -delta);
+        }
+    }
+    match (more == Candies::none(), fewer == Candies::none()) {
+        (false, false) => format!("you have{} more and{} fewer", more, fewer),
+        (false, true) => format!("you have{} more", more),
+        (true, false) => format!("you have{} fewer", fewer),
+        (true, true) => "no difference".to_string(),
+    }
+}
+
+pub fn explain(candy_works: &CandyWorks, current: Candies, suggestions: usize) -> String {
+    let mut out = String::new();
+    if let Some(index) = candy_works
+        .combinations
+        .states()
+        .position(|candies| candies == current)
+    {
+        let route = candy_works.route_to(index);
+        writeln!(out, "Reachable in {} trades:", route.len()).unwrap();
+        out.push_str(&candy_works.format_route(candy_works.root_of(index), &route));
+        return out;
+    }
+
+    writeln!(
+        out,
+        "({}) cannot be reached from ({}) with these trades",
+        current.display(true),
+        candy_works.candies.display(true)
+    )
+    .unwrap();
+    if !candy_works.fits(&current) {
+        writeln!(
+            out,
+            "It holds {} candies, more than the cap of {}",
+            current.total(),
+            candy_works.max_candies
+        )
+        .unwrap();
+    }
+    let mut nearest = candy_works
+        .combinations
+        .states()
+        .enumerate()
+        .map(|(index, candies)| (distance(&candies, &current), index))
+        .collect::<Vec<_>>();
+    nearest.sort();
+    if nearest.is_empty() {
+        return out;
+    }
+    writeln!(
+        out,
+        "Closest reachable inventories, in case a trade or count was logged wrong:"
+    )
+    .unwrap();
+    for (_, index) in nearest.into_iter().take(suggestions) {
+        let candies = candy_works.combinations.state(index);
+        writeln!(
+            out,
+            "  ({}) in {} trades, {}",
+            candies.display(true),
+            candy_works.route_to(index).len(),
+            difference(&candies, &current)
+        )
+        .unwrap();
+    }
+    out
+}
@@ -0,0 +1,318 @@
+use std::{
+    fmt::{self, Write},
+    sync::Mutex,
+};
+
+use clap::ValueEnum;
+
+use crate::{
+    display_side, dot::DotOptions, kinds, names, route::Route, Candies, CandyWorks, Statistics,
+    Trade,
+};
+
+/// What `export` writes.
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ExportKind {
+    /// The route to a target.
+    Route,
+    /// The statistics `stats` prints.
+    Stats,
+    /// Every explored state with the trade first reaching it.
+    Graph,
+}
+
+impl fmt::Display for ExportKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            ExportKind::Route => "routes",
+            ExportKind::Stats => "statistics",
+            ExportKind::Graph => "graphs",
+        })
+    }
+}
+
+/// An output format for `export --format`. A format need not write every
+/// kind of output: the ones it leaves at their default answer `None`, and
+/// `export` says the format cannot write them.
+pub trait Exporter: Send + Sync {
+    /// The name `--format` chooses the format by.
+    fn name(&self) -> &str;
+
+    /// A route found on `candy_works`, whose use limits and notes it may
+    /// show.
+    fn route(&self, _route: &Route, _candy_works: &CandyWorks) -> Option<String> {
+        None
+    }
+
+    fn stats(&self, _stats: &Statistics) -> Option<String> {
+        None
+    }
+
+    /// The explored states, each with its parent and the trade from it.
+    fn graph(&self, _candy_works: &CandyWorks) -> Option<String> {
+        None
+    }
+}
+
+static REGISTRY: Mutex<Vec<&'static dyn Exporter>> = Mutex::new(Vec::new());
+
+/// Makes `exporter` available to `--format` for the rest of the run, in
+/// place of any format of the same name. Formats have to be registered
+/// before the command line is read.
+pub fn register(exporter: impl Exporter + 'static) {
+    let exporter: &'static dyn Exporter = Box::leak(Box::new(exporter));
+    let mut registry = REGISTRY.lock().unwrap();
+    registry.retain(|known| known.name() != exporter.name());
+    registry.push(exporter);
+}
+
+/// Registers the formats candyworks comes with: text, json, csv and dot.
+pub fn register_builtin() {
+    register(Text);
+    register(Json);
+    register(Csv);
+    register(Dot);
+}
+
+/// The registered format called `name`, as `--format` reads it.
+pub fn find(name: &str) -> Result<&'static dyn Exporter, String> {
+    let registry = REGISTRY.lock().unwrap();
+    registry
+        .iter()
+        .find(|exporter| exporter.name() == name)
+        .copied()
+        .ok_or_else(|| {
+            let names = registry
+                .iter()
+                .map(|exporter| exporter.name())
+                .collect::<Vec<_>>();
+            format!("unknown format `{}`, expected {}", name, names.join(", "))
+        })
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// `stats` as the JSON object the json format exports.
+pub fn stats_json(stats: &Statistics) -> String {
+    let depths = stats
+        .depth_counts
+        .iter()
+        .map(|count| count.to_string())
+        .collect::<Vec<_>>();
+    format!(
+        "{{\"combinations\":{},\"min_candies\":{},\"max_candies\":{},\"max_trades\":{},\
+         \"states_by_trades\":[{}],\"edges\":{},\"max_branching\":{},\"dead_ends\":{},\
+         \"exploration\":{}}}",
+        stats.combinations,
+        stats.min_candies,
+        stats.max_candies,
+        stats.max_trades,
+        depths.join(","),
+        stats.edges,
+        stats.max_branching,
+        stats.dead_ends,
+        stats
+            .exploration
+            .map_or("null".to_string(), |exploration| quote(
+                &exploration.to_string()
+            ))
+    )
+}
+
+/// The states of `candy_works` with their parent and the trade from it.
+fn edges(
+    candy_works: &CandyWorks,
+) -> impl Iterator<Item = (Candies, Option<(Candies, Trade)>)> + '_ {
+    candy_works.combinations.iter().map(|(candies, parent)| {
+        (
+            candies,
+            parent.map(|(index, trade)| (candy_works.combinations.state(index), trade)),
+        )
+    })
+}
+
+/// What the CLI prints.
+struct Text;
+
+impl Exporter for Text {
+    fn name(&self) -> &str {
+        "text"
+    }
+
+    fn route(&self, route: &Route, candy_works: &CandyWorks) -> Option<String> {
+        Some(candy_works.format_route(route.start(), route.trades()))
+    }
+
+    fn stats(&self, stats: &Statistics) -> Option<String> {
+        Some(format!("{}\n", stats))
+    }
+
+    fn graph(&self, candy_works: &CandyWorks) -> Option<String> {
+        let mut out = String::new();
+        for (candies, parent) in edges(candy_works) {
+            match parent {
+                Some((from, trade)) => writeln!(
+                    out,
+                    "({}) from ({}) by {} -> {}",
+                    candies.display(true),
+                    from.display(true),
+                    display_side(&trade.give),
+                    display_side(&trade.receive)
+                ),
+                None => writeln!(out, "({}) to start with", candies.display(true)),
+            }
+            .unwrap();
+        }
+        Some(out)
+    }
+}
+
+struct Json;
+
+impl Exporter for Json {
+    fn name(&self) -> &str {
+        "json"
+    }
+
+    fn route(&self, route: &Route, candy_works: &CandyWorks) -> Option<String> {
+        let steps = route
+            .trades()
+            .iter()
+            .map(|trade| {
+                format!(
+                    "{{\"give\":{},\"receive\":{}{}}}",
+                    quote(&trade.give.compact()),
+                    quote(&trade.receive.compact()),
+                    candy_works
+                        .notes
+                        .get(trade)
+                        .map_or(String::new(), |note| format!(",\"note\":{}", quote(note)))
+                )
+            })
+            .collect::<Vec<_>>();
+        Some(format!("[{}]\n", steps.join(",")))
+    }
+
+    fn stats(&self, stats: &Statistics) -> Option<String> {
+        Some(format!("{}\n", stats_json(stats)))
+    }
+
+    fn graph(&self, candy_works: &CandyWorks) -> Option<String> {
+        let states = edges(candy_works)
+            .map(|(candies, parent)| {
+                let parent = parent.map_or(String::new(), |(from, trade)| {
+                    format!(
+                        ",\"parent\":{},\"give\":{},\"receive\":{}",
+                        quote(&from.id_label()),
+                        quote(&trade.give.compact()),
+                        quote(&trade.receive.compact())
+                    )
+                });
+                format!(
+                    "{{\"id\":{},\"candies\":{}{}}}",
+                    quote(&candies.id_label()),
+                    quote(&candies.compact()),
+                    parent
+                )
+            })
+            .collect::<Vec<_>>();
+        Some(format!("{{\"states\":[{}]}}\n", states.join(",")))
+    }
+}
+
+/// One row per step, statistic or state, with a header row.
+struct Csv;
+
+impl Exporter for Csv {
+    fn name(&self) -> &str {
+        "csv"
+    }
+
+    fn route(&self, route: &Route, candy_works: &CandyWorks) -> Option<String> {
+        let names = names::active();
+        let mut out = String::from("step,give,receive");
+        for i in 0..kinds::count() {
+            write!(out, ",{}", names.plural(i)).unwrap();
+        }
+        out.push_str(",note\n");
+        let mut candies = route.start();
+        for (n, trade) in route.trades().iter().enumerate() {
+            candies = candies.trade(trade).unwrap();
+            write!(
+                out,
+                "{},{},{}",
+                n + 1,
+                trade.give.compact(),
+                trade.receive.compact()
+            )
+            .unwrap();
+            for i in 0..kinds::count() {
+                write!(out, ",{}", candies.get_by_index(i)).unwrap();
+            }
+            let note = candy_works.notes.get(trade).unwrap_or_default();
+            writeln!(out, ",\"{}\"", note.replace('"', "\"\"")).unwrap();
+        }
+        Some(out)
+    }
+
+    fn stats(&self, stats: &Statistics) -> Option<String> {
+        let mut out = String::from("statistic,value\n");
+        for (name, value) in [
+            ("combinations", stats.combinations),
+            ("min_candies", stats.min_candies.get() as usize),
+            ("max_candies", stats.max_candies.get() as usize),
+            ("max_trades", stats.max_trades),
+            ("edges", stats.edges),
+            ("max_branching", stats.max_branching),
+            ("dead_ends", stats.dead_ends),
+        ] {
+            writeln!(out, "{},{}", name, value).unwrap();
+        }
+        for (depth, count) in stats.depth_counts.iter().enumerate() {
+            writeln!(out, "states_after_{}_trades,{}", depth, count).unwrap();
+        }
+        if let Some(exploration) = stats.exploration {
+            writeln!(out, "exploration,{}", exploration).unwrap();
+        }
+        Some(out)
+    }
+
+    fn graph(&self, candy_works: &CandyWorks) -> Option<String> {
+        let mut out = String::from("id,candies,parent,give,receive\n");
+        for (candies, parent) in edges(candy_works) {
+            write!(out, "{},{}", candies.id_label(), candies.compact()).unwrap();
+            match parent {
+                Some((from, trade)) => writeln!(
+                    out,
+                    ",{},{},{}",
+                    from.id_label(),
+                    trade.give.compact(),
+                    trade.receive.compact()
+                ),
+                None => writeln!(out, ",,,"),
+            }
+            .unwrap();
+        }
+        Some(out)
+    }
+}
+
+/// Graphviz, for drawing the explored tree; routes and statistics have no
+/// drawing.
+struct Dot;
+
+impl Exporter for Dot {
+    fn name(&self) -> &str {
+        "dot"
+    }
+
+    fn graph(&self, candy_works: &CandyWorks) -> Option<String> {
+        let mut out = Vec::new();
+        candy_works
+            .export_dot(&mut out, &DotOptions::default())
+            .unwrap();
+        Some(String::from_utf8(out).unwrap())
+    }
+}
@@ -0,0 +1,69 @@
+use std::{collections::HashMap, fmt::Write};
+
+use crate::{count::Count, Candies, CandyWorks, Trade};
+
+/// For each step of `route` as text, the best other trade that could be
+/// made instead and what switching to it costs: how many fewer candies
+/// the best plan from there ends with, and how many more trades it takes.
+pub fn format(candy_works: &CandyWorks, route: &[Trade], target: Candies) -> String {
+    let mut out = String::new();
+    if route.is_empty() {
+        return out;
+    }
+    let best = candy_works.best_reachable(target);
+    let index = candy_works
+        .combinations
+        .states()
+        .enumerate()
+        .map(|(i, candies)| (candies, i))
+        .collect::<HashMap<_, _>>();
+    let end = candy_works.candies.trade_all(route).unwrap().total();
+    writeln!(out, "Fallbacks:").unwrap();
+    let mut before = candy_works.candies;
+    for (step, trade) in route.iter().enumerate() {
+        let fallback = candy_works
+            .trades
+            .iter()
+            .filter(|other| *other != trade)
+            .filter_map(|other| {
+                let after = before.trade(other)?;
+                let value = best[*index.get(&after)?]?;
+                Some((value, *other, after))
+            })
+            .fold(
+                None,
+                |found: Option<(Count, Trade, Candies)>, candidate| match found {
+                    Some(found) if found.0 >= candidate.0 => Some(found),
+                    _ => Some(candidate),
+                },
+            );
+        match fallback {
+            Some((value, other, after)) => {
+                let mut rest = candy_works.unexplored();
+                rest.candies = after;
+                rest.explore();
+                let trades = rest
+                    .find_optimal_route(target)
+                    .map_or(0, |rest| step + 1 + rest.len());
+                writeln!(
+                    out,
+                    "  step {}: instead of `{}`, `{}`: {} fewer candies at the end, {:+} trades",
+                    step + 1,
+                    trade.to_string().trim(),
+                    other.to_string().trim(),
+                    end - value,
+                    trades as i64 - route.len() as i64
+                )
+                .unwrap();
+            }
+            None => writeln!(
+                out,
+                "  step {}: no other trade still reaches the target",
+                step + 1
+            )
+            .unwrap(),
+        }
+        before = before.trade(trade).unwrap();
+    }
+    out
+}
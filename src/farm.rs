@@ -0,0 +1,241 @@
+use std::collections::HashMap;
+
+use crate::{count::Count, display_side, names, Candies, CandyWorks, Trade};
+
+/// Differences between loop means smaller than this are rounding.
+const EPSILON: f64 = 1e-9;
+
+/// The inventories reachable under the cap with the farmed candy taken out
+/// as it comes in, and the trades between them with how much of the candy
+/// each makes or spends.
+struct Graph {
+    states: Vec<Candies>,
+    edges: Vec<Vec<(usize, i32, Trade)>>,
+}
+
+fn only(candy: usize, count: Count) -> Candies {
+    let mut candies = Candies::none();
+    candies.add_by_index(candy, count);
+    candies
+}
+
+fn explore(candy_works: &CandyWorks, candy: usize) -> Graph {
+    let take_out = |candies: &Candies| {
+        candies
+            .without(&only(candy, candies.get_by_index(candy)))
+            .unwrap()
+    };
+    let trades = candy_works
+        .trades
+        .iter()
+        .map(|trade| {
+            let made = (trade.receive.get_by_index(candy) - trade.give.get_by_index(candy)).get();
+            let rest = Trade {
+                give: take_out(&trade.give),
+                receive: take_out(&trade.receive),
+            };
+            (rest, made, *trade)
+        })
+        .collect::<Vec<_>>();
+    let start = take_out(&candy_works.candies);
+    let mut index = HashMap::from([(start, 0)]);
+    let mut graph = Graph {
+        states: vec![start],
+        edges: Vec::new(),
+    };
+    let mut next = 0;
+    while next < graph.states.len() {
+        let candies = graph.states[next];
+        let mut edges = Vec::new();
+        for (rest, made, trade) in &trades {
+            let Some(after) = candies.trade(rest).filter(|after| candy_works.fits(after)) else {
+                continue;
+            };
+            let target = *index.entry(after).or_insert_with(|| {
+                graph.states.push(after);
+                graph.states.len() - 1
+            });
+            edges.push((target, *made, *trade));
+        }
+        graph.edges.push(edges);
+        next += 1;
+    }
+    graph
+}
+
+/// For every inventory, the trade to make for the best loop it can get
+/// into and that loop's mean, found by policy iteration (Howard's
+/// algorithm) over the inventories that are never stuck.
+fn best_policy(graph: &Graph) -> (Vec<Option<usize>>, Vec<f64>) {
+    let n = graph.states.len();
+    // An inventory whose every trade leads to a stuck one is stuck itself.
+    let mut alive = vec![true; n];
+    loop {
+        let stuck = (0..n)
+            .filter(|&u| alive[u] && !graph.edges[u].iter().any(|(v, ..)| alive[*v]))
+            .collect::<Vec<_>>();
+        if stuck.is_empty() {
+            break;
+        }
+        stuck.iter().for_each(|&u| alive[u] = false);
+    }
+    let mut policy = (0..n)
+        .map(|u| {
+            graph.edges[u]
+                .iter()
+                .enumerate()
+                .filter(|(_, (v, ..))| alive[*v])
+                .max_by_key(|(_, (_, made, _))| *made)
+                .map(|(e, _)| e)
+        })
+        .collect::<Vec<_>>();
+    let mut mean = vec![0.0; n];
+    let mut bias = vec![0.0; n];
+    loop {
+        // Each inventory follows its trade into exactly one loop: the loop's
+        // mean, and how far ahead of it the way there gets.
+        let mut done = vec![false; n];
+        for start in (0..n).filter(|&u| alive[u]) {
+            let mut path = Vec::new();
+            let mut on_path = HashMap::new();
+            let mut u = start;
+            while !done[u] && !on_path.contains_key(&u) {
+                on_path.insert(u, path.len());
+                path.push(u);
+                u = graph.edges[u][policy[u].unwrap()].0;
+            }
+            if let Some(&at) = on_path.get(&u) {
+                let cycle = &path[at..];
+                let made = cycle
+                    .iter()
+                    .map(|&v| graph.edges[v][policy[v].unwrap()].1 as f64)
+                    .sum::<f64>();
+                let lap_mean = made / cycle.len() as f64;
+                mean[u] = lap_mean;
+                bias[u] = 0.0;
+                done[u] = true;
+                for &v in cycle[1..].iter().rev() {
+                    let (next, made, _) = graph.edges[v][policy[v].unwrap()];
+                    mean[v] = lap_mean;
+                    bias[v] = made as f64 - lap_mean + bias[next];
+                    done[v] = true;
+                }
+                path.truncate(at);
+            }
+            for &v in path.iter().rev() {
+                let (next, made, _) = graph.edges[v][policy[v].unwrap()];
+                mean[v] = mean[next];
+                bias[v] = made as f64 - mean[next] + bias[next];
+                done[v] = true;
+            }
+        }
+
+        let mut changed = false;
+        for u in (0..n).filter(|&u| alive[u]) {
+            let current = policy[u].unwrap();
+            let live = || {
+                graph.edges[u]
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, (v, ..))| alive[*v])
+            };
+            // A better loop first, then a better way into the same one.
+            let reach = live()
+                .map(|(_, (v, ..))| mean[*v])
+                .fold(f64::NEG_INFINITY, f64::max);
+            let better = if reach > mean[u] + EPSILON {
+                live()
+                    .filter(|(_, (v, ..))| mean[*v] > reach - EPSILON)
+                    .map(|(e, (v, made, _))| (e, *made as f64 - reach + bias[*v]))
+                    .max_by(|a, b| a.1.total_cmp(&b.1))
+                    .map(|(e, _)| e)
+            } else {
+                live()
+                    .filter(|(_, (v, ..))| (mean[*v] - mean[u]).abs() <= EPSILON)
+                    .map(|(e, (v, made, _))| (e, *made as f64 - mean[u] + bias[*v]))
+                    .filter(|(_, value)| *value > bias[u] + EPSILON)
+                    .max_by(|a, b| a.1.total_cmp(&b.1))
+                    .map(|(e, _)| e)
+            };
+            if let Some(e) = better.filter(|&e| e != current) {
+                policy[u] = Some(e);
+                changed = true;
+            }
+        }
+        if !changed {
+            return (policy, mean);
+        }
+    }
+}
+
+fn print_trades(trades: &[Trade], first: usize) {
+    for (n, trade) in trades.iter().enumerate() {
+        println!(
+            "{:>3}. trade {} -> {}",
+            first + n,
+            display_side(&trade.give),
+            display_side(&trade.receive)
+        );
+    }
+}
+
+/// Finds the loop of trades that, repeated forever, makes the most of
+/// `candy` per trade: the loop of largest mean in the graph of
+/// inventories, with each trade weighted by how much of the candy it
+/// makes. The candy is taken out of the inventory as it comes in, sold,
+/// donated or stored, so the cap bounds the other candies.
+pub fn best_loop(candy_works: &CandyWorks, candy: usize) {
+    let graph = explore(candy_works, candy);
+    let (policy, mean) = best_policy(&graph);
+    let plural = names::active().plural(candy);
+    if policy[0].is_none() || mean[0] <= EPSILON {
+        println!(
+            "Explored {} inventories; no loop of trades keeps making {}",
+            graph.states.len(),
+            plural
+        );
+        return;
+    }
+
+    let mut path = Vec::new();
+    let mut visited = HashMap::from([(0, 0)]);
+    let mut state = 0;
+    let entry = loop {
+        let (next, made, trade) = graph.edges[state][policy[state].unwrap()];
+        path.push((made, trade));
+        state = next;
+        if let Some(&at) = visited.get(&state) {
+            break at;
+        }
+        visited.insert(state, path.len());
+    };
+    let (lead, lap) = path.split_at(entry);
+    let made = lap.iter().map(|(made, _)| made).sum::<i32>();
+    // The most of the candy the lap is behind at any point, which has to
+    // be at hand when a lap starts.
+    let (_, behind) = lap.iter().fold((0, 0), |(running, behind), (made, _)| {
+        (running + made, behind.max(-(running + made)))
+    });
+    let trades = |steps: &[(i32, Trade)]| steps.iter().map(|(_, t)| *t).collect::<Vec<_>>();
+
+    println!(
+        "Explored {} inventories; the best loop makes {} {} every {} trades ({:.3} per trade)",
+        graph.states.len(),
+        made,
+        plural,
+        lap.len(),
+        mean[0]
+    );
+    if !lead.is_empty() {
+        println!("To get into the loop:");
+        print_trades(&trades(lead), 1);
+    }
+    println!("Then repeat:");
+    print_trades(&trades(lap), lead.len() + 1);
+    if behind > 0 {
+        println!(
+            "A lap spends up to {} {} before it pays back, so keep that many at hand",
+            behind, plural
+        );
+    }
+}
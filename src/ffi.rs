@@ -0,0 +1,258 @@
+//! C bindings, built with the `ffi` feature, for tools outside Rust to
+//! plan with; `include/candyworks.h` declares them.
+//!
+//! A solver is created with its cap, given the inventory, trades and
+//! target as the scenario file writes them, and solved:
+//!
+//! ```c
+//! CandyWorksSolver *solver = candyworks_new(20);
+//! candyworks_set_inventory(solver, "6e");
+//! candyworks_add_trade(solver, "2e -> 1c");
+//! candyworks_set_target(solver, "3c");
+//! char *route = candyworks_solve(solver);
+//! if (route == NULL) {
+//!     puts(candyworks_last_error(solver));
+//! } else {
+//!     puts(route);
+//!     candyworks_free_string(route);
+//! }
+//! candyworks_free(solver);
+//! ```
+//!
+//! Functions taking text return 0 on success and -1 on failure, and
+//! `candyworks_last_error` says what failed until the next call setting
+//! up or solving the solver, which clears it first.
+
+use std::{
+    ffi::{c_char, c_int, CStr, CString},
+    ptr,
+};
+
+use crate::{Candies, CandyWorks, Trade};
+
+/// A problem being set up through the C bindings.
+pub struct CandyWorksSolver {
+    cap: u32,
+    inventory: Candies,
+    trades: Vec<Trade>,
+    target: Option<Candies>,
+    error: Option<CString>,
+}
+
+impl CandyWorksSolver {
+    fn fail(&mut self, message: String) -> c_int {
+        // Messages are built here and hold no NUL.
+        self.error = Some(CString::new(message).unwrap());
+        -1
+    }
+
+    fn solve(&self) -> Result<String, String> {
+        let target = self.target.ok_or("No target set")?;
+        let mut candy_works = CandyWorks::builder(self.inventory)
+            .cap(self.cap)
+            .trades(self.trades.iter().copied())
+            .build()
+            .map_err(|err| err.to_string())?;
+        candy_works.explore();
+        let route = candy_works
+            .find_optimal_route(target)
+            .ok_or("No route found")?;
+        Ok(candy_works.format_route(route.start(), route.trades()))
+    }
+}
+
+/// Reads the text at `text` for the solver to parse.
+///
+/// # Safety
+///
+/// `text` is null or a NUL-terminated string.
+unsafe fn text<'a>(text: *const c_char) -> Result<&'a str, String> {
+    if text.is_null() {
+        return Err("No text given".to_string());
+    }
+    CStr::from_ptr(text)
+        .to_str()
+        .map_err(|_| "The text is not UTF-8".to_string())
+}
+
+/// A solver with a cap of `cap` candies and an empty inventory, to be
+/// freed with `candyworks_free`.
+#[no_mangle]
+pub extern "C" fn candyworks_new(cap: u32) -> *mut CandyWorksSolver {
+    Box::into_raw(Box::new(CandyWorksSolver {
+        cap,
+        inventory: Candies::none(),
+        trades: Vec::new(),
+        target: None,
+        error: None,
+    }))
+}
+
+/// Sets the inventory to trade from, such as `3e 3w`.
+///
+/// # Safety
+///
+/// `solver` comes from `candyworks_new` and is not yet freed, and
+/// `candies` is a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn candyworks_set_inventory(
+    solver: *mut CandyWorksSolver,
+    candies: *const c_char,
+) -> c_int {
+    let solver = &mut *solver;
+    solver.error = None;
+    match text(candies).and_then(|text| text.parse().map_err(|err| format!("{}", err))) {
+        Ok(candies) => {
+            solver.inventory = candies;
+            0
+        }
+        Err(err) => solver.fail(format!("Invalid inventory: {}", err)),
+    }
+}
+
+/// Offers a trade, such as `1w -> 2e`, besides the standard trades.
+///
+/// # Safety
+///
+/// As for `candyworks_set_inventory`, with `trade` the string.
+#[no_mangle]
+pub unsafe extern "C" fn candyworks_add_trade(
+    solver: *mut CandyWorksSolver,
+    trade: *const c_char,
+) -> c_int {
+    let solver = &mut *solver;
+    solver.error = None;
+    match text(trade).and_then(|text| text.parse().map_err(|err| format!("{}", err))) {
+        Ok(trade) => {
+            solver.trades.push(trade);
+            0
+        }
+        Err(err) => solver.fail(format!("Invalid trade: {}", err)),
+    }
+}
+
+/// Sets the candies to hold at the end of the route, such as `5e`.
+///
+/// # Safety
+///
+/// As for `candyworks_set_inventory`, with `target` the string.
+#[no_mangle]
+pub unsafe extern "C" fn candyworks_set_target(
+    solver: *mut CandyWorksSolver,
+    target: *const c_char,
+) -> c_int {
+    let solver = &mut *solver;
+    solver.error = None;
+    match text(target).and_then(|text| text.parse().map_err(|err| format!("{}", err))) {
+        Ok(target) => {
+            solver.target = Some(target);
+            0
+        }
+        Err(err) => solver.fail(format!("Invalid target: {}", err)),
+    }
+}
+
+/// Plans the route to the target, as the CLI prints it, to be freed with
+/// `candyworks_free_string`; null if there is none or the problem is not
+/// set up, with `candyworks_last_error` saying why.
+///
+/// # Safety
+///
+/// `solver` comes from `candyworks_new` and is not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn candyworks_solve(solver: *mut CandyWorksSolver) -> *mut c_char {
+    let solver = &mut *solver;
+    solver.error = None;
+    match solver.solve() {
+        // Candy names and trades hold no NUL.
+        Ok(route) => CString::new(route).unwrap().into_raw(),
+        Err(err) => {
+            solver.fail(err);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Why the last call setting up or solving the solver failed, or null if
+/// it succeeded; owned by the solver.
+///
+/// # Safety
+///
+/// `solver` comes from `candyworks_new` and is not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn candyworks_last_error(solver: *const CandyWorksSolver) -> *const c_char {
+    (*solver)
+        .error
+        .as_ref()
+        .map_or(ptr::null(), |error| error.as_ptr())
+}
+
+/// Frees a route returned by `candyworks_solve`.
+///
+/// # Safety
+///
+/// `route` is null or comes from `candyworks_solve` and is not yet
+/// freed.
+#[no_mangle]
+pub unsafe extern "C" fn candyworks_free_string(route: *mut c_char) {
+    if !route.is_null() {
+        drop(CString::from_raw(route));
+    }
+}
+
+/// Frees a solver returned by `candyworks_new`.
+///
+/// # Safety
+///
+/// `solver` is null or comes from `candyworks_new` and is not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn candyworks_free(solver: *mut CandyWorksSolver) {
+    if !solver.is_null() {
+        drop(Box::from_raw(solver));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn c(text: &str) -> CString {
+        CString::new(text).unwrap()
+    }
+
+    #[test]
+    fn solves_through_the_bindings() {
+        unsafe {
+            let solver = candyworks_new(20);
+            assert_eq!(candyworks_set_inventory(solver, c("6e").as_ptr()), 0);
+            assert_eq!(candyworks_add_trade(solver, c("2e -> 1c").as_ptr()), 0);
+            assert_eq!(candyworks_set_target(solver, c("3c").as_ptr()), 0);
+            let route = candyworks_solve(solver);
+            assert!(!route.is_null());
+            assert!(CStr::from_ptr(route).to_str().unwrap().contains("2 eggs"));
+            candyworks_free_string(route);
+            candyworks_free(solver);
+        }
+    }
+
+    #[test]
+    fn reports_what_failed() {
+        unsafe {
+            let solver = candyworks_new(20);
+            assert!(candyworks_last_error(solver).is_null());
+            assert_eq!(candyworks_add_trade(solver, c("2e to 1c").as_ptr()), -1);
+            assert!(CStr::from_ptr(candyworks_last_error(solver))
+                .to_str()
+                .unwrap()
+                .starts_with("Invalid trade"));
+            assert!(candyworks_solve(solver).is_null());
+            assert_eq!(
+                CStr::from_ptr(candyworks_last_error(solver)).to_str(),
+                Ok("No target set")
+            );
+            assert_eq!(candyworks_set_target(solver, c("3c").as_ptr()), 0);
+            assert!(candyworks_last_error(solver).is_null());
+            candyworks_free(solver);
+        }
+    }
+}
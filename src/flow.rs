@@ -0,0 +1,119 @@
+use std::fmt::Write;
+
+use clap::ValueEnum;
+
+use crate::{count::Count, kinds, names, Candies, Trade};
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum FlowFormat {
+    Text,
+    Json,
+}
+
+/// What a route consumes and produces of each candy.
+pub struct Flow {
+    consumed: Candies,
+    produced: Candies,
+    /// Candies of the target gained over the route.
+    obtained: i32,
+}
+
+impl Flow {
+    pub fn of(start: Candies, route: &[Trade], target: Candies) -> Flow {
+        let mut consumed = Candies::none();
+        let mut produced = Candies::none();
+        for trade in route {
+            consumed = consumed.with(&trade.give);
+            produced = produced.with(&trade.receive);
+        }
+        let end = start.trade_all(route).unwrap();
+        let obtained = (0..kinds::count())
+            .map(|i| {
+                let wanted = target.get_by_index(i);
+                (wanted.min(end.get_by_index(i)) - wanted.min(start.get_by_index(i))).get()
+            })
+            .sum();
+        Flow {
+            consumed,
+            produced,
+            obtained,
+        }
+    }
+
+    /// Target candies obtained per candy spent, if anything was spent.
+    fn efficiency(&self) -> Option<f64> {
+        let spent = self.consumed.total();
+        (spent > Count::ZERO).then(|| self.obtained as f64 / spent.get() as f64)
+    }
+
+    /// The flow as `format` asks for, ending in a newline.
+    pub fn render(&self, format: FlowFormat) -> String {
+        match format {
+            FlowFormat::Text => self.text(),
+            FlowFormat::Json => self.json() + "\n",
+        }
+    }
+
+    fn text(&self) -> String {
+        let mut out = String::new();
+        let names = names::active();
+        writeln!(
+            out,
+            "{:<10}{:>10}{:>10}{:>10}",
+            "Flow", "consumed", "produced", "net"
+        )
+        .unwrap();
+        for i in 0..kinds::count() {
+            let (consumed, produced) =
+                (self.consumed.get_by_index(i), self.produced.get_by_index(i));
+            if consumed != Count::ZERO || produced != Count::ZERO {
+                writeln!(
+                    out,
+                    "{:<10}{:>10}{:>10}{:>+10}",
+                    names.plural(i),
+                    consumed,
+                    produced,
+                    produced - consumed
+                )
+                .unwrap();
+            }
+        }
+        match self.efficiency() {
+            Some(efficiency) => writeln!(
+                out,
+                "Efficiency: {:.3} target candies per candy spent ({} for {})",
+                efficiency,
+                self.obtained,
+                self.consumed.total()
+            )
+            .unwrap(),
+            None => writeln!(out, "Efficiency: nothing was spent").unwrap(),
+        }
+        out
+    }
+
+    fn json(&self) -> String {
+        let names = names::active();
+        let candies = (0..kinds::count())
+            .map(|i| {
+                let (consumed, produced) =
+                    (self.consumed.get_by_index(i), self.produced.get_by_index(i));
+                format!(
+                    "{{\"candy\":\"{}\",\"consumed\":{},\"produced\":{},\"net\":{}}}",
+                    names.plural(i).replace('\\', "\\\\").replace('"', "\\\""),
+                    consumed,
+                    produced,
+                    produced - consumed
+                )
+            })
+            .collect::<Vec<_>>();
+        format!(
+            "{{\"flow\":[{}],\"spent\":{},\"obtained\":{},\"efficiency\":{}}}",
+            candies.join(","),
+            self.consumed.total(),
+            self.obtained,
+            self.efficiency()
+                .map_or("null".to_string(), |efficiency| efficiency.to_string())
+        )
+    }
+}
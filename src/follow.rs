@@ -0,0 +1,133 @@
+use std::collections::HashSet;
+
+use crate::{advisor::Advisor, count::Count, display_side, prompt::Prompt, Candies, Trade};
+
+/// What the player says happened since the last step was shown.
+enum Report {
+    /// The trade shown was made.
+    Made,
+    Found(Candies),
+    Lost(Candies),
+    Quit,
+}
+
+fn parse_report(line: &str) -> Result<Report, String> {
+    let line = line.trim();
+    let candies = |text: &str, word: &str| {
+        let text = text.trim();
+        let text = text.strip_suffix(word).unwrap_or(text);
+        text.parse::<Candies>().map_err(|err| err.to_string())
+    };
+    match line {
+        "" | "done" | "ok" => Ok(Report::Made),
+        "quit" | "q" => Ok(Report::Quit),
+        _ if line.starts_with('+') => candies(&line[1..], "found").map(Report::Found),
+        _ if line.starts_with('-') => candies(&line[1..], "lost").map(Report::Lost),
+        _ => Err(
+            "expected an empty line once the trade is made, `+2 eggs found`, \
+             `-1 worm lost` or `quit`"
+                .to_string(),
+        ),
+    }
+}
+
+/// The rest of the plan from `current`, following the advice until the
+/// target is in hand.
+fn remaining(advisor: &mut Advisor, current: Candies) -> Vec<Trade> {
+    let mut plan = Vec::new();
+    let mut candies = current;
+    let mut seen = HashSet::from([candies]);
+    while let Some(trade) = advisor.advise(candies) {
+        candies = candies.trade(&trade).unwrap();
+        plan.push(trade);
+        if !seen.insert(candies) {
+            break;
+        }
+    }
+    plan
+}
+
+/// Walks the player through the plan from `start` one trade at a time.
+/// Candies found or lost along the way are folded into the inventory and
+/// the rest of the plan is made again from there; plans already made are
+/// reused whenever the inventory lands back on one of them.
+pub fn follow(
+    advisor: &mut Advisor,
+    start: Candies,
+    reserved: Candies,
+    cap: u32,
+    rl: &mut impl Prompt,
+) {
+    let mut current = start;
+    let mut plan = remaining(advisor, current);
+    print!(
+        "{}",
+        advisor
+            .problem()
+            .format_route(current.with(&reserved), &plan)
+    );
+    loop {
+        if advisor.done(&current) {
+            println!("Done: the target is in hand");
+            return;
+        }
+        let question = match plan.first() {
+            Some(next) => {
+                println!(
+                    "Next: trade {} -> {}",
+                    display_side(&next.give),
+                    display_side(&next.receive)
+                );
+                "Made it? (enter, +2 eggs found, -1 worm lost, quit) "
+            }
+            None => {
+                println!("No route reaches the target from here");
+                "Report a change (+2 eggs found, -1 worm lost) or quit: "
+            }
+        };
+        let Some(line) = rl.ask(question) else {
+            return;
+        };
+        let changed = match parse_report(&line) {
+            Ok(Report::Made) => match plan.first() {
+                Some(next) => {
+                    current = current.trade(next).unwrap();
+                    advisor.made(next);
+                    plan.remove(0);
+                    continue;
+                }
+                None => continue,
+            },
+            Ok(Report::Found(found)) => current.with(&found),
+            Ok(Report::Lost(lost)) => match current.without(&lost) {
+                Some(left) => left,
+                None => {
+                    println!("The tracked inventory does not hold that many");
+                    continue;
+                }
+            },
+            Ok(Report::Quit) => return,
+            Err(err) => {
+                println!("Could not read that: {}", err);
+                continue;
+            }
+        };
+        current = changed;
+        if current.total() > Count::new(cap as i32) {
+            println!(
+                "That is over the cap, so the next trade has to bring the total back under it"
+            );
+        }
+        plan = remaining(advisor, current);
+        println!(
+            "Planned again from ({}):",
+            current.with(&reserved).display(false).trim()
+        );
+        print!(
+            "{}",
+            advisor
+                .problem()
+                .format_route(current.with(&reserved), &plan)
+        );
+    }
+}
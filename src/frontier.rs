@@ -0,0 +1,135 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, VecDeque},
+};
+
+use clap::ValueEnum;
+
+use crate::{count::Count, kinds, Candies};
+
+/// The order in which explored states are expanded. It decides which
+/// route to each state is kept and how soon good states turn up when the
+/// exploration is cut short.
+#[derive(Clone, Default)]
+pub enum Frontier {
+    /// Newest state first, which dives deep quickly.
+    #[default]
+    Lifo,
+    /// Oldest state first: every state is reached in the fewest trades.
+    Fifo,
+    /// The state holding the most candies first.
+    Most,
+    /// The state missing the fewest candies of the nearest target first.
+    Nearest(Vec<Candies>),
+}
+
+/// `Frontier` as chosen on the command line, before the targets are known.
+#[derive(Clone, Copy, ValueEnum)]
+pub enum FrontierOrder {
+    Lifo,
+    Fifo,
+    Most,
+    Nearest,
+}
+
+impl FrontierOrder {
+    pub fn frontier(self, targets: &[Candies]) -> Frontier {
+        match self {
+            FrontierOrder::Lifo => Frontier::Lifo,
+            FrontierOrder::Fifo => Frontier::Fifo,
+            FrontierOrder::Most => Frontier::Most,
+            FrontierOrder::Nearest => Frontier::Nearest(targets.to_vec()),
+        }
+    }
+}
+
+/// Candies of `target` that `candies` lacks.
+fn missing(candies: &Candies, target: &Candies) -> i32 {
+    (0..kinds::count())
+        .map(|i| (target.get_by_index(i) - candies.get_by_index(i)).max(Count::ZERO))
+        .sum::<Count>()
+        .get()
+}
+
+/// The states waiting to be expanded, by index, in `Frontier` order.
+pub enum Queue<'a> {
+    Deque {
+        indices: VecDeque<usize>,
+        lifo: bool,
+    },
+    /// Highest priority first, then the state stored first.
+    Heap {
+        frontier: &'a Frontier,
+        indices: BinaryHeap<((i32, i32), Reverse<usize>)>,
+    },
+}
+
+impl<'a> Queue<'a> {
+    pub fn new(frontier: &'a Frontier) -> Queue<'a> {
+        match frontier {
+            Frontier::Lifo | Frontier::Fifo => Queue::Deque {
+                indices: VecDeque::new(),
+                lifo: matches!(frontier, Frontier::Lifo),
+            },
+            Frontier::Most | Frontier::Nearest(_) => Queue::Heap {
+                frontier,
+                indices: BinaryHeap::new(),
+            },
+        }
+    }
+
+    /// Adds a state to start from; with `Lifo` the first root is expanded
+    /// first.
+    pub fn push_root(&mut self, index: usize, candies: &Candies) {
+        match self {
+            Queue::Deque {
+                indices,
+                lifo: true,
+            } => indices.push_front(index),
+            _ => self.push(index, candies),
+        }
+    }
+
+    pub fn push(&mut self, index: usize, candies: &Candies) {
+        match self {
+            Queue::Deque { indices, .. } => indices.push_back(index),
+            Queue::Heap { frontier, indices } => {
+                let priority = match frontier {
+                    Frontier::Nearest(targets) => (
+                        -targets
+                            .iter()
+                            .map(|target| missing(candies, target))
+                            .min()
+                            .unwrap_or(0),
+                        candies.total().get(),
+                    ),
+                    _ => (candies.total().get(), 0),
+                };
+                indices.push((priority, Reverse(index)));
+            }
+        }
+    }
+
+    /// Number of states waiting.
+    pub fn len(&self) -> usize {
+        match self {
+            Queue::Deque { indices, .. } => indices.len(),
+            Queue::Heap { indices, .. } => indices.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn pop(&mut self) -> Option<usize> {
+        match self {
+            Queue::Deque {
+                indices,
+                lifo: true,
+            } => indices.pop_back(),
+            Queue::Deque { indices, .. } => indices.pop_front(),
+            Queue::Heap { indices, .. } => indices.pop().map(|(_, Reverse(index))| index),
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use std::{collections::HashMap, error::Error, fmt, path::Path};
+
+use crate::{count::Count, CandyWorks};
+
+/// Something wrong with a graph read from a file. The first three break
+/// route reconstruction, so `CandyWorks::load` refuses graphs with them;
+/// the rest only make the graph disagree with its own trades and cap.
+#[derive(Debug)]
+pub enum GraphError {
+    /// The file ends after `read` of the `expected` states.
+    Truncated {
+        read: usize,
+        expected: u64,
+    },
+    /// The parent is the state itself or comes after it, so following
+    /// parents from it might never reach a root.
+    ParentNotEarlier {
+        index: usize,
+        parent: u64,
+    },
+    /// The state was reached by a trade the graph does not list.
+    UnknownTrade {
+        index: usize,
+        trade: usize,
+    },
+    /// Making its trade from the parent does not give the state.
+    WrongTrade {
+        index: usize,
+    },
+    OverCap {
+        index: usize,
+        total: Count,
+    },
+    Duplicate {
+        index: usize,
+        first: usize,
+    },
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GraphError::Truncated { read, expected } => {
+                write!(f, "the file ends after {} of {} states", read, expected)
+            }
+            GraphError::ParentNotEarlier { index, parent } => {
+                write!(
+                    f,
+                    "state {} has parent {}, which does not come before it",
+                    index, parent
+                )
+            }
+            GraphError::UnknownTrade { index, trade } => {
+                write!(
+                    f,
+                    "state {} was reached by trade {}, which the graph does not list",
+                    index, trade
+                )
+            }
+            GraphError::WrongTrade { index } => {
+                write!(
+                    f,
+                    "state {} is not what its trade gives from its parent",
+                    index
+                )
+            }
+            GraphError::OverCap { index, total } => {
+                write!(
+                    f,
+                    "state {} holds {} candies, over the graph's cap",
+                    index, total
+                )
+            }
+            GraphError::Duplicate { index, first } => {
+                write!(f, "state {} is the same as state {}", index, first)
+            }
+        }
+    }
+}
+
+impl Error for GraphError {}
+
+impl CandyWorks {
+    /// The problems found by replaying every state's trade from its parent,
+    /// which `load` refuses graphs for and `salvage` leaves to `fsck`.
+    pub fn inconsistencies(&self) -> Vec<GraphError> {
+        let mut problems = Vec::new();
+        let mut seen = HashMap::new();
+        for (index, (candies, parent)) in self.combinations.iter().enumerate() {
+            if let Some((parent, trade)) = parent {
+                if self.combinations.state(parent).trade(&trade) != Some(candies) {
+                    problems.push(GraphError::WrongTrade { index });
+                }
+            }
+            if !self.fits(&candies) {
+                problems.push(GraphError::OverCap {
+                    index,
+                    total: candies.total(),
+                });
+            }
+            if let Some(&first) = seen.get(&candies) {
+                problems.push(GraphError::Duplicate { index, first });
+            } else {
+                seen.insert(candies, index);
+            }
+        }
+        problems
+    }
+}
+
+/// Checks the graph file at `path`, reading whatever it can of a damaged
+/// one, and prints what is wrong; states whose parent is unusable count as
+/// roots while the rest is checked. Returns whether the graph is sound.
+pub fn fsck(path: &Path) -> bool {
+    let (candy_works, mut problems) = match CandyWorks::salvage(path) {
+        Ok(salvaged) => salvaged,
+        Err(err) => {
+            println!("{}: cannot be read: {}", path.display(), err);
+            return false;
+        }
+    };
+    problems.extend(candy_works.inconsistencies());
+    let states = candy_works.combinations.len();
+    let roots = candy_works
+        .combinations
+        .iter()
+        .filter(|(_, parent)| parent.is_none())
+        .count();
+    if problems.is_empty() {
+        println!(
+            "{}: {} states from {} root{}, every route replays within the cap of {}",
+            path.display(),
+            states,
+            roots,
+            if roots == 1 { "" } else { "s" },
+            candy_works.max_candies
+        );
+        return true;
+    }
+    for problem in &problems {
+        println!("{}: {}", path.display(), problem);
+    }
+    println!(
+        "{} in {} readable states",
+        match problems.len() {
+            1 => "1 problem".to_string(),
+            n => format!("{} problems", n),
+        },
+        states
+    );
+    false
+}
@@ -0,0 +1,386 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use crate::{
+    count::Count, error::CandyWorksError, frontier::Frontier, fsck::GraphError, kinds,
+    notes::Notes, objective::Objective, storage::Combinations, uses::UseLimits, Candies,
+    CandyWorks, Exploration, Trade,
+};
+
+const MAGIC: &[u8; 8] = b"CWGRAPH\0";
+/// Version 2 adds the letters of the kinds of candy after the version.
+/// Graphs of the built-in candies are still written as version 1, which
+/// older builds read.
+const VERSION: u32 = 2;
+const NO_PARENT: u64 = u64::MAX;
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+fn write_u32(w: &mut impl Write, value: u32) -> io::Result<()> {
+    w.write_all(&value.to_le_bytes())
+}
+
+fn write_u64(w: &mut impl Write, value: u64) -> io::Result<()> {
+    w.write_all(&value.to_le_bytes())
+}
+
+fn write_candies(w: &mut impl Write, candies: &Candies) -> io::Result<()> {
+    for i in 0..kinds::count() {
+        w.write_all(&candies.get_by_index(i).get().to_le_bytes())?;
+    }
+    Ok(())
+}
+
+fn read_u32(r: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64(r: &mut impl Read) -> io::Result<u64> {
+    let mut buf = [0; 8];
+    r.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn read_candies(r: &mut impl Read) -> io::Result<Candies> {
+    let mut candies = Candies::none();
+    for i in 0..kinds::count() {
+        candies.add_by_index(i, Count::new(read_u32(r)? as i32));
+    }
+    Ok(candies)
+}
+
+/// A state with its parent's index and the index of its trade.
+fn read_combination(r: &mut impl Read) -> io::Result<(Candies, u64, usize)> {
+    Ok((read_candies(r)?, read_u64(r)?, read_u32(r)? as usize))
+}
+
+impl CandyWorks {
+    /// Writes the graph to `path`, for `load` to read back.
+    pub fn save(&self, path: &Path) -> Result<(), CandyWorksError> {
+        self.write(path)
+            .map_err(|err| CandyWorksError::file(path, err))
+    }
+
+    fn write(&self, path: &Path) -> io::Result<()> {
+        let mut w = BufWriter::new(File::create(path)?);
+        w.write_all(MAGIC)?;
+        let kinds = kinds::active();
+        if kinds.is_builtin() {
+            write_u32(&mut w, 1)?;
+        } else {
+            write_u32(&mut w, VERSION)?;
+            write_u32(&mut w, kinds.len() as u32)?;
+            for letter in kinds.letters() {
+                write_u32(&mut w, letter as u32)?;
+            }
+        }
+        write_u32(&mut w, self.max_candies)?;
+        write_candies(&mut w, &self.candies)?;
+        write_u32(&mut w, self.trades.len() as u32)?;
+        for trade in &self.trades {
+            write_candies(&mut w, &trade.give)?;
+            write_candies(&mut w, &trade.receive)?;
+        }
+        write_u64(&mut w, self.combinations.len() as u64)?;
+        for (candies, parent) in self.combinations.iter() {
+            write_candies(&mut w, &candies)?;
+            match parent {
+                Some((index, trade)) => {
+                    let trade_index = self.trades.iter().position(|t| *t == trade).unwrap();
+                    write_u64(&mut w, index as u64)?;
+                    write_u32(&mut w, trade_index as u32)?;
+                }
+                None => {
+                    write_u64(&mut w, NO_PARENT)?;
+                    write_u32(&mut w, 0)?;
+                }
+            }
+        }
+        w.flush()
+    }
+
+    /// Reads a graph written by `save`, refusing one whose routes could not
+    /// be followed back to a root or do not replay: a state its parent's
+    /// trade does not lead to, one over the cap, or one saved twice.
+    pub fn load(path: &Path) -> Result<CandyWorks, CandyWorksError> {
+        let (candy_works, mut problems) =
+            CandyWorks::salvage(path).map_err(|err| CandyWorksError::file(path, err))?;
+        problems.extend(candy_works.inconsistencies());
+        match problems.into_iter().next() {
+            Some(problem) => Err(CandyWorksError::file(path, problem)),
+            None => Ok(candy_works),
+        }
+    }
+
+    /// Reads the graph at `path` as the exploration of this problem, if it
+    /// was explored from the same inventory with the same cap and trades,
+    /// in whatever order; `None` if it was explored for another problem.
+    /// Only complete explorations are kept to be read again like this, so
+    /// the graph counts as one.
+    pub fn load_cached(&self, path: &Path) -> Result<Option<CandyWorks>, CandyWorksError> {
+        let cached = CandyWorks::load(path)?;
+        let same_trades = cached.trades.len() == self.trades.len()
+            && self
+                .trades
+                .iter()
+                .all(|trade| cached.trades.contains(trade));
+        if cached.candies != self.candies || cached.max_candies != self.max_candies || !same_trades
+        {
+            return Ok(None);
+        }
+        let capped = cached.combinations.states().any(|candies| {
+            self.trades
+                .iter()
+                .any(|trade| candies.trade(trade).is_some_and(|after| !self.fits(&after)))
+        });
+        Ok(Some(CandyWorks {
+            trades: self.trades.clone(),
+            combinations: cached.combinations,
+            exploration: Some(if capped {
+                Exploration::Capped
+            } else {
+                Exploration::Complete
+            }),
+            ..self.unexplored()
+        }))
+    }
+
+    /// Reads as much of a graph as is usable, with what was wrong with the
+    /// rest: states after a truncation are lost and states whose parent
+    /// cannot be followed are kept as roots. Only a damaged header fails.
+    pub fn salvage(path: &Path) -> io::Result<(CandyWorks, Vec<GraphError>)> {
+        let mut r = BufReader::new(File::open(path)?);
+        let mut magic = [0; 8];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("not a candyworks graph file"));
+        }
+        let kinds = kinds::active();
+        match read_u32(&mut r)? {
+            1 if kinds.is_builtin() => CandyWorks::read_states(&mut r),
+            1 => Err(invalid(
+                "the graph is of eggs, worms, cakes, fishes and meats; read it without --kinds",
+            )),
+            VERSION => {
+                let count = read_u32(&mut r)?;
+                let mut letters = String::new();
+                for _ in 0..count.min(kinds::MAX_KINDS as u32 + 1) {
+                    letters.extend(char::from_u32(read_u32(&mut r)?));
+                }
+                if letters != kinds.letters().collect::<String>() {
+                    return Err(invalid(&format!(
+                        "the graph is of the candies `{}`; read it with the same --kinds",
+                        letters
+                    )));
+                }
+                CandyWorks::read_states(&mut r)
+            }
+            version if version > VERSION => Err(invalid(&format!(
+                "graph file version {} is newer than this candyworks supports ({})",
+                version, VERSION
+            ))),
+            version => Err(invalid(&format!("unknown graph file version {}", version))),
+        }
+    }
+
+    /// The graph after its version, or its kinds of candy from version 2.
+    fn read_states(r: &mut impl Read) -> io::Result<(CandyWorks, Vec<GraphError>)> {
+        let max_candies = read_u32(r)?;
+        let candies = read_candies(r)?;
+        let trade_count = read_u32(r)?;
+        let mut trades = Vec::new();
+        for _ in 0..trade_count {
+            let give = read_candies(r)?;
+            let receive = read_candies(r)?;
+            trades.push(Trade { give, receive });
+        }
+        let count = read_u64(r)?;
+        let mut combinations = Combinations::new();
+        let mut problems = Vec::new();
+        for _ in 0..count {
+            let index = combinations.len();
+            let (state, parent, trade_index) = match read_combination(r) {
+                Ok(combination) => combination,
+                Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => {
+                    problems.push(GraphError::Truncated {
+                        read: index,
+                        expected: count,
+                    });
+                    break;
+                }
+                Err(err) => return Err(err),
+            };
+            if parent == NO_PARENT {
+                combinations.push((state, None));
+            } else if parent >= index as u64 {
+                problems.push(GraphError::ParentNotEarlier { index, parent });
+                combinations.push((state, None));
+            } else if let Some(&trade) = trades.get(trade_index) {
+                combinations.push((state, Some((parent as usize, trade))));
+            } else {
+                problems.push(GraphError::UnknownTrade {
+                    index,
+                    trade: trade_index,
+                });
+                combinations.push((state, None));
+            }
+        }
+        let candy_works = CandyWorks {
+            candies,
+            max_candies,
+            kind_caps: None,
+            trades,
+            use_limits: UseLimits::default(),
+            notes: Notes::default(),
+            combinations,
+            prefilter: false,
+            prune_dominated: false,
+            frontier: Frontier::default(),
+            objective: Objective::default(),
+            timings: None,
+            exploration: None,
+        };
+        Ok((candy_works, problems))
+    }
+
+    pub fn merge(&self, other: &CandyWorks) -> Result<CandyWorks, String> {
+        if self.trades != other.trades {
+            return Err("graphs were explored with different trades".to_string());
+        }
+        let mut combinations = self.combinations.clone();
+        let mut known = combinations
+            .states()
+            .enumerate()
+            .map(|(i, candies)| (candies, i))
+            .collect::<HashMap<_, _>>();
+        let mut remap = Vec::with_capacity(other.combinations.len());
+        for (candies, parent) in other.combinations.iter() {
+            if let Some(&index) = known.get(&candies) {
+                remap.push(index);
+                continue;
+            }
+            let parent = parent.map(|(index, trade)| (remap[index], trade));
+            remap.push(combinations.len());
+            known.insert(candies, combinations.len());
+            combinations.push((candies, parent));
+        }
+        Ok(CandyWorks {
+            candies: self.candies,
+            max_candies: self.max_candies.max(other.max_candies),
+            kind_caps: None,
+            trades: self.trades.clone(),
+            use_limits: UseLimits::default(),
+            notes: Notes::default(),
+            combinations,
+            prefilter: false,
+            prune_dominated: false,
+            frontier: Frontier::default(),
+            objective: Objective::default(),
+            timings: self.timings.clone(),
+            exploration: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, env, fs, path::PathBuf};
+
+    use super::*;
+    use crate::test_support::{self, candies};
+
+    fn explored(inventory: &str, cap: u32) -> CandyWorks {
+        test_support::explored(inventory, cap, &["2e -> 1c"])
+    }
+
+    fn temp(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("candyworks-{}-{}.graph", name, std::process::id()))
+    }
+
+    #[test]
+    fn loads_what_was_saved() {
+        let candy_works = explored("6e", 20);
+        let path = temp("round-trip");
+        candy_works.save(&path).unwrap();
+        let loaded = CandyWorks::load(&path).unwrap();
+        assert_eq!(loaded.candies, candy_works.candies);
+        assert_eq!(loaded.max_candies, 20);
+        assert_eq!(loaded.trades, candy_works.trades);
+        assert!(loaded
+            .combinations
+            .iter()
+            .eq(candy_works.combinations.iter()));
+        let target = candies("3c");
+        assert_eq!(
+            loaded.find_optimal_route(target).unwrap().trades(),
+            candy_works.find_optimal_route(target).unwrap().trades()
+        );
+    }
+
+    #[test]
+    fn refuses_a_graph_whose_states_do_not_replay() {
+        let mut candy_works = explored("6e", 20);
+        let trade = candy_works.trades[0];
+        candy_works
+            .combinations
+            .push((candies("5c"), Some((0, trade))));
+        let path = temp("wrong-trade");
+        candy_works.save(&path).unwrap();
+        let err = CandyWorks::load(&path).err().unwrap();
+        assert!(matches!(&err, CandyWorksError::File { path: p, .. } if *p == path));
+        assert_eq!(err.exit_code(), 1);
+        assert!(CandyWorks::salvage(&path).is_ok());
+    }
+
+    #[test]
+    fn refuses_a_file_that_is_no_graph() {
+        let path = temp("not-a-graph");
+        fs::write(&path, "inventory = 6e\n").unwrap();
+        let err = CandyWorks::load(&path).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            format!("{}: not a candyworks graph file", path.display())
+        );
+    }
+
+    #[test]
+    fn saving_where_no_file_can_be_made_fails() {
+        let path = temp("missing").join("graph");
+        let err = explored("6e", 20).save(&path).err().unwrap();
+        assert!(matches!(&err, CandyWorksError::File { path: p, .. } if *p == path));
+    }
+
+    #[test]
+    fn merges_the_states_of_both() {
+        let first = explored("6e", 20);
+        let second = explored("4e 2w", 20);
+        let merged = first.merge(&second).unwrap();
+        let states = merged.combinations.states().collect::<HashSet<_>>();
+        assert_eq!(states.len(), merged.combinations.len());
+        assert!(first
+            .combinations
+            .states()
+            .all(|state| states.contains(&state)));
+        assert!(second
+            .combinations
+            .states()
+            .all(|state| states.contains(&state)));
+        assert!(merged.inconsistencies().is_empty());
+    }
+
+    #[test]
+    fn merges_only_graphs_of_the_same_trades() {
+        let first = explored("6e", 20);
+        let mut second = CandyWorks::new(candies("6e"), 20, Vec::new());
+        second.explore();
+        assert!(first.merge(&second).is_err());
+    }
+}
@@ -0,0 +1,74 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::{query::Filter, Candies, CandyWorks};
+
+/// The fewest trades each reachable state takes from the start. The
+/// explored graph keeps the first route found to every state, which can be
+/// longer, so the states are searched again breadth first.
+pub fn shortest_depths(candy_works: &CandyWorks) -> Vec<(Candies, usize)> {
+    let start = candy_works.candies;
+    let mut depths = HashMap::from([(start, 0)]);
+    let mut order = vec![(start, 0)];
+    let mut queue = VecDeque::from([start]);
+    while let Some(candies) = queue.pop_front() {
+        let depth = depths[&candies] + 1;
+        for trade in &candy_works.trades {
+            if let Some(next) = candies.trade(trade) {
+                if candy_works.fits(&next) && !depths.contains_key(&next) {
+                    depths.insert(next, depth);
+                    order.push((next, depth));
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+    order
+}
+
+/// Prints a bar per number of trades of the states reachable within that
+/// many, `width` characters for the most, and of those matching `filter`
+/// drawn as `*` over the start of each bar.
+pub fn chart(
+    candy_works: &CandyWorks,
+    filter: Option<&Filter>,
+    width: usize,
+) -> Result<(), String> {
+    let depths = shortest_depths(candy_works);
+    let deepest = depths.last().map_or(0, |(_, depth)| *depth);
+    let mut reached = vec![0usize; deepest + 1];
+    let mut matching = vec![0usize; deepest + 1];
+    for (candies, depth) in &depths {
+        reached[*depth] += 1;
+        if let Some(true) = filter
+            .map(|filter| filter.matches(candies, *depth))
+            .transpose()?
+        {
+            matching[*depth] += 1;
+        }
+    }
+    for depth in 1..=deepest {
+        reached[depth] += reached[depth - 1];
+        matching[depth] += matching[depth - 1];
+    }
+    let most = reached[deepest];
+    let bar = |count: usize| (count * width).div_ceil(most);
+    match filter {
+        Some(_) => println!("trades   states matching"),
+        None => println!("trades   states"),
+    }
+    for depth in 0..=deepest {
+        let (all, matched) = (bar(reached[depth]), bar(matching[depth]));
+        match filter {
+            Some(_) => println!(
+                "{:>6} {:>8} {:>8}  {}{}",
+                depth,
+                reached[depth],
+                matching[depth],
+                "*".repeat(matched),
+                "#".repeat(all - matched)
+            ),
+            None => println!("{:>6} {:>8}  {}", depth, reached[depth], "#".repeat(all)),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use std::mem;
+
+use crate::kinds;
+
+/// Explorations estimated above this many states are refused unless forced:
+/// at that size they take minutes and gigabytes.
+const MAX_STATES: u64 = 50_000_000;
+
+/// Rough exploration speed of a release build, for the estimate shown.
+const STATES_PER_SECOND: u64 = 1_000_000;
+
+/// Memory one explored state takes: the stored state and its link to its
+/// parent, its visited-set entry and its queue slot, all for states with an
+/// ID, which are the ones below any cap the estimate accepts.
+pub fn state_bytes() -> usize {
+    3 * mem::size_of::<u64>() + mem::size_of::<usize>()
+}
+
+/// An upper bound on the states below `cap`: every way of holding at most
+/// `cap` candies of the run's k kinds, C(cap + k, k).
+pub fn estimate_states(cap: u64) -> u64 {
+    let kinds = kinds::count() as u64;
+    (1..=kinds).fold(1_u64, |count, k| {
+        count.saturating_mul(cap.saturating_add(k)) / k
+    })
+}
+
+/// Refuses an exploration under `cap` that is obviously too large to finish
+/// in reasonable time, explaining the estimate and the ways around it.
+pub fn check(cap: u64) -> Result<(), String> {
+    let states = estimate_states(cap);
+    if states <= MAX_STATES {
+        return Ok(());
+    }
+    let megabytes = states.saturating_mul(state_bytes() as u64) / (1024 * 1024);
+    Err(format!(
+        "A cap of {} allows up to {} states, which could take {} minutes and {} MB to explore.\n\
+         Pass --force to explore anyway, or --time-limit SECONDS to answer with the best \
+         route found in that time.",
+        cap,
+        states,
+        (states / STATES_PER_SECOND).div_ceil(60),
+        megabytes
+    ))
+}
@@ -0,0 +1,87 @@
+use crate::{Candies, Trade};
+
+/// A change made at the prompt, holding what it replaced so that it can
+/// be undone.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Edit {
+    Inventory {
+        before: Candies,
+        after: Candies,
+    },
+    Cap {
+        before: u32,
+        after: u32,
+    },
+    /// A trade offered, added after the others.
+    AddTrade(Trade),
+    /// Trade `n`, counting from 1, no longer offered.
+    RemoveTrade {
+        n: usize,
+        trade: Trade,
+    },
+    /// `trade` made in the game from `before`.
+    Make {
+        trade: Trade,
+        before: Candies,
+    },
+}
+
+impl Edit {
+    /// The command typed at the prompt to make the edit, as saved sessions
+    /// hold it.
+    pub fn command(&self) -> String {
+        let trade =
+            |trade: &Trade| format!("{} -> {}", trade.give.compact(), trade.receive.compact());
+        match self {
+            Edit::Inventory { after, .. } => format!("set inventory {}", after.compact()),
+            Edit::Cap { after, .. } => format!("set cap {}", after),
+            Edit::AddTrade(added) => format!("add-trade {}", trade(added)),
+            Edit::RemoveTrade { n, .. } => format!("remove-trade {}", n),
+            Edit::Make { trade: made, .. } => format!("make {}", trade(made)),
+        }
+    }
+}
+
+/// The edits made in a session, in order, and those undone since the last
+/// edit, which `redo` makes again; a new edit forgets them.
+#[derive(Default)]
+pub struct History {
+    done: Vec<Edit>,
+    undone: Vec<Edit>,
+}
+
+impl History {
+    pub fn record(&mut self, edit: Edit) {
+        self.done.push(edit);
+        self.undone.clear();
+    }
+
+    /// The last edit made, to be undone.
+    pub fn undo(&mut self) -> Option<Edit> {
+        let edit = self.done.pop()?;
+        self.undone.push(edit);
+        Some(edit)
+    }
+
+    /// The last edit undone, to be made again.
+    pub fn redo(&mut self) -> Option<Edit> {
+        let edit = self.undone.pop()?;
+        self.done.push(edit);
+        Some(edit)
+    }
+
+    pub fn done(&self) -> &[Edit] {
+        &self.done
+    }
+
+    /// The edits made as a session file: a comment line, then the command
+    /// of each edit, one per line, to be replayed on the same problem.
+    pub fn save(&self) -> String {
+        let mut out = "# candyworks session\n".to_string();
+        for edit in &self.done {
+            out.push_str(&edit.command());
+            out.push('\n');
+        }
+        out
+    }
+}
@@ -0,0 +1,336 @@
+use std::path::Path;
+
+use clap::ValueEnum;
+
+use crate::{
+    count::Count,
+    json::{self, Value},
+    names::{CandyNames, Lookup},
+    scenario::{Scenario, VERSION},
+    Candies,
+};
+
+/// The planner exports `import` reads.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ImportFormat {
+    /// Tell the format from the document.
+    Auto,
+    /// The JSON export of the CandySheet spreadsheet template:
+    ///
+    /// ```text
+    /// {
+    ///   "format": "candy-sheet",       required
+    ///   "version": 1,                  required, only 1 so far
+    ///   "bag_limit": 20,               optional cap, 20 if left out
+    ///   "inventory": {"Eggs": 6},      required, candy names to counts
+    ///   "trades": [                    optional
+    ///     {"give": {"Eggs": 4},        required, at least one candy
+    ///      "get": {"Cakes": 1},        required, at least one candy
+    ///      "vendor": "market",         optional location
+    ///      "note": "after quest 3"}    optional
+    ///   ],
+    ///   "goals": [{"Meats": 2}]        optional, one target each
+    /// }
+    /// ```
+    CandySheet,
+    /// The export of trade-ledger planners, one row per item and per offer:
+    ///
+    /// ```text
+    /// {
+    ///   "ledger": {"version": 2,       required, 1 or 2
+    ///              "capacity": 20},    optional cap, 20 if left out
+    ///   "items": [                     required
+    ///     {"item": "Egg",              required candy name
+    ///      "owned": 6,                 required count
+    ///      "wanted": 0}                optional; the wanted counts make
+    ///   ],                             up the one target
+    ///   "offers": [                    optional
+    ///     {"pay": [{"item": "Egg", "qty": 4}],       required, not empty
+    ///      "receive": [{"item": "Cake", "qty": 1}],  required, not empty
+    ///      "location": "market",                     optional
+    ///      "comment": "after quest 3"}               optional
+    ///   ]
+    /// }
+    /// ```
+    TradeLedger,
+}
+
+/// A value of the document with the path it was found at, such as
+/// `$.trades[2].give`, for errors that say where the document breaks its
+/// schema.
+pub struct At<'a> {
+    pub value: &'a Value,
+    pub path: String,
+}
+
+impl<'a> At<'a> {
+    pub fn error(&self, message: &str) -> String {
+        format!("{}: {}", self.path, message)
+    }
+
+    /// The fields of an object, which may only have the `allowed` keys.
+    pub fn object(&self, allowed: &[&str]) -> Result<(), String> {
+        let Value::Object(fields) = self.value else {
+            return Err(self.error("expected an object"));
+        };
+        if let Some((key, _)) = fields
+            .iter()
+            .find(|(key, _)| !allowed.contains(&key.as_str()))
+        {
+            return Err(self.error(&format!(
+                "unknown key `{}`, expected one of {}",
+                key,
+                allowed.join(", ")
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, key: &str) -> Option<At<'a>> {
+        self.value.get(key).map(|value| At {
+            value,
+            path: format!("{}.{}", self.path, key),
+        })
+    }
+
+    pub fn require(&self, key: &str) -> Result<At<'a>, String> {
+        self.get(key)
+            .ok_or_else(|| self.error(&format!("missing `{}`", key)))
+    }
+
+    pub fn array(&self) -> Result<Vec<At<'a>>, String> {
+        let Value::Array(items) = self.value else {
+            return Err(self.error("expected an array"));
+        };
+        Ok(items
+            .iter()
+            .enumerate()
+            .map(|(n, value)| At {
+                value,
+                path: format!("{}[{}]", self.path, n),
+            })
+            .collect())
+    }
+
+    /// A string to be written into a scenario line, which cannot hold what
+    /// scenarios read as comments, options, locations or variables.
+    pub fn text(&self) -> Result<&'a str, String> {
+        let text = self
+            .value
+            .as_str()
+            .ok_or_else(|| self.error("expected a string"))?;
+        if text.contains(['#', ';', '@', '\n']) || text.contains("${") {
+            return Err(self.error("cannot hold `#`, `;`, `@`, `${` or line breaks"));
+        }
+        Ok(text.trim())
+    }
+
+    pub fn count(&self) -> Result<Count, String> {
+        self.value
+            .as_f64()
+            .filter(|count| count.fract() == 0.0 && (0.0..=i32::MAX as f64).contains(count))
+            .map(|count| Count::new(count as i32))
+            .ok_or_else(|| self.error("expected a whole number of at least 0"))
+    }
+
+    pub fn candy(&self) -> Result<usize, String> {
+        let name = self
+            .value
+            .as_str()
+            .ok_or_else(|| self.error("expected a candy name"))?;
+        match CandyNames::builtin().lookup(name) {
+            Lookup::Exact(index) => Ok(index),
+            _ => Err(self.error(&format!("unknown candy `{}`", name))),
+        }
+    }
+}
+
+/// Candies written as an object of candy names to counts.
+pub fn named_counts(at: &At) -> Result<Candies, String> {
+    let Value::Object(fields) = at.value else {
+        return Err(at.error("expected an object of candy names to counts"));
+    };
+    let mut candies = Candies::none();
+    for (name, count) in fields {
+        let count = At {
+            value: count,
+            path: format!("{}.{}", at.path, name),
+        };
+        let Lookup::Exact(index) = CandyNames::builtin().lookup(name) else {
+            return Err(at.error(&format!("unknown candy `{}`", name)));
+        };
+        candies.add_by_index(index, count.count()?);
+    }
+    Ok(candies)
+}
+
+/// Candies written as rows of an `item` and its `qty`.
+fn rows(at: &At) -> Result<Candies, String> {
+    let mut candies = Candies::none();
+    for row in at.array()? {
+        row.object(&["item", "qty"])?;
+        let index = row.require("item")?.candy()?;
+        candies.add_by_index(index, row.require("qty")?.count()?);
+    }
+    Ok(candies)
+}
+
+/// A side of a trade as a scenario writes it, which has to hold a candy.
+pub fn side(candies: Candies, at: &At) -> Result<String, String> {
+    if candies == Candies::none() {
+        return Err(at.error("expected at least one candy"));
+    }
+    Ok(candies.compact())
+}
+
+/// A scenario `trade` line, with the note and location if there are any.
+pub fn trade_line(
+    give: String,
+    receive: String,
+    location: Option<&str>,
+    note: Option<&str>,
+) -> String {
+    let mut line = format!("trade = {} -> {}", give, receive);
+    if let Some(note) = note.filter(|note| !note.is_empty()) {
+        line.push_str(&format!("; note {}", note));
+    }
+    if let Some(location) = location.filter(|location| !location.is_empty()) {
+        line.push_str(&format!(" @ {}", location));
+    }
+    line
+}
+
+/// The scenario `cap` line for the count at `at`, if it is there.
+pub fn cap(at: Option<At>) -> Result<Option<String>, String> {
+    at.map(|at| at.count().map(|cap| format!("cap = {}", cap)))
+        .transpose()
+}
+
+fn candy_sheet(root: &At) -> Result<Vec<String>, String> {
+    root.object(&[
+        "format",
+        "version",
+        "bag_limit",
+        "inventory",
+        "trades",
+        "goals",
+    ])?;
+    let version = root.require("version")?;
+    if version.value.as_f64() != Some(1.0) {
+        return Err(version.error("only version 1 is known"));
+    }
+    let mut lines = Vec::from_iter(cap(root.get("bag_limit"))?);
+    lines.push(format!(
+        "inventory = {}",
+        named_counts(&root.require("inventory")?)?.compact()
+    ));
+    for trade in root
+        .get("trades")
+        .map(|at| at.array())
+        .transpose()?
+        .unwrap_or_default()
+    {
+        trade.object(&["give", "get", "vendor", "note"])?;
+        let give = trade.require("give")?;
+        let get = trade.require("get")?;
+        lines.push(trade_line(
+            side(named_counts(&give)?, &give)?,
+            side(named_counts(&get)?, &get)?,
+            trade.get("vendor").map(|at| at.text()).transpose()?,
+            trade.get("note").map(|at| at.text()).transpose()?,
+        ));
+    }
+    for goal in root
+        .get("goals")
+        .map(|at| at.array())
+        .transpose()?
+        .unwrap_or_default()
+    {
+        lines.push(format!("target = {}", side(named_counts(&goal)?, &goal)?));
+    }
+    Ok(lines)
+}
+
+fn trade_ledger(root: &At) -> Result<Vec<String>, String> {
+    root.object(&["ledger", "items", "offers"])?;
+    let ledger = root.require("ledger")?;
+    ledger.object(&["version", "capacity"])?;
+    let version = ledger.require("version")?;
+    if !matches!(version.value.as_f64(), Some(v) if v == 1.0 || v == 2.0) {
+        return Err(version.error("only versions 1 and 2 are known"));
+    }
+    let mut lines = Vec::from_iter(cap(ledger.get("capacity"))?);
+    let mut owned = Candies::none();
+    let mut wanted = Candies::none();
+    for item in root.require("items")?.array()? {
+        item.object(&["item", "owned", "wanted"])?;
+        let index = item.require("item")?.candy()?;
+        owned.add_by_index(index, item.require("owned")?.count()?);
+        if let Some(count) = item.get("wanted") {
+            wanted.add_by_index(index, count.count()?);
+        }
+    }
+    lines.push(format!("inventory = {}", owned.compact()));
+    for offer in root
+        .get("offers")
+        .map(|at| at.array())
+        .transpose()?
+        .unwrap_or_default()
+    {
+        offer.object(&["pay", "receive", "location", "comment"])?;
+        let pay = offer.require("pay")?;
+        let receive = offer.require("receive")?;
+        lines.push(trade_line(
+            side(rows(&pay)?, &pay)?,
+            side(rows(&receive)?, &receive)?,
+            offer.get("location").map(|at| at.text()).transpose()?,
+            offer.get("comment").map(|at| at.text()).transpose()?,
+        ));
+    }
+    if wanted != Candies::none() {
+        lines.push(format!("target = {}", wanted.compact()));
+    }
+    Ok(lines)
+}
+
+/// Reads a planner export as a scenario, checking it against the schema of
+/// `format` first.
+pub fn import(path: &Path, format: ImportFormat) -> Result<Scenario, String> {
+    let input = crate::replay::read_to_string(path).map_err(|err| err.to_string())?;
+    let document = json::parse(&input)?;
+    let root = At {
+        value: &document,
+        path: "$".to_string(),
+    };
+    let format = match format {
+        ImportFormat::Auto
+            if document.get("format").and_then(Value::as_str) == Some("candy-sheet") =>
+        {
+            ImportFormat::CandySheet
+        }
+        ImportFormat::Auto if document.get("ledger").is_some() => ImportFormat::TradeLedger,
+        ImportFormat::Auto => {
+            return Err(
+                "not a known planner export: expected `\"format\": \"candy-sheet\"` or a \
+                 `ledger` object; pass --format to read it as one of them"
+                    .to_string(),
+            )
+        }
+        format => format,
+    };
+    let lines = match format {
+        ImportFormat::CandySheet => {
+            let tag = root.require("format")?;
+            if tag.value.as_str() != Some("candy-sheet") {
+                return Err(tag.error("expected \"candy-sheet\""));
+            }
+            candy_sheet(&root)?
+        }
+        _ => trade_ledger(&root)?,
+    };
+    let text = format!("version = {}\n{}\n", VERSION, lines.join("\n"));
+    // The schema admits documents no scenario can hold, such as counts
+    // that overflow once traded.
+    Scenario::parse(&text)
+        .map_err(|err| format!("the imported scenario is invalid: {}", err.message))
+}
@@ -0,0 +1,108 @@
+use std::{collections::VecDeque, time::Instant};
+
+use crate::{
+    bloom::Visited,
+    storage::Combinations,
+    timings::{self, Phase},
+    CandyWorks, Exploration, Trade,
+};
+
+impl CandyWorks {
+    /// Offers `trade` as well, exploring only what it newly reaches: the
+    /// trade is made from every explored state, and the states it reaches
+    /// are explored with all the trades. The states come out the same as
+    /// exploring again, while the routes to states already known are kept.
+    pub fn add_trade(&mut self, trade: Trade) {
+        self.trades.push(trade);
+        if !self.extendable() {
+            return self.explore();
+        }
+        let seeds = (0..self.combinations.len()).collect();
+        let capped = self.exploration == Some(Exploration::Capped);
+        self.extend(seeds, &[trade], capped)
+    }
+
+    /// Changes the cap to `cap`. A higher cap explores only the states the
+    /// old one left out: the trades that went over it are made again from
+    /// the states close enough to it, and what they reach is explored. A
+    /// lower cap explores again.
+    pub fn set_cap(&mut self, cap: u32) {
+        let old = self.max_candies;
+        self.max_candies = cap;
+        if cap < old || !self.extendable() {
+            return self.explore();
+        }
+        if cap == old || self.exploration == Some(Exploration::Complete) {
+            return;
+        }
+        // Only a state within the most any trade adds of the old cap can
+        // have had a trade go over it.
+        let gain = self
+            .trades
+            .iter()
+            .map(|trade| (trade.receive.total() - trade.give.total()).get())
+            .max()
+            .unwrap_or(0);
+        let seeds = (0..self.combinations.len())
+            .filter(|&index| self.combinations.state(index).total().get() + gain > old as i32)
+            .collect();
+        let trades = self.trades.clone();
+        self.extend(seeds, &trades, false)
+    }
+
+    /// Whether the graph can be extended in place: it holds every state
+    /// within the cap, none of them skipped as dominated.
+    fn extendable(&self) -> bool {
+        !self.prune_dominated && self.exploration.is_some_and(Exploration::is_complete)
+    }
+
+    /// Makes `seed_trades` from the states at `seeds` and explores the new
+    /// states they reach with every trade. `capped` says whether a trade
+    /// not made again already went over the cap.
+    fn extend(&mut self, seeds: Vec<usize>, seed_trades: &[Trade], mut capped: bool) {
+        let started = Instant::now();
+        let mut known_sets = Visited::new(self.prefilter);
+        for candies in self.combinations.states() {
+            known_sets.insert(candies);
+        }
+        let index_of = |combinations: &mut Combinations, trades: &[Trade]| {
+            trades
+                .iter()
+                .map(|trade| (*trade, combinations.trade_index(*trade)))
+                .collect::<Vec<_>>()
+        };
+        let seed_trades = index_of(&mut self.combinations, seed_trades);
+        let trades = index_of(&mut self.combinations, &self.trades);
+        let mut queue = seeds
+            .into_iter()
+            .map(|index| (index, &seed_trades))
+            .collect::<VecDeque<_>>();
+        while let Some((index, trades_from)) = queue.pop_front() {
+            let candies = self.combinations.state(index);
+            for (trade, trade_index) in trades_from {
+                let Some(new_candies) = candies.trade(trade) else {
+                    continue;
+                };
+                if !self.fits(&new_candies) {
+                    capped = true;
+                    continue;
+                }
+                if known_sets.insert(new_candies) {
+                    queue.push_back((self.combinations.len(), &trades));
+                    self.combinations
+                        .push_child(new_candies, index, *trade_index);
+                }
+            }
+        }
+        self.exploration = Some(if capped {
+            Exploration::Capped
+        } else {
+            Exploration::Complete
+        });
+        timings::record(
+            self.timings.as_deref(),
+            Phase::Exploration,
+            started.elapsed(),
+        );
+    }
+}
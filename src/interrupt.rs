@@ -0,0 +1,57 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(unix)]
+mod unix {
+    use std::sync::atomic::Ordering;
+
+    const SIGINT: i32 = 2;
+    const SIG_DFL: usize = 0;
+
+    extern "C" {
+        fn signal(signum: i32, handler: usize) -> usize;
+    }
+
+    /// Notes the interruption and lets the next Ctrl-C end the process as
+    /// usual, in case the exploration does not get to stop.
+    extern "C" fn handle(_: i32) {
+        super::INTERRUPTED.store(true, Ordering::SeqCst);
+        unsafe {
+            signal(SIGINT, SIG_DFL);
+        }
+    }
+
+    pub fn catch() {
+        unsafe {
+            signal(SIGINT, handle as extern "C" fn(i32) as usize);
+        }
+    }
+
+    pub fn release() {
+        unsafe {
+            signal(SIGINT, SIG_DFL);
+        }
+    }
+}
+
+/// Until `release`, turns Ctrl-C into a request for explorations to stop
+/// early, keeping what they reached; a second Ctrl-C ends the process.
+/// Elsewhere than on Unix, Ctrl-C ends the process as before.
+pub fn catch() {
+    INTERRUPTED.store(false, Ordering::SeqCst);
+    #[cfg(unix)]
+    unix::catch();
+}
+
+/// Lets Ctrl-C end the process again.
+pub fn release() {
+    #[cfg(unix)]
+    unix::release();
+    INTERRUPTED.store(false, Ordering::SeqCst);
+}
+
+/// Whether Ctrl-C was pressed since `catch`.
+pub fn requested() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
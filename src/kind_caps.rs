@@ -0,0 +1,175 @@
+use std::{fmt, str::FromStr};
+
+use crate::{count::Count, kinds, Candies};
+
+/// What a kind without a limit of its own counts as holding at most.
+const UNLIMITED: Count = Count::new(i32::MAX);
+
+/// The most of each kind of candy a state may hold, on top of the cap on
+/// the total, as in `kind_cap = 10` for every kind or `kind_cap = 10e 5m`
+/// for some; the kinds not listed are only held to the total.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KindCaps {
+    most: Candies,
+}
+
+impl KindCaps {
+    /// The same limit for every kind.
+    pub fn all(most: Count) -> Result<KindCaps, String> {
+        if most < Count::ZERO {
+            return Err("a kind cap cannot be negative".to_string());
+        }
+        let mut caps = Candies::none();
+        for i in 0..kinds::count() {
+            caps.add_by_index(i, most);
+        }
+        Ok(KindCaps { most: caps })
+    }
+
+    /// A limit for each kind `listed` holds any of, at that many.
+    pub fn of(listed: &Candies) -> Result<KindCaps, String> {
+        if *listed == Candies::none() {
+            return Err("expected a number or candies such as `10e 5m`".to_string());
+        }
+        let mut most = Candies::none();
+        for i in 0..kinds::count() {
+            let count = listed.get_by_index(i);
+            most.add_by_index(
+                i,
+                if count > Count::ZERO {
+                    count
+                } else {
+                    UNLIMITED
+                },
+            );
+        }
+        Ok(KindCaps { most })
+    }
+
+    /// Whether `candies` holds no kind past its limit.
+    pub fn allows(&self, candies: &Candies) -> bool {
+        self.most.contains(candies)
+    }
+
+    /// The first kind `candies` holds past its limit, if any.
+    pub fn exceeded(&self, candies: &Candies) -> Option<usize> {
+        (0..kinds::count()).find(|&i| candies.get_by_index(i) > self.most.get_by_index(i))
+    }
+
+    /// The most of the kind at `index` a state may hold, if it is limited.
+    pub fn limit(&self, index: usize) -> Option<Count> {
+        Some(self.most.get_by_index(index)).filter(|&most| most != UNLIMITED)
+    }
+
+    /// The limits left for planning once `reserved` is set aside, as the
+    /// cap on the total is lowered by as much.
+    pub fn less(&self, reserved: &Candies) -> KindCaps {
+        let mut most = self.most;
+        for i in 0..kinds::count() {
+            if let Some(limit) = self.limit(i) {
+                let left = (limit - reserved.get_by_index(i)).max(Count::ZERO);
+                most.add_by_index(i, left - limit);
+            }
+        }
+        KindCaps { most }
+    }
+}
+
+impl FromStr for KindCaps {
+    type Err = String;
+
+    /// One number for every kind, or candies such as `10e 5m`.
+    fn from_str(input: &str) -> Result<KindCaps, String> {
+        if let Ok(most) = input.trim().parse::<Count>() {
+            return KindCaps::all(most);
+        }
+        KindCaps::of(&input.parse::<Candies>().map_err(|err| err.to_string())?)
+    }
+}
+
+impl fmt::Display for KindCaps {
+    /// As `from_str` reads it: the number when every kind has the same
+    /// limit, and the limited kinds otherwise.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let limits = (0..kinds::count())
+            .map(|i| self.limit(i))
+            .collect::<Vec<_>>();
+        if let Some(&Some(first)) = limits.first() {
+            if limits.iter().all(|&limit| limit == Some(first)) {
+                return write!(f, "{}", first);
+            }
+        }
+        let mut listed = Candies::none();
+        for (i, limit) in limits.into_iter().enumerate() {
+            listed.add_by_index(i, limit.unwrap_or(Count::ZERO));
+        }
+        f.write_str(&listed.compact())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::candies;
+    use crate::{advisor::Advisor, min_cap, scenario::Scenario, CandyWorks};
+
+    const SCENARIO: &str = "\
+cap = 20
+inventory = 3e 3w
+kind_cap = 4e
+trade = 1w -> 2e
+target = 4e
+";
+
+    fn problem() -> CandyWorks {
+        Scenario::parse(SCENARIO).unwrap().problem().unwrap()
+    }
+
+    #[test]
+    fn problem_keeps_the_caps() {
+        assert_eq!(
+            problem().kind_caps,
+            Some(KindCaps::of(&candies("4e")).unwrap())
+        );
+        let mut scenario = Scenario::parse(SCENARIO).unwrap();
+        scenario.reserved = candies("1e");
+        assert_eq!(
+            scenario.problem().unwrap().kind_caps.unwrap().limit(0),
+            Some(Count::new(3))
+        );
+    }
+
+    #[test]
+    fn routes_keep_within_the_caps() {
+        let mut candy_works = problem();
+        candy_works.explore();
+        assert!(candy_works
+            .combinations
+            .states()
+            .all(|candies| candy_works.fits(&candies)));
+        let route = candy_works.find_optimal_route(candies("4e")).unwrap();
+        let mut held = route.start();
+        for trade in route.trades() {
+            held = held.trade(trade).unwrap();
+            assert!(candy_works.fits(&held));
+        }
+        assert!(candy_works.find_optimal_route(candies("5e")).is_none());
+        assert!(candy_works.find_route_direct(candies("5e")).is_none());
+    }
+
+    #[test]
+    fn min_cap_keeps_within_the_caps() {
+        let candy_works = problem();
+        assert!(min_cap::find(&candy_works, candies("5e"), true).is_err());
+        assert_eq!(min_cap::find(&candy_works, candies("4e"), true), Ok(4));
+    }
+
+    #[test]
+    fn advice_keeps_within_the_caps() {
+        let mut advisor = Advisor::new(&problem(), candies("5e"));
+        assert_eq!(advisor.advise(candies("3e 3w")), None);
+        let mut advisor = Advisor::new(&problem(), candies("4e"));
+        let trade = advisor.advise(candies("3e 3w")).unwrap();
+        assert!(problem().fits(&candies("3e 3w").trade(&trade).unwrap()));
+    }
+}
@@ -0,0 +1,165 @@
+use std::sync::OnceLock;
+
+/// The most kinds of candy a run can have. `Candies` keeps a count for
+/// every one of them, unused ones at zero, so trading stays a fixed-length
+/// loop whatever the game.
+pub const MAX_KINDS: usize = 8;
+
+const NAMES: [&str; 5] = ["eggs", "worms", "cakes", "fishes", "meats"];
+const SINGULAR_NAMES: [&str; 5] = ["egg", "worm", "cake", "fish", "meat"];
+const LETTERS: [char; 5] = ['e', 'w', 'c', 'f', 'm'];
+
+/// A kind of candy: the letter the compact form writes it with and the
+/// names it is displayed and parsed by.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CandyKind {
+    pub letter: char,
+    pub singular: String,
+    pub plural: String,
+}
+
+/// The kinds of candy of the game being planned for, in the order
+/// `Candies` keeps their counts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CandyKinds(Vec<CandyKind>);
+
+impl CandyKinds {
+    /// Eggs, worms, cakes, fishes and meats.
+    pub fn builtin() -> CandyKinds {
+        CandyKinds(
+            (0..LETTERS.len())
+                .map(|i| CandyKind {
+                    letter: LETTERS[i],
+                    singular: SINGULAR_NAMES[i].to_string(),
+                    plural: NAMES[i].to_string(),
+                })
+                .collect(),
+        )
+    }
+
+    /// The kinds, if there are between two and `MAX_KINDS` of them, each
+    /// with its own lowercase letter and names.
+    pub fn new(kinds: Vec<CandyKind>) -> Result<CandyKinds, String> {
+        if !(2..=MAX_KINDS).contains(&kinds.len()) {
+            return Err(format!(
+                "expected 2 to {} kinds of candy, found {}",
+                MAX_KINDS,
+                kinds.len()
+            ));
+        }
+        for (i, kind) in kinds.iter().enumerate() {
+            if !kind.letter.is_ascii_lowercase() {
+                return Err(format!(
+                    "`{}` cannot be a candy letter: use a lowercase letter",
+                    kind.letter
+                ));
+            }
+            if kind.singular.is_empty() || kind.plural.is_empty() {
+                return Err("candy names cannot be empty".to_string());
+            }
+            let names =
+                |kind: &CandyKind| [kind.singular.to_lowercase(), kind.plural.to_lowercase()];
+            if let Some(other) = kinds[..i].iter().find(|other| {
+                other.letter == kind.letter
+                    || names(other).iter().any(|name| names(kind).contains(name))
+            }) {
+                return Err(format!(
+                    "{} and {} share a letter or a name",
+                    other.plural, kind.plural
+                ));
+            }
+        }
+        Ok(CandyKinds(kinds))
+    }
+
+    /// Reads a `--kinds` file: one kind per line, written as a scenario
+    /// names a candy, such as `g: Gem / Gems`. Without a plural one is
+    /// derived from the singular. `#` starts a comment.
+    pub fn parse(input: &str) -> Result<CandyKinds, String> {
+        let mut kinds = Vec::new();
+        for (number, line) in input.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let error = |message: &str| format!("line {}: {}", number + 1, message);
+            let (letter, names) = line
+                .split_once(':')
+                .ok_or_else(|| error("expected `g: Gem / Gems`"))?;
+            let mut letters = letter.trim().chars();
+            let (Some(letter), None) = (letters.next(), letters.next()) else {
+                return Err(error("expected a single letter before `:`"));
+            };
+            let (singular, plural) = match names.split_once('/') {
+                Some((singular, plural)) => (singular.trim(), plural.trim().to_string()),
+                None => (names.trim(), crate::names::pluralize(names.trim())),
+            };
+            kinds.push(CandyKind {
+                letter,
+                singular: singular.to_string(),
+                plural,
+            });
+        }
+        CandyKinds::new(kinds)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Never true: there are always at least two kinds.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> &CandyKind {
+        &self.0[index]
+    }
+
+    pub fn letter(&self, index: usize) -> char {
+        self.0[index].letter
+    }
+
+    pub fn letters(&self) -> impl Iterator<Item = char> + '_ {
+        self.0.iter().map(|kind| kind.letter)
+    }
+
+    /// The kind written with `letter`, which has to be lowercase.
+    pub fn position(&self, letter: char) -> Option<usize> {
+        self.0.iter().position(|kind| kind.letter == letter)
+    }
+
+    /// The letter to type for each kind, as the prompts explain them:
+    /// `E for eggs, W for worms, C for cakes, F for fishes and M for meats`.
+    pub fn legend(&self) -> String {
+        let parts = self
+            .0
+            .iter()
+            .map(|kind| format!("{} for {}", kind.letter.to_ascii_uppercase(), kind.plural))
+            .collect::<Vec<_>>();
+        let (last, rest) = parts.split_last().unwrap();
+        format!("{} and {}", rest.join(", "), last)
+    }
+
+    pub fn is_builtin(&self) -> bool {
+        *self == CandyKinds::builtin()
+    }
+}
+
+static ACTIVE: OnceLock<CandyKinds> = OnceLock::new();
+
+/// Sets the kinds of candy for the rest of the run. It has to be called
+/// before any candies are read or shown, and fails once other kinds are
+/// in use.
+pub fn configure(kinds: CandyKinds) -> Result<(), String> {
+    crate::configure_once(&ACTIVE, kinds, "the kinds of candy are")
+}
+
+pub fn active() -> &'static CandyKinds {
+    ACTIVE.get_or_init(CandyKinds::builtin)
+}
+
+/// How many kinds of candy the run has.
+pub fn count() -> usize {
+    active().len()
+}
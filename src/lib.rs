@@ -0,0 +1,1234 @@
+//! Finds routes of trades from the candies you have to the ones you want.
+//!
+//! A [`CandyWorks`] holds a planning problem: the starting inventory, the
+//! most candies a bag may hold and the trades on offer. Exploring it
+//! reaches every inventory the trades lead to within the cap, and each
+//! target is then reached by the route to the explored state holding it
+//! with the most candies left over.
+//!
+//! ```
+//! use candyworks::{Candies, CandyWorks, Trade};
+//!
+//! let start: Candies = "6e".parse().unwrap();
+//! let cake: Trade = "2e -> 1c".parse().unwrap();
+//! let mut candy_works = CandyWorks::builder(start)
+//!     .cap(20)
+//!     .trade(cake)
+//!     .build()
+//!     .unwrap();
+//! candy_works.explore();
+//! let route = candy_works
+//!     .find_optimal_route("3c".parse().unwrap())
+//!     .unwrap();
+//! assert_eq!(route.trades(), [cake; 3]);
+//! ```
+//!
+//! The command line tool is built on the same modules; most of them are
+//! public so that other front ends can use what it does.
+
+pub mod advisor;
+pub mod alternatives;
+pub mod backward;
+pub mod batch;
+pub mod bench;
+pub mod bloom;
+pub mod bridge;
+pub mod catalog;
+pub mod charges;
+pub mod check;
+pub mod compact;
+pub mod compare;
+pub mod components;
+pub mod config;
+pub mod conservation;
+pub mod cooperate;
+pub mod cost;
+pub mod count;
+pub mod dashboard;
+pub mod decision;
+pub mod delta;
+pub mod direct;
+pub mod display;
+pub mod dominance;
+pub mod donate;
+pub mod dot;
+pub mod economy;
+pub mod effort;
+pub mod error;
+pub mod explain;
+pub mod export;
+pub mod fallbacks;
+pub mod farm;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod flow;
+pub mod follow;
+pub mod frontier;
+pub mod fsck;
+pub mod graph;
+pub mod growth;
+pub mod guard;
+pub mod history;
+pub mod import;
+pub mod incremental;
+pub mod interrupt;
+pub mod json;
+pub mod kind_caps;
+pub mod kinds;
+pub mod macro_script;
+pub mod metrics;
+pub mod min_cap;
+pub mod min_trades;
+pub mod names;
+pub mod notes;
+pub mod objective;
+pub mod output;
+pub mod parallel;
+pub mod player;
+pub mod preset;
+pub mod profile;
+pub mod progress;
+pub mod projection;
+pub mod prompt;
+pub mod query;
+pub mod quests;
+pub mod random;
+pub mod reachable;
+pub mod reconcile;
+pub mod repeat;
+pub mod repl;
+pub mod replan;
+pub mod replay;
+pub mod report;
+pub mod route;
+pub mod route_file;
+pub mod scenario;
+pub mod sequence;
+pub mod shared;
+pub mod solve;
+pub mod solvers;
+pub mod standard;
+pub mod starts;
+pub mod statistics;
+pub mod storage;
+pub mod sustain;
+pub mod sweep;
+pub mod target_spec;
+pub mod template;
+#[cfg(test)]
+mod test_support;
+pub mod timings;
+pub mod tracker;
+pub mod trades_csv;
+pub mod travel;
+pub mod two_phase;
+pub mod uncertain;
+pub mod uses;
+pub mod value;
+pub mod vendor;
+pub mod verify;
+pub mod wide;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    hash::{Hash, Hasher},
+    str::FromStr,
+    sync::{Arc, OnceLock},
+    time::{Duration, Instant},
+};
+
+use bloom::Visited;
+use count::{Count, Counts};
+use display::DisplayOptions;
+use dominance::Dominated;
+use frontier::{Frontier, Queue};
+use kind_caps::KindCaps;
+use kinds::MAX_KINDS;
+use names::CandyNames;
+use notes::Notes;
+use objective::Objective;
+use preset::Preset;
+use progress::Progress;
+use route::Route;
+use storage::Combinations;
+use timings::{Phase, Timings};
+use uses::{Ranking, UseLimits};
+
+const ID_BITS: usize = 12;
+
+/// The cap of a problem that does not set one.
+pub const DEFAULT_CAP: u32 = 20;
+
+/// An inventory: how many of each kind of candy, in `kinds` order.
+#[derive(Debug, Clone, Copy, Eq, PartialOrd, Ord)]
+pub struct Candies {
+    /// Counts in `kinds` order, the kinds the run does not have at zero.
+    /// Kept as a plain array so trading is a fixed-length loop the
+    /// compiler can vectorize.
+    counts: [i32; MAX_KINDS],
+}
+
+// Compared and hashed count by count, as the separate fields were; the
+// derived array versions go through `memcmp` and hash a length prefix,
+// which made visited-set probes measurably slower.
+impl PartialEq for Candies {
+    fn eq(&self, other: &Candies) -> bool {
+        (0..MAX_KINDS).all(|i| self.counts[i] == other.counts[i])
+    }
+}
+
+impl Hash for Candies {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Two counts to a write, so the unused kinds cost no more writes
+        // than the five built-in ones did.
+        for pair in self.counts.chunks_exact(2) {
+            state.write_u64(pair[0] as u32 as u64 | (pair[1] as u32 as u64) << 32);
+        }
+    }
+}
+
+impl Candies {
+    pub fn trade(&self, trade: &Trade) -> Option<Candies> {
+        let counts: [i32; MAX_KINDS] = std::array::from_fn(|i| {
+            self.counts[i] + trade.receive.counts[i] - trade.give.counts[i]
+        });
+        // The sign bit survives the `|` if any count went negative, which
+        // checks all counts without a branch per candy.
+        let negative = counts.iter().fold(0, |negative, &count| negative | count);
+        (negative >= 0).then_some(Candies { counts })
+    }
+
+    /// The inventory `trade` must have been made from to end up here.
+    pub fn untrade(&self, trade: &Trade) -> Option<Candies> {
+        if !self.contains(&trade.receive) {
+            return None;
+        }
+        let mut counts = self.counts;
+        for (i, count) in counts.iter_mut().enumerate() {
+            *count += trade.give.counts[i] - trade.receive.counts[i];
+        }
+        let before = Candies { counts };
+        before.trade(trade).map(|_| before)
+    }
+
+    pub fn trade_all(&self, trades: &[Trade]) -> Option<Candies> {
+        trades
+            .iter()
+            .try_fold(*self, |candies, trade| candies.trade(trade))
+    }
+
+    pub fn total(&self) -> Count {
+        Count::new(self.counts.iter().sum())
+    }
+
+    pub fn contains(&self, other: &Candies) -> bool {
+        self.counts
+            .iter()
+            .zip(&other.counts)
+            .fold(true, |contains, (have, want)| contains & (have >= want))
+    }
+
+    /// These candies plus `other`.
+    pub fn with(&self, other: &Candies) -> Candies {
+        let counts = std::array::from_fn(|i| self.counts[i] + other.counts[i]);
+        Candies { counts }
+    }
+
+    /// These candies minus `other`, if they contain it.
+    pub fn without(&self, other: &Candies) -> Option<Candies> {
+        let counts = std::array::from_fn(|i| self.counts[i] - other.counts[i]);
+        self.contains(other).then_some(Candies { counts })
+    }
+
+    pub fn none() -> Candies {
+        Candies {
+            counts: [0; MAX_KINDS],
+        }
+    }
+
+    pub fn add_by_index(&mut self, index: usize, value: Count) {
+        if let Some(count) = self.counts.get_mut(index) {
+            *count += value.get();
+        }
+    }
+
+    pub fn get_by_index(&self, index: usize) -> Count {
+        Count::new(self.counts.get(index).copied().unwrap_or(0))
+    }
+
+    /// A stable identifier packing each count into 12 bits, so the same
+    /// inventory has the same ID across runs and graph files. Inventories
+    /// with a count outside `0..4096` have no ID. With more than five kinds
+    /// of candy the counts get the bits that fit in 64, 8 for eight kinds.
+    pub fn id(&self) -> Option<u64> {
+        let bits = id_bits();
+        let mut id = 0;
+        for i in (0..kinds::count()).rev() {
+            let count = self.get_by_index(i);
+            if !(0..1 << bits).contains(&count.get()) {
+                return None;
+            }
+            id = id << bits | count.get() as u64;
+        }
+        Some(id)
+    }
+
+    pub fn id_label(&self) -> String {
+        self.id()
+            .map_or_else(|| "-".to_string(), |id| id.to_string())
+    }
+
+    pub fn from_id(id: u64) -> Option<Candies> {
+        let bits = id_bits();
+        if bits * kinds::count() < 64 && id >> (bits * kinds::count()) != 0 {
+            return None;
+        }
+        let mut candies = Candies::none();
+        for i in 0..kinds::count() {
+            candies.add_by_index(i, Count::new((id >> (bits * i) & ((1 << bits) - 1)) as i32));
+        }
+        Some(candies)
+    }
+
+    pub fn compact(&self) -> String {
+        let parts = kinds::active()
+            .letters()
+            .enumerate()
+            .filter(|(i, _)| self.get_by_index(*i) != Count::ZERO)
+            .map(|(i, c)| format!("{}{}", self.get_by_index(i), c))
+            .collect::<Vec<_>>();
+        if parts.is_empty() {
+            "none".to_string()
+        } else {
+            parts.join(" ")
+        }
+    }
+
+    /// The candies written out with the `display` settings of the run,
+    /// padded so that inventories line up; with `include_zeros`, candies
+    /// the inventory has none of are listed too.
+    pub fn display(&self, include_zeros: bool) -> String {
+        let active = display::active();
+        self.display_with(&DisplayOptions {
+            order: active.order.clone(),
+            include_zeros: include_zeros || active.include_zeros,
+            color: active.color,
+        })
+    }
+}
+
+/// Why candies or a trade could not be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseCandiesError(String);
+
+impl fmt::Display for ParseCandiesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ParseCandiesError {}
+
+impl Candies {
+    /// Parses either the compact form (`3e2w`, `3e 2w`, `eee`) or the
+    /// display form (`3 eggs, 2 worms`) using the given candy names; `none`
+    /// and the empty string are no candies.
+    pub fn parse_with(input: &str, names: &CandyNames) -> Result<Candies, ParseCandiesError> {
+        Counts::parse_with(input, names).map(Candies::from)
+    }
+}
+
+impl FromStr for Candies {
+    type Err = ParseCandiesError;
+
+    fn from_str(input: &str) -> Result<Candies, ParseCandiesError> {
+        Candies::parse_with(input, names::active())
+    }
+}
+
+/// The bits each count gets in an ID.
+fn id_bits() -> usize {
+    (u64::BITS as usize / kinds::count()).min(ID_BITS)
+}
+
+impl fmt::Display for Candies {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.display(false))
+    }
+}
+
+/// A trade a vendor offers: `give` for `receive`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Trade {
+    pub give: Candies,
+    pub receive: Candies,
+}
+
+impl Trade {
+    /// The standard trade of `ratio` of candy `a` for one of candy `b`.
+    pub fn standard_trade(a: usize, b: usize, ratio: u32) -> Self {
+        let mut give = Candies::none();
+        let mut receive = Candies::none();
+        give.add_by_index(a, Count::new(ratio as i32));
+        receive.add_by_index(b, Count::new(1));
+        Trade { give, receive }
+    }
+}
+
+impl Trade {
+    pub fn parse_with(input: &str, names: &CandyNames) -> Result<Trade, ParseCandiesError> {
+        let (give, receive) = input.split_once("->").ok_or_else(|| {
+            ParseCandiesError("missing `->` between give and receive".to_string())
+        })?;
+        Ok(Trade {
+            give: Candies::parse_with(give, names)?,
+            receive: Candies::parse_with(receive, names)?,
+        })
+    }
+}
+
+impl FromStr for Trade {
+    type Err = ParseCandiesError;
+
+    fn from_str(input: &str) -> Result<Trade, ParseCandiesError> {
+        Trade::parse_with(input, names::active())
+    }
+}
+
+impl fmt::Display for Trade {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} -> {}", self.give, self.receive)
+    }
+}
+
+/// The ceiling an exploration hit before it could reach every state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exhausted {
+    Time,
+    States,
+    /// Ctrl-C was pressed while `interrupt` caught it.
+    Interrupted,
+}
+
+/// How far an exploration got, and so how far its answers can be trusted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exploration {
+    /// Every reachable state was explored.
+    Complete,
+    /// Every state within the cap was explored, and some trades were left
+    /// out for going over it: the answers are the best within the cap.
+    Capped,
+    /// It stopped at a ceiling before reaching every state, so better
+    /// routes, or routes to targets it did not reach, may exist.
+    Stopped(Exhausted),
+}
+
+impl Exploration {
+    /// Whether every state within the cap was explored.
+    pub fn is_complete(self) -> bool {
+        !matches!(self, Exploration::Stopped(_))
+    }
+}
+
+impl fmt::Display for Exploration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Exploration::Complete => "complete",
+            Exploration::Capped => "complete within the cap",
+            Exploration::Stopped(Exhausted::Time) => "stopped at the time limit",
+            Exploration::Stopped(Exhausted::States) => "stopped at the state limit",
+            Exploration::Stopped(Exhausted::Interrupted) => "stopped when interrupted",
+        })
+    }
+}
+
+/// What an exploration found, as `stats` prints it.
+pub struct Statistics {
+    /// Number of states explored.
+    pub combinations: usize,
+    /// The fewest and the most candies any state holds.
+    pub min_candies: Count,
+    pub max_candies: Count,
+    /// The most trades any state is first reached after.
+    pub max_trades: usize,
+    /// Number of states first reached after each number of trades.
+    pub depth_counts: Vec<usize>,
+    /// How far the exploration the statistics describe got; `None` for a
+    /// graph read from a file.
+    pub exploration: Option<Exploration>,
+    /// Trades that can be made from the states, summed over all of them.
+    pub edges: usize,
+    pub max_branching: usize,
+    /// States from which no trade can be made.
+    pub dead_ends: usize,
+}
+
+impl Statistics {
+    fn count_branching(&mut self, branching: usize) {
+        self.edges += branching;
+        self.max_branching = self.max_branching.max(branching);
+        if branching == 0 {
+            self.dead_ends += 1;
+        }
+    }
+}
+
+impl fmt::Display for Statistics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Total combinations: {}", self.combinations)?;
+        writeln!(f, "Min candies: {}", self.min_candies)?;
+        writeln!(f, "Max candies: {}", self.max_candies)?;
+        writeln!(f, "Max trades: {}", self.max_trades)?;
+        let counts = self
+            .depth_counts
+            .iter()
+            .map(|count| count.to_string())
+            .collect::<Vec<_>>();
+        writeln!(f, "States by trades: {}", counts.join(" "))?;
+        writeln!(
+            f,
+            "Branching: {:.2} trades per state on average, at most {}",
+            self.edges as f64 / self.combinations as f64,
+            self.max_branching
+        )?;
+        write!(f, "Dead ends: {}", self.dead_ends)?;
+        match self.exploration {
+            Some(exploration) => write!(f, "\nExploration: {}", exploration),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A planning problem and, once explored, every state reachable from its
+/// starting inventory with the trade first reaching it.
+pub struct CandyWorks {
+    /// The starting inventory.
+    pub candies: Candies,
+    /// The most candies a state may hold.
+    pub max_candies: u32,
+    /// The most of each kind a state may hold, when kinds are limited
+    /// besides the total.
+    pub kind_caps: Option<KindCaps>,
+    /// The trades on offer, the standard ones included.
+    pub trades: Vec<Trade>,
+    /// How many times the trades a vendor makes only so often can be made
+    /// along a route. Exploring is not limited by them, but every route
+    /// found keeps to them.
+    pub use_limits: UseLimits,
+    /// The notes on its trades, shown with the steps of its routes.
+    pub notes: Notes,
+    /// The explored states; empty before exploring.
+    pub combinations: Combinations,
+    /// Front the visited set with a Bloom filter while exploring.
+    pub prefilter: bool,
+    /// Skip states holding no more of any candy than one already explored.
+    /// Exact for routes when no trade receives more candies than it gives,
+    /// but the graph and its statistics hold fewer states.
+    pub prune_dominated: bool,
+    /// The order states are expanded in while exploring.
+    pub frontier: Frontier,
+    /// What makes the route `find_optimal_route` picks the best.
+    pub objective: Objective,
+    /// Where exploring and searching add up how long they took, shared
+    /// with the other problems of the run; `None` to leave them untimed.
+    pub timings: Option<Arc<Timings>>,
+    /// How far the last exploration got; `None` before exploring and for
+    /// graphs read from files, which do not record it.
+    exploration: Option<Exploration>,
+}
+
+impl CandyWorks {
+    /// The problem of trading from `candies`, with the standard trades of
+    /// three of one candy for one of another added to `custom_trades`.
+    /// `builder` checks the problem first.
+    pub fn new(candies: Candies, max_candies: u32, custom_trades: Vec<Trade>) -> Self {
+        let mut trades = custom_trades;
+        trades.extend(standard::trades(&Preset::builtin()));
+        CandyWorks {
+            candies,
+            max_candies,
+            kind_caps: None,
+            trades,
+            use_limits: UseLimits::default(),
+            notes: Notes::default(),
+            combinations: Combinations::new(),
+            prefilter: false,
+            prune_dominated: false,
+            frontier: Frontier::default(),
+            objective: Objective::default(),
+            timings: None,
+            exploration: None,
+        }
+    }
+
+    /// A problem starting from `candies`, to be given its cap and trades.
+    pub fn builder(candies: Candies) -> CandyWorksBuilder {
+        CandyWorksBuilder {
+            candies,
+            max_candies: DEFAULT_CAP,
+            kind_caps: None,
+            trades: Vec::new(),
+            use_limits: UseLimits::default(),
+            notes: Notes::default(),
+            preset: Preset::builtin(),
+            standard_trades: true,
+            prefilter: false,
+            prune_dominated: false,
+            frontier: Frontier::default(),
+            objective: Objective::default(),
+        }
+    }
+
+    /// The same problem with nothing explored yet.
+    pub fn unexplored(&self) -> CandyWorks {
+        CandyWorks {
+            candies: self.candies,
+            max_candies: self.max_candies,
+            kind_caps: self.kind_caps,
+            trades: self.trades.clone(),
+            use_limits: self.use_limits.clone(),
+            notes: self.notes.clone(),
+            combinations: Combinations::new(),
+            prefilter: self.prefilter,
+            prune_dominated: self.prune_dominated,
+            frontier: self.frontier.clone(),
+            objective: self.objective.clone(),
+            timings: self.timings.clone(),
+            exploration: None,
+        }
+    }
+
+    /// Whether a state holding `candies` keeps within the cap, and within
+    /// the caps of the kinds if there are any.
+    pub fn fits(&self, candies: &Candies) -> bool {
+        candies.total() <= Count::new(self.max_candies as i32)
+            && self.kind_caps.is_none_or(|caps| caps.allows(candies))
+    }
+
+    /// Explores every state reachable within the cap.
+    pub fn explore(&mut self) {
+        self.explore_from(&[self.candies], None);
+    }
+
+    /// Explores for at most `limit`, keeping whatever was reached when time
+    /// runs out. Returns whether the exploration completed.
+    pub fn explore_within(&mut self, limit: Option<Duration>) -> bool {
+        let deadline = limit.map(|limit| Instant::now() + limit);
+        self.explore_from(&[self.candies], deadline)
+    }
+
+    pub fn explore_from(&mut self, roots: &[Candies], deadline: Option<Instant>) -> bool {
+        self.explore_bounded(roots, deadline, None, None).is_ok()
+    }
+
+    /// Like `explore_within`, showing `progress` along the way.
+    pub fn explore_reporting(&mut self, limit: Option<Duration>, progress: &mut Progress) -> bool {
+        let deadline = limit.map(|limit| Instant::now() + limit);
+        self.explore_bounded(&[self.candies], deadline, None, Some(progress))
+            .is_ok()
+    }
+
+    /// Like `explore_from`, but also stops once more than `max_states`
+    /// states are stored, reporting which ceiling was hit.
+    pub fn explore_bounded(
+        &mut self,
+        roots: &[Candies],
+        deadline: Option<Instant>,
+        max_states: Option<usize>,
+        mut progress: Option<&mut Progress>,
+    ) -> Result<(), Exhausted> {
+        let started = Instant::now();
+        let timed = self.timings.is_some();
+        let (mut hashing, mut storing) = (Duration::ZERO, Duration::ZERO);
+        let mut outcome = Ok(());
+        let mut capped = false;
+        let mut collections = Combinations::new();
+        let mut known_sets = Visited::new(self.prefilter);
+        let mut dominated = self.prune_dominated.then(Dominated::new);
+        let mut queue = Queue::new(&self.frontier);
+        let kind_caps = self.kind_caps;
+        let trades = self
+            .trades
+            .iter()
+            .map(|trade| (*trade, collections.trade_index(*trade)))
+            .collect::<Vec<_>>();
+        for root in roots {
+            if known_sets.insert(*root) {
+                if let Some(dominated) = dominated.as_mut() {
+                    dominated.cover(*root);
+                }
+                queue.push_root(collections.len(), root);
+                collections.push((*root, None));
+            }
+        }
+        while let Some(index) = queue.pop() {
+            if index % 1024 == 0 && deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                outcome = Err(Exhausted::Time);
+                break;
+            }
+            if index % 1024 == 0 && interrupt::requested() {
+                outcome = Err(Exhausted::Interrupted);
+                break;
+            }
+            if index % 1024 == 0 {
+                if let Some(progress) = progress.as_mut() {
+                    progress.tick(&collections, queue.len());
+                }
+            }
+            if max_states.is_some_and(|max_states| collections.len() > max_states) {
+                outcome = Err(Exhausted::States);
+                break;
+            }
+            let candies = collections.state(index);
+            for (trade, trade_index) in &trades {
+                if let Some(new_candies) = candies.trade(trade) {
+                    let total = new_candies.total();
+                    if total > Count::new(self.max_candies as i32)
+                        || kind_caps.is_some_and(|caps| !caps.allows(&new_candies))
+                    {
+                        capped = true;
+                        continue;
+                    }
+                    if let Some(dominated) = dominated.as_mut() {
+                        if dominated.covers(&new_candies) {
+                            continue;
+                        }
+                        dominated.cover(new_candies);
+                    }
+                    if !timed {
+                        if known_sets.insert(new_candies) {
+                            queue.push(collections.len(), &new_candies);
+                            collections.push_child(new_candies, index, *trade_index);
+                        }
+                        continue;
+                    }
+                    let t = Instant::now();
+                    let fresh = known_sets.insert(new_candies);
+                    hashing += t.elapsed();
+                    if fresh {
+                        let t = Instant::now();
+                        queue.push(collections.len(), &new_candies);
+                        collections.push_child(new_candies, index, *trade_index);
+                        storing += t.elapsed();
+                    }
+                }
+            }
+        }
+        if let Some(progress) = progress {
+            progress.finish();
+        }
+        self.combinations = collections;
+        self.exploration = Some(match outcome {
+            Err(exhausted) => Exploration::Stopped(exhausted),
+            Ok(()) if capped => Exploration::Capped,
+            Ok(()) => Exploration::Complete,
+        });
+        let timings = self.timings.as_deref();
+        timings::record(timings, Phase::Exploration, started.elapsed());
+        timings::record(timings, Phase::Hashing, hashing);
+        timings::record(timings, Phase::Storing, storing);
+        outcome
+    }
+
+    /// How far the last exploration got, for answers to say how far they
+    /// can be trusted.
+    pub fn exploration(&self) -> Option<Exploration> {
+        self.exploration
+    }
+
+    pub fn roots(&self) -> Vec<Candies> {
+        self.combinations
+            .iter()
+            .filter(|(_, parent)| parent.is_none())
+            .map(|(candies, _)| candies)
+            .collect()
+    }
+
+    pub fn depths(&self) -> Vec<usize> {
+        let mut depths: Vec<usize> = Vec::with_capacity(self.combinations.len());
+        for (_, parent) in self.combinations.iter() {
+            depths.push(parent.map_or(0, |(index, _)| depths[index] + 1));
+        }
+        depths
+    }
+
+    /// How many trades can be made from `candies` without passing the cap.
+    pub fn branching(&self, candies: &Candies) -> usize {
+        self.trades
+            .iter()
+            .filter_map(|trade| candies.trade(trade))
+            .filter(|candies| self.fits(candies))
+            .count()
+    }
+
+    pub fn statistics(&self) -> Option<Statistics> {
+        if self.combinations.is_empty() {
+            return None;
+        }
+        let min_candies = self
+            .combinations
+            .states()
+            .map(|candies| candies.total())
+            .min()
+            .unwrap();
+        let max_candies = self
+            .combinations
+            .states()
+            .map(|candies| candies.total())
+            .max()
+            .unwrap();
+        let max_trades = self
+            .combinations
+            .iter()
+            .filter(|(_, parent)| parent.is_some())
+            .map(|(_, parent)| self.len_from_combination(parent.unwrap().0))
+            .max()
+            .unwrap_or(0);
+        let mut depth_counts = vec![0; max_trades + 1];
+        for depth in self.depths() {
+            depth_counts[depth] += 1;
+        }
+        let mut stats = Statistics {
+            combinations: self.combinations.len(),
+            min_candies,
+            max_candies,
+            max_trades,
+            depth_counts,
+            edges: 0,
+            max_branching: 0,
+            dead_ends: 0,
+            exploration: self.exploration,
+        };
+        for candies in self.combinations.states() {
+            stats.count_branching(self.branching(&candies));
+        }
+        Some(stats)
+    }
+
+    /// Computes the same statistics as exploring and calling `statistics`,
+    /// but level by level without storing the graph: only the visited set
+    /// and the current frontier are kept, for caps too large to hold every
+    /// state with its parent.
+    pub fn streaming_statistics(&self) -> Statistics {
+        let total = self.candies.total();
+        let mut stats = Statistics {
+            combinations: 1,
+            min_candies: total,
+            max_candies: total,
+            max_trades: 0,
+            depth_counts: vec![1],
+            edges: 0,
+            max_branching: 0,
+            dead_ends: 0,
+            exploration: Some(Exploration::Complete),
+        };
+        let mut known = HashSet::from([self.candies]);
+        let mut frontier = vec![self.candies];
+        loop {
+            let mut next = Vec::new();
+            for candies in &frontier {
+                let mut branching = 0;
+                for trade in &self.trades {
+                    if let Some(new_candies) = candies.trade(trade) {
+                        let total = new_candies.total();
+                        if !self.fits(&new_candies) {
+                            stats.exploration = Some(Exploration::Capped);
+                            continue;
+                        }
+                        branching += 1;
+                        if known.insert(new_candies) {
+                            stats.min_candies = stats.min_candies.min(total);
+                            stats.max_candies = stats.max_candies.max(total);
+                            next.push(new_candies);
+                        }
+                    }
+                }
+                stats.count_branching(branching);
+            }
+            if next.is_empty() {
+                return stats;
+            }
+            stats.combinations += next.len();
+            stats.depth_counts.push(next.len());
+            stats.max_trades += 1;
+            frontier = next;
+        }
+    }
+
+    pub fn len_from_combination(&self, index: usize) -> usize {
+        let mut current = index;
+        let mut len = 0;
+        while let Some((_, parent)) = self.combinations.get(current) {
+            len += 1;
+            if let Some((i, _)) = parent {
+                current = i;
+            } else {
+                break;
+            }
+        }
+        len
+    }
+
+    /// The explored state holding `target` that is best by the objective.
+    /// For the fewest trades that is the end of `fewest_trades_route`,
+    /// whose route may be shorter than the one the graph keeps to it.
+    pub fn find_optimal_combination(&self, target: Candies) -> Option<usize> {
+        if let Objective::MinTrades = self.objective {
+            let end = self.fewest_trades_route(target)?.end();
+            return self.combinations.states().position(|state| state == end);
+        }
+        let max = self
+            .combinations
+            .states()
+            .filter(|candies| candies.contains(&target))
+            .map(|candies| self.objective.score(&candies))
+            .max()?;
+        self.combinations
+            .states()
+            .position(|candies| candies.contains(&target) && self.objective.score(&candies) == max)
+    }
+
+    pub fn find_by_id(&self, id: u64) -> Option<usize> {
+        let candies = Candies::from_id(id)?;
+        self.combinations
+            .states()
+            .position(|state| state == candies)
+    }
+
+    /// The trades from the root to state `index`. Parents always come
+    /// before their children, as `load` makes sure of for graphs read from
+    /// files, so following them always ends at a root.
+    pub fn route_to(&self, index: usize) -> Vec<Trade> {
+        let mut result = Vec::new();
+        let mut current = self.combinations.parent(index);
+        while let Some((parent, trade)) = current {
+            result.push(trade);
+            current = self.combinations.parent(parent);
+        }
+        result.reverse();
+        result
+    }
+
+    pub fn root_of(&self, index: usize) -> Candies {
+        let mut current = index;
+        while let Some((parent, _)) = self.combinations.parent(current) {
+            current = parent;
+        }
+        self.combinations.state(current)
+    }
+
+    /// The route to state `index`, planned from its root. Exploring only
+    /// keeps states where their parent's trade leads, and `load` refuses
+    /// graph files holding any other, so the route always replays.
+    pub fn route(&self, index: usize) -> Route {
+        Route::new(self.root_of(index), self.route_to(index))
+            .expect("every state is reached by its parent's trade")
+            .found_in(self.exploration)
+    }
+
+    /// The best route to an explored state holding `target` by the
+    /// objective, or `None` if no explored state holds it. A target already
+    /// held needs no trades, unless the candies are weighted and a route
+    /// could leave more of their value. The route keeps to the use limits,
+    /// searched for apart from the graph when the one in it breaks them.
+    pub fn find_optimal_route(&self, target: Candies) -> Option<Route> {
+        match &self.objective {
+            Objective::MaxWeightedValue(_) => (),
+            _ if self.candies.contains(&target) => {
+                return Some(Route::empty(self.candies).found_in(self.exploration));
+            }
+            Objective::MinTrades => {
+                return timings::time(self.timings.as_deref(), Phase::Search, || {
+                    self.fewest_trades_route(target)
+                });
+            }
+            Objective::MaxLeftoverTotal => (),
+        }
+        let timings = self.timings.as_deref();
+        timings::time(timings, Phase::Search, || {
+            self.find_optimal_combination(target)
+        })
+        .map(|index| timings::time(timings, Phase::Reconstruction, || self.route(index)))
+        .and_then(|route| {
+            self.keep_to_uses(
+                route,
+                |candies| candies.contains(&target),
+                Ranking::Objective,
+            )
+        })
+    }
+
+    /// The best route to `target` from `from` instead of the starting
+    /// inventory, with the same trades and cap. The explored graph only
+    /// holds one route to each state, so the states reachable from `from`
+    /// are explored afresh.
+    pub fn route_from(&self, from: Candies, target: Candies) -> Option<Route> {
+        let mut moved = self.unexplored();
+        moved.candies = from;
+        moved.explore();
+        moved.find_optimal_route(target)
+    }
+}
+
+/// Why `CandyWorksBuilder::build` refused a problem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildError {
+    /// The inventory, or a side of a trade, has a count below zero.
+    Negative(Candies),
+    /// The trade can take a count past what an `i32` holds without
+    /// passing the cap.
+    Overflow(Trade),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BuildError::Negative(candies) => {
+                write!(f, "({}) has a count below zero", candies.compact())
+            }
+            BuildError::Overflow(trade) => write!(
+                f,
+                "the trade {} -> {} can take a count past what an i32 holds under the cap",
+                trade.give.compact(),
+                trade.receive.compact()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+/// Sets up a `CandyWorks` and checks it can be explored, which `new`
+/// leaves to the caller.
+pub struct CandyWorksBuilder {
+    candies: Candies,
+    max_candies: u32,
+    kind_caps: Option<KindCaps>,
+    trades: Vec<Trade>,
+    use_limits: UseLimits,
+    notes: Notes,
+    preset: Preset,
+    standard_trades: bool,
+    prefilter: bool,
+    prune_dominated: bool,
+    frontier: Frontier,
+    objective: Objective,
+}
+
+impl CandyWorksBuilder {
+    /// The most candies a state may hold, `DEFAULT_CAP` if not set.
+    pub fn cap(self, max_candies: u32) -> CandyWorksBuilder {
+        CandyWorksBuilder {
+            max_candies,
+            ..self
+        }
+    }
+
+    /// The most of each kind a state may hold, besides the cap on the
+    /// total.
+    pub fn kind_caps(self, kind_caps: KindCaps) -> CandyWorksBuilder {
+        CandyWorksBuilder {
+            kind_caps: Some(kind_caps),
+            ..self
+        }
+    }
+
+    /// Offers `trade` as well as the standard trades.
+    pub fn trade(mut self, trade: Trade) -> CandyWorksBuilder {
+        self.trades.push(trade);
+        self
+    }
+
+    /// Offers each of `trades` as well as the standard trades.
+    pub fn trades(mut self, trades: impl IntoIterator<Item = Trade>) -> CandyWorksBuilder {
+        self.trades.extend(trades);
+        self
+    }
+
+    /// How many times trades can be made along a route; see
+    /// `CandyWorks::use_limits`.
+    pub fn use_limits(self, use_limits: UseLimits) -> CandyWorksBuilder {
+        CandyWorksBuilder { use_limits, ..self }
+    }
+
+    /// The notes on the trades, shown with the steps of routes.
+    pub fn notes(self, notes: Notes) -> CandyWorksBuilder {
+        CandyWorksBuilder { notes, ..self }
+    }
+
+    /// The preset whose standard trades and specials are offered besides
+    /// the trades given, the standard 3:1 trades if not set.
+    pub fn preset(self, preset: Preset) -> CandyWorksBuilder {
+        CandyWorksBuilder { preset, ..self }
+    }
+
+    /// Whether the standard trades are offered besides those given, as
+    /// they are if not set; without them, the trades given are all there
+    /// is.
+    pub fn standard_trades(self, standard_trades: bool) -> CandyWorksBuilder {
+        CandyWorksBuilder {
+            standard_trades,
+            ..self
+        }
+    }
+
+    /// Fronts the visited set with a Bloom filter while exploring.
+    pub fn prefilter(self, prefilter: bool) -> CandyWorksBuilder {
+        CandyWorksBuilder { prefilter, ..self }
+    }
+
+    /// Skips states dominated by one already explored; see
+    /// `CandyWorks::prune_dominated`.
+    pub fn prune_dominated(self, prune_dominated: bool) -> CandyWorksBuilder {
+        CandyWorksBuilder {
+            prune_dominated,
+            ..self
+        }
+    }
+
+    /// The order states are expanded in while exploring.
+    pub fn frontier(self, frontier: Frontier) -> CandyWorksBuilder {
+        CandyWorksBuilder { frontier, ..self }
+    }
+
+    /// What makes one route better than another, the most candies left if
+    /// not set.
+    pub fn objective(self, objective: Objective) -> CandyWorksBuilder {
+        CandyWorksBuilder { objective, ..self }
+    }
+
+    /// The problem, if no count can go below zero or past what an `i32`
+    /// holds while exploring it.
+    pub fn build(self) -> Result<CandyWorks, BuildError> {
+        let negative =
+            |candies: Candies| (0..kinds::count()).any(|i| candies.get_by_index(i) < Count::ZERO);
+        for candies in [self.candies].into_iter().chain(
+            self.trades
+                .iter()
+                .flat_map(|trade| [trade.give, trade.receive]),
+        ) {
+            if negative(candies) {
+                return Err(BuildError::Negative(candies));
+            }
+        }
+        // States stay within the cap, so a trade can only take a count
+        // past what an i32 holds by receiving more than is left above it.
+        let start = (0..kinds::count())
+            .map(|i| i64::from(self.candies.get_by_index(i)))
+            .sum::<i64>();
+        let ceiling = i64::from(self.max_candies).max(start);
+        for trade in &self.trades {
+            let most = (0..kinds::count())
+                .map(|i| i64::from(trade.receive.get_by_index(i)))
+                .sum::<i64>();
+            if ceiling + most > i64::from(i32::MAX) {
+                return Err(BuildError::Overflow(*trade));
+            }
+        }
+        let own = self.trades.len();
+        let mut candy_works = CandyWorks::new(self.candies, self.max_candies, self.trades);
+        candy_works.trades.truncate(own);
+        if self.standard_trades {
+            candy_works.trades.extend(standard::trades(&self.preset));
+        }
+        candy_works.kind_caps = self.kind_caps;
+        candy_works.use_limits = self.use_limits;
+        candy_works.notes = self.notes;
+        candy_works.prefilter = self.prefilter;
+        candy_works.prune_dominated = self.prune_dominated;
+        candy_works.frontier = self.frontier;
+        candy_works.objective = self.objective;
+        Ok(candy_works)
+    }
+}
+
+/// Sets `cell`, a setting of the run, to `value`. Setting it again is
+/// only allowed to the same value, since what the run already did with
+/// the first would not match: `what`, as in `the kinds of candy are`, says
+/// which setting differs.
+pub fn configure_once<T: PartialEq>(
+    cell: &OnceLock<T>,
+    value: T,
+    what: &str,
+) -> Result<(), String> {
+    match cell.set(value) {
+        Err(value) if cell.get() != Some(&value) => {
+            Err(format!("{} already set otherwise for this run", what))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// One side of a trade as a sentence, `nothing` for no candies.
+pub fn display_side(candies: &Candies) -> String {
+    if *candies == Candies::none() {
+        "nothing".to_string()
+    } else {
+        candies.display(false).trim().to_string()
+    }
+}
+
+impl CandyWorks {
+    /// `route` from `start` as the CLI prints it, with the uses left by the
+    /// problem's limits and its notes; see `format_route_with`.
+    pub fn format_route(&self, start: Candies, route: &[Trade]) -> String {
+        format_route_with(start, route, &self.use_limits, &self.notes)
+    }
+}
+
+/// `route` as the CLI prints it: each inventory along the way with the
+/// trade made from it, lined up, and the inventory it ends with. A trade
+/// made several times in a row, or a multiple of one searched as a single
+/// step, is one line such as `3e -> 1m (x4)`. Limited trades show the uses
+/// `limits` leaves them, flagging the steps making a trade's last use, and
+/// every trade shows its note in `notes`.
+pub fn format_route_with(
+    start: Candies,
+    route: &[Trade],
+    limits: &UseLimits,
+    notes: &Notes,
+) -> String {
+    let route = repeat::expand(route);
+    let width = route
+        .iter()
+        .map(|trade| trade.give.to_string().len())
+        .max()
+        .unwrap_or(0);
+    let mut out = String::new();
+    let mut previous = start;
+    let mut used = HashMap::<Trade, u32>::new();
+    for (trade, times) in repeat::runs(&route) {
+        let used = used.entry(trade).or_insert(0);
+        *used += times as u32;
+        out.push_str(&format!(
+            "({}) {}{}{}{}\n",
+            previous.display(true),
+            trade.to_aligned_string(width),
+            repeat::suffix(times),
+            limits.suffix(&trade, *used),
+            notes.suffix(&trade)
+        ));
+        for _ in 0..times {
+            previous = previous.trade(&trade).unwrap();
+        }
+    }
+    out.push_str(&format!("({})\n", previous.display(true)));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn configuring_again_needs_the_same_value() {
+        static SETTING: OnceLock<u32> = OnceLock::new();
+        assert_eq!(configure_once(&SETTING, 3, "the setting is"), Ok(()));
+        assert_eq!(configure_once(&SETTING, 3, "the setting is"), Ok(()));
+        assert_eq!(
+            configure_once(&SETTING, 4, "the setting is"),
+            Err("the setting is already set otherwise for this run".to_string())
+        );
+        assert_eq!(SETTING.get(), Some(&3));
+    }
+}
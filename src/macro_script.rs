@@ -0,0 +1,113 @@
+use std::{fs, path::Path};
+
+use crate::{notes::Notes, Trade};
+
+/// How `--macro` writes a route for in-game macro tools: a header, one
+/// step for every run of the same trade and a footer. Placeholders are
+/// `{steps}` in the header and footer, and `{step}`, `{offer}`, `{give}`,
+/// `{receive}`, `{trade}`, `{times}` and `{note}`, the trade's note or
+/// nothing, in a step.
+pub struct MacroTemplate {
+    header: String,
+    step: String,
+    footer: String,
+}
+
+/// `text` without the padding aligned output puts between words.
+fn words(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+impl Default for MacroTemplate {
+    fn default() -> Self {
+        MacroTemplate {
+            header: "# {steps} trades".to_string(),
+            step: "offer {offer} x{times}  # {trade}".to_string(),
+            footer: String::new(),
+        }
+    }
+}
+
+impl MacroTemplate {
+    /// Reads a template of `header`, `step` and `footer` lines in the
+    /// `key = value` form; repeating a key adds a line to it, and `\n` and
+    /// `\t` stand for a newline and a tab.
+    pub fn load(path: &Path) -> Result<MacroTemplate, String> {
+        let input = crate::replay::read_to_string(path).map_err(|err| err.to_string())?;
+        let mut parts: [Option<String>; 3] = [None, None, None];
+        for (number, line) in input.lines().enumerate() {
+            if line.trim().is_empty() || line.trim_start().starts_with('#') {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: expected `key = value`", number + 1))?;
+            let index = match key.trim() {
+                "header" => 0,
+                "step" => 1,
+                "footer" => 2,
+                key => return Err(format!("line {}: unknown key `{}`", number + 1, key)),
+            };
+            let value = value.trim().replace("\\n", "\n").replace("\\t", "\t");
+            parts[index] = Some(match parts[index].take() {
+                Some(previous) => format!("{}\n{}", previous, value),
+                None => value,
+            });
+        }
+        let [header, step, footer] = parts;
+        let default = MacroTemplate::default();
+        Ok(MacroTemplate {
+            header: header.unwrap_or(default.header),
+            step: step.ok_or("the template has no `step`")?,
+            footer: footer.unwrap_or(default.footer),
+        })
+    }
+
+    /// Writes `route` with the template, the trades' notes taken from
+    /// `notes`. Offers are numbered from 1 in the order of `offers`.
+    pub fn render(&self, route: &[Trade], offers: &[Trade], notes: &Notes) -> String {
+        let steps = route.len().to_string();
+        let mut out = String::new();
+        let mut line = |text: String| {
+            if !text.is_empty() {
+                out.push_str(&text);
+                out.push('\n');
+            }
+        };
+        line(self.header.replace("{steps}", &steps));
+        let mut start = 0;
+        let mut number = 0;
+        while start < route.len() {
+            let trade = route[start];
+            let times = route[start..].iter().take_while(|t| **t == trade).count();
+            number += 1;
+            let offer = offers
+                .iter()
+                .position(|offer| *offer == trade)
+                .map_or("?".to_string(), |i| (i + 1).to_string());
+            line(
+                self.step
+                    .replace("{step}", &number.to_string())
+                    .replace("{offer}", &offer)
+                    .replace("{give}", &trade.give.compact())
+                    .replace("{receive}", &trade.receive.compact())
+                    .replace("{trade}", &words(&trade.to_string()))
+                    .replace("{times}", &times.to_string())
+                    .replace("{note}", notes.get(&trade).unwrap_or_default()),
+            );
+            start += times;
+        }
+        line(self.footer.replace("{steps}", &steps));
+        out
+    }
+
+    pub fn save(
+        &self,
+        path: &Path,
+        route: &[Trade],
+        offers: &[Trade],
+        notes: &Notes,
+    ) -> std::io::Result<()> {
+        fs::write(path, self.render(route, offers, notes))
+    }
+}
@@ -1,303 +1,3363 @@
+mod tutorial;
+
 use std::{
-    collections::{HashSet, VecDeque},
-    fmt,
-    time::Instant,
+    cell::OnceCell,
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+    str::FromStr,
+    sync::{Arc, OnceLock},
+    time::{Duration, Instant},
+};
+
+use candyworks::{
+    advisor::Advisor,
+    backward::Strategy,
+    batch, bench, bridge,
+    catalog::{self, Catalogs},
+    charges::Tax,
+    check, compare, config, conservation, cooperate,
+    cost::TradeCosts,
+    count::{Count, Counts, Overflow},
+    dashboard::{self, SavedQueries},
+    decision::{DecisionTree, TreeFormat},
+    delta,
+    display::{self, CandyOrder, ColorChoice, DisplayOptions},
+    display_side, dominance, donate,
+    dot::DotOptions,
+    economy,
+    effort::EffortModel,
+    error::CandyWorksError,
+    explain,
+    export::{self, ExportKind, Exporter},
+    farm,
+    flow::FlowFormat,
+    follow,
+    frontier::FrontierOrder,
+    fsck, growth, guard,
+    import::{self, ImportFormat},
+    kind_caps::KindCaps,
+    kinds::{self, CandyKinds},
+    macro_script::MacroTemplate,
+    metrics::{self, MetricsFormat},
+    min_cap, min_trades,
+    names::{self, CandyNames},
+    notes::Notes,
+    objective::Objective,
+    output::{self, OutputFormat},
+    player::{self, Player},
+    preset::{self, Preset},
+    profile::TradeProfile,
+    prompt::{self, Prompt, Remembered},
+    query::{self, Filter},
+    quests, random, reachable, reconcile,
+    repeat::{self, Repeats},
+    repl, replan,
+    replay::{self, Replayable},
+    report,
+    route::Route,
+    route_file,
+    scenario::{self, Scenario},
+    sequence, shared,
+    solve::{self, Planner, Status},
+    solvers, starts, statistics,
+    storage::Combinations,
+    sustain, sweep,
+    target_spec::TargetSpec,
+    template::{self, Variables},
+    timings::{self, Phase, Timings},
+    tracker::Tracker,
+    uses::Ranking,
+    value,
+    vendor::{self, Notation},
+    verify::{self, StepError},
+    wide::{self, WideScenario},
+    Candies, CandyWorks, Exhausted, Exploration, Trade,
 };
+use clap::{Parser, Subcommand};
+
+#[derive(Parser)]
+#[command(about = "Finds a route of trades from your candies to the ones you want")]
+struct Cli {
+    /// Save the explored graph to this file
+    #[arg(long, global = true)]
+    save_graph: Option<PathBuf>,
+    /// Read inventory, trades, cap and targets from a .candy scenario file instead of prompting
+    #[arg(long, global = true)]
+    scenario: Option<PathBuf>,
+    /// Read inventory, cap, trades and targets from this JSON config file instead of
+    /// prompting: {"inventory": {"eggs": 6}, "max_candies": 20, "trades": [{"give":
+    /// {"worms": 2}, "receive": {"meats": 1}}], "target": {"meats": 2}}
+    #[arg(long, global = true, value_name = "FILE", conflicts_with_all = ["scenario", "demo"])]
+    config: Option<PathBuf>,
+    /// Plan with the kinds of candy of this file instead of eggs, worms, cakes, fishes and
+    /// meats: one per line, such as "g: Gem / Gems", up to eight
+    #[arg(long, global = true, value_name = "FILE")]
+    kinds: Option<PathBuf>,
+    /// Plan with the trades of this preset of the presets file: its ratio for the standard
+    /// trades instead of 3:1, its kinds of candy, its default cap and its special trades
+    #[arg(long, global = true, value_name = "NAME")]
+    preset: Option<String>,
+    /// Read --preset from this file instead of presets.candy in the candyworks home: a
+    /// "[name]" line for each preset, then "ratio = 4", "cap = 30", "kind = p: Pumpkin"
+    /// and "trade = 2p -> 1b" lines
+    #[arg(long, global = true, value_name = "FILE", requires = "preset")]
+    presets: Option<PathBuf>,
+    /// Layer the trades of this catalog file over the scenario's for this run; a trade
+    /// replaces those of the scenario and of the shared and player catalogs with the
+    /// same give side and location, and later files win
+    #[arg(long, global = true, value_name = "FILE")]
+    catalog: Vec<PathBuf>,
+    /// Layer the trades of this CSV export of a spreadsheet over the scenario's like a
+    /// --catalog file: a header such as "give_eggs,give_worms,recv_meats,vendor" or
+    /// "give,receive,note", then one trade a row, such as "3,,1,market" or "3e,1m,"
+    #[arg(long, global = true, value_name = "FILE")]
+    trades_csv: Vec<PathBuf>,
+    /// Offer only the trades of the scenario and its catalogs, without the standard 3:1
+    /// trades or those of the --preset
+    #[arg(long, global = true)]
+    no_standard_trades: bool,
+    /// Give a `${NAME}` variable of the scenario file a value, e.g. "CAP=30";
+    /// variables not set are read from the environment
+    #[arg(long = "set", global = true, value_name = "NAME=VALUE")]
+    set: Vec<String>,
+    /// Run a built-in example scenario instead of prompting
+    #[arg(long, global = true, conflicts_with = "scenario")]
+    demo: bool,
+    /// Save the entered inventory, trades, cap and targets as a .candy scenario file
+    #[arg(long, global = true)]
+    save_scenario: Option<PathBuf>,
+    /// Order of candies in output: default, count, name, or letters such as "mfcwe"
+    #[arg(long, global = true, default_value = "default")]
+    order: CandyOrder,
+    /// Always show candies with a zero count
+    #[arg(long, global = true)]
+    zeros: bool,
+    /// Color gains and losses in the summary after a route
+    #[arg(long, global = true, value_enum, default_value = "auto")]
+    color: ColorChoice,
+    /// Start from a previously saved route; the search only replaces it with a better one
+    #[arg(long, global = true)]
+    warm_start: Option<PathBuf>,
+    /// Save the route found for the first target to this file
+    #[arg(long, global = true)]
+    save_route: Option<PathBuf>,
+    /// Write the route found for the first target as a macro script for in-game macro tools
+    #[arg(long = "macro", global = true, value_name = "FILE")]
+    macro_file: Option<PathBuf>,
+    /// Template for --macro with `header`, `step` and `footer` lines; steps can use
+    /// {step}, {offer}, {give}, {receive}, {trade}, {times} and {note}
+    #[arg(long, global = true, requires = "macro_file")]
+    macro_template: Option<PathBuf>,
+    /// Order trades by how often they were used in earlier solves recorded in this file,
+    /// and record the routes found now
+    #[arg(long, global = true)]
+    trade_profile: Option<PathBuf>,
+    /// File of named filters kept by `query --save` and run by `dashboard`
+    #[arg(long, global = true)]
+    queries: Option<PathBuf>,
+    /// Stop exploring after this many seconds and answer with the best route found so far
+    #[arg(long, global = true)]
+    time_limit: Option<f64>,
+    /// While exploring, show the best route to each target found so far every this many
+    /// seconds, so a long search can be stopped once it is good enough
+    #[arg(long, global = true, value_name = "SECONDS")]
+    progress: Option<f64>,
+    /// Explore even when the cap makes the search space look too large to finish
+    #[arg(long, global = true)]
+    force: bool,
+    /// How to read pasted trade descriptions without a verb, such as "3 Eggs for 1 Cake"
+    #[arg(long, global = true, value_enum, default_value = "give-first")]
+    vendor_notation: Notation,
+    /// Also list the combinations of just these candies the explored states hold,
+    /// e.g. "eggs,worms"
+    #[arg(long, global = true, value_delimiter = ',')]
+    project: Vec<String>,
+    /// Reuse the graph kept in this file when it was explored from the same inventory
+    /// with the same cap and trades, and otherwise explore and keep the graph there
+    /// for next time if the exploration completes
+    #[arg(long, global = true, value_name = "FILE")]
+    cache: Option<PathBuf>,
+    /// Hold at most this many of each candy besides the cap on the total: one number
+    /// for every candy or per candy like "10e 5m", in place of the scenario's
+    /// kind_cap; graphs explored with it are not cached
+    #[arg(long, global = true, value_name = "CAPS")]
+    kind_cap: Option<KindCaps>,
+    /// Also search each trade made 2 up to this many times in a row as a single step, so
+    /// long runs of one trade take fewer steps; routes still show every trade made, and
+    /// graphs explored with it are not cached
+    #[arg(long, global = true, value_name = "TIMES")]
+    repeat: Option<u32>,
+    /// Only compute the statistics, without keeping the explored graph in memory
+    #[arg(long, global = true, conflicts_with_all = ["save_graph", "warm_start", "cache"])]
+    stats_only: bool,
+    /// Find only the reachable states first and rebuild each route backwards from
+    /// its target, keeping no parent links for states off the route
+    #[arg(
+        long,
+        global = true,
+        conflicts_with_all = ["save_graph", "warm_start", "stats_only", "time_limit", "cache"]
+    )]
+    two_phase: bool,
+    /// With several targets, pick routes that start with as many of the same trades as
+    /// possible and show those trades, so the choice of target can wait
+    #[arg(long, global = true, conflicts_with_all = ["headroom", "most_headroom"])]
+    shared_prefix: bool,
+    /// With several targets, merge their routes into a tree of the trades to make before
+    /// each choice of target, as indented text or JSON
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        num_args = 0..=1,
+        default_missing_value = "text",
+        conflicts_with_all = ["headroom", "most_headroom"]
+    )]
+    decision_tree: Option<TreeFormat>,
+    /// Prefer a route that stays this many candies below the cap after every step, when one
+    /// reaches the target, so pickups along the way are not wasted
+    #[arg(long, global = true)]
+    headroom: Option<u32>,
+    /// Prefer the route that stays furthest below the cap at its fullest point
+    #[arg(long, global = true, conflicts_with = "headroom")]
+    most_headroom: bool,
+    /// Also list up to this many different strategies for each target; routes using
+    /// the same trades in another order count once
+    #[arg(long, global = true, default_value_t = 0)]
+    alternatives: usize,
+    /// Among equally good routes prefer the one needing the least effort, estimated
+    /// from clicks per trade and per change of trade, e.g. "trade=1,switch=2"
+    #[arg(long, global = true, num_args = 0..=1, default_missing_value = "")]
+    effort: Option<EffortModel>,
+    /// Set these candies aside, e.g. "5f": routes only use the rest
+    #[arg(long, global = true)]
+    reserve: Option<String>,
+    /// Offer the answers recorded in this file as defaults and record the new ones
+    #[arg(long, global = true)]
+    session: Option<PathBuf>,
+    /// Largest count accepted when prompting: one number for every candy, or per candy like "500e 99m"
+    #[arg(long, global = true, default_value = "999")]
+    max_count: MaxCounts,
+    /// Put a Bloom filter in front of the visited set, which saves set lookups on
+    /// very large explorations
+    #[arg(long, global = true)]
+    bloom: bool,
+    /// Skip states holding no more of any candy than one already explored, which
+    /// shrinks the search without losing routes when no trade receives more candies
+    /// than it gives; leave it off for the statistics and graph of every state
+    #[arg(long, global = true, conflicts_with_all = ["stats_only", "two_phase", "cache"])]
+    prune_dominated: bool,
+    /// Skip exploring when the candy conservation laws show that no target can be
+    /// reached within the cap
+    #[arg(long, global = true)]
+    prune_conserved: bool,
+    /// Explore one level of trades at a time on this many threads, for large caps on
+    /// machines with several cores; the states and routes are those of --frontier fifo
+    #[arg(
+        long,
+        global = true,
+        default_value_t = 1,
+        conflicts_with_all = [
+            "progress",
+            "bloom",
+            "prune_dominated",
+            "frontier",
+            "stats_only",
+            "two_phase"
+        ]
+    )]
+    threads: usize,
+    /// The order states are expanded in: newest first (lifo), oldest first (fifo, which
+    /// keeps the shortest route to every state), most candies first (most) or nearest
+    /// to a target first (nearest); matters most with --time-limit
+    #[arg(long, global = true, value_enum, default_value_t = FrontierOrder::Lifo)]
+    frontier: FrontierOrder,
+    /// What makes one route to a target better than another: the most candies left
+    /// (most-candies, the default), the fewest trades (fewest-trades), or the most
+    /// value left with each candy at its weight, e.g. "value:1e,5m"; ties go to the
+    /// route leaving more candies, then to the one explored first
+    #[arg(long, global = true, conflicts_with = "two_phase")]
+    objective: Option<Objective>,
+    /// Search each target's route forward through every reachable state, backward from
+    /// the target through what has to be held before each trade, which is quicker for
+    /// small targets under a large cap, or auto for backward only when the forward search
+    /// would be refused as too large
+    #[arg(long, global = true, value_enum, default_value = "auto")]
+    strategy: Strategy,
+    /// Print a one-line metrics record to stderr when the command finishes
+    #[arg(long, global = true, value_enum)]
+    metrics: Option<MetricsFormat>,
+    /// After each route, show the best other trade at every step and what it costs
+    #[arg(long, global = true)]
+    fallbacks: bool,
+    /// Show what each route consumes and produces of every candy, as text or JSON
+    #[arg(long, global = true, value_enum, num_args = 0..=1, default_missing_value = "text")]
+    flow: Option<FlowFormat>,
+    /// Keep the inventory and goals, prompt answers, trade history, queries, last graph and
+    /// route progress of this player apart from everyone else's, as files of their own
+    #[arg(long, global = true)]
+    player: Option<String>,
+    /// Record the arguments, input files and prompt answers of this run to this file
+    #[arg(long, global = true)]
+    record: Option<PathBuf>,
+    /// Run again exactly as recorded with --record, ignoring the other arguments
+    #[arg(long, global = true, conflicts_with = "record")]
+    replay: Option<PathBuf>,
+    /// Print how long exploring, hashing, storing and route reconstruction took to stderr
+    #[arg(long, global = true)]
+    timings: bool,
+    /// Answer as text, or as one JSON document on stdout with the statistics and the
+    /// route to each target, step by step, or the error that ended the run; for planning
+    /// without a command, explore and stats --detailed
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        default_value_t = OutputFormat::Text,
+        conflicts_with_all = ["stats_only", "two_phase", "progress", "shared_prefix", "decision_tree"]
+    )]
+    output: OutputFormat,
+    /// The same as --output, given before any command; export and import keep
+    /// --format for their own formats
+    #[arg(
+        long,
+        value_enum,
+        conflicts_with_all = ["output", "stats_only", "two_phase", "progress", "shared_prefix", "decision_tree"]
+    )]
+    format: Option<OutputFormat>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run the interactive flow and write a report of the scenario
+    #[command(group(clap::ArgGroup::new("report_file").required(true).multiple(true)))]
+    Report {
+        /// Write the report as Markdown to this file
+        #[arg(long, group = "report_file")]
+        md: Option<PathBuf>,
+        /// Write the report as a self-contained HTML page to this file
+        #[arg(long, group = "report_file")]
+        html: Option<PathBuf>,
+        /// Add a chart of the states reached by number of trades, an embedded image
+        #[arg(long)]
+        charts: bool,
+    },
+    /// Merge two saved graphs explored with the same trades
+    Merge {
+        first: PathBuf,
+        second: PathBuf,
+        /// File to write the merged graph to
+        #[arg(long)]
+        out: PathBuf,
+    },
+    /// Shrink a saved graph to the routes to its Pareto-optimal states and the scenario's targets
+    Compact {
+        /// Graph file written with --save-graph or merge
+        graph: PathBuf,
+        /// File to write the compacted graph to
+        #[arg(long)]
+        out: PathBuf,
+    },
+    /// Check a saved graph for truncation and states its trades do not lead to
+    Fsck {
+        /// Graph file written with --save-graph or merge
+        graph: PathBuf,
+    },
+    /// Explore the inventory and trades given as arguments and print the route to each
+    /// target without prompting; exits with status 3 if a target cannot be reached
+    Explore {
+        /// The candies to start with, for example "3e,2w"
+        #[arg(long, value_name = "CANDIES")]
+        inventory: String,
+        /// The most candies held at once
+        #[arg(long, visible_alias = "max", default_value_t = run_preset().default_cap())]
+        cap: u32,
+        /// A vendor trade, written as in a scenario, for example "4e -> 1c @ market";
+        /// repeat for more, and add the trades of catalog files with --catalog
+        #[arg(long = "trade", value_name = "TRADE")]
+        trades: Vec<String>,
+        /// Candies to find a route to, for example "1m,2c"; repeat for more targets
+        #[arg(long = "target", value_name = "CANDIES", required = true)]
+        targets: Vec<String>,
+    },
+    /// Find a route in a saved graph without exploring again, or track the steps made
+    /// of a route; exits with status 3 if a target cannot be reached
+    #[command(args_conflicts_with_subcommands = true)]
+    Route {
+        /// Graph file written with --save-graph or merge; with --player, the graph the
+        /// player explored last
+        #[arg(long)]
+        graph: Option<PathBuf>,
+        /// Plan from this state instead of the graph's start: a state ID as printed
+        /// in reports or candies such as "3e 2w"
+        #[arg(long)]
+        from: Option<String>,
+        /// Plan to exactly this state, given the same way, instead of the targets
+        #[arg(long)]
+        to: Option<String>,
+        /// Plan to these candies, for example "1m,2c", instead of the scenario's targets;
+        /// "=2m" asks for exactly 2 meats and "5*" for 5 candies of any kinds. Repeat
+        /// for more targets
+        #[arg(long = "target", value_name = "CANDIES", conflicts_with = "to")]
+        targets: Vec<String>,
+        /// Search the scenario straight for each target, in the fewest trades, instead
+        /// of reading a graph: fast for one-off questions about targets near the start
+        #[arg(long, conflicts_with_all = ["graph", "from", "to"])]
+        direct: bool,
+        #[command(subcommand)]
+        progress: Option<RouteProgress>,
+    },
+    /// List the players kept with --player
+    Players,
+    /// Show a state of a saved graph by its ID and the route to it
+    State {
+        /// Graph file written with --save-graph
+        #[arg(long)]
+        graph: PathBuf,
+        /// State ID as printed in reports
+        id: u64,
+    },
+    /// Report what a new trade would add to a saved graph
+    Delta {
+        /// Graph file written with --save-graph
+        #[arg(long)]
+        graph: PathBuf,
+        /// The new trade, for example "3e -> 1m"
+        #[arg(long)]
+        trade: String,
+        /// Maximum number of states listed per section
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+    },
+    /// Find trades that explain how the starting inventory became the current one
+    Explain {
+        /// The inventory you have now, for example "2e 4w"; prompted if omitted
+        #[arg(long)]
+        current: Option<String>,
+        /// Use a saved graph instead of exploring the scenario
+        #[arg(long)]
+        graph: Option<PathBuf>,
+        /// Number of nearby reachable inventories shown when there is no exact match
+        #[arg(long, default_value_t = 3)]
+        suggestions: usize,
+    },
+    /// Compare two states: how they differ, which dominates and the shortest route between them
+    CompareStates {
+        /// A state ID as printed in reports, or candies such as "3e 2w"
+        a: String,
+        /// The other state, given the same way
+        b: String,
+        /// Use a saved graph instead of exploring the scenario
+        #[arg(long)]
+        graph: Option<PathBuf>,
+    },
+    /// List, count or export the explored states matching a filter
+    Query {
+        /// A filter such as "cakes >= 3 && total() <= 15 && depth < 6"; without it
+        /// filters are read one per line until an empty line
+        filter: Option<String>,
+        /// Use a saved graph instead of exploring the scenario
+        #[arg(long)]
+        graph: Option<PathBuf>,
+        /// Only count the matching states
+        #[arg(long)]
+        count: bool,
+        /// Write the matching states to this file as CSV
+        #[arg(long, requires = "filter")]
+        export: Option<PathBuf>,
+        /// Maximum number of states listed
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+        /// Keep the filter under this name in the --queries file
+        #[arg(long, requires = "filter")]
+        save: Option<String>,
+    },
+    /// Chart how many states are reachable within each number of trades
+    Growth {
+        /// Also chart the states matching a filter such as "cakes >= 3"
+        filter: Option<String>,
+        /// Use a saved graph instead of exploring the scenario
+        #[arg(long)]
+        graph: Option<PathBuf>,
+        /// Width of the longest bar in characters
+        #[arg(long, default_value_t = 50)]
+        width: usize,
+    },
+    /// List the best inventories within reach: those no other reachable inventory
+    /// holds at least as many of every candy as, with the fewest trades to each
+    #[command(visible_alias = "frontier")]
+    Reachable {
+        /// Use a saved graph instead of exploring the scenario
+        #[arg(long)]
+        graph: Option<PathBuf>,
+        /// Maximum number of inventories listed
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Run every query saved in the --queries file and summarize the matches
+    Dashboard {
+        /// Use a saved graph instead of exploring the scenario
+        #[arg(long)]
+        graph: Option<PathBuf>,
+    },
+    /// Compare the scenario's tracked inventory with what the game shows
+    Reconcile {
+        /// Scenario file holding the tracked inventory
+        scenario: PathBuf,
+        /// The inventory the game shows, for example "2e 4w"; prompted if omitted
+        #[arg(long)]
+        game: Option<String>,
+    },
+    /// Solve every .candy scenario in a directory
+    Batch {
+        dir: PathBuf,
+        /// Directory for the per-scenario results and summary.csv
+        #[arg(long)]
+        out: PathBuf,
+        /// Number of scenarios solved in parallel
+        #[arg(long, default_value_t = 1)]
+        jobs: usize,
+        /// Abort a scenario whose graph would need more than this many megabytes
+        #[arg(long)]
+        memory_limit: Option<u64>,
+    },
+    /// Validate a .candy scenario file without running the solver
+    ///
+    /// Diagnostics are printed one per line as `path:line: severity[code]: message`
+    /// and the exit status is non-zero if any error was found.
+    Check { file: PathBuf },
+    /// Show the trades in effect once the shared catalog, the player's, the scenario's
+    /// trades and the --catalog files are layered, and the trades they replaced
+    Catalog,
+    /// Write a route, the statistics or the explored graph in a format chosen by name
+    Export {
+        #[arg(value_enum)]
+        what: ExportKind,
+        /// Use a saved graph instead of exploring the scenario
+        #[arg(long)]
+        graph: Option<PathBuf>,
+        /// The target of the route, e.g. "2m 1c"; without it the scenario's first target,
+        /// or prompted
+        #[arg(long)]
+        target: Option<String>,
+        /// The format to write: text, json, csv, dot, or any other registered format
+        #[arg(long, default_value = "text", value_parser = export::find)]
+        format: &'static dyn Exporter,
+        /// File to write to instead of printing
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Draw the explored states as a Graphviz DOT graph, for `dot -Tsvg`
+    Graph {
+        /// Use a saved graph instead of exploring the scenario
+        #[arg(long)]
+        graph: Option<PathBuf>,
+        /// Only draw states at most this many trades from the start
+        #[arg(long)]
+        max_depth: Option<usize>,
+        /// Only draw the states holding these candies, e.g. "2m 1c", and those on the
+        /// way to them
+        #[arg(long)]
+        target: Option<String>,
+        /// File to write to instead of printing
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Turn the JSON export of another planner into a .candy scenario file
+    Import {
+        /// The exported inventory, trades and goals
+        file: PathBuf,
+        /// Which planner wrote the file
+        #[arg(long, value_enum, default_value_t = ImportFormat::Auto)]
+        format: ImportFormat,
+        /// File to write the scenario to, instead of printing it; with --player, the
+        /// player's scenario
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Rewrite a .candy scenario file in canonical form (comments are not kept)
+    Fmt {
+        file: PathBuf,
+        /// Only check whether the file is already canonical
+        #[arg(long)]
+        check: bool,
+    },
+    /// Plan converting everything but the reserved candies into as many of one candy as possible
+    Donate {
+        /// The candy to donate, by letter or name, for example "m" or "meats"
+        candy: String,
+    },
+    /// Plan the trades leaving the inventory worth the most, each candy at its weight
+    Value {
+        /// What each candy is worth, for example "1e,5m"; candies not listed are
+        /// worth nothing
+        #[arg(long)]
+        weights: String,
+        /// Make at most this many trades; any number if left out
+        #[arg(long)]
+        max_trades: Option<usize>,
+    },
+    /// Plan with the scenario's random trades, such as `random = 3e -> 1m 40% | 1c 60%`:
+    /// the chance of holding the target, or the inventory's worth on average
+    Chance {
+        /// Make at most this many trades
+        #[arg(long)]
+        within: usize,
+        /// Plan for the most the inventory is worth on average instead, each candy at
+        /// its weight, for example "1e,5m"
+        #[arg(long)]
+        weights: Option<String>,
+    },
+    /// Plan with the candies of other players pooled in: every player makes trades out
+    /// of their own bag, held to the cap, and can hand candies to another
+    Cooperate {
+        /// Another player and what they hold, such as "bob=6e 2w"; repeat for more
+        #[arg(long = "partner", value_name = "NAME=CANDIES", required = true)]
+        partners: Vec<String>,
+        /// Your name in the plan, which starts from the scenario's inventory
+        #[arg(long, default_value = "you")]
+        name: String,
+        /// The player who should end up holding the target; you if left out
+        #[arg(long = "for", value_name = "NAME")]
+        receiver: Option<String>,
+        /// What a player loses on top of the candies each time they hand some over
+        #[arg(long, value_name = "CANDIES")]
+        transfer_fee: Option<String>,
+        /// Give up after storing this many combinations of everyone's bags
+        #[arg(long, default_value_t = 1_000_000)]
+        max_states: usize,
+    },
+    /// Find the loop of trades that, repeated, makes the most of one candy per trade
+    Farm {
+        /// The candy to farm, by letter or name, for example "e" or "eggs"
+        candy: String,
+    },
+    /// Solve the targets with every strategy and compare the routes, states and time
+    Solvers {
+        /// The target, for example "2m"; defaults to the scenario's targets
+        #[arg(long)]
+        target: Option<String>,
+    },
+    /// Find the starting inventories within the cap from which the target can be reached
+    Starts {
+        /// The target, for example "2m"; defaults to the scenario's first target
+        #[arg(long)]
+        target: Option<String>,
+        /// Maximum number of smallest inventories listed
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+    },
+    /// Find the fewest scenario trades that still reach every target, and the vendors to visit
+    MinTrades {
+        /// The target, for example "2m"; defaults to the scenario's targets
+        #[arg(long)]
+        target: Option<String>,
+    },
+    /// Fill the targets one after another, handing each over before planning the next
+    Sequence {
+        /// A target, for example "2c"; repeat for more, in the order they are filled.
+        /// Defaults to the scenario's targets
+        #[arg(long = "target", value_name = "CANDIES")]
+        targets: Vec<String>,
+    },
+    /// Suggest the smallest new trades that would make an unreachable target reachable
+    Bridge {
+        /// The target, for example "2m"; defaults to the scenario's first target
+        #[arg(long)]
+        target: Option<String>,
+        /// Maximum number of trades listed
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+    },
+    /// Measure solver throughput on a built-in corpus and compare it with a baseline, or
+    /// with --caps compare ways of exploring the scenario across caps
+    Bench {
+        /// Baseline written earlier with --save-baseline
+        #[arg(long)]
+        baseline: Option<PathBuf>,
+        /// Write the measured throughput to this file as a new baseline
+        #[arg(long)]
+        save_baseline: Option<PathBuf>,
+        /// Runs per scenario; the fastest counts
+        #[arg(long, default_value_t = 3)]
+        runs: usize,
+        /// Slowdown in percent reported as a regression
+        #[arg(long, default_value_t = 10.0)]
+        tolerance: f64,
+        /// Explore the scenario at these caps instead of the corpus, a range such as
+        /// "10..40:10" or a list such as "10,20,40"
+        #[arg(long)]
+        caps: Option<String>,
+        /// The ways of exploring to compare at each of --caps
+        #[arg(
+            long,
+            value_enum,
+            value_delimiter = ',',
+            default_value = "plain,pruned,bloom,parallel,streaming"
+        )]
+        explorers: Vec<bench::Explorer>,
+    },
+    /// Solve the scenario across a grid of parameters and show how reachability and
+    /// route length change
+    Sweep {
+        /// A parameter and its values: `cap` or a scenario variable, with a range such
+        /// as "cap=10..40:5" or a list such as "EGGS=2,4,8"; repeat for more axes
+        #[arg(long, required = true)]
+        param: Vec<sweep::Param>,
+    },
+    /// Choose the quests to complete, and the trades funding them, for the largest total reward
+    Quests {
+        /// JSON file of quests, each with a `name`, the `requires` candies and a `reward`
+        file: PathBuf,
+        /// Trades that may be made to fund the quests; without trades only the
+        /// inventory as it is counts
+        #[arg(long, default_value_t = 0)]
+        budget: usize,
+    },
+    /// Find the loop of trades and repeated quests that keeps earning the most reward
+    Simulate {
+        /// JSON file of quests as for `quests`; quests can be completed again and again
+        file: PathBuf,
+        /// Moves looked ahead when judging which loop pays best
+        #[arg(long, default_value_t = 200)]
+        horizon: usize,
+    },
+    /// Check a plan of trades step by step from the scenario's inventory, stopping at
+    /// the first trade that cannot be made
+    Verify {
+        /// File of trades, one per line such as "3e -> 1m", as --save-route writes
+        /// them; read from stdin if left out or "-"
+        trades: Option<PathBuf>,
+    },
+    /// Find the smallest cap under which the target can be reached
+    MinCap {
+        /// The target, for example "2m"; defaults to the scenario's first target
+        #[arg(long)]
+        target: Option<String>,
+    },
+    /// Recommend one trade at a time: reads the current inventory, prints the next trade
+    Advise {
+        /// The current inventory, for example "2e 4w"; without it inventories are read
+        /// one per line until an empty line
+        #[arg(long)]
+        current: Option<String>,
+    },
+    /// Walk through the plan one trade at a time, reporting candies found or lost on
+    /// the way to plan the rest again
+    Follow,
+    /// Plan at a prompt of commands, such as `set inventory 3e 2w`, `add-trade 3e -> 1m`
+    /// and `route 2c 1f`, keeping the explored graph until the inventory, cap or
+    /// trades change; starts from the scenario if one is given
+    Repl {
+        /// Restore an earlier session by first making the changes it saved with `save`
+        #[arg(long, value_name = "FILE")]
+        restore: Option<PathBuf>,
+    },
+    /// Plan again from a failed step of a saved route, without the trade that failed
+    Replan {
+        /// Route file written with --save-route
+        route: PathBuf,
+        /// The step that could not be made, counted from 1
+        #[arg(long)]
+        failed_step: usize,
+        /// Why the step failed, shown with the new plan
+        #[arg(long)]
+        reason: Option<String>,
+    },
+    /// Analyze the trade set without a target: conversion rates, arbitrage, dominated trades
+    Economy,
+    /// Learn the basics by solving a small built-in scenario step by step
+    Tutorial,
+    /// Print statistics of a saved graph without exploring again
+    Stats {
+        /// Graph file written with --save-graph
+        #[arg(long)]
+        graph: PathBuf,
+        /// Add histograms of the fewest trades and the candies held, and the trades
+        /// most used on the shortest routes; as one document with --output json
+        #[arg(long)]
+        detailed: bool,
+        /// Count the states matching a query, such as "meats >= 2"; repeat for more
+        #[arg(long = "count", value_name = "QUERY", requires = "detailed")]
+        counts: Vec<String>,
+    },
+}
+
+/// Steps of a route kept in a tracker file while it is made in the game.
+#[derive(Subcommand)]
+enum RouteProgress {
+    /// Start tracking a route written with --save-route
+    Start {
+        /// Route file to make
+        route: PathBuf,
+        /// File keeping the progress [default: route-progress.txt, or the player's]
+        #[arg(long)]
+        tracker: Option<PathBuf>,
+    },
+    /// Mark the route done up to this step, counted from 1
+    Done {
+        step: usize,
+        /// File keeping the progress [default: route-progress.txt, or the player's]
+        #[arg(long)]
+        tracker: Option<PathBuf>,
+    },
+    /// Show the steps still to make
+    Status {
+        /// File keeping the progress [default: route-progress.txt, or the player's]
+        #[arg(long)]
+        tracker: Option<PathBuf>,
+    },
+}
+
+impl RouteProgress {
+    fn tracker(&mut self) -> &mut Option<PathBuf> {
+        match self {
+            RouteProgress::Start { tracker, .. }
+            | RouteProgress::Done { tracker, .. }
+            | RouteProgress::Status { tracker } => tracker,
+        }
+    }
+}
+
+/// The most of each candy a prompt accepts, to catch typos like an extra
+/// digit before they reach the search.
+#[derive(Clone)]
+struct MaxCounts(Candies);
+
+impl FromStr for MaxCounts {
+    type Err = String;
+
+    /// Either one number for every candy or per candy, as in "500e 99m",
+    /// with the candies not listed keeping the default.
+    fn from_str(input: &str) -> Result<MaxCounts, String> {
+        let mut max = Candies::none();
+        if let Ok(all) = input.trim().parse::<Count>() {
+            for i in 0..kinds::count() {
+                max.add_by_index(i, all);
+            }
+            return Ok(MaxCounts(max));
+        }
+        let listed = input.parse::<Candies>().map_err(|err| err.to_string())?;
+        for i in 0..kinds::count() {
+            let count = listed.get_by_index(i);
+            max.add_by_index(
+                i,
+                if count > Count::ZERO {
+                    count
+                } else {
+                    DEFAULT_MAX_COUNT
+                },
+            );
+        }
+        Ok(MaxCounts(max))
+    }
+}
+
+const DEFAULT_MAX_COUNT: Count = Count::new(999);
+
+/// Asks `prompt` and reads the answer, leaving the run when input ends or
+/// the question is cancelled with Ctrl-C or Ctrl-D.
+fn answer(rl: &mut impl Prompt, prompt: &str) -> String {
+    match rl.ask(prompt) {
+        Some(answer) => answer,
+        None => {
+            eprintln!("Cancelled");
+            process::exit(130);
+        }
+    }
+}
 
-use rustyline::DefaultEditor;
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-struct Candies {
-    eggs: i32,
-    worms: i32,
-    cakes: i32,
-    fishes: i32,
-    meats: i32,
-}
-
-impl Candies {
-    pub fn trade(&self, trade: &Trade) -> Option<Candies> {
-        let result = Candies {
-            eggs: self.eggs + trade.receive.eggs - trade.give.eggs,
-            meats: self.meats + trade.receive.meats - trade.give.meats,
-            fishes: self.fishes + trade.receive.fishes - trade.give.fishes,
-            worms: self.worms + trade.receive.worms - trade.give.worms,
-            cakes: self.cakes + trade.receive.cakes - trade.give.cakes,
+/// Asks how many of every candy there are; an empty answer counts none.
+fn read_candies(rl: &mut impl Prompt, question: &str, max: &MaxCounts) -> Candies {
+    let mut candies = Candies::none();
+    for (i, name) in names::active().plurals().iter().enumerate() {
+        rl.say(&format!("How many {} do you {}?", name, question));
+        let limit = max.0.get_by_index(i);
+        let value = loop {
+            let input = answer(rl, ">> ");
+            if input.trim().is_empty() {
+                break Count::ZERO;
+            }
+            match input.trim().parse::<Count>() {
+                Ok(value) if value < Count::ZERO => rl.say("Counts cannot be negative, try again"),
+                Ok(value) if value > limit => rl.say(&format!(
+                    "That is more than {} {}; if you really have that many, raise --max-count",
+                    limit, name
+                )),
+                Ok(value) => break value,
+                Err(_) => rl.say("Please enter a whole number"),
+            }
         };
-        if result.eggs < 0
-            || result.meats < 0
-            || result.fishes < 0
-            || result.worms < 0
-            || result.cakes < 0
-        {
-            None
+        candies.add_by_index(i, value);
+    }
+    candies
+}
+
+/// Counts one candy per letter, so "EEW" is two eggs and a worm.
+fn parse_letters(input: &str) -> Candies {
+    let mut candies = Candies::none();
+    for c in input.to_lowercase().chars() {
+        if let Some(index) = kinds::active().position(c) {
+            candies.add_by_index(index, Count::new(1));
+        }
+    }
+    candies
+}
+
+fn read_trade_side(rl: &mut impl Prompt, prompt: &str) -> Candies {
+    parse_letters(&answer(rl, prompt))
+}
+
+fn read_trades(rl: &mut impl Prompt, notation: Notation) -> Vec<Trade> {
+    let mut trades = Vec::new();
+    let kinds = kinds::active();
+    let title = |name: &str| {
+        let mut chars = name.chars();
+        chars.next().map_or(String::new(), |first| {
+            first.to_uppercase().chain(chars).collect()
+        })
+    };
+    rl.say(&format!("Use {}", kinds.legend()));
+    rl.say(&format!(
+        "or paste the vendor's description, like \"Give 3 {}, Receive 1 {}\"",
+        title(&kinds.get(0).plural),
+        title(&kinds.get(kinds.len() / 2).singular)
+    ));
+    rl.say("Press Enter without a trade once there are no more");
+    while trades.len() < 3 {
+        let answer = answer(rl, "Trade give: ");
+        if answer.trim().is_empty() {
+            break;
+        }
+        let trade = match vendor::parse(&answer, notation, names::active()) {
+            Some(Ok(trade)) => trade,
+            Some(Err(err)) => {
+                rl.say(&format!("Could not read that description: {}", err));
+                continue;
+            }
+            None => Trade {
+                give: parse_letters(&answer),
+                receive: read_trade_side(rl, "Trade receive: "),
+            },
+        };
+        // Letters that are not candies are skipped silently, so show what
+        // was understood before relying on it.
+        let answer = rl
+            .ask(&format!(
+                "You entered: {} -> {}, correct? [Y/n] ",
+                display_side(&trade.give),
+                display_side(&trade.receive)
+            ))
+            .unwrap_or_default();
+        if answer.trim().to_lowercase().starts_with('n') {
+            rl.say("Enter the trade again");
         } else {
-            Some(result)
+            trades.push(trade);
+        }
+    }
+    trades
+}
+
+fn print_statistics(candy_works: &CandyWorks) {
+    match candy_works.statistics() {
+        Some(stats) => println!("{}", stats),
+        None => println!("No combinations found"),
+    }
+}
+
+fn print_projection(candy_works: &CandyWorks, project: &[String]) {
+    if project.is_empty() || candy_works.combinations.is_empty() {
+        return;
+    }
+    let mut candies = Vec::new();
+    for name in project {
+        match donate::parse_candy(name) {
+            Some(candy) if !candies.contains(&candy) => candies.push(candy),
+            Some(_) => {}
+            None => {
+                eprintln!("Unknown candy {:?}", name);
+                process::exit(1);
+            }
         }
     }
+    println!("{}", candy_works.project(&candies));
+}
 
-    pub fn total(&self) -> i32 {
-        self.eggs + self.worms + self.cakes + self.fishes + self.meats
+/// Prints the table of what `route` changes overall, if it changes anything.
+fn print_change(start: Candies, route: &[Trade]) {
+    if !route.is_empty() {
+        let end = start.trade_all(route).unwrap();
+        println!("{}", display::change_table(&start, &end));
     }
+}
 
-    pub fn contains(&self, other: &Candies) -> bool {
-        self.eggs >= other.eggs
-            && self.meats >= other.meats
-            && self.fishes >= other.fishes
-            && self.worms >= other.worms
-            && self.cakes >= other.cakes
+fn load_graph(path: &Path) -> CandyWorks {
+    match CandyWorks::load(path) {
+        Ok(candy_works) => candy_works,
+        Err(err) => {
+            eprintln!("Could not load graph {}", err);
+            process::exit(err.exit_code());
+        }
     }
+}
 
-    pub fn none() -> Candies {
-        Candies {
-            eggs: 0,
-            worms: 0,
-            cakes: 0,
-            fishes: 0,
-            meats: 0,
+fn track_route(cli: &Cli, progress: &RouteProgress) {
+    let default = PathBuf::from("route-progress.txt");
+    let load = |path: &Path| match Tracker::load(path) {
+        Ok(tracker) => tracker,
+        Err(err) => {
+            eprintln!("Could not load route progress {}: {}", path.display(), err);
+            process::exit(1);
+        }
+    };
+    match progress {
+        RouteProgress::Start { route, tracker } => {
+            let tracker = tracker.as_ref().unwrap_or(&default);
+            let input = replay::read_to_string(route).map_err(|err| err.to_string());
+            let route = match input.and_then(|input| route_file::parse_noted(&input)) {
+                Ok(route) => route,
+                Err(err) => {
+                    eprintln!("Could not load route {}: {}", route.display(), err);
+                    process::exit(1);
+                }
+            };
+            let started = Tracker::start(route);
+            written(cli, tracker, started.save(tracker));
+            println!("Tracking the route in {}", tracker.display());
+            started.print();
+        }
+        RouteProgress::Done { step, tracker } => {
+            let path = tracker.as_ref().unwrap_or(&default);
+            let mut tracker = load(path);
+            if let Err(err) = tracker.mark_done(*step) {
+                eprintln!("{}", err);
+                process::exit(1);
+            }
+            written(cli, path, tracker.save(path));
+            tracker.print();
+        }
+        RouteProgress::Status { tracker } => {
+            load(tracker.as_ref().unwrap_or(&default)).print_remaining()
         }
     }
+}
 
-    pub fn add_by_index(&mut self, index: usize, value: i32) {
-        match index {
-            0 => self.eggs += value,
-            1 => self.worms += value,
-            2 => self.cakes += value,
-            3 => self.fishes += value,
-            4 => self.meats += value,
-            _ => (),
+fn load_queries(path: &Path) -> SavedQueries {
+    match SavedQueries::load(path) {
+        Ok(queries) => queries,
+        Err(err) => {
+            eprintln!("Could not load queries {}: {}", path.display(), err);
+            process::exit(1);
         }
     }
+}
+
+/// The problem `scenario` poses, as `Scenario::problem` sets it up: the
+/// reserve set aside, with the cap and the caps of the kinds lowered by as
+/// much.
+fn problem_of(cli: &Cli, scenario: &Scenario) -> CandyWorks {
+    scenario
+        .problem()
+        .unwrap_or_else(|err| fail(cli, &format!("Cannot plan the scenario: {}", err)))
+}
+
+/// The saved graph at `graph`, or else the scenario explored afresh.
+fn graph_or_scenario(cli: &Cli, graph: Option<&Path>, rl: &mut impl Prompt) -> CandyWorks {
+    let mut candy_works = match graph {
+        Some(graph) => {
+            let mut candy_works = load_graph(graph);
+            if cli.scenario.is_some() || cli.config.is_some() || cli.demo {
+                // A graph keeps no use limits or notes, so they come from the
+                // scenario.
+                let scenario = obtain_scenario(cli, rl);
+                candy_works.use_limits = scenario.use_limits();
+                candy_works.notes = Notes::new(&scenario);
+            }
+            candy_works
+        }
+        None => {
+            let mut candy_works = problem_of(cli, &obtain_scenario(cli, rl));
+            candy_works.explore();
+            candy_works
+        }
+    };
+    candy_works.objective = cli.objective.clone().unwrap_or_default();
+    metrics::record_states(candy_works.combinations.len());
+    candy_works
+}
+
+/// Parses the scenario `input`, read from `source`, with the trade catalogs
+/// layered around it. `describe` says where a line of `input` came from,
+/// for errors in it; errors in catalog trades name their file.
+fn parse_layered(
+    input: &str,
+    source: &str,
+    describe: impl Fn(usize) -> Option<String>,
+) -> Result<Scenario, String> {
+    let catalogs = catalog::active();
+    let merged = catalogs.merge(input, source)?;
+    let mut scenario = Scenario::parse_with(&merged.input, template::active(), run_preset())
+        .map_err(
+            |err| match err.line.map(|line| (merged.origin(line), describe(line))) {
+                Some((Some(entry), _)) => {
+                    format!("{} ({}): {}", entry.source, entry.layer, err.message)
+                }
+                Some((None, Some(place))) => format!("{}: {}", place, err.message),
+                _ => err.to_string(),
+            },
+        )?;
+    scenario.standard_trades = !catalogs.without_standard;
+    Ok(scenario)
+}
+
+/// Makes `scenario` the one candies are named by.
+fn use_scenario(cli: &Cli, scenario: Scenario) -> Scenario {
+    names::configure(scenario.names.clone())
+        .unwrap_or_else(|err| fail(cli, &format!("Cannot plan with the scenario: {}", err)));
+    scenario
+}
+
+/// The option of `cli` that planning a `count = i64` scenario cannot
+/// honour, if one is given: their explorer only goes breadth first under
+/// the cap, with the trades and the time limit.
+fn unsupported_by_wide(cli: &Cli) -> Option<&'static str> {
+    let options = [
+        (cli.output == OutputFormat::Json, "--output json"),
+        (cli.strategy == Strategy::Backward, "--strategy backward"),
+        (cli.two_phase, "--two-phase"),
+        (cli.stats_only, "--stats-only"),
+        (cli.save_graph.is_some(), "--save-graph"),
+        (cli.save_route.is_some(), "--save-route"),
+        (cli.macro_file.is_some(), "--macro"),
+        (cli.warm_start.is_some(), "--warm-start"),
+        (cli.cache.is_some(), "--cache"),
+        (cli.kind_cap.is_some(), "--kind-cap"),
+        (cli.repeat.is_some(), "--repeat"),
+        (cli.reserve.is_some(), "--reserve"),
+        (cli.objective.is_some(), "--objective"),
+        (cli.effort.is_some(), "--effort"),
+        (cli.headroom.is_some() || cli.most_headroom, "--headroom"),
+        (cli.alternatives > 0, "--alternatives"),
+        (cli.shared_prefix, "--shared-prefix"),
+        (cli.decision_tree.is_some(), "--decision-tree"),
+        (cli.fallbacks, "--fallbacks"),
+        (cli.flow.is_some(), "--flow"),
+        (cli.threads > 1, "--threads"),
+        (cli.progress.is_some(), "--progress"),
+    ];
+    options
+        .into_iter()
+        .find(|(given, _)| *given)
+        .map(|(_, option)| option)
+}
 
-    fn display(&self, include_zeros: bool) -> String {
-        let mut result = Vec::new();
-        result.push((self.eggs, "egg", "eggs"));
-        result.push((self.worms, "worm", "worms"));
-        result.push((self.cakes, "cake", "cakes"));
-        result.push((self.fishes, "fish", "fishes"));
-        result.push((self.meats, "meat", "meats"));
-        if !include_zeros {
-            result.retain(|(count, _, _)| *count != 0);
+/// The `count = i64` scenario at `path`, or `None` if it keeps `i32`
+/// counts, read with the run's preset and standard trades.
+fn load_wide(cli: &Cli, path: &Path) -> Option<WideScenario<i64>> {
+    let input = replay::read_to_string(path).unwrap_or_default();
+    if !wide::is_wide(&input) {
+        return None;
+    }
+    if let Some(option) = unsupported_by_wide(cli) {
+        fail(
+            cli,
+            &format!("{} is not supported with `count = i64` scenarios", option),
+        );
+    }
+    match WideScenario::parse_with(&input, template::active(), run_preset()) {
+        Ok(mut scenario) => {
+            scenario.standard_trades = !cli.no_standard_trades;
+            Some(scenario)
         }
-        result
-            .into_iter()
-            .map(|(count, s, p)| format!("{:2} {}", count, if count == 1 { s } else { p }))
-            .collect::<Vec<_>>()
-            .join(", ")
+        Err(err) => fail(
+            cli,
+            &format!("Could not load scenario {}: {}", path.display(), err),
+        ),
     }
 }
 
-impl fmt::Display for Candies {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.display(false))
+/// Loads the scenario at `path` with the trade catalogs layered around it.
+fn load_scenario(cli: &Cli, path: &Path) -> Scenario {
+    let loaded = replay::read_to_string(path)
+        .map_err(|err| err.to_string())
+        .and_then(|input| parse_layered(&input, &path.display().to_string(), |_| None));
+    match loaded {
+        Ok(scenario) => use_scenario(cli, scenario),
+        Err(err) => fail(
+            cli,
+            &format!("Could not load scenario {}: {}", path.display(), err),
+        ),
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-struct Trade {
-    give: Candies,
-    receive: Candies,
+/// Parses scenario lines, each with where it came from for errors in it,
+/// with the trade catalogs layered around them as around a scenario file.
+fn parse_lines(lines: &[(String, String)]) -> Result<Scenario, String> {
+    let input = lines
+        .iter()
+        .map(|(line, _)| format!("{}\n", line))
+        .collect::<String>();
+    parse_layered(&input, "", |line| {
+        lines.get(line - 1).map(|(_, place)| place.clone())
+    })
 }
 
-impl Trade {
-    pub fn standard_trade(a: usize, b: usize) -> Self {
-        let mut give = Candies::none();
-        let mut receive = Candies::none();
-        give.add_by_index(a, 3);
-        receive.add_by_index(b, 1);
-        Trade { give, receive }
+/// Loads the `--config` file at `path` with the trade catalogs layered
+/// around it.
+fn load_config(cli: &Cli, path: &Path) -> Scenario {
+    match config::read(path).and_then(|lines| parse_lines(&lines)) {
+        Ok(scenario) => use_scenario(cli, scenario),
+        Err(err) => fail(
+            cli,
+            &format!("Could not load config {}: {}", path.display(), err),
+        ),
     }
 }
 
-impl fmt::Display for Trade {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{} -> {}", self.give, self.receive)
+/// The targets of the `--scenario` or `--config` file, if there is one.
+fn given_targets(cli: &Cli) -> Vec<Candies> {
+    match (&cli.scenario, &cli.config) {
+        (Some(path), _) => load_scenario(cli, path).targets,
+        (None, Some(path)) => load_config(cli, path).targets,
+        (None, None) => Vec::new(),
     }
 }
 
-struct CandyWorks {
-    candies: Candies,
-    max_candies: u32,
-    trades: Vec<Trade>,
-    combinations: Vec<(Candies, Option<(usize, Trade)>)>,
+/// The scenario `explore` is given as arguments.
+fn argument_scenario(
+    cli: &Cli,
+    inventory: &str,
+    cap: u32,
+    trades: &[String],
+    targets: &[String],
+) -> Scenario {
+    let mut lines = vec![
+        (format!("version = {}", scenario::VERSION), String::new()),
+        (format!("cap = {}", cap), format!("--cap {}", cap)),
+        (
+            format!("inventory = {}", inventory),
+            format!("--inventory {:?}", inventory),
+        ),
+    ];
+    for trade in trades {
+        lines.push((format!("trade = {}", trade), format!("--trade {:?}", trade)));
+    }
+    for target in targets {
+        lines.push((
+            format!("target = {}", target),
+            format!("--target {:?}", target),
+        ));
+    }
+    if let Some((_, arg)) = lines.iter().find(|(line, _)| line.contains('\n')) {
+        fail(cli, &format!("Invalid {}: it cannot span lines", arg));
+    }
+    match parse_lines(&lines) {
+        Ok(scenario) => use_scenario(cli, scenario),
+        Err(err) => fail(cli, &format!("Could not explore: {}", err)),
+    }
 }
 
-impl CandyWorks {
-    pub fn new(candies: Candies, max_candies: u32, custom_trades: Vec<Trade>) -> Self {
-        let mut trades = custom_trades;
-        for i in 0..5 {
-            for j in 0..5 {
-                if i != j {
-                    trades.push(Trade::standard_trade(i, j));
+/// Writes `what` in `format` to `out`, or prints it.
+fn export(
+    cli: &Cli,
+    what: ExportKind,
+    graph: Option<&Path>,
+    target: Option<&str>,
+    format: &dyn Exporter,
+    out: Option<&Path>,
+) {
+    let mut rl = terminal(cli);
+    let candy_works = graph_or_scenario(cli, graph, &mut rl);
+    let exported = match what {
+        ExportKind::Route => {
+            let target = match target {
+                Some(target) => parse_candies_arg(cli, target),
+                None => {
+                    let targets = match cli.demo {
+                        true => Scenario::parse(scenario::DEMO)
+                            .map(|scenario| scenario.targets)
+                            .unwrap_or_default(),
+                        false => given_targets(cli),
+                    };
+                    match targets.first() {
+                        Some(target) => *target,
+                        None => read_candies(&mut rl, "want", &cli.max_count),
+                    }
                 }
+            };
+            let route = match candy_works.try_route(target) {
+                Ok(route) => route,
+                Err(err) => fail_with(cli, "Could not export the route", &err),
+            };
+            metrics::record_route(route.len());
+            format.route(&route, &candy_works)
+        }
+        ExportKind::Stats => match candy_works.statistics() {
+            Some(stats) => format.stats(&stats),
+            None => {
+                println!("No combinations found");
+                return;
+            }
+        },
+        ExportKind::Graph => format.graph(&candy_works),
+    };
+    let Some(exported) = exported else {
+        eprintln!("The {} format cannot write {}", format.name(), what);
+        process::exit(1);
+    };
+    match out {
+        Some(out) => {
+            if let Err(err) = fs::write(out, exported) {
+                eprintln!("Could not write {}: {}", out.display(), err);
+                process::exit(1);
+            }
+            println!("Exported to {}", out.display());
+        }
+        None => print!("{}", exported),
+    }
+}
+
+/// Lists the trades in effect and those replaced, each with its layer and
+/// the line it was read from.
+fn print_catalog(cli: &Cli) {
+    let (input, source) = match &cli.scenario {
+        Some(path) => {
+            // Loading first reports the trades that do not read.
+            load_scenario(cli, path);
+            let input = match replay::read_to_string(path) {
+                Ok(input) => input,
+                Err(err) => fail_with(
+                    cli,
+                    "Could not read the scenario",
+                    &CandyWorksError::file(path, err),
+                ),
+            };
+            (input, path.display().to_string())
+        }
+        None => (String::new(), String::new()),
+    };
+    let merged = match catalog::active().merge(&input, &source) {
+        Ok(merged) => merged,
+        Err(err) => {
+            eprintln!("Could not read the catalogs: {}", err);
+            process::exit(1);
+        }
+    };
+    let besides = if cli.no_standard_trades {
+        ""
+    } else {
+        " besides the standard ones"
+    };
+    if merged.trades.is_empty() {
+        println!(
+            "No trades{}; the shared catalog is read from {}",
+            besides,
+            catalog::shared().display()
+        );
+    } else {
+        println!("Trades in effect{}:", besides);
+    }
+    let width = merged
+        .trades
+        .iter()
+        .chain(merged.replaced.iter().map(|replaced| &replaced.trade))
+        .map(|entry| entry.value.chars().count())
+        .max()
+        .unwrap_or(0);
+    for entry in &merged.trades {
+        println!(
+            "  {:<width$}  {:<8}  {}",
+            entry.value,
+            entry.layer.to_string(),
+            entry.source,
+            width = width
+        );
+    }
+    if !merged.replaced.is_empty() {
+        println!("Replaced:");
+    }
+    for replaced in &merged.replaced {
+        println!(
+            "  {:<width$}  {:<8}  {}, {} by {} ({})",
+            replaced.trade.value,
+            replaced.trade.layer.to_string(),
+            replaced.trade.source,
+            if replaced.by.drop {
+                "dropped"
+            } else {
+                "replaced"
+            },
+            replaced.by.source,
+            replaced.by.layer,
+            width = width
+        );
+    }
+}
+
+/// The scenario to plan, with `--reserve` and `--kind-cap` in place of its
+/// own reserve and caps of kinds when given.
+fn obtain_scenario(cli: &Cli, rl: &mut impl Prompt) -> Scenario {
+    let mut scenario = read_scenario(cli, rl);
+    if let Some(reserve) = &cli.reserve {
+        scenario.reserved = parse_candies_arg(cli, reserve);
+    }
+    if let Some(kind_caps) = cli.kind_cap {
+        scenario.kind_caps = Some(kind_caps);
+    }
+    scenario
+}
+
+fn read_scenario(cli: &Cli, rl: &mut impl Prompt) -> Scenario {
+    if let Some(Command::Explore {
+        inventory,
+        cap,
+        trades,
+        targets,
+    }) = &cli.command
+    {
+        if cli.scenario.is_some() || cli.config.is_some() || cli.demo {
+            fail(
+                cli,
+                "explore reads its inventory, trades and targets from its arguments; leave out \
+                 --scenario, --config and --demo",
+            );
+        }
+        return argument_scenario(cli, inventory, *cap, trades, targets);
+    }
+    match &cli.scenario {
+        Some(path) => load_scenario(cli, path),
+        None if cli.config.is_some() => load_config(cli, cli.config.as_ref().unwrap()),
+        None if cli.demo => {
+            match Scenario::parse_with(scenario::DEMO, template::active(), run_preset()) {
+                Ok(scenario) => Scenario {
+                    standard_trades: !cli.no_standard_trades,
+                    ..scenario
+                },
+                Err(err) => unreachable!("the demo scenario is invalid: {}", err),
+            }
+        }
+        None if cli.output == OutputFormat::Json => fail(
+            cli,
+            "JSON output cannot prompt; give the problem with --scenario, --config or --demo",
+        ),
+        None => {
+            let inventory = read_candies(rl, "have", &cli.max_count);
+            let trades = read_trades(rl, cli.vendor_notation);
+            Scenario {
+                names: CandyNames::builtin(),
+                inventory,
+                cap: run_preset().default_cap(),
+                kind_caps: None,
+                locations: vec![None; trades.len()],
+                uncertain: vec![Candies::none(); trades.len()],
+                lots: vec![1; trades.len()],
+                fee: Candies::none(),
+                fees: vec![None; trades.len()],
+                tax: Tax::none(),
+                taxes: vec![None; trades.len()],
+                taxed: vec![Candies::none(); trades.len()],
+                notes: vec![None; trades.len()],
+                uses: vec![None; trades.len()],
+                costs: vec![None; trades.len()],
+                trades,
+                random: Vec::new(),
+                travel: Vec::new(),
+                start: None,
+                reserved: Candies::none(),
+                targets: Vec::new(),
+                target_specs: Vec::new(),
+                overflow: Overflow::default(),
+                standard_trades: !cli.no_standard_trades,
+                preset: run_preset().clone(),
+            }
+        }
+    }
+}
+
+fn print_no_route() {
+    println!("No route found");
+}
+
+/// Ends the run with `message` as an error, in the form `--output` asks
+/// for: as JSON on stdout for `--output json`, else on stderr.
+fn fail(cli: &Cli, message: &str) -> ! {
+    exit_with(cli, message, 1)
+}
+
+/// Fails with why `path` could not be written, if `result` says it was
+/// not.
+fn written(cli: &Cli, path: &Path, result: io::Result<()>) {
+    saved(cli, result.map_err(|err| CandyWorksError::file(path, err)));
+}
+
+/// Fails with why a file could not be written, if `result` says it was not.
+fn saved(cli: &Cli, result: Result<(), CandyWorksError>) {
+    if let Err(err) = result {
+        exit_with(cli, &format!("Could not write {}", err), err.exit_code());
+    }
+}
+
+/// Fails with `err` after `context`, exiting with the status it maps to.
+fn fail_with(cli: &Cli, context: &str, err: &CandyWorksError) -> ! {
+    exit_with(cli, &format!("{}: {}", context, err), err.exit_code())
+}
+
+fn exit_with(cli: &Cli, message: &str, code: i32) -> ! {
+    if cli.output == OutputFormat::Json {
+        println!("{}", output::error_json(message));
+    } else {
+        eprintln!("{}", message);
+    }
+    process::exit(code);
+}
+
+fn parse_candies_arg(cli: &Cli, input: &str) -> Candies {
+    match input.parse() {
+        Ok(candies) => candies,
+        Err(err) => fail(
+            cli,
+            &format!("Could not parse candies {:?}: {}", input, err),
+        ),
+    }
+}
+
+/// A target such as "2m =1c 5*", with exact counts and a total besides
+/// candies to hold at least.
+fn parse_target_arg(cli: &Cli, input: &str) -> TargetSpec {
+    match input.parse() {
+        Ok(spec) => spec,
+        Err(err) => fail(cli, &format!("Could not parse target {:?}: {}", input, err)),
+    }
+}
+
+/// Explores `inventory` under `cap` with the trades of `scenario` and
+/// prints the statistics and the best route to each of its targets as one
+/// JSON document, for `--output json`. Returns whether every target has a
+/// route.
+fn print_json(cli: &Cli, scenario: &Scenario) -> bool {
+    if scenario.targets.is_empty() {
+        fail(
+            cli,
+            "No targets to plan to; give them in the scenario or config",
+        );
+    }
+    let mut candy_works = problem_of(cli, scenario);
+    candy_works.prefilter = cli.bloom;
+    candy_works.prune_dominated = cli.prune_dominated;
+    candy_works.frontier = cli.frontier.frontier(&scenario.targets);
+    candy_works.objective = cli.objective.clone().unwrap_or_default();
+    let limit = cli.time_limit.map(Duration::from_secs_f64);
+    if cli.threads > 1 {
+        let deadline = limit.map(|limit| Instant::now() + limit);
+        let _ = candy_works.explore_parallel_bounded(cli.threads, deadline, None);
+    } else {
+        candy_works.explore_within(limit);
+    }
+    metrics::record_states(candy_works.combinations.len());
+    if let Some(path) = &cli.save_graph {
+        saved(cli, candy_works.save(path));
+    }
+    let costs = TradeCosts::new(&scenario.trades, &scenario.costs);
+    let mut reached = true;
+    let routes = scenario
+        .targets
+        .iter()
+        .map(|target| {
+            let route = if costs.is_empty() {
+                candy_works.find_optimal_route(*target)
+            } else {
+                candy_works.cheapest_route(&costs, *target)
+            };
+            match &route {
+                Some(route) => metrics::record_route(route.len()),
+                None => reached = false,
+            }
+            let cost = (!costs.is_empty())
+                .then(|| route.as_ref().map(|route| costs.total(route.trades())))
+                .flatten();
+            output::route_json(
+                target,
+                route.as_ref(),
+                &candy_works.notes,
+                &scenario.reserved,
+                cost,
+            )
+        })
+        .collect::<Vec<_>>();
+    println!(
+        "{}",
+        output::report_json(candy_works.statistics().as_ref(), &routes)
+    );
+    reached
+}
+
+fn terminal(cli: &Cli) -> impl Prompt {
+    Replayable(Remembered::new(prompt::terminal(), cli.session.as_deref()))
+}
+
+fn command_name(command: &Option<Command>) -> &'static str {
+    match command {
+        None => "solve",
+        Some(Command::Report { .. }) => "report",
+        Some(Command::Merge { .. }) => "merge",
+        Some(Command::Compact { .. }) => "compact",
+        Some(Command::Fsck { .. }) => "fsck",
+        Some(Command::Explore { .. }) => "explore",
+        Some(Command::Route { .. }) => "route",
+        Some(Command::Players) => "players",
+        Some(Command::State { .. }) => "state",
+        Some(Command::Batch { .. }) => "batch",
+        Some(Command::Check { .. }) => "check",
+        Some(Command::Catalog) => "catalog",
+        Some(Command::Export { .. }) => "export",
+        Some(Command::Graph { .. }) => "graph",
+        Some(Command::Import { .. }) => "import",
+        Some(Command::Fmt { .. }) => "fmt",
+        Some(Command::Delta { .. }) => "delta",
+        Some(Command::Explain { .. }) => "explain",
+        Some(Command::CompareStates { .. }) => "compare-states",
+        Some(Command::Query { .. }) => "query",
+        Some(Command::Growth { .. }) => "growth",
+        Some(Command::Reachable { .. }) => "reachable",
+        Some(Command::Dashboard { .. }) => "dashboard",
+        Some(Command::Reconcile { .. }) => "reconcile",
+        Some(Command::Stats { .. }) => "stats",
+        Some(Command::Donate { .. }) => "donate",
+        Some(Command::Farm { .. }) => "farm",
+        Some(Command::Value { .. }) => "value",
+        Some(Command::Chance { .. }) => "chance",
+        Some(Command::Cooperate { .. }) => "cooperate",
+        Some(Command::Tutorial) => "tutorial",
+        Some(Command::Solvers { .. }) => "solvers",
+        Some(Command::Starts { .. }) => "starts",
+        Some(Command::MinTrades { .. }) => "min-trades",
+        Some(Command::Sequence { .. }) => "sequence",
+        Some(Command::Bridge { .. }) => "bridge",
+        Some(Command::Economy) => "economy",
+        Some(Command::Replan { .. }) => "replan",
+        Some(Command::Advise { .. }) => "advise",
+        Some(Command::Follow) => "follow",
+        Some(Command::Repl { .. }) => "repl",
+        Some(Command::Bench { .. }) => "bench",
+        Some(Command::Sweep { .. }) => "sweep",
+        Some(Command::MinCap { .. }) => "min-cap",
+        Some(Command::Quests { .. }) => "quests",
+        Some(Command::Simulate { .. }) => "simulate",
+        Some(Command::Verify { .. }) => "verify",
+    }
+}
+
+/// The arguments of this run without `--record` and its value, which a
+/// replay should not repeat.
+fn recorded_args() -> Vec<String> {
+    let mut args = Vec::new();
+    let mut skip = false;
+    for arg in std::env::args().skip(1) {
+        if skip {
+            skip = false;
+        } else if arg == "--record" {
+            skip = true;
+        } else if !arg.starts_with("--record=") {
+            args.push(arg);
+        }
+    }
+    args
+}
+
+/// The value of `option` among `args`, given as `--option VALUE` or
+/// `--option=VALUE`.
+fn option_value(args: &[String], option: &str) -> Option<String> {
+    args.iter()
+        .enumerate()
+        .find_map(|(i, arg)| match arg.strip_prefix(option) {
+            Some("") => args.get(i + 1).cloned(),
+            Some(rest) => rest.strip_prefix('=').map(str::to_string),
+            None => None,
+        })
+}
+
+/// Reads the `--kinds` file among `args`. The kinds have to be known
+/// before the command line is parsed, since defaults such as
+/// `--max-count` hold candies.
+fn configure_kinds(args: &[String]) -> Option<PathBuf> {
+    let path = option_value(args, "--kinds").map(PathBuf::from)?;
+    let kinds = replay::read_to_string(&path)
+        .map_err(|err| err.to_string())
+        .and_then(|text| CandyKinds::parse(&text))
+        .and_then(kinds::configure);
+    match kinds {
+        Ok(()) => (),
+        Err(err) => {
+            eprintln!("Invalid --kinds {}: {}", path.display(), err);
+            process::exit(1);
+        }
+    }
+    Some(path)
+}
+
+static PRESET: OnceLock<Preset> = OnceLock::new();
+
+/// The `--preset` the run plans with, or the standard 3:1 trades.
+fn run_preset() -> &'static Preset {
+    PRESET.get_or_init(Preset::builtin)
+}
+
+/// Reads the `--preset` among `args` from its presets file, configuring
+/// its kinds of candy before its trades are read with them. Like the
+/// kinds, the preset has to be known before the command line is parsed,
+/// since it sets the default cap.
+fn configure_preset(args: &[String]) -> Option<String> {
+    let name = option_value(args, "--preset")?;
+    let path = option_value(args, "--presets").map_or_else(preset::file, PathBuf::from);
+    let invalid = |err: String| -> ! {
+        eprintln!("Invalid --preset {} in {}: {}", name, path.display(), err);
+        process::exit(1);
+    };
+    let section = replay::read_to_string(&path)
+        .map_err(|err| err.to_string())
+        .and_then(|text| preset::section(&text, &name))
+        .unwrap_or_else(|err| invalid(err));
+    if let Some(kinds) = section.kinds.clone() {
+        if option_value(args, "--kinds").is_some() {
+            invalid("the preset has kinds of candy of its own; leave out --kinds".to_string());
+        }
+        kinds::configure(kinds).unwrap_or_else(|err| invalid(err));
+    }
+    let preset = section.preset().unwrap_or_else(|err| invalid(err));
+    // Only main sets it, once, before any problem is planned with it.
+    let _ = PRESET.set(preset);
+    Some(name)
+}
+
+fn main() {
+    export::register_builtin();
+    let args = std::env::args().collect::<Vec<_>>();
+    let preset = configure_preset(&args);
+    let kinds = configure_kinds(&args);
+    let mut cli = Cli::parse();
+    if let Some(path) = &cli.replay {
+        match replay::replay(path) {
+            Ok(args) if option_value(&args, "--kinds").map(PathBuf::from) != kinds => {
+                eprintln!(
+                    "Could not replay {}: it was recorded with other --kinds; give the same \
+                     --kinds along with --replay",
+                    path.display()
+                );
+                process::exit(1);
+            }
+            Ok(args) if option_value(&args, "--preset") != preset => {
+                eprintln!(
+                    "Could not replay {}: it was recorded with another --preset; give the same \
+                     --preset along with --replay",
+                    path.display()
+                );
+                process::exit(1);
+            }
+            Ok(args) => {
+                cli = Cli::parse_from(std::iter::once("candyworks".to_string()).chain(args))
+            }
+            Err(err) => {
+                eprintln!("Could not replay {}: {}", path.display(), err);
+                process::exit(1);
+            }
+        }
+    } else if let Some(path) = &cli.record {
+        if let Err(err) = replay::record(path, &recorded_args()) {
+            eprintln!("Could not record to {}: {}", path.display(), err);
+            process::exit(1);
+        }
+    }
+    if let Some(format) = cli.format {
+        cli.output = format;
+    }
+    let mut player_catalog = None;
+    if let Some(name) = cli.player.clone() {
+        match Player::open(&name) {
+            Ok(player) => {
+                use_player(&mut cli, &player);
+                player_catalog = Some(player.catalog());
+            }
+            Err(err) => {
+                eprintln!("Invalid --player: {}", err);
+                process::exit(1);
+            }
+        }
+    }
+    let mut catalogs = Catalogs::new(player_catalog, &cli.catalog, &cli.trades_csv);
+    catalogs.without_standard = cli.no_standard_trades;
+    if let Err(err) = catalog::configure(catalogs) {
+        fail(&cli, &err);
+    }
+    let started = Instant::now();
+    let timings = cli.timings.then(|| Arc::new(Timings::new()));
+    let reached = run(&cli, timings.as_ref());
+    if let Some(timings) = &timings {
+        timings::report(timings, started);
+    }
+    if let Some(format) = cli.metrics {
+        metrics::emit(format, command_name(&cli.command), started);
+    }
+    if matches!(
+        cli.command,
+        Some(Command::Explore { .. } | Command::Route { .. })
+    ) && !reached
+    {
+        process::exit(3);
+    }
+}
+
+/// Points the options not given at the player's own files. A player
+/// without a scenario yet is prompted, and the answers become their
+/// scenario.
+fn use_player(cli: &mut Cli, player: &Player) {
+    let explore = matches!(cli.command, Some(Command::Explore { .. }));
+    if cli.scenario.is_none() && cli.config.is_none() && !cli.demo && !explore {
+        if player.scenario().exists() {
+            cli.scenario = Some(player.scenario());
+        } else {
+            cli.save_scenario.get_or_insert_with(|| player.scenario());
+        }
+    }
+    cli.session.get_or_insert_with(|| player.session());
+    cli.trade_profile
+        .get_or_insert_with(|| player.trade_profile());
+    cli.queries.get_or_insert_with(|| player.queries());
+    match &mut cli.command {
+        None | Some(Command::Explore { .. }) if !cli.stats_only && !cli.two_phase => {
+            cli.save_graph.get_or_insert_with(|| player.graph());
+        }
+        Some(Command::Import { out, .. }) => {
+            out.get_or_insert_with(|| player.scenario());
+        }
+        Some(Command::Route {
+            graph, progress, ..
+        }) => match progress {
+            Some(progress) => {
+                progress
+                    .tracker()
+                    .get_or_insert_with(|| player.route_progress());
+            }
+            None => {
+                graph.get_or_insert_with(|| player.graph());
+            }
+        },
+        _ => (),
+    }
+}
+
+/// Runs the command `cli` asks for, or plans, timing the planning in
+/// `timings` if given. Returns whether every target planned to has a
+/// route, for `explore` and `route` to exit with status 3 otherwise, apart
+/// from the 1 of errors and the 2 of misused arguments.
+fn run(cli: &Cli, timings: Option<&Arc<Timings>>) -> bool {
+    let mut reached = true;
+    if cli.output == OutputFormat::Json
+        && !matches!(
+            cli.command,
+            None | Some(Command::Explore { .. } | Command::Stats { detailed: true, .. })
+        )
+    {
+        fail(
+            cli,
+            "--output json is for planning without a command, explore and stats --detailed",
+        );
+    }
+    let display = DisplayOptions {
+        order: cli.order.clone(),
+        include_zeros: cli.zeros,
+        color: cli.color.enabled(),
+    };
+    if let Err(err) = display::configure(display) {
+        fail(cli, &err);
+    }
+    let mut variables = Variables::default();
+    for assignment in &cli.set {
+        if let Err(err) = variables.set(assignment) {
+            eprintln!("Invalid --set: {}", err);
+            process::exit(1);
+        }
+    }
+    if let Err(err) = template::configure(variables) {
+        fail(cli, &format!("Invalid --set: {}", err));
+    }
+    match &cli.command {
+        Some(Command::Players) => {
+            let players = player::list();
+            if players.is_empty() {
+                println!("No players yet; start one with --player NAME");
             }
+            for name in players {
+                println!("{}", name);
+            }
+            return reached;
+        }
+        Some(Command::Repl { restore }) => {
+            let mut rl = terminal(cli);
+            let mut session = if cli.scenario.is_some() || cli.config.is_some() || cli.demo {
+                let scenario = obtain_scenario(cli, &mut rl);
+                let mut session =
+                    repl::Session::new(scenario.inventory, scenario.cap, scenario.trades);
+                session.kind_caps = scenario.kind_caps;
+                session.standard_trades = scenario.standard_trades;
+                session.preset = scenario.preset;
+                session.targets = scenario.targets;
+                session
+            } else {
+                let mut session =
+                    repl::Session::new(Candies::none(), run_preset().default_cap(), Vec::new());
+                session.standard_trades = !cli.no_standard_trades;
+                session.preset = run_preset().clone();
+                session
+            };
+            session.objective = cli.objective.clone().unwrap_or_default();
+            session.force = cli.force;
+            if let Some(path) = restore {
+                session.replay(path);
+            }
+            repl::run(&mut session, &mut rl);
+            return reached;
+        }
+        Some(Command::Catalog) => {
+            print_catalog(cli);
+            return reached;
+        }
+        Some(Command::Export {
+            what,
+            graph,
+            target,
+            format,
+            out,
+        }) => {
+            export(
+                cli,
+                *what,
+                graph.as_deref(),
+                target.as_deref(),
+                *format,
+                out.as_deref(),
+            );
+            return reached;
         }
-        CandyWorks {
-            candies,
-            max_candies,
-            trades,
-            combinations: Vec::new(),
-        }
-    }
-
-    pub fn explore(&mut self) {
-        let t = Instant::now();
-        let mut collections = vec![(self.candies, None)];
-        let mut known_sets = HashSet::new();
-        let mut queue = VecDeque::new();
-        queue.push_front(0);
-        known_sets.insert(self.candies);
-        while let Some(index) = queue.pop_back() {
-            let (candies, _) = collections[index];
-            let mut new_collections = Vec::new();
-            for trade in &self.trades {
-                if let Some(new_candies) = candies.trade(trade) {
-                    let total = new_candies.total();
-                    if total <= self.max_candies as i32 && !known_sets.contains(&new_candies) {
-                        new_collections.push((new_candies, Some((index, *trade))));
-                        known_sets.insert(new_candies);
+        Some(Command::Graph {
+            graph,
+            max_depth,
+            target,
+            out,
+        }) => {
+            let candy_works = graph_or_scenario(cli, graph.as_deref(), &mut terminal(cli));
+            let options = DotOptions {
+                max_depth: *max_depth,
+                target: target
+                    .as_deref()
+                    .map(|target| parse_candies_arg(cli, target)),
+            };
+            if let Err(err) = candy_works.write_dot(out.as_deref(), &options) {
+                eprintln!("Could not write the graph: {}", err);
+                process::exit(1);
+            }
+            if let Some(out) = out {
+                println!("Graph written to {}", out.display());
+            }
+            return reached;
+        }
+        Some(Command::Import { file, format, out }) => {
+            let scenario = match import::import(file, *format) {
+                Ok(scenario) => scenario,
+                Err(err) => {
+                    eprintln!("Could not import {}: {}", file.display(), err);
+                    process::exit(1);
+                }
+            };
+            match out {
+                Some(out) => {
+                    written(cli, out, fs::write(out, scenario.format()));
+                    println!(
+                        "Imported {} trade{} and {} target{} to {}",
+                        scenario.trades.len(),
+                        if scenario.trades.len() == 1 { "" } else { "s" },
+                        scenario.targets.len(),
+                        if scenario.targets.len() == 1 { "" } else { "s" },
+                        out.display()
+                    );
+                }
+                None => print!("{}", scenario.format()),
+            }
+            return reached;
+        }
+        Some(Command::Stats {
+            graph,
+            detailed,
+            counts,
+        }) => {
+            let candy_works = load_graph(graph);
+            metrics::record_states(candy_works.combinations.len());
+            if *detailed {
+                let queries = counts
+                    .iter()
+                    .map(|query| match Filter::parse(query) {
+                        Ok(filter) => (query.clone(), filter),
+                        Err(err) => fail(cli, &format!("Invalid query {:?}: {}", query, err)),
+                    })
+                    .collect::<Vec<_>>();
+                let (Some(stats), Some(details)) =
+                    (candy_works.statistics(), candy_works.detailed_statistics())
+                else {
+                    fail(cli, "No combinations found");
+                };
+                let matching = statistics::count_matching(&candy_works, &queries)
+                    .unwrap_or_else(|err| fail(cli, &format!("Could not run a query: {}", err)));
+                if cli.output == OutputFormat::Json {
+                    println!("{}", statistics::detailed_json(&stats, &details, &matching));
+                } else {
+                    print!("{}", statistics::detailed_text(&stats, &details, &matching));
+                }
+                return reached;
+            }
+            print_statistics(&candy_works);
+            print_projection(&candy_works, &cli.project);
+            let components = candy_works.components();
+            println!(
+                "Strongly connected components: {} (largest: {})",
+                components.count, components.largest
+            );
+            return reached;
+        }
+        Some(Command::Merge { first, second, out }) => {
+            let merged = match load_graph(first).merge(&load_graph(second)) {
+                Ok(merged) => merged,
+                Err(err) => {
+                    eprintln!("Could not merge graphs: {}", err);
+                    process::exit(1);
+                }
+            };
+            metrics::record_states(merged.combinations.len());
+            saved(cli, merged.save(out));
+            println!(
+                "Merged graph with {} combinations saved to {}",
+                merged.combinations.len(),
+                out.display()
+            );
+            return reached;
+        }
+        Some(Command::Compact { graph, out }) => {
+            let mut candy_works = load_graph(graph);
+            let targets = given_targets(cli);
+            let before = candy_works.combinations.len();
+            candy_works.compact(&targets);
+            metrics::record_states(candy_works.combinations.len());
+            saved(cli, candy_works.save(out));
+            println!(
+                "Kept {} of {} combinations, saved to {}",
+                candy_works.combinations.len(),
+                before,
+                out.display()
+            );
+            return reached;
+        }
+        Some(Command::Route {
+            progress: Some(progress),
+            ..
+        }) => {
+            track_route(cli, progress);
+            return reached;
+        }
+        Some(Command::Route {
+            graph,
+            from,
+            to,
+            targets,
+            direct,
+            ..
+        }) => {
+            if *direct {
+                if let Some(scenario) = cli
+                    .scenario
+                    .as_deref()
+                    .and_then(|path| load_wide(cli, path))
+                {
+                    let targets = targets
+                        .iter()
+                        .map(|target| {
+                            Counts::parse_with(target, &CandyNames::builtin()).unwrap_or_else(
+                                |err| {
+                                    fail(
+                                        cli,
+                                        &format!("Could not parse candies {:?}: {}", target, err),
+                                    )
+                                },
+                            )
+                        })
+                        .collect::<Vec<_>>();
+                    let (routes, all) = scenario.direct(&targets);
+                    print!("{}", routes);
+                    return all;
+                }
+                let mut rl = terminal(cli);
+                let scenario = obtain_scenario(cli, &mut rl);
+                let candy_works = problem_of(cli, &scenario);
+                let targets = if !targets.is_empty() {
+                    targets
+                        .iter()
+                        .map(|target| parse_candies_arg(cli, target))
+                        .collect()
+                } else if !scenario.targets.is_empty() {
+                    scenario.targets
+                } else {
+                    vec![read_candies(&mut rl, "want", &cli.max_count)]
+                };
+                for target in targets {
+                    match candy_works.find_route_direct(target) {
+                        Some(route) => {
+                            metrics::record_route(route.len());
+                            print!(
+                                "{}",
+                                candy_works.format_route(
+                                    route.start().with(&scenario.reserved),
+                                    route.trades()
+                                )
+                            );
+                        }
+                        None => {
+                            print_no_route();
+                            reached = false;
+                        }
+                    }
+                }
+                return reached;
+            }
+            let Some(graph) = graph else {
+                eprintln!("Finding a route needs a --graph, or a --player who explored one");
+                process::exit(1);
+            };
+            let mut candy_works = load_graph(graph);
+            candy_works.objective = cli.objective.clone().unwrap_or_default();
+            if cli.scenario.is_some() || cli.config.is_some() || cli.demo {
+                // A graph keeps no use limits or notes, so they come from the scenario.
+                let scenario = obtain_scenario(cli, &mut terminal(cli));
+                candy_works.use_limits = scenario.use_limits();
+                candy_works.notes = Notes::new(&scenario);
+            }
+            metrics::record_states(candy_works.combinations.len());
+            let state_arg = |arg: &Option<String>| {
+                arg.as_deref().map(|arg| match compare::parse_state(arg) {
+                    Ok(state) => state,
+                    Err(err) => {
+                        eprintln!("Invalid state: {}", err);
+                        process::exit(1);
+                    }
+                })
+            };
+            let (from, to) = (state_arg(from), state_arg(to));
+            if let Some(to) = to {
+                let start = from.unwrap_or(candy_works.candies);
+                match candy_works.shortest_route(start, to) {
+                    Some(route) => {
+                        metrics::record_route(route.len());
+                        print!("{}", candy_works.format_route(start, route.trades()));
+                    }
+                    None => {
+                        print_no_route();
+                        reached = false;
+                    }
+                }
+                return reached;
+            }
+            let scenario_targets = given_targets(cli);
+            let targets = if !targets.is_empty() {
+                targets
+                    .iter()
+                    .map(|target| parse_target_arg(cli, target))
+                    .collect()
+            } else if !scenario_targets.is_empty() {
+                scenario_targets.into_iter().map(TargetSpec::from).collect()
+            } else {
+                let mut rl = terminal(cli);
+                vec![TargetSpec::from(read_candies(
+                    &mut rl,
+                    "want",
+                    &cli.max_count,
+                ))]
+            };
+            if let Some(from) = from {
+                if candy_works.combinations.states().all(|state| state != from) {
+                    println!(
+                        "({}) cannot be reached from the graph's start; planning from it anyway",
+                        from.display(false).trim()
+                    );
+                }
+                for spec in targets {
+                    let Some(target) = spec.lower_bound() else {
+                        fail(
+                            cli,
+                            "--from only plans to targets of candies to hold at least",
+                        );
+                    };
+                    match candy_works.route_from(from, target) {
+                        Some(route) => {
+                            metrics::record_route(route.len());
+                            print!("{}", candy_works.format_route(from, route.trades()));
+                        }
+                        None => {
+                            print_no_route();
+                            reached = false;
+                        }
+                    }
+                }
+                return reached;
+            }
+            for spec in targets {
+                match candy_works.graph_spec_route(&spec) {
+                    Some(route) => {
+                        metrics::record_route(route.len());
+                        println!("Starting inventory:\n{}", route.start().to_table());
+                        print!(
+                            "{}",
+                            candy_works.format_route(route.start(), route.trades())
+                        );
+                    }
+                    None => {
+                        print_no_route();
+                        reached = false;
+                    }
+                }
+            }
+            return reached;
+        }
+        Some(Command::State { graph, id }) => {
+            let candy_works = load_graph(graph);
+            metrics::record_states(candy_works.combinations.len());
+            let Some(index) = candy_works.find_by_id(*id) else {
+                eprintln!("State {} is not in {}", id, graph.display());
+                process::exit(1);
+            };
+            let route = candy_works.route_to(index);
+            metrics::record_route(route.len());
+            println!("{}", candy_works.combinations.state(index).to_table());
+            print!(
+                "{}",
+                candy_works.format_route(candy_works.root_of(index), &route)
+            );
+            return reached;
+        }
+        Some(Command::Batch {
+            dir,
+            out,
+            jobs,
+            memory_limit,
+        }) => {
+            let limits = batch::Limits {
+                time: cli.time_limit.map(Duration::from_secs_f64),
+                memory_mb: *memory_limit,
+                standard_trades: !cli.no_standard_trades,
+                preset: run_preset().clone(),
+            };
+            if let Err(err) = batch::run(dir, out, *jobs, limits) {
+                eprintln!("Batch failed: {}", err);
+                process::exit(1);
+            }
+            return reached;
+        }
+        Some(Command::Bench {
+            baseline,
+            save_baseline,
+            runs,
+            tolerance,
+            caps,
+            explorers,
+        }) => {
+            if let Some(caps) = caps {
+                let caps = bench::caps(caps)
+                    .unwrap_or_else(|err| fail(cli, &format!("Invalid --caps: {}", err)));
+                let scenario = obtain_scenario(cli, &mut terminal(cli));
+                if let Err(err) = bench::sweep(&scenario, &caps, explorers, *runs, cli.force) {
+                    fail(cli, &format!("Could not run the benchmark: {}", err));
+                }
+                return reached;
+            }
+            match bench::run(
+                *runs,
+                baseline.as_deref(),
+                save_baseline.as_deref(),
+                *tolerance,
+            ) {
+                Ok(true) => (),
+                Ok(false) => process::exit(1),
+                Err(err) => {
+                    eprintln!("Could not run the benchmark: {}", err);
+                    process::exit(1);
+                }
+            }
+            return reached;
+        }
+        Some(Command::Tutorial) => {
+            if !kinds::active().is_builtin() {
+                eprintln!("The tutorial plays with the built-in candies; run it without --kinds");
+                process::exit(1);
+            }
+            tutorial::run(&mut prompt::terminal());
+            return reached;
+        }
+        Some(Command::Sweep { param }) => {
+            let Some(path) = &cli.scenario else {
+                eprintln!("sweep needs a scenario file, pass --scenario");
+                process::exit(1);
+            };
+            if let Err(err) = sweep::run(path, param, template::active(), run_preset(), cli.force) {
+                eprintln!("Could not sweep {}: {}", path.display(), err);
+                process::exit(1);
+            }
+            return reached;
+        }
+        Some(Command::Fsck { graph }) => {
+            if !fsck::fsck(graph) {
+                process::exit(1);
+            }
+            return reached;
+        }
+        Some(Command::Check { file }) => {
+            if !check::check(file, run_preset()) {
+                process::exit(1);
+            }
+            return reached;
+        }
+        Some(Command::Fmt { file, check }) => {
+            if !check::format(file, *check) {
+                process::exit(1);
+            }
+            return reached;
+        }
+        Some(Command::Delta {
+            graph,
+            trade,
+            limit,
+        }) => {
+            let trade = match trade.parse::<Trade>() {
+                Ok(trade) => trade,
+                Err(err) => {
+                    eprintln!(
+                        "Could not parse trade {:?}: {}, expected e.g. \"3e -> 1m\"",
+                        trade, err
+                    );
+                    process::exit(1);
+                }
+            };
+            let candy_works = load_graph(graph);
+            metrics::record_states(candy_works.combinations.len());
+            delta::report(&candy_works, trade, *limit);
+            return reached;
+        }
+        Some(Command::Explain {
+            current,
+            graph,
+            suggestions,
+        }) => {
+            let mut rl = terminal(cli);
+            let candy_works = graph_or_scenario(cli, graph.as_deref(), &mut rl);
+            let current = match current {
+                Some(current) => parse_candies_arg(cli, current),
+                None => read_candies(&mut rl, "have now", &cli.max_count),
+            };
+            print!("{}", explain::explain(&candy_works, current, *suggestions));
+            return reached;
+        }
+        Some(Command::CompareStates { a, b, graph }) => {
+            let (a, b) = match (compare::parse_state(a), compare::parse_state(b)) {
+                (Ok(a), Ok(b)) => (a, b),
+                (Err(err), _) | (_, Err(err)) => {
+                    eprintln!("Invalid state: {}", err);
+                    process::exit(1);
+                }
+            };
+            let mut rl = terminal(cli);
+            let candy_works = graph_or_scenario(cli, graph.as_deref(), &mut rl);
+            print!("{}", compare::compare(&candy_works, a, b));
+            return reached;
+        }
+        Some(Command::Query {
+            filter,
+            graph,
+            count,
+            export,
+            limit,
+            save,
+        }) => {
+            let mut rl = terminal(cli);
+            let candy_works = graph_or_scenario(cli, graph.as_deref(), &mut rl);
+            let Some(filter) = filter else {
+                while let Some(line) = rl.ask("Filter: ").filter(|line| !line.trim().is_empty()) {
+                    let listed = Filter::parse(&line)
+                        .and_then(|filter| query::list(&candy_works, &filter, *limit, *count));
+                    if let Err(err) = listed {
+                        println!("Could not run the filter: {}", err);
                     }
                 }
+                return reached;
+            };
+            let filter_text = Some(filter.as_str());
+            let filter = match Filter::parse(filter) {
+                Ok(filter) => filter,
+                Err(err) => {
+                    eprintln!("Invalid filter: {}", err);
+                    process::exit(1);
+                }
+            };
+            if let (Some(name), Some(text)) = (save, filter_text) {
+                let Some(path) = &cli.queries else {
+                    eprintln!("Saving a query needs a --queries file");
+                    process::exit(1);
+                };
+                let mut queries = load_queries(path);
+                queries.insert(name, text);
+                written(cli, path, queries.save(path));
+                println!("Query {} saved to {}", name, path.display());
+            }
+            let listed = match export {
+                Some(path) => query::export(&candy_works, &filter, path).map(|matching| {
+                    println!("{} states written to {}", matching, path.display());
+                }),
+                None => query::list(&candy_works, &filter, *limit, *count),
+            };
+            if let Err(err) = listed {
+                fail(cli, &format!("Could not run the filter: {}", err));
+            }
+            return reached;
+        }
+        Some(Command::Growth {
+            filter,
+            graph,
+            width,
+        }) => {
+            let filter = filter.as_deref().map(|filter| match Filter::parse(filter) {
+                Ok(filter) => filter,
+                Err(err) => {
+                    eprintln!("Invalid filter: {}", err);
+                    process::exit(1);
+                }
+            });
+            let mut rl = terminal(cli);
+            let candy_works = graph_or_scenario(cli, graph.as_deref(), &mut rl);
+            if let Err(err) = growth::chart(&candy_works, filter.as_ref(), *width) {
+                fail(cli, &format!("Could not run the filter: {}", err));
+            }
+            return reached;
+        }
+        Some(Command::Reachable { graph, limit }) => {
+            let mut rl = terminal(cli);
+            let candy_works = graph_or_scenario(cli, graph.as_deref(), &mut rl);
+            reachable::print(&candy_works, *limit);
+            return reached;
+        }
+        Some(Command::Dashboard { graph }) => {
+            let Some(path) = &cli.queries else {
+                eprintln!("The dashboard needs a --queries file");
+                process::exit(1);
+            };
+            let queries = load_queries(path);
+            let mut rl = terminal(cli);
+            let candy_works = graph_or_scenario(cli, graph.as_deref(), &mut rl);
+            dashboard::print(&candy_works, &queries);
+            return reached;
+        }
+        Some(Command::Reconcile { scenario, game }) => {
+            let tracked = load_scenario(cli, scenario).inventory;
+            let mut rl = terminal(cli);
+            let game = match game {
+                Some(game) => parse_candies_arg(cli, game),
+                None => read_candies(&mut rl, "have in the game", &cli.max_count),
+            };
+            if let Err(err) = reconcile::reconcile(scenario, tracked, game, &mut rl) {
+                fail_with(cli, "Could not update the inventory", &err);
             }
-            queue.extend((0..new_collections.len()).map(|i| i + collections.len()));
-            collections.append(&mut new_collections);
+            return reached;
         }
-        self.combinations = collections;
-        println!("Elapsed time: {:?}", t.elapsed());
+        _ => (),
     }
-
-    pub fn stadistics(&self) {
-        if self.combinations.is_empty() {
-            println!("No combinations found");
-            return;
+    if let (None, Some(path)) = (&cli.command, &cli.scenario) {
+        if let Some(scenario) = load_wide(cli, path) {
+            if !cli.force && cli.time_limit.is_none() {
+                if let Err(err) = guard::check(scenario.cap().unsigned_abs()) {
+                    eprintln!("{}", err);
+                    process::exit(1);
+                }
+            }
+            print!(
+                "{}",
+                scenario.plan(cli.time_limit.map(Duration::from_secs_f64))
+            );
+            return reached;
         }
-        println!("Total combinations: {}", self.combinations.len());
-        let min_candies = self
-            .combinations
-            .iter()
-            .map(|(candies, _)| candies.total())
-            .min()
-            .unwrap();
-        let max_candies = self
-            .combinations
+    }
+    let mut rl = terminal(cli);
+    let mut scenario = obtain_scenario(cli, &mut rl);
+    // Reserved candies are set aside: planning sees only the rest, with the
+    // cap lowered by as much, and routes are shown with them added back.
+    let reserved = scenario.reserved;
+    let Some(inventory) = scenario.inventory.without(&reserved) else {
+        fail(
+            cli,
+            &format!(
+                "Cannot reserve ({}), the inventory only has ({})",
+                reserved.display(false).trim(),
+                scenario.inventory.display(false).trim()
+            ),
+        );
+    };
+    let cap = scenario.cap.saturating_sub(reserved.total().get() as u32);
+    let kind_caps = scenario.kind_caps.map(|caps| caps.less(&reserved));
+    let mut problem = problem_of(cli, &scenario);
+    problem.timings = timings.cloned();
+    if let Some(most) = cli.repeat {
+        // A multiple of a trade limited in its uses would make it past them.
+        let repeatable = problem
+            .trades
             .iter()
-            .map(|(candies, _)| candies.total())
-            .max()
-            .unwrap();
-        println!("Min candies: {}", min_candies);
-        println!("Max candies: {}", max_candies);
-        let max_trades = self
-            .combinations
+            .filter(|trade| problem.use_limits.limit(trade).is_none())
+            .copied()
+            .collect::<Vec<_>>();
+        if let Err(err) = repeat::configure(Repeats::new(&repeatable, most)) {
+            fail(cli, &format!("Invalid --repeat: {}", err));
+        }
+    }
+    let cacheable = kind_caps.is_none() && cli.repeat.is_none();
+    let new_problem = || {
+        let mut candy_works = problem.unexplored();
+        candy_works.trades = repeat::active().with_multiples(&candy_works.trades);
+        candy_works
+    };
+    if reserved != Candies::none() && cli.output == OutputFormat::Text {
+        println!("Keeping ({}) reserved", reserved.display(false).trim());
+    }
+    let answers_targets = matches!(cli.command, None | Some(Command::Explore { .. }));
+    let backward = match cli.strategy {
+        Strategy::Backward => true,
+        Strategy::Forward => false,
+        Strategy::Auto => {
+            answers_targets
+                && !cli.force
+                && cli.time_limit.is_none()
+                && !cli.stats_only
+                && !cli.two_phase
+                && cli.output == OutputFormat::Text
+                && scenario.target_specs.is_empty()
+                && guard::check(cap.into()).is_err()
+        }
+    };
+    if backward {
+        if !answers_targets || cli.output == OutputFormat::Json {
+            fail(
+                cli,
+                "--strategy backward only prints the routes to the targets; leave out the \
+                 command and --output json",
+            );
+        }
+        if !scenario.target_specs.is_empty() {
+            fail(
+                cli,
+                "--strategy backward only plans to targets of candies to hold at least",
+            );
+        }
+        let mut candy_works = new_problem();
+        candy_works.objective = cli.objective.clone().unwrap_or_default();
+        if scenario.targets.is_empty() {
+            scenario
+                .targets
+                .push(read_candies(&mut rl, "want", &cli.max_count));
+        }
+        println!("Searching backward from each target");
+        let mut explored = None;
+        for (n, target) in scenario.targets.iter().enumerate() {
+            let route = match candy_works.backward_route(*target) {
+                Ok(route) => route,
+                Err(reason) => {
+                    println!("Searching forward instead: {}", reason);
+                    if !cli.force && cli.time_limit.is_none() {
+                        if let Err(err) = guard::check(cap.into()) {
+                            fail(cli, &err.to_string());
+                        }
+                    }
+                    let forward = explored.get_or_insert_with(|| {
+                        let mut forward = candy_works.unexplored();
+                        forward.explore_within(cli.time_limit.map(Duration::from_secs_f64));
+                        metrics::record_states(forward.combinations.len());
+                        forward
+                    });
+                    forward.find_optimal_route(*target)
+                }
+            };
+            let Some(route) = route else {
+                print_no_route();
+                reached = false;
+                continue;
+            };
+            metrics::record_route(route.len());
+            print!(
+                "{}",
+                candy_works.format_route(candy_works.candies.with(&reserved), route.trades())
+            );
+            print_change(candy_works.candies.with(&reserved), route.trades());
+            if n == 0 {
+                if let Some(path) = &cli.save_route {
+                    written(
+                        cli,
+                        path,
+                        route_file::save(path, route.trades(), &candy_works.notes),
+                    );
+                    println!("Route saved to {}", path.display());
+                }
+            }
+        }
+        return reached;
+    }
+    if !cli.force && cli.time_limit.is_none() {
+        if let Err(err) = guard::check(cap.into()) {
+            fail(cli, &err.to_string());
+        }
+    }
+    if let Some(Command::Donate { candy }) = &cli.command {
+        let Some(candy) = donate::parse_candy(candy) else {
+            eprintln!("Unknown candy {:?}", candy);
+            process::exit(1);
+        };
+        let mut candy_works = new_problem();
+        candy_works.explore();
+        metrics::record_states(candy_works.combinations.len());
+        print!("{}", donate::plan(&candy_works, candy, reserved));
+        return reached;
+    }
+    if let Some(Command::Value {
+        weights,
+        max_trades,
+    }) = &cli.command
+    {
+        let weights = parse_candies_arg(cli, weights);
+        let candy_works = new_problem();
+        print!(
+            "{}",
+            value::plan(&candy_works, &weights, *max_trades, reserved)
+        );
+        return reached;
+    }
+    if let Some(Command::Chance { within, weights }) = &cli.command {
+        let random = std::mem::take(&mut scenario.random);
+        if random.is_empty() {
+            fail(cli, "The scenario has no random trades; add some such as `random = 3e -> 1m 40% | 1c 60%`");
+        }
+        let candy_works = new_problem();
+        let chances = random::Chances {
+            candy_works: &candy_works,
+            random: &random,
+        };
+        if let Some(weights) = weights {
+            chances.print_worth(&parse_candies_arg(cli, weights), *within, reserved);
+            return reached;
+        }
+        if scenario.targets.is_empty() {
+            scenario
+                .targets
+                .push(read_candies(&mut rl, "want", &cli.max_count));
+        }
+        for target in &scenario.targets {
+            if scenario.targets.len() > 1 {
+                println!("Target: ({})", display_side(target));
+            }
+            chances.print_reach(*target, *within);
+        }
+        return reached;
+    }
+    if let Some(Command::Cooperate {
+        partners,
+        name,
+        receiver,
+        transfer_fee,
+        max_states,
+    }) = &cli.command
+    {
+        let candy_works = new_problem();
+        let mut names = vec![name.trim().to_string()];
+        let mut inventories = vec![candy_works.candies];
+        for partner in partners {
+            let Some((partner, candies)) = partner
+                .split_once('=')
+                .filter(|(partner, _)| !partner.trim().is_empty())
+            else {
+                fail(
+                    cli,
+                    &format!(
+                        "Invalid --partner {:?}: expected a name and candies such as \"bob=6e 2w\"",
+                        partner
+                    ),
+                );
+            };
+            names.push(partner.trim().to_string());
+            inventories.push(parse_candies_arg(cli, candies));
+        }
+        if let Some(twice) = names
             .iter()
-            .filter(|(_, parent)| parent.is_some())
-            .map(|(_, parent)| self.len_from_combination(parent.as_ref().unwrap().0))
-            .max()
-            .unwrap();
-        println!("Max trades: {}", max_trades);
-    }
-
-    pub fn len_from_combination(&self, index: usize) -> usize {
-        let mut current = index;
-        let mut len = 0;
-        while let Some((_, parent)) = self.combinations.get(current) {
-            len += 1;
-            if let Some((i, _)) = parent {
-                current = *i;
-            } else {
-                break;
+            .enumerate()
+            .find(|(n, name)| names[..*n].contains(name))
+        {
+            fail(cli, &format!("{} is named twice", twice.1));
+        }
+        let receiver = match receiver {
+            Some(receiver) => match names.iter().position(|name| name == receiver.trim()) {
+                Some(receiver) => receiver,
+                None => fail(cli, &format!("{} is not one of the players", receiver)),
+            },
+            None => 0,
+        };
+        let cooperation = cooperate::Cooperation {
+            candy_works: &candy_works,
+            names,
+            inventories,
+            fee: transfer_fee
+                .as_deref()
+                .map_or(Candies::none(), |candies| parse_candies_arg(cli, candies)),
+        };
+        if scenario.targets.is_empty() {
+            scenario
+                .targets
+                .push(read_candies(&mut rl, "want", &cli.max_count));
+        }
+        for target in &scenario.targets {
+            if scenario.targets.len() > 1 {
+                println!("Target: ({})", display_side(target));
             }
+            if !cooperation.print(receiver, *target, *max_states) {
+                print_no_route();
+                reached = false;
+            }
+        }
+        return reached;
+    }
+    if let Some(Command::Farm { candy }) = &cli.command {
+        let Some(candy) = donate::parse_candy(candy) else {
+            eprintln!("Unknown candy {:?}", candy);
+            process::exit(1);
+        };
+        let candy_works = new_problem();
+        farm::best_loop(&candy_works, candy);
+        return reached;
+    }
+    if let Some(Command::Replan {
+        route,
+        failed_step,
+        reason,
+    }) = &cli.command
+    {
+        let route = match route_file::load(route)
+            .and_then(|trades| Route::new(inventory, trades).map_err(|err| err.to_string()))
+        {
+            Ok(route) => route,
+            Err(err) => {
+                eprintln!("Could not load route {}: {}", route.display(), err);
+                process::exit(1);
+            }
+        };
+        if scenario.targets.is_empty() {
+            scenario
+                .targets
+                .push(read_candies(&mut rl, "want", &cli.max_count));
+        }
+        let candy_works = new_problem();
+        let target = scenario.targets[0];
+        match replan::replan(
+            &candy_works,
+            &route,
+            *failed_step,
+            reason.as_deref(),
+            target,
+            reserved,
+        ) {
+            Ok(Some(corrected)) => {
+                metrics::record_route(corrected.len());
+                if let Some(path) = &cli.save_route {
+                    written(
+                        cli,
+                        path,
+                        route_file::save(path, corrected.trades(), &candy_works.notes),
+                    );
+                    println!("Route saved to {}", path.display());
+                }
+            }
+            Ok(None) => (),
+            Err(err) => {
+                eprintln!("Cannot replan: {}", err);
+                process::exit(1);
+            }
+        }
+        return reached;
+    }
+    if let Some(Command::Advise { current }) = &cli.command {
+        if scenario.targets.is_empty() {
+            scenario
+                .targets
+                .push(read_candies(&mut rl, "want", &cli.max_count));
+        }
+        let mut advisor = Advisor::new(&new_problem(), scenario.targets[0]);
+        let mut answer = |current: Candies| {
+            let Some(current) = current.without(&reserved) else {
+                println!(
+                    "That is less than the reserved ({})",
+                    reserved.display(false).trim()
+                );
+                return;
+            };
+            match advisor.advise(current) {
+                Some(trade) => println!("Next: {}", trade.to_string().trim()),
+                None if advisor.done(&current) => println!("Done: the target is in hand"),
+                None => println!("No route reaches the target from here"),
+            }
+        };
+        match current {
+            Some(current) => answer(parse_candies_arg(cli, current)),
+            None => {
+                while let Some(line) = rl.ask("Inventory: ").filter(|line| !line.trim().is_empty())
+                {
+                    match line.parse() {
+                        Ok(current) => answer(current),
+                        Err(err) => println!("Could not parse candies: {}", err),
+                    }
+                }
+            }
+        }
+        return reached;
+    }
+    if let Some(Command::Follow) = &cli.command {
+        if scenario.targets.is_empty() {
+            scenario
+                .targets
+                .push(read_candies(&mut rl, "want", &cli.max_count));
+        }
+        let mut advisor = Advisor::new(&new_problem(), scenario.targets[0]);
+        follow::follow(&mut advisor, inventory, reserved, cap, &mut rl);
+        return reached;
+    }
+    if let Some(Command::Quests { file, budget }) = &cli.command {
+        let quests = match quests::load(file) {
+            Ok(quests) => quests,
+            Err(err) => {
+                eprintln!("Could not load quests {}: {}", file.display(), err);
+                process::exit(1);
+            }
+        };
+        if *budget == 0 && quests.iter().all(|quest| quest.produces == Candies::none()) {
+            let selected = quests::select(&quests, inventory);
+            quests::print_plan(&quests, &selected, inventory);
+            return reached;
+        }
+        let candy_works = new_problem();
+        match quests::plan(&candy_works, &quests, *budget) {
+            Ok(steps) => quests::print_steps(&quests, &steps, inventory),
+            Err(err) => {
+                eprintln!("Cannot plan the quests: {}", err);
+                process::exit(1);
+            }
+        }
+        return reached;
+    }
+    if let Some(Command::Simulate { file, horizon }) = &cli.command {
+        let quests = match quests::load(file) {
+            Ok(quests) => quests,
+            Err(err) => {
+                eprintln!("Could not load quests {}: {}", file.display(), err);
+                process::exit(1);
+            }
+        };
+        let candy_works = new_problem();
+        sustain::simulate(&candy_works, &quests, *horizon);
+        return reached;
+    }
+    if let Some(Command::Verify { trades }) = &cli.command {
+        let input = match trades.as_deref().filter(|path| *path != Path::new("-")) {
+            Some(path) => replay::read_to_string(path),
+            None => io::read_to_string(io::stdin()),
+        };
+        let plan = match input
+            .map_err(|err| err.to_string())
+            .and_then(|input| route_file::parse(&input))
+        {
+            Ok(plan) => plan,
+            Err(err) => fail(cli, &format!("Could not read the plan: {}", err)),
+        };
+        let candy_works = new_problem();
+        let start = candy_works.candies.with(&reserved);
+        match verify::verify(&candy_works, &plan) {
+            Ok(_) => {
+                print!("{}", candy_works.format_route(start, &plan));
+                println!(
+                    "All {} trade{} can be made",
+                    plan.len(),
+                    if plan.len() == 1 { "" } else { "s" }
+                );
+            }
+            Err(err) => {
+                let made = match &err {
+                    StepError::NotOffered { step, .. }
+                    | StepError::Short { step, .. }
+                    | StepError::OverCap { step, .. } => step - 1,
+                };
+                print!("{}", candy_works.format_route(start, &plan[..made]));
+                fail(cli, &format!("Invalid plan: {}", err));
+            }
+        }
+        return reached;
+    }
+    if let Some(Command::MinCap { target }) = &cli.command {
+        let target = match target {
+            Some(target) => parse_candies_arg(cli, target),
+            None => match scenario.targets.first() {
+                Some(target) => *target,
+                None => read_candies(&mut rl, "want", &cli.max_count),
+            },
+        };
+        let problem = new_problem();
+        let min_cap = match min_cap::find(&problem, target, cli.force) {
+            Ok(min_cap) => min_cap,
+            Err(err) => {
+                println!("({}) {}", target.display(false).trim(), err);
+                return reached;
+            }
+        };
+        // The reserved candies are held throughout and count against the cap.
+        println!(
+            "Smallest cap reaching ({}): {}",
+            target.display(false).trim(),
+            min_cap + reserved.total().get() as u32
+        );
+        let mut candy_works = problem.unexplored();
+        candy_works.max_candies = min_cap;
+        candy_works.explore();
+        metrics::record_states(candy_works.combinations.len());
+        if let Some(route) = candy_works.find_optimal_route(target) {
+            metrics::record_route(route.len());
+            print!(
+                "{}",
+                candy_works.format_route(inventory.with(&reserved), route.trades())
+            );
+        }
+        return reached;
+    }
+    if let Some(Command::Economy) = &cli.command {
+        let mut candy_works = new_problem();
+        candy_works.explore();
+        metrics::record_states(candy_works.combinations.len());
+        economy::report(&candy_works, &scenario.trades);
+        return reached;
+    }
+    if let Some(Command::Solvers { target }) = &cli.command {
+        if let Some(target) = target {
+            scenario.targets = vec![parse_candies_arg(cli, target)];
+        } else if scenario.targets.is_empty() {
+            scenario
+                .targets
+                .push(read_candies(&mut rl, "want", &cli.max_count));
+        }
+        let candy_works = new_problem();
+        let time_limit = cli.time_limit.map(Duration::from_secs_f64);
+        solvers::compare(&candy_works, &scenario.targets, time_limit);
+        return reached;
+    }
+    if let Some(Command::Starts { target, limit } | Command::Bridge { target, limit }) =
+        &cli.command
+    {
+        let target = match target {
+            Some(target) => parse_candies_arg(cli, target),
+            None => match scenario.targets.first() {
+                Some(target) => *target,
+                None => read_candies(&mut rl, "want", &cli.max_count),
+            },
+        };
+        let candy_works = new_problem();
+        match &cli.command {
+            Some(Command::Bridge { .. }) => {
+                print!("{}", bridge::suggest(&candy_works, target, *limit))
+            }
+            _ => starts::print(&candy_works, target, *limit),
+        }
+        return reached;
+    }
+    if let Some(Command::MinTrades { target }) = &cli.command {
+        if let Some(target) = target {
+            scenario.targets = vec![parse_candies_arg(cli, target)];
+        } else if scenario.targets.is_empty() {
+            scenario
+                .targets
+                .push(read_candies(&mut rl, "want", &cli.max_count));
         }
-        len
+        min_trades::print(&scenario, inventory, cap, &scenario.targets);
+        return reached;
+    }
+    if let Some(Command::Sequence { targets }) = &cli.command {
+        if !targets.is_empty() {
+            scenario.targets = targets
+                .iter()
+                .map(|target| parse_candies_arg(cli, target))
+                .collect();
+        } else if scenario.targets.is_empty() {
+            scenario
+                .targets
+                .push(read_candies(&mut rl, "want", &cli.max_count));
+        }
+        let mut candy_works = new_problem();
+        candy_works.objective = cli.objective.clone().unwrap_or_default();
+        print!(
+            "{}",
+            sequence::format(&candy_works, &scenario.targets, reserved)
+        );
+        return reached;
+    }
+    if cli.stats_only {
+        let candy_works = new_problem();
+        let stats = timings::time(timings.map(Arc::as_ref), Phase::Exploration, || {
+            candy_works.streaming_statistics()
+        });
+        metrics::record_states(stats.combinations);
+        println!("{}", stats);
+        return reached;
+    }
+    if cli.two_phase {
+        let candy_works = new_problem();
+        let reachable = timings::time(timings.map(Arc::as_ref), Phase::Exploration, || {
+            candy_works.reachable_set()
+        });
+        println!("Reachable states: {}", reachable.len());
+        metrics::record_states(reachable.len());
+        if scenario.targets.is_empty() {
+            scenario
+                .targets
+                .push(read_candies(&mut rl, "want", &cli.max_count));
+        }
+        for (n, target) in scenario.targets.iter().enumerate() {
+            match candy_works.route_backward(&reachable, *target) {
+                Some(route) => {
+                    metrics::record_route(route.len());
+                    print!(
+                        "{}",
+                        candy_works
+                            .format_route(candy_works.candies.with(&reserved), route.trades())
+                    );
+                    print_change(candy_works.candies.with(&reserved), route.trades());
+                    if n == 0 {
+                        if let Some(path) = &cli.save_route {
+                            written(
+                                cli,
+                                path,
+                                route_file::save(path, route.trades(), &candy_works.notes),
+                            );
+                            println!("Route saved to {}", path.display());
+                        }
+                    }
+                }
+                None => {
+                    print_no_route();
+                    reached = false;
+                }
+            }
+        }
+        return reached;
+    }
+    if cli.output == OutputFormat::Json {
+        return print_json(cli, &scenario);
+    }
+    let previous = match &cli.warm_start {
+        Some(path) => match route_file::load(path) {
+            Ok(route) => Some(route),
+            Err(err) => {
+                eprintln!("Could not load route {}: {}", path.display(), err);
+                process::exit(1);
+            }
+        },
+        None => None,
+    };
+    let template = match &cli.macro_template {
+        Some(path) => match MacroTemplate::load(path) {
+            Ok(template) => template,
+            Err(err) => {
+                eprintln!("Could not load macro template {}: {}", path.display(), err);
+                process::exit(1);
+            }
+        },
+        None => MacroTemplate::default(),
+    };
+    let mut candy_works = new_problem();
+    candy_works.prefilter = cli.bloom;
+    candy_works.prune_dominated = cli.prune_dominated;
+    if cli.prune_dominated && !dominance::exact_for(&candy_works.trades, kind_caps.as_ref()) {
+        eprintln!(
+            "Warning: some trades receive more candies than they give, so --prune-dominated \
+             can miss routes that only fit under the cap from a poorer state"
+        );
+    }
+    candy_works.frontier = cli.frontier.frontier(&scenario.targets);
+    candy_works.objective = cli.objective.clone().unwrap_or_default();
+    let mut profile = match &cli.trade_profile {
+        Some(path) => match TradeProfile::load(path) {
+            Ok(profile) => {
+                profile.order(&mut candy_works.trades);
+                Some(profile)
+            }
+            Err(err) => {
+                eprintln!("Could not load trade profile {}: {}", path.display(), err);
+                process::exit(1);
+            }
+        },
+        None => None,
+    };
+    // With targets known up front, a previous route the objective proves
+    // cannot be beaten makes exploring unnecessary altogether.
+    let settled = previous
+        .as_ref()
+        .is_some_and(|previous| solve::settles(&candy_works, &scenario, previous));
+    // The laws are only worked out to explain a target without a route, or
+    // with --prune-conserved to skip exploring when they rule out every
+    // target.
+    let conserved = OnceCell::new();
+    let laws = |trades: &[Trade]| conserved.get_or_init(|| conservation::laws(trades));
+    let ruled_out = cli.prune_conserved
+        && !scenario.targets.is_empty()
+        && scenario.target_specs.is_empty()
+        && scenario.targets.iter().all(|target| {
+            conservation::ruling_out(laws(&candy_works.trades), &inventory, target, cap).is_some()
+        });
+    let cached = cli
+        .cache
+        .as_deref()
+        .filter(|path| path.exists() && !settled && !ruled_out && cacheable)
+        .and_then(|path| match candy_works.load_cached(path) {
+            Ok(Some(cached)) => {
+                println!("Reusing the graph cached in {}", path.display());
+                Some(cached)
+            }
+            Ok(None) => {
+                println!(
+                    "The graph cached in {} was explored with another inventory, cap or \
+                     trades, exploring again",
+                    path.display()
+                );
+                None
+            }
+            Err(err) => {
+                println!(
+                    "Could not read the cache {}: {}, exploring again",
+                    path.display(),
+                    err
+                );
+                None
+            }
+        });
+    let complete = if settled {
+        println!("The previous route is still optimal, skipping exploration");
+        candy_works.combinations = Combinations::from_iter([(inventory, None)]);
+        false
+    } else if ruled_out {
+        println!("Conservation laws rule out every target, skipping exploration");
+        candy_works.combinations = Combinations::from_iter([(inventory, None)]);
+        true
+    } else if let Some(cached) = cached {
+        candy_works = cached;
+        print_statistics(&candy_works);
+        print_projection(&candy_works, &cli.project);
+        true
+    } else {
+        let limit = cli.time_limit.map(Duration::from_secs_f64);
+        let every = cli.progress.map(|every| {
+            Duration::try_from_secs_f64(every)
+                .ok()
+                .filter(|every| !every.is_zero())
+                .unwrap_or_else(|| {
+                    eprintln!("--progress needs a positive number of seconds");
+                    process::exit(1);
+                })
+        });
+        let complete = solve::explore(
+            &mut candy_works,
+            limit,
+            cli.threads,
+            every,
+            &scenario.targets,
+        );
+        if candy_works.exploration() == Some(Exploration::Stopped(Exhausted::Interrupted)) {
+            println!(
+                "Interrupted, exploration is incomplete; answering from the {} states explored",
+                candy_works.combinations.len()
+            );
+        } else if !complete {
+            println!("Time limit reached, exploration is incomplete");
+        } else if let Some(path) = cli.cache.as_ref().filter(|_| cacheable) {
+            match candy_works.save(path) {
+                Ok(()) => println!("Graph cached in {}", path.display()),
+                Err(err) => eprintln!("Could not cache the graph in {}", err),
+            }
+        }
+        print_statistics(&candy_works);
+        print_projection(&candy_works, &cli.project);
+        complete
+    };
+    metrics::record_states(candy_works.combinations.len());
+    metrics::record_graph_bytes(candy_works.combinations.memory_bytes());
+    if let Some(path) = &cli.save_graph {
+        saved(cli, candy_works.save(path));
+        println!("Graph saved to {}", path.display());
     }
 
-    pub fn find_optimal_route(&self, target: Candies) -> Option<Vec<Trade>> {
-        if self.candies.contains(&target) {
-            return Some(Vec::new());
+    if scenario.targets.is_empty() && scenario.target_specs.is_empty() {
+        scenario
+            .targets
+            .push(read_candies(&mut rl, "want", &cli.max_count));
+    }
+    if let Some(path) = &cli.save_scenario {
+        written(cli, path, fs::write(path, scenario.format()));
+        println!("Scenario saved to {}", path.display());
+    }
+    let mut planner = Planner::new(&scenario);
+    planner.effort = cli.effort.clone();
+    planner.most_headroom = cli.most_headroom;
+    planner.previous = previous;
+    planner.settled = settled;
+    planner.flow = cli.flow;
+    planner.fallbacks = cli.fallbacks;
+    if let Some(headroom) = cli.headroom.filter(|&headroom| headroom > 0 && !settled) {
+        // The same trades under a cap lowered by the headroom, for targets
+        // whose best route fills the inventory to the cap.
+        planner.keep_headroom(
+            &candy_works,
+            headroom,
+            cli.time_limit.map(Duration::from_secs_f64),
+        );
+    }
+    let shared = (cli.shared_prefix || cli.decision_tree.is_some())
+        .then(|| candy_works.shared_routes(&scenario.targets))
+        .flatten();
+    if let (Some(shared), true) = (&shared, cli.shared_prefix) {
+        print!(
+            "{}",
+            shared::format_prefix(&candy_works, shared, candy_works.candies.with(&reserved))
+        );
+    }
+    let mut results = Vec::new();
+    for (n, target) in scenario.targets.iter().enumerate() {
+        let found = shared
+            .as_ref()
+            .and_then(|shared| shared.ends[n])
+            .map(|end| {
+                candy_works.keep_to_uses(
+                    candy_works.route(end),
+                    |candies| candies.contains(target),
+                    Ranking::Objective,
+                )
+            });
+        let planned = planner.plan(&candy_works, *target, found);
+        if let Some(route) = &planned.route {
+            metrics::record_route(route.len());
+        }
+        print!(
+            "{}",
+            planner.describe(&candy_works, *target, &planned, || laws(
+                &candy_works.trades
+            ))
+        );
+        let route = planned.route;
+        if route.is_none() {
+            reached = false;
+        }
+        if cli.alternatives > 0 && !settled {
+            print!(
+                "{}",
+                candy_works.format_alternatives(
+                    *target,
+                    cli.alternatives,
+                    cli.effort.as_ref(),
+                    reserved
+                )
+            );
+        }
+        if cli.time_limit.is_some() || settled {
+            let status = Status::of(&candy_works, route.as_ref(), target, complete);
+            println!("Status: {}", status);
         }
-        let mut candidates = self
-            .combinations
+        results.push((*target, route));
+    }
+    for spec in &scenario.target_specs {
+        match candy_works.find_spec_route(spec) {
+            Some(route) => {
+                metrics::record_route(route.len());
+                print!(
+                    "{}",
+                    candy_works.format_route(candy_works.candies.with(&reserved), route.trades())
+                );
+                print_change(candy_works.candies.with(&reserved), route.trades());
+            }
+            None => {
+                print_no_route();
+                reached = false;
+            }
+        }
+    }
+    if let Some(format) = cli.decision_tree {
+        let routes = results
             .iter()
-            .filter(|(candies, _)| candies.contains(&target))
+            .filter_map(|(target, route)| {
+                let label = target
+                    .display(false)
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                route.as_ref().map(|route| (label, route.trades().to_vec()))
+            })
             .collect::<Vec<_>>();
-        if candidates.is_empty() {
-            return None;
+        if routes.len() > 1 {
+            if let TreeFormat::Text = format {
+                println!("Decision tree:");
+            }
+            print!(
+                "{}",
+                DecisionTree::new(&routes).render(format, &candy_works.notes)
+            );
         }
-        let max = candidates
-            .iter()
-            .map(|(candies, _)| candies.total())
-            .max()
-            .unwrap();
-        candidates.retain(|(candies, _)| candies.total() == max);
-        let mut result = Vec::new();
-        let mut current = candidates[0].1;
-        while let Some((parent, trade)) = current {
-            result.push(trade);
-            current = self.combinations[parent].1;
+    }
+    if let (Some(path), Some(profile)) = (&cli.trade_profile, &mut profile) {
+        for (_, route) in &results {
+            profile.record(route.as_ref().map_or(&[], Route::trades));
         }
-        result.reverse();
-        Some(result)
+        written(cli, path, profile.save(path));
     }
-}
-
-fn main() {
-    let mut candies = Candies::none();
-    let mut rl = DefaultEditor::new().unwrap();
-    let names = ["eggs", "worms", "cakes", "fishes", "meats"];
-    for (i, name) in names.iter().enumerate() {
-        println!("How many {} do you have?", name);
-        let input = rl.readline(">> ").unwrap();
-        let value = input.trim().parse::<i32>().unwrap();
-        candies.add_by_index(i, value);
+    if let Some(path) = &cli.save_route {
+        if let Some((_, Some(route))) = results.first() {
+            written(
+                cli,
+                path,
+                route_file::save(path, route.trades(), &candy_works.notes),
+            );
+            println!("Route saved to {}", path.display());
+        }
     }
-    let mut trades = Vec::new();
-    println!("Use E for eggs, W for worms, C for cakes, F for fishes and M for meats");
-    for _ in 0..3 {
-        let input = rl.readline("Trade give: ").unwrap();
-        let mut give = Candies::none();
-        for c in input.to_lowercase().chars() {
-            match c {
-                'e' => give.eggs += 1,
-                'w' => give.worms += 1,
-                'c' => give.cakes += 1,
-                'f' => give.fishes += 1,
-                'm' => give.meats += 1,
-                _ => (),
-            }
-        }
-        let input = rl.readline("Trade receive: ").unwrap();
-        let mut receive = Candies::none();
-        for c in input.to_lowercase().chars() {
-            match c {
-                'e' => receive.eggs += 1,
-                'w' => receive.worms += 1,
-                'c' => receive.cakes += 1,
-                'f' => receive.fishes += 1,
-                'm' => receive.meats += 1,
-                _ => (),
-            }
-        }
-        trades.push(Trade { give, receive });
-    }
-    let mut candy_works = CandyWorks::new(candies, 20, trades);
-    candy_works.explore();
-    candy_works.stadistics();
-
-    let mut target = Candies::none();
-    for (i, name) in names.iter().enumerate() {
-        println!("How many {} do you want?", name);
-        let input = rl.readline(">> ").unwrap();
-        let value = input.trim().parse::<i32>().unwrap();
-        target.add_by_index(i, value);
-    }
-    let route = candy_works.find_optimal_route(target);
-    if let Some(route) = route {
-        let mut previous = candy_works.candies;
-        for trade in route {
-            println!("({}) {}", previous.display(true), trade);
-            previous = previous.trade(&trade).unwrap();
-        }
-        println!("({})", previous.display(true));
-    } else {
-        println!("No route found");
+    if let Some(path) = &cli.macro_file {
+        if let Some((_, Some(route))) = results.first() {
+            // Offers are numbered as listed: the scenario's trades, then the
+            // standard ones, whatever order the search tried them in.
+            let offers = new_problem().trades;
+            written(
+                cli,
+                path,
+                template.save(path, route.trades(), &offers, &candy_works.notes),
+            );
+            println!("Macro saved to {}", path.display());
+        }
+    }
+
+    if let Some(Command::Report { md, html, charts }) = &cli.command {
+        if let Some(md) = md {
+            let report = report::markdown(&candy_works, &scenario.trades, &results, *charts);
+            written(cli, md, fs::write(md, report));
+            println!("Report written to {}", md.display());
+        }
+        if let Some(html) = html {
+            let report = report::html(&candy_works, &scenario.trades, &results, *charts);
+            written(cli, html, fs::write(html, report));
+            println!("Report written to {}", html.display());
+        }
     }
+    reached
 }
@@ -0,0 +1,80 @@
+use std::{
+    fs,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Instant,
+};
+
+use clap::ValueEnum;
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum MetricsFormat {
+    Logfmt,
+    Json,
+}
+
+static STATES: AtomicUsize = AtomicUsize::new(0);
+static ROUTE_LENGTH: AtomicUsize = AtomicUsize::new(0);
+static ROUTES: AtomicUsize = AtomicUsize::new(0);
+static GRAPH_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+/// Counts explored or loaded states towards the metrics record.
+pub fn record_states(states: usize) {
+    STATES.fetch_add(states, Ordering::Relaxed);
+}
+
+/// Counts memory held by explored graphs towards the metrics record.
+pub fn record_graph_bytes(bytes: usize) {
+    GRAPH_BYTES.fetch_add(bytes, Ordering::Relaxed);
+}
+
+/// Counts the trades of a found route towards the metrics record.
+pub fn record_route(length: usize) {
+    ROUTE_LENGTH.fetch_add(length, Ordering::Relaxed);
+    ROUTES.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Peak resident memory in kilobytes, where the platform reports it.
+pub fn peak_memory_kb() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    line.split_whitespace().nth(1)?.parse().ok()
+}
+
+/// Prints a one-line metrics record for the finished command to stderr, so
+/// it can be picked up without mixing into the regular output.
+pub fn emit(format: MetricsFormat, command: &str, started: Instant) {
+    let elapsed_ms = started.elapsed().as_millis();
+    let states = STATES.load(Ordering::Relaxed);
+    let routes = ROUTES.load(Ordering::Relaxed);
+    let route_length = (routes > 0).then(|| ROUTE_LENGTH.load(Ordering::Relaxed));
+    let peak_memory_kb = peak_memory_kb();
+    let graph_kb = GRAPH_BYTES.load(Ordering::Relaxed) / 1024;
+    let line = match format {
+        MetricsFormat::Logfmt => {
+            let mut line = format!(
+                "command={} states={} elapsed_ms={} graph_kb={}",
+                command, states, elapsed_ms, graph_kb
+            );
+            if let Some(kb) = peak_memory_kb {
+                line.push_str(&format!(" peak_memory_kb={}", kb));
+            }
+            if let Some(length) = route_length {
+                line.push_str(&format!(" route_length={}", length));
+            }
+            line
+        }
+        MetricsFormat::Json => {
+            let optional = |value: Option<u64>| value.map_or("null".to_string(), |v| v.to_string());
+            format!(
+                "{{\"command\":\"{}\",\"states\":{},\"elapsed_ms\":{},\"graph_kb\":{},\"peak_memory_kb\":{},\"route_length\":{}}}",
+                command,
+                states,
+                elapsed_ms,
+                graph_kb,
+                optional(peak_memory_kb),
+                optional(route_length.map(|length| length as u64))
+            )
+        }
+    };
+    eprintln!("{}", line);
+}
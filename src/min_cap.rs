@@ -0,0 +1,137 @@
+use std::collections::HashSet;
+
+use crate::{count::Count, guard, uses::Node, Candies, CandyWorks};
+
+/// The states reachable under a cap, with the ones a trade led from to a
+/// state over the cap: only those can lead anywhere new once it is raised.
+/// With use limits, states are told apart by the uses made to reach them.
+#[derive(Clone)]
+struct Reach {
+    cap: u32,
+    seen: HashSet<Node>,
+    blocked: Vec<Node>,
+    found: bool,
+}
+
+impl Reach {
+    fn new(start: Node, target: Candies) -> Reach {
+        Reach {
+            cap: 0,
+            found: start.0.contains(&target),
+            seen: HashSet::from([start.clone()]),
+            blocked: vec![start],
+        }
+    }
+
+    /// Continues the exploration under a `cap` at least as high, from the
+    /// blocked states alone: everything reached before is still reachable.
+    /// States over the caps of the kinds, or reached by making a trade past
+    /// its uses, are never reached whatever the cap, so they block nothing.
+    fn raise(&mut self, candy_works: &CandyWorks, cap: u32, target: Candies) {
+        self.cap = cap;
+        let mut queue = std::mem::take(&mut self.blocked);
+        while let Some((candies, used)) = queue.pop() {
+            let mut blocked = false;
+            for trade in &candy_works.trades {
+                let Some(next) = candies.trade(trade) else {
+                    continue;
+                };
+                if candy_works
+                    .kind_caps
+                    .is_some_and(|caps| !caps.allows(&next))
+                {
+                    continue;
+                }
+                let Some(next_used) = candy_works.use_limits.after(&used, trade) else {
+                    continue;
+                };
+                if next.total() > Count::new(cap as i32) {
+                    blocked = true;
+                } else if self.seen.insert((next, next_used.clone())) {
+                    self.found |= next.contains(&target);
+                    queue.push((next, next_used));
+                }
+            }
+            if blocked {
+                self.blocked.push((candies, used));
+            }
+        }
+    }
+}
+
+/// Finds the smallest cap under which `target` can be reached from the
+/// inventory of `candy_works`, trying only caps the size guard allows
+/// unless `force`. Caps are tried doubling until one works, then bisected;
+/// each try extends the exploration of the highest cap known to fail
+/// instead of starting over.
+pub fn find(candy_works: &CandyWorks, target: Candies, force: bool) -> Result<u32, String> {
+    search(candy_works, target, force, None)
+}
+
+/// The smallest cap up to the cap of `candy_works` under which `target`
+/// can be reached, which leaves the most room below that cap at the
+/// fullest point of the route.
+pub fn within(candy_works: &CandyWorks, target: Candies) -> Option<u32> {
+    search(candy_works, target, true, Some(candy_works.max_candies)).ok()
+}
+
+fn search(
+    candy_works: &CandyWorks,
+    target: Candies,
+    force: bool,
+    ceiling: Option<u32>,
+) -> Result<u32, String> {
+    let mut failing = Reach::new(
+        (candy_works.candies, candy_works.use_limits.unused()),
+        target,
+    );
+    if failing.found {
+        return Ok(0);
+    }
+    let mut working = None;
+    let mut cap = (target.total().get() as u32).max(1);
+    if let Some(ceiling) = ceiling {
+        cap = cap.min(ceiling);
+    }
+    while working.is_none() {
+        if !force && guard::check(cap.into()).is_err() {
+            return Err(match failing.cap {
+                0 => format!(
+                    "needs a cap of at least {}, which is too large to explore without \
+                     --force",
+                    cap
+                ),
+                tried => format!(
+                    "is not reachable under a cap up to {}, pass --force to try larger ones",
+                    tried
+                ),
+            });
+        }
+        let mut reach = failing.clone();
+        reach.raise(candy_works, cap, target);
+        if reach.found {
+            working = Some(cap);
+        } else if reach.blocked.is_empty() {
+            // No trade is held back by the cap any more, so no higher cap
+            // reaches anything new.
+            return Err("is not reachable under any cap".to_string());
+        } else if ceiling == Some(cap) {
+            return Err(format!("is not reachable under a cap of {}", cap));
+        } else {
+            failing = reach;
+            cap = ceiling.map_or(cap * 2, |ceiling| (cap * 2).min(ceiling));
+        }
+    }
+    let mut working = working.unwrap();
+    while working - failing.cap > 1 {
+        let cap = failing.cap + (working - failing.cap) / 2;
+        let mut reach = failing.clone();
+        reach.raise(candy_works, cap, target);
+        if reach.found {
+            working = cap;
+        } else {
+            failing = reach;
+        }
+    }
+    Ok(working)
+}
@@ -0,0 +1,157 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::{display_side, scenario::Scenario, Candies, CandyWorks};
+
+/// Subsets checked at most when proving no smaller set of trades will do.
+const MAX_CHECKS: u64 = 2000;
+
+/// Whether every target can be reached from the start with `picked`
+/// scenario trades on top of the standard ones, within the cap and the
+/// caps of the scenario's kinds.
+fn reaches_all(
+    scenario: &Scenario,
+    start: Candies,
+    cap: u32,
+    picked: &[usize],
+    targets: &[Candies],
+) -> bool {
+    let mut candy_works = CandyWorks::new(start, cap, Vec::new());
+    candy_works.trades = picked
+        .iter()
+        .map(|&n| scenario.trades[n])
+        .chain(scenario.standard())
+        .collect();
+    candy_works.kind_caps = scenario.kind_caps.map(|caps| caps.less(&scenario.reserved));
+    let mut missing = targets
+        .iter()
+        .filter(|target| !start.contains(target))
+        .collect::<Vec<_>>();
+    let mut seen = HashSet::from([start]);
+    let mut queue = VecDeque::from([start]);
+    while let Some(candies) = queue.pop_front() {
+        if missing.is_empty() {
+            break;
+        }
+        for trade in &candy_works.trades {
+            if let Some(next) = candies.trade(trade) {
+                if candy_works.fits(&next) && seen.insert(next) {
+                    missing.retain(|target| !next.contains(target));
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+    missing.is_empty()
+}
+
+/// Calls `visit` with every subset of `0..n` of `size` indices, in order,
+/// until it returns true.
+fn any_subset(n: usize, size: usize, visit: &mut impl FnMut(&[usize]) -> bool) -> bool {
+    fn extend(
+        n: usize,
+        size: usize,
+        picked: &mut Vec<usize>,
+        visit: &mut impl FnMut(&[usize]) -> bool,
+    ) -> bool {
+        if picked.len() == size {
+            return visit(picked);
+        }
+        let from = picked.last().map_or(0, |last| last + 1);
+        for next in from..n {
+            picked.push(next);
+            if extend(n, size, picked, visit) {
+                return true;
+            }
+            picked.pop();
+        }
+        false
+    }
+    extend(n, size, &mut Vec::new(), visit)
+}
+
+fn binomial(n: usize, k: usize) -> u64 {
+    (0..k as u64).fold(1, |count, i| count * (n as u64 - i) / (i + 1))
+}
+
+/// Prints the fewest scenario trades that still reach every target from
+/// `start`, with the vendor locations they are made at. Trades are first
+/// dropped one at a time while the targets stay reachable; smaller sets
+/// are then tried exhaustively when there are few enough of them.
+pub fn print(scenario: &Scenario, start: Candies, cap: u32, targets: &[Candies]) {
+    let all = (0..scenario.trades.len()).collect::<Vec<_>>();
+    if !reaches_all(scenario, start, cap, &all, targets) {
+        println!("Not every target can be reached even with every trade");
+        return;
+    }
+    let mut picked = all;
+    let mut n = 0;
+    while n < picked.len() {
+        let mut fewer = picked.clone();
+        fewer.remove(n);
+        if reaches_all(scenario, start, cap, &fewer, targets) {
+            picked = fewer;
+        } else {
+            n += 1;
+        }
+    }
+    let count = scenario.trades.len();
+    let checks = (0..picked.len())
+        .map(|size| binomial(count, size))
+        .sum::<u64>();
+    let proven = checks <= MAX_CHECKS;
+    if proven {
+        for size in 0..picked.len() {
+            let mut smaller = None;
+            if any_subset(count, size, &mut |subset| {
+                let found = reaches_all(scenario, start, cap, subset, targets);
+                if found {
+                    smaller = Some(subset.to_vec());
+                }
+                found
+            }) {
+                picked = smaller.unwrap();
+                break;
+            }
+        }
+    }
+    if picked.is_empty() {
+        println!("The standard trades alone reach every target");
+        return;
+    }
+    println!(
+        "{} of the {} scenario trades {} enough to reach every target{}:",
+        picked.len(),
+        count,
+        if picked.len() == 1 { "is" } else { "are" },
+        if proven {
+            ""
+        } else {
+            ", though a smaller set may exist"
+        }
+    );
+    for &n in &picked {
+        let trade = scenario.trades[n];
+        print!(
+            "  {} -> {}",
+            display_side(&trade.give),
+            display_side(&trade.receive)
+        );
+        match &scenario.locations[n] {
+            Some(location) => println!(" @ {}", location),
+            None => println!(),
+        }
+    }
+    let mut vendors = Vec::new();
+    for location in picked
+        .iter()
+        .filter_map(|&n| scenario.locations[n].as_ref())
+    {
+        if !vendors.contains(&location) {
+            vendors.push(location);
+        }
+    }
+    if !vendors.is_empty() {
+        let vendors = vendors.iter().map(|v| v.as_str()).collect::<Vec<_>>();
+        println!("Vendors to visit: {}", vendors.join(", "));
+    }
+}
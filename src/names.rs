@@ -0,0 +1,169 @@
+use std::sync::OnceLock;
+
+use crate::kinds;
+
+#[derive(Clone, PartialEq)]
+pub struct CandyNames {
+    singular: Vec<String>,
+    plural: Vec<String>,
+    aliases: Vec<Vec<String>>,
+}
+
+impl CandyNames {
+    /// The names of the run's kinds of candy, as `kinds` configured them.
+    pub fn builtin() -> Self {
+        let kinds = kinds::active();
+        CandyNames {
+            singular: (0..kinds.len())
+                .map(|i| kinds.get(i).singular.clone())
+                .collect(),
+            plural: (0..kinds.len())
+                .map(|i| kinds.get(i).plural.clone())
+                .collect(),
+            aliases: vec![Vec::new(); kinds.len()],
+        }
+    }
+
+    pub fn singular(&self, index: usize) -> &str {
+        &self.singular[index]
+    }
+
+    pub fn plural(&self, index: usize) -> &str {
+        &self.plural[index]
+    }
+
+    pub fn plurals(&self) -> &[String] {
+        &self.plural
+    }
+
+    pub fn is_builtin(&self, index: usize) -> bool {
+        let kind = kinds::active().get(index);
+        self.singular[index] == kind.singular && self.plural[index] == kind.plural
+    }
+
+    /// Renames a candy; without an explicit plural one is derived from the
+    /// singular with `pluralize`.
+    pub fn rename(&mut self, index: usize, singular: &str, plural: Option<&str>) {
+        self.singular[index] = singular.to_string();
+        self.plural[index] = plural.map_or_else(|| pluralize(singular), str::to_string);
+    }
+
+    pub fn aliases(&self, index: usize) -> &[String] {
+        &self.aliases[index]
+    }
+
+    /// Adds another name the candy can be written as.
+    pub fn add_alias(&mut self, index: usize, alias: &str) {
+        self.aliases[index].push(alias.to_string());
+    }
+
+    fn candidates(&self, index: usize) -> impl Iterator<Item = &str> {
+        let kind = kinds::active().get(index);
+        [
+            self.singular[index].as_str(),
+            self.plural[index].as_str(),
+            kind.singular.as_str(),
+            kind.plural.as_str(),
+        ]
+        .into_iter()
+        .chain(self.aliases[index].iter().map(String::as_str))
+    }
+
+    /// Looks a name up among the configured and built-in names and the
+    /// aliases, ignoring case. Failing an exact match it accepts a prefix
+    /// of one candy's name, then a name within a typo or two (a swapped,
+    /// missing, extra or wrong letter, one for names up to four letters).
+    pub fn lookup(&self, name: &str) -> Lookup {
+        let name = name.trim().to_lowercase();
+        if name.is_empty() {
+            return Lookup::Unknown;
+        }
+        let matching = |matches: &dyn Fn(&str) -> bool| {
+            (0..kinds::count())
+                .filter(|&i| self.candidates(i).any(|c| matches(&c.to_lowercase())))
+                .collect::<Vec<_>>()
+        };
+        if let [index] = matching(&|c| c == name)[..] {
+            return Lookup::Exact(index);
+        }
+        let prefixed = matching(&|c| c.starts_with(&name));
+        let typos = if name.chars().count() <= 4 { 1 } else { 2 };
+        let fuzzy = |distance: usize| matching(&|c| edit_distance(c, &name) == distance);
+        let found = match prefixed.len() {
+            0 => (1..=typos).map(fuzzy).find(|found| !found.is_empty()),
+            _ => Some(prefixed),
+        };
+        match found.as_deref() {
+            None => Lookup::Unknown,
+            Some([index]) => Lookup::Close(*index),
+            Some(indices) => Lookup::Ambiguous(indices.to_vec()),
+        }
+    }
+
+    /// Finds a candy by name as `lookup` does, if only one candy fits.
+    pub fn find(&self, name: &str) -> Option<usize> {
+        match self.lookup(name) {
+            Lookup::Exact(index) | Lookup::Close(index) => Some(index),
+            Lookup::Ambiguous(_) | Lookup::Unknown => None,
+        }
+    }
+}
+
+pub enum Lookup {
+    Exact(usize),
+    /// A prefix of or near miss for this candy's name.
+    Close(usize),
+    /// A prefix or near miss for several candies.
+    Ambiguous(Vec<usize>),
+    Unknown,
+}
+
+/// Edits turning `a` into `b`, counting a swap of neighbouring letters as
+/// one edit.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+    let mut rows = vec![(0..=b.len()).collect::<Vec<_>>()];
+    for i in 1..=a.len() {
+        let mut row = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let substitution = rows[i - 1][j - 1] + usize::from(a[i - 1] != b[j - 1]);
+            row[j] = substitution.min(rows[i - 1][j] + 1).min(row[j - 1] + 1);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(rows[i - 2][j - 2] + 1);
+            }
+        }
+        rows.push(row);
+    }
+    rows[a.len()][b.len()]
+}
+
+pub fn pluralize(word: &str) -> String {
+    let lower = word.to_lowercase();
+    if ["s", "x", "z", "ch", "sh"]
+        .iter()
+        .any(|suffix| lower.ends_with(suffix))
+    {
+        format!("{}es", word)
+    } else if lower.ends_with('y')
+        && !lower.ends_with("ay")
+        && !lower.ends_with("ey")
+        && !lower.ends_with("oy")
+    {
+        format!("{}ies", &word[..word.len() - 1])
+    } else {
+        format!("{}s", word)
+    }
+}
+
+static ACTIVE: OnceLock<CandyNames> = OnceLock::new();
+
+/// Sets the names used for display and parsing for the rest of the run,
+/// failing once other names are in use.
+pub fn configure(names: CandyNames) -> Result<(), String> {
+    crate::configure_once(&ACTIVE, names, "the names of the candies are")
+}
+
+pub fn active() -> &'static CandyNames {
+    ACTIVE.get_or_init(CandyNames::builtin)
+}
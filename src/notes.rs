@@ -0,0 +1,43 @@
+use std::collections::HashMap;
+
+use crate::{scenario::Scenario, Trade};
+
+/// Notes written on a scenario's trades, as in `3e -> 1m; note after quest
+/// 3`, for showing wherever the trade appears in a route.
+#[derive(Clone, Default, PartialEq)]
+pub struct Notes {
+    notes: HashMap<Trade, String>,
+}
+
+impl Notes {
+    /// The notes on `scenario`'s trades; trades written more than once keep
+    /// every note, in the order written.
+    pub fn new(scenario: &Scenario) -> Notes {
+        let mut notes = HashMap::<Trade, String>::new();
+        for (trade, note) in scenario.trades.iter().zip(&scenario.notes) {
+            let Some(note) = note else {
+                continue;
+            };
+            notes
+                .entry(*trade)
+                .and_modify(|notes| {
+                    if !notes.split("; ").any(|known| known == note) {
+                        notes.push_str("; ");
+                        notes.push_str(note);
+                    }
+                })
+                .or_insert_with(|| note.clone());
+        }
+        Notes { notes }
+    }
+
+    pub fn get(&self, trade: &Trade) -> Option<&str> {
+        self.notes.get(trade).map(String::as_str)
+    }
+
+    /// `  # note` for `trade`, or nothing, for the end of a line showing it.
+    pub fn suffix(&self, trade: &Trade) -> String {
+        self.get(trade)
+            .map_or(String::new(), |note| format!("  # {}", note))
+    }
+}
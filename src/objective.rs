@@ -0,0 +1,268 @@
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
+
+use crate::{count::Count, kinds, route::Route, uses::Ranking, Candies, CandyWorks};
+
+/// What makes one route to a target better than another, as
+/// `CandyWorks::find_optimal_route` chooses among them.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum Objective {
+    /// The most candies left in total. Of states holding as many, the one
+    /// explored first wins.
+    #[default]
+    MaxLeftoverTotal,
+    /// The fewest trades, counted afresh between the explored states
+    /// rather than along the route the graph keeps to each. Of routes as
+    /// short, the one leaving the most candies wins, then the one found
+    /// first trying the trades in their order.
+    MinTrades,
+    /// The highest value of the candies left, each kind counted at its
+    /// weight here and unlisted kinds at nothing. Of states worth as much,
+    /// the one with the most candies wins, then the one explored first.
+    MaxWeightedValue(Candies),
+}
+
+impl FromStr for Objective {
+    type Err = String;
+
+    /// Parses `most-candies`, `fewest-trades` or `value:` with the weights,
+    /// such as `value:1e,5m`.
+    fn from_str(input: &str) -> Result<Objective, String> {
+        match input.trim() {
+            "most-candies" => Ok(Objective::MaxLeftoverTotal),
+            "fewest-trades" => Ok(Objective::MinTrades),
+            input => match input.strip_prefix("value:") {
+                Some(weights) => weights
+                    .parse()
+                    .map(Objective::MaxWeightedValue)
+                    .map_err(|err| format!("invalid weights: {}", err)),
+                None => Err(format!(
+                    "unknown objective `{}`, expected most-candies, fewest-trades or \
+                     value:WEIGHTS such as value:1e,5m",
+                    input
+                )),
+            },
+        }
+    }
+}
+
+impl Objective {
+    /// How good ending with `candies` is, higher being better, before the
+    /// tie-breaks by exploration order. Fewest trades is decided by the
+    /// search itself, so states of one length compare by their candies.
+    pub fn score(&self, candies: &Candies) -> (i64, Count) {
+        match self {
+            Objective::MaxLeftoverTotal | Objective::MinTrades => (0, candies.total()),
+            Objective::MaxWeightedValue(weights) => (
+                (0..kinds::count())
+                    .map(|i| {
+                        i64::from(weights.get_by_index(i)) * i64::from(candies.get_by_index(i))
+                    })
+                    .sum(),
+                candies.total(),
+            ),
+        }
+    }
+
+    /// How good a route of `trades` trades ending with `candies` is, higher
+    /// being better, for weighing routes found apart such as a previous
+    /// route against a new one. Where the objective leaves a tie, fewer
+    /// trades win.
+    pub fn rank(&self, candies: &Candies, trades: usize) -> (i64, i64, i64) {
+        let total = i64::from(candies.total());
+        let trades = -(trades as i64);
+        match self {
+            Objective::MaxLeftoverTotal => (total, trades, 0),
+            Objective::MinTrades => (trades, total, 0),
+            Objective::MaxWeightedValue(_) => (self.score(candies).0, total, trades),
+        }
+    }
+
+    /// Whether no route to `target` under `cap` can do better by the
+    /// objective than `route`, whatever the exploration missed. Most
+    /// candies needs it to end at the cap, the most any state holds;
+    /// fewest trades needs it to make none, or to make one ending at the
+    /// cap from a start without the target; weighted value needs it to
+    /// fill the cap with candies of the heaviest weight.
+    pub fn proves_optimal(&self, route: &Route, target: &Candies, cap: u32) -> bool {
+        let at_cap = route.end().total() == Count::new(cap as i32);
+        match self {
+            Objective::MaxLeftoverTotal => at_cap,
+            Objective::MinTrades => {
+                route.is_empty() || (route.len() == 1 && at_cap && !route.start().contains(target))
+            }
+            Objective::MaxWeightedValue(weights) => {
+                let heaviest = (0..kinds::count())
+                    .map(|i| i64::from(weights.get_by_index(i)))
+                    .max()
+                    .unwrap_or(0);
+                at_cap && self.score(&route.end()).0 == heaviest * i64::from(cap)
+            }
+        }
+    }
+}
+
+impl CandyWorks {
+    /// The route to `target` in the fewest trades, searched breadth first
+    /// among the explored states, or `None` if no explored state holds it.
+    pub fn fewest_trades_route(&self, target: Candies) -> Option<Route> {
+        self.fewest_trades_matching(|candies| candies.contains(&target))
+    }
+
+    /// The route in the fewest trades to an explored state `matches`
+    /// accepts; of states as close, the one holding the most candies. Of
+    /// routes keeping to the use limits, if the explored one breaks them.
+    pub fn fewest_trades_matching(&self, matches: impl Fn(&Candies) -> bool) -> Option<Route> {
+        let explored = self.combinations.states().collect::<HashSet<_>>();
+        let mut previous = HashMap::from([(self.candies, None)]);
+        let mut level = vec![self.candies];
+        while !level.is_empty() {
+            let best = level.iter().filter(|candies| matches(candies)).fold(
+                None,
+                |best: Option<&Candies>, candies| match best {
+                    Some(best) if best.total() >= candies.total() => Some(best),
+                    _ => Some(candies),
+                },
+            );
+            if let Some(&end) = best {
+                let mut trades = Vec::new();
+                let mut current = end;
+                while let Some(Some((trade, before))) = previous.get(&current).copied() {
+                    trades.push(trade);
+                    current = before;
+                }
+                trades.reverse();
+                let route = Route::new(self.candies, trades).unwrap();
+                return self.keep_to_uses(
+                    route.found_in(self.exploration()),
+                    matches,
+                    Ranking::Objective,
+                );
+            }
+            let mut next = Vec::new();
+            for candies in &level {
+                for trade in &self.trades {
+                    if let Some(after) = candies.trade(trade) {
+                        if explored.contains(&after) && !previous.contains_key(&after) {
+                            previous.insert(after, Some((*trade, *candies)));
+                            next.push(after);
+                        }
+                    }
+                }
+            }
+            level = next;
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{candies, trade};
+
+    /// Three worms buy a meat in one trade, or a worm at a time turns
+    /// into an egg and two eggs buy the meat, keeping a candy more.
+    fn explored(objective: Objective) -> CandyWorks {
+        let mut candy_works = CandyWorks::builder(candies("3w"))
+            .trade(trade("1w -> 1e"))
+            .trade(trade("2e -> 1m"))
+            .build()
+            .unwrap();
+        candy_works.objective = objective;
+        candy_works.explore();
+        candy_works
+    }
+
+    #[test]
+    fn ranks_by_the_objective_first() {
+        let (short, long) = ((candies("1m"), 1), (candies("1e 1m"), 2));
+        let most = Objective::MaxLeftoverTotal;
+        assert!(most.rank(&long.0, long.1) > most.rank(&short.0, short.1));
+        let fewest = Objective::MinTrades;
+        assert!(fewest.rank(&short.0, short.1) > fewest.rank(&long.0, long.1));
+        assert!(most.rank(&short.0, 1) > most.rank(&short.0, 2));
+    }
+
+    #[test]
+    fn proves_optimal_only_within_the_bound() {
+        let start = candies("3w");
+        let route = |trades: &[&str]| {
+            Route::new(start, trades.iter().map(|input| trade(input)).collect()).unwrap()
+        };
+        let long = route(&["1w -> 1e", "1w -> 1e", "2e -> 1m"]);
+        let short = route(&["3w -> 1m"]);
+        let target = candies("1m");
+        assert!(Objective::MaxLeftoverTotal.proves_optimal(&long, &target, 2));
+        assert!(!Objective::MaxLeftoverTotal.proves_optimal(&long, &target, 3));
+        assert!(!Objective::MinTrades.proves_optimal(&long, &target, 2));
+        assert!(Objective::MinTrades.proves_optimal(&short, &target, 1));
+        assert!(!Objective::MinTrades.proves_optimal(&short, &target, 2));
+        let meat = Objective::MaxWeightedValue(candies("1e 5m"));
+        assert!(!meat.proves_optimal(&long, &target, 2));
+        assert!(meat.proves_optimal(&short, &target, 1));
+    }
+
+    #[test]
+    fn parses_objectives() {
+        assert_eq!("most-candies".parse(), Ok(Objective::MaxLeftoverTotal));
+        assert_eq!("fewest-trades".parse(), Ok(Objective::MinTrades));
+        assert_eq!(
+            "value:1e,5m".parse(),
+            Ok(Objective::MaxWeightedValue(candies("1e 5m")))
+        );
+        assert!("value:5x".parse::<Objective>().is_err());
+        assert!("shortest".parse::<Objective>().is_err());
+    }
+
+    #[test]
+    fn fewest_trades_beats_more_candies() {
+        let route = explored(Objective::MinTrades)
+            .find_optimal_route(candies("1m"))
+            .unwrap();
+        assert_eq!(route.trades(), [trade("3w -> 1m")]);
+    }
+
+    #[test]
+    fn fewest_trades_breaks_ties_by_candies_left() {
+        // Both one-trade routes reach a worm, and keeping the eggs leaves
+        // more candies than the standard trade of them.
+        let mut candy_works = CandyWorks::builder(candies("3e 1f"))
+            .trade(trade("1f -> 1w 1c"))
+            .build()
+            .unwrap();
+        candy_works.objective = Objective::MinTrades;
+        candy_works.explore();
+        let route = candy_works.find_optimal_route(candies("1w")).unwrap();
+        assert_eq!(route.trades(), [trade("1f -> 1w 1c")]);
+    }
+
+    #[test]
+    fn most_candies_takes_the_longer_route() {
+        let route = explored(Objective::MaxLeftoverTotal)
+            .find_optimal_route(candies("1m"))
+            .unwrap();
+        assert_eq!(route.end().total(), Count::new(2));
+        assert!(route.len() > 1);
+    }
+
+    #[test]
+    fn weights_decide_before_candies() {
+        for (weights, end) in [("1w", "1w 1m"), ("1e", "1e 1m")] {
+            let route = explored(Objective::MaxWeightedValue(candies(weights)))
+                .find_optimal_route(candies("1m"))
+                .unwrap();
+            assert_eq!(route.end(), candies(end));
+        }
+    }
+
+    #[test]
+    fn equal_weights_fall_back_to_the_most_candies() {
+        let route = explored(Objective::MaxWeightedValue(candies("1m")))
+            .find_optimal_route(candies("1m"))
+            .unwrap();
+        assert_eq!(route.end().total(), Count::new(2));
+    }
+}
@@ -0,0 +1,93 @@
+use clap::ValueEnum;
+
+use crate::{
+    export::stats_json, json::quote, kinds, names, notes::Notes, route::Route, Candies, Statistics,
+};
+
+/// How the main flow and `explore` answer: as text to read, or as one JSON
+/// document on stdout for other programs, errors included.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+/// `candies` as a JSON object from the name of every kind, in lowercase as
+/// `--config` reads them, to how many of it there are.
+pub fn candies_json(candies: &Candies) -> String {
+    let names = names::active();
+    let counts = (0..kinds::count())
+        .map(|i| {
+            format!(
+                "{}:{}",
+                quote(&names.plural(i).to_lowercase()),
+                candies.get_by_index(i)
+            )
+        })
+        .collect::<Vec<_>>();
+    format!("{{{}}}", counts.join(","))
+}
+
+/// The answer for `target`: whether it was reached and, if so, every
+/// trade of `route` with its note in `notes` and the inventory before and
+/// after it. `reserved`
+/// candies are added back to the inventories, as the text shows them, and
+/// `cost` is what the route costs when trades carry one.
+pub fn route_json(
+    target: &Candies,
+    route: Option<&Route>,
+    notes: &Notes,
+    reserved: &Candies,
+    cost: Option<f64>,
+) -> String {
+    let target = quote(&target.compact());
+    let Some(route) = route else {
+        return format!("{{\"target\":{},\"reachable\":false}}", target);
+    };
+    let mut before = route.start().with(reserved);
+    let steps = route
+        .trades()
+        .iter()
+        .map(|trade| {
+            let after = before.trade(trade).unwrap();
+            let step = format!(
+                "{{\"give\":{},\"receive\":{}{},\"before\":{},\"after\":{}}}",
+                quote(&trade.give.compact()),
+                quote(&trade.receive.compact()),
+                notes
+                    .get(trade)
+                    .map_or(String::new(), |note| format!(",\"note\":{}", quote(note))),
+                candies_json(&before),
+                candies_json(&after)
+            );
+            before = after;
+            step
+        })
+        .collect::<Vec<_>>();
+    format!(
+        "{{\"target\":{},\"reachable\":true,\"trades\":{},\"start\":{},\"end\":{}{},\"steps\":[{}]}}",
+        target,
+        route.len(),
+        candies_json(&route.start().with(reserved)),
+        candies_json(&route.end().with(reserved)),
+        cost.map_or(String::new(), |cost| format!(",\"cost\":{}", cost)),
+        steps.join(",")
+    )
+}
+
+/// The whole answer: the statistics of the exploration, if there are any,
+/// and the routes of `route_json` in the order of the targets.
+pub fn report_json(stats: Option<&Statistics>, routes: &[String]) -> String {
+    format!(
+        "{{\"statistics\":{},\"routes\":[{}]}}",
+        stats.map_or("null".to_string(), stats_json),
+        routes.join(",")
+    )
+}
+
+/// An error ending the run, as the JSON document printed in place of the
+/// answer.
+pub fn error_json(message: &str) -> String {
+    format!("{{\"error\":{}}}", quote(message))
+}
@@ -0,0 +1,210 @@
+use std::{
+    hash::{BuildHasher, RandomState},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    bloom::Visited,
+    count::Count,
+    interrupt,
+    storage::Combinations,
+    timings::{self, Phase},
+    Candies, CandyWorks, Exhausted, Exploration,
+};
+
+/// A state reached from the level being expanded, with the index of the
+/// state it was reached from and of the trade reaching it.
+type Found = (Candies, usize, u32);
+
+impl CandyWorks {
+    /// Explores every state reachable within the cap one level of trades at
+    /// a time, as the `fifo` frontier does, spreading each level over
+    /// `threads` threads. The visited set is split into one shard per
+    /// thread by hash, so checking new states runs in parallel too; only
+    /// storing them is left to one thread. The graph is the one `fifo`
+    /// explores whatever the number of threads: of the states reaching a
+    /// new one, the earliest explored keeps it, by its earliest trade.
+    pub fn explore_parallel(&mut self, threads: usize) {
+        // Without a deadline or a ceiling only an interrupt stops it.
+        let _ = self.explore_parallel_bounded(threads, None, None);
+    }
+
+    /// Like `explore_parallel`, but stops between levels once `deadline`
+    /// passes, the run is interrupted or more than `max_states` states are
+    /// stored, as `explore_bounded` does, reporting which ceiling was hit.
+    pub fn explore_parallel_bounded(
+        &mut self,
+        threads: usize,
+        deadline: Option<Instant>,
+        max_states: Option<usize>,
+    ) -> Result<(), Exhausted> {
+        let threads = threads.max(1);
+        let started = Instant::now();
+        let cap = Count::new(self.max_candies as i32);
+        let kind_caps = self.kind_caps;
+        let hasher = RandomState::new();
+        let shard = |candies: &Candies| hasher.hash_one(candies) as usize % threads;
+        let mut collections = Combinations::new();
+        let trades = self
+            .trades
+            .iter()
+            .map(|trade| (*trade, collections.trade_index(*trade)))
+            .collect::<Vec<_>>();
+        let mut shards = (0..threads)
+            .map(|_| Visited::new(false))
+            .collect::<Vec<_>>();
+        shards[shard(&self.candies)].insert(self.candies);
+        collections.push((self.candies, None));
+        let mut level = vec![0];
+        let mut capped = false;
+        let (mut hashing, mut storing) = (Duration::ZERO, Duration::ZERO);
+        let mut outcome = Ok(());
+        while !level.is_empty() {
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                outcome = Err(Exhausted::Time);
+                break;
+            }
+            if interrupt::requested() {
+                outcome = Err(Exhausted::Interrupted);
+                break;
+            }
+            if max_states.is_some_and(|max_states| collections.len() > max_states) {
+                outcome = Err(Exhausted::States);
+                break;
+            }
+            // Every thread trades from a slice of the level and sorts what
+            // it reaches by the shard that checks it.
+            let (stored, trades) = (&collections, &trades);
+            let expanded = thread::scope(|scope| {
+                level
+                    .chunks(level.len().div_ceil(threads))
+                    .map(|slice| {
+                        scope.spawn(move || {
+                            let mut found = vec![Vec::<Found>::new(); threads];
+                            let mut capped = false;
+                            for &index in slice {
+                                let candies = stored.state(index);
+                                for (trade, trade_index) in trades {
+                                    if let Some(new_candies) = candies.trade(trade) {
+                                        if new_candies.total() > cap
+                                            || kind_caps
+                                                .is_some_and(|caps| !caps.allows(&new_candies))
+                                        {
+                                            capped = true;
+                                            continue;
+                                        }
+                                        found[shard(&new_candies)].push((
+                                            new_candies,
+                                            index,
+                                            *trade_index,
+                                        ));
+                                    }
+                                }
+                            }
+                            (found, capped)
+                        })
+                    })
+                    .collect::<Vec<_>>()
+                    .into_iter()
+                    .map(|handle| handle.join().unwrap())
+                    .collect::<Vec<_>>()
+            });
+            capped |= expanded.iter().any(|(_, capped)| *capped);
+            // Every shard keeps the states it has not seen, taken in the
+            // order of the level so the earliest discovery wins.
+            let expanded = &expanded;
+            let t = Instant::now();
+            let mut fresh = thread::scope(|scope| {
+                shards
+                    .iter_mut()
+                    .enumerate()
+                    .map(|(i, known)| {
+                        scope.spawn(move || {
+                            expanded
+                                .iter()
+                                .flat_map(|(found, _)| &found[i])
+                                .filter(|(candies, _, _)| known.insert(*candies))
+                                .copied()
+                                .collect::<Vec<_>>()
+                        })
+                    })
+                    .collect::<Vec<_>>()
+                    .into_iter()
+                    .flat_map(|handle| handle.join().unwrap())
+                    .collect::<Vec<_>>()
+            });
+            hashing += t.elapsed();
+            let t = Instant::now();
+            fresh.sort_unstable_by_key(|(_, parent, trade_index)| (*parent, *trade_index));
+            level = Vec::with_capacity(fresh.len());
+            for (candies, parent, trade_index) in fresh {
+                level.push(collections.len());
+                collections.push_child(candies, parent, trade_index);
+            }
+            storing += t.elapsed();
+        }
+        self.combinations = collections;
+        self.exploration = Some(match outcome {
+            Err(exhausted) => Exploration::Stopped(exhausted),
+            Ok(()) if capped => Exploration::Capped,
+            Ok(()) => Exploration::Complete,
+        });
+        let timings = self.timings.as_deref();
+        timings::record(timings, Phase::Exploration, started.elapsed());
+        timings::record(timings, Phase::Hashing, hashing);
+        timings::record(timings, Phase::Storing, storing);
+        outcome
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frontier::Frontier;
+    use crate::test_support::{candies, trade};
+
+    fn problem() -> CandyWorks {
+        CandyWorks::builder(candies("6e 3w"))
+            .cap(12)
+            .trade(trade("2e -> 1c"))
+            .frontier(Frontier::Fifo)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn explores_the_graph_fifo_explores() {
+        let mut serial = problem();
+        serial.explore();
+        for threads in [1, 2, 3, 8] {
+            let mut parallel = problem();
+            parallel.explore_parallel(threads);
+            assert!(parallel.combinations.iter().eq(serial.combinations.iter()));
+            assert_eq!(parallel.exploration(), serial.exploration());
+        }
+    }
+
+    #[test]
+    fn stops_at_the_deadline_and_the_state_limit() {
+        let mut late = problem();
+        let passed = Instant::now();
+        assert_eq!(
+            late.explore_parallel_bounded(2, Some(passed), None),
+            Err(Exhausted::Time)
+        );
+        assert_eq!(late.combinations.len(), 1);
+        let mut full = problem();
+        assert_eq!(
+            full.explore_parallel_bounded(2, None, Some(5)),
+            Err(Exhausted::States)
+        );
+        assert_eq!(
+            full.exploration(),
+            Some(Exploration::Stopped(Exhausted::States))
+        );
+        let mut explored = problem();
+        explored.explore();
+        assert!(full.combinations.len() < explored.combinations.len());
+    }
+}
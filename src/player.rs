@@ -0,0 +1,85 @@
+use std::{env, fs, path::PathBuf};
+
+/// Where the players' files are kept: `$CANDYWORKS_HOME`, or `.candyworks`
+/// in the home directory.
+pub fn home() -> PathBuf {
+    if let Some(home) = env::var_os("CANDYWORKS_HOME") {
+        return PathBuf::from(home);
+    }
+    env::var_os("HOME")
+        .map_or_else(PathBuf::new, PathBuf::from)
+        .join(".candyworks")
+}
+
+/// One of several people planning on the same machine, as chosen with
+/// `--player`, with a directory of their own for the files that otherwise
+/// have to be named on every run.
+pub struct Player {
+    dir: PathBuf,
+}
+
+impl Player {
+    /// The player called `name`, creating their directory on first use.
+    pub fn open(name: &str) -> Result<Player, String> {
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(format!(
+                "player names can only have letters, digits, `-` and `_`, not `{}`",
+                name
+            ));
+        }
+        let dir = home().join("players").join(name);
+        fs::create_dir_all(&dir).map_err(|err| format!("{}: {}", dir.display(), err))?;
+        Ok(Player { dir })
+    }
+
+    /// The inventory and goals, read instead of prompting once saved.
+    pub fn scenario(&self) -> PathBuf {
+        self.dir.join("scenario.candy")
+    }
+
+    /// The answers given at the prompts, offered again as defaults.
+    pub fn session(&self) -> PathBuf {
+        self.dir.join("session.txt")
+    }
+
+    /// How often each trade was used, as `--trade-profile` keeps.
+    pub fn trade_profile(&self) -> PathBuf {
+        self.dir.join("trade-profile.txt")
+    }
+
+    pub fn queries(&self) -> PathBuf {
+        self.dir.join("queries.txt")
+    }
+
+    /// The graph explored last, for the commands reading a saved graph.
+    pub fn graph(&self) -> PathBuf {
+        self.dir.join("graph.cwg")
+    }
+
+    /// Trades of this player's own, layered over the shared catalog.
+    pub fn catalog(&self) -> PathBuf {
+        self.dir.join("catalog.candy")
+    }
+
+    /// Progress through the route being made, as `route start` keeps.
+    pub fn route_progress(&self) -> PathBuf {
+        self.dir.join("route-progress.txt")
+    }
+}
+
+/// The players with a directory, by name.
+pub fn list() -> Vec<String> {
+    let mut players = fs::read_dir(home().join("players"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect::<Vec<_>>();
+    players.sort();
+    players
+}
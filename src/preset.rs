@@ -0,0 +1,180 @@
+use std::path::PathBuf;
+
+use crate::{kinds::CandyKinds, player, Trade, DEFAULT_CAP};
+
+/// The trades of one game or event, as a section of the presets file
+/// names them:
+///
+/// ```text
+/// [halloween2024]
+/// ratio = 4
+/// cap = 30
+/// kind = p: Pumpkin / Pumpkins
+/// kind = b: Bat / Bats
+/// trade = 2p -> 1b
+/// ```
+///
+/// `ratio` is how many of one candy the standard trades give for one of
+/// any other, `kind` lines are the game's kinds of candy as a `--kinds`
+/// file writes them, `cap` stands for scenarios that set none, and each
+/// `trade` is a special offered besides the standard trades.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Preset {
+    pub name: String,
+    pub ratio: u32,
+    pub cap: Option<u32>,
+    pub specials: Vec<Trade>,
+}
+
+impl Preset {
+    /// The standard 3:1 trades and nothing else.
+    pub fn builtin() -> Preset {
+        Preset {
+            name: "standard".to_string(),
+            ratio: 3,
+            cap: None,
+            specials: Vec::new(),
+        }
+    }
+
+    /// The cap of scenarios that set none: the preset's, or `DEFAULT_CAP`.
+    pub fn default_cap(&self) -> u32 {
+        self.cap.unwrap_or(DEFAULT_CAP)
+    }
+}
+
+/// A preset as written in the file, its trades still unread until its
+/// kinds are known.
+pub struct Section {
+    pub name: String,
+    pub kinds: Option<CandyKinds>,
+    ratio: u32,
+    cap: Option<u32>,
+    trades: Vec<(usize, String)>,
+}
+
+/// Where presets are read from without `--presets`: `presets.candy` in the
+/// directory `--player` keeps its files in.
+pub fn file() -> PathBuf {
+    player::home().join("presets.candy")
+}
+
+/// The names of the presets in a presets file, in the order written.
+pub fn names(input: &str) -> Vec<String> {
+    input
+        .lines()
+        .filter_map(|line| {
+            let line = line.split('#').next().unwrap().trim();
+            let name = line.strip_prefix('[')?.strip_suffix(']')?;
+            Some(name.trim().to_string())
+        })
+        .collect()
+}
+
+/// Reads the preset called `name` from a presets file. `#` starts a
+/// comment, as in scenarios.
+pub fn section(input: &str, name: &str) -> Result<Section, String> {
+    let mut found = false;
+    let mut inside = false;
+    let mut kinds = Vec::new();
+    let mut section = Section {
+        name: name.to_string(),
+        kinds: None,
+        ratio: 3,
+        cap: None,
+        trades: Vec::new(),
+    };
+    for (number, line) in input.lines().enumerate() {
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let error = |message: &str| format!("line {}: {}", number + 1, message);
+        if let Some(header) = line.strip_prefix('[') {
+            let header = header
+                .strip_suffix(']')
+                .ok_or_else(|| error("expected `[name]`"))?;
+            inside = header.trim() == name;
+            if inside && found {
+                return Err(error(&format!("preset `{}` is written twice", name)));
+            }
+            found = found || inside;
+            continue;
+        }
+        if !inside {
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| error("expected `key = value`"))?;
+        let value = value.trim();
+        match key.trim() {
+            "ratio" => {
+                let give = value.strip_suffix(":1").unwrap_or(value).trim();
+                section.ratio = match give.parse::<u32>() {
+                    Ok(0) | Err(_) => {
+                        return Err(error(&format!(
+                            "`{}` is not a ratio, expected a count such as `4` or `4:1`",
+                            value
+                        )))
+                    }
+                    Ok(ratio) => ratio,
+                };
+            }
+            "cap" => {
+                section.cap = Some(
+                    value
+                        .parse()
+                        .map_err(|_| error(&format!("`{}` is not a cap", value)))?,
+                );
+            }
+            "kind" => kinds.push((number, value.to_string())),
+            "trade" => section.trades.push((number + 1, value.to_string())),
+            other => {
+                return Err(error(&format!(
+                    "unknown key `{}`, expected ratio, cap, kind or trade",
+                    other
+                )))
+            }
+        }
+    }
+    if !found {
+        let known = names(input);
+        return Err(if known.is_empty() {
+            format!("no preset `{}`: the file has no presets", name)
+        } else {
+            format!("no preset `{}`, expected one of {}", name, known.join(", "))
+        });
+    }
+    if !kinds.is_empty() {
+        // Each kind on the line it was written on, for errors to point at.
+        let mut lines = vec![String::new(); input.lines().count()];
+        for (number, kind) in kinds {
+            lines[number] = kind;
+        }
+        section.kinds = Some(CandyKinds::parse(&lines.join("\n"))?);
+    }
+    Ok(section)
+}
+
+impl Section {
+    /// The preset, its trades read with the kinds of candy configured for
+    /// the run, which have to be its own by then.
+    pub fn preset(self) -> Result<Preset, String> {
+        let specials = self
+            .trades
+            .iter()
+            .map(|(line, trade)| {
+                trade
+                    .parse::<Trade>()
+                    .map_err(|err| format!("line {}: {}", line, err))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Preset {
+            name: self.name,
+            ratio: self.ratio,
+            cap: self.cap,
+            specials,
+        })
+    }
+}
@@ -0,0 +1,69 @@
+use std::{collections::HashMap, fs, io, path::Path};
+
+use crate::Trade;
+
+/// How often each trade appeared in solved routes, persisted between runs
+/// so exploration can try historically useful trades first.
+#[derive(Default)]
+pub struct TradeProfile {
+    counts: HashMap<Trade, u64>,
+}
+
+impl TradeProfile {
+    /// Reads a profile written by `save`; a missing file is an empty profile.
+    pub fn load(path: &Path) -> Result<TradeProfile, String> {
+        let input = match fs::read_to_string(path) {
+            Ok(input) => input,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(TradeProfile::default()),
+            Err(err) => return Err(err.to_string()),
+        };
+        let mut counts = HashMap::new();
+        for (number, line) in input.lines().enumerate() {
+            let error = |message: String| format!("line {}: {}", number + 1, message);
+            let Some((count, trade)) = line.split_once('\t') else {
+                return Err(error("expected `count<TAB>trade`".to_string()));
+            };
+            let count = count
+                .parse::<u64>()
+                .map_err(|_| error(format!("invalid count `{}`", count)))?;
+            let trade = trade
+                .parse::<Trade>()
+                .map_err(|err| error(err.to_string()))?;
+            counts.insert(trade, count);
+        }
+        Ok(TradeProfile { counts })
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut entries = self.counts.iter().collect::<Vec<_>>();
+        entries.sort_by_key(|(trade, count)| {
+            (
+                std::cmp::Reverse(**count),
+                trade.give.compact(),
+                trade.receive.compact(),
+            )
+        });
+        let mut out = String::new();
+        for (trade, count) in entries {
+            out.push_str(&format!(
+                "{}\t{} -> {}\n",
+                count,
+                trade.give.compact(),
+                trade.receive.compact()
+            ));
+        }
+        fs::write(path, out)
+    }
+
+    /// Sorts trades so the most used come first, keeping the existing order
+    /// among trades used equally often.
+    pub fn order(&self, trades: &mut [Trade]) {
+        trades.sort_by_key(|trade| std::cmp::Reverse(self.counts.get(trade).copied().unwrap_or(0)));
+    }
+
+    pub fn record(&mut self, route: &[Trade]) {
+        for trade in route {
+            *self.counts.entry(*trade).or_insert(0) += 1;
+        }
+    }
+}
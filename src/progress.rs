@@ -0,0 +1,136 @@
+use std::{
+    io::{self, IsTerminal, Write},
+    time::{Duration, Instant},
+};
+
+use crate::{storage::Combinations, Candies, Trade};
+
+/// Trades of a candidate route shown before the rest is elided.
+const SHOWN_TRADES: usize = 8;
+
+/// How often the status line on a terminal is redrawn.
+const STATUS_EVERY: Duration = Duration::from_millis(100);
+
+/// Frames of the spinner at the start of the status line.
+const SPINNER: [char; 4] = ['|', '/', '-', '\\'];
+
+/// Shows the best route to each target found so far while a search runs,
+/// every `every`. On a terminal each report replaces the one before.
+/// Made by `status` instead, it only keeps one line on stderr up to date
+/// with the states explored, the states waiting and the time taken.
+pub struct Progress<'a> {
+    targets: &'a [Candies],
+    every: Duration,
+    started: Instant,
+    next: Instant,
+    overwrite: bool,
+    /// Lines printed by the last report, to move back over.
+    printed: usize,
+    /// The frame of the spinner when only the status line is shown.
+    status: Option<usize>,
+}
+
+impl<'a> Progress<'a> {
+    pub fn new(targets: &'a [Candies], every: Duration) -> Progress<'a> {
+        let started = Instant::now();
+        Progress {
+            targets,
+            every,
+            started,
+            next: started + every,
+            overwrite: io::stdout().is_terminal(),
+            printed: 0,
+            status: None,
+        }
+    }
+
+    /// A status line on stderr, or `None` when stderr is not a terminal
+    /// for it to be redrawn on.
+    pub fn status() -> Option<Progress<'static>> {
+        io::stderr().is_terminal().then(|| Progress {
+            status: Some(0),
+            ..Progress::new(&[], STATUS_EVERY)
+        })
+    }
+
+    /// Reports the states explored so far, and the `waiting` ones still to
+    /// expand, if the time for it has come.
+    pub fn tick(&mut self, combinations: &Combinations, waiting: usize) {
+        let now = Instant::now();
+        if now < self.next {
+            return;
+        }
+        self.next = now + self.every;
+        if let Some(frame) = self.status.as_mut() {
+            *frame = (*frame + 1) % SPINNER.len();
+            eprint!(
+                "\r\x1b[K{} {} states explored, {} waiting, {:.1}s (Ctrl-C to stop early)",
+                SPINNER[*frame],
+                combinations.len(),
+                waiting,
+                self.started.elapsed().as_secs_f64()
+            );
+            self.printed = 1;
+            return;
+        }
+        let mut lines = vec![format!(
+            "After {:.1}s, {} states explored, {} waiting:",
+            self.started.elapsed().as_secs_f64(),
+            combinations.len(),
+            waiting
+        )];
+        for target in self.targets {
+            let best = (0..combinations.len())
+                .filter(|&index| combinations.state(index).contains(target))
+                .max_by_key(|&index| combinations.state(index).total());
+            lines.push(match best {
+                Some(index) => candidate(combinations, index, target),
+                None => format!("  ({}) not reached yet", target.display(false).trim()),
+            });
+        }
+        let mut out = io::stdout().lock();
+        if self.overwrite && self.printed > 0 {
+            write!(out, "\x1b[{}A\x1b[J", self.printed).unwrap();
+        }
+        for line in &lines {
+            writeln!(out, "{}", line).unwrap();
+        }
+        out.flush().unwrap();
+        self.printed = lines.len();
+    }
+
+    /// Clears the status line once the search is over.
+    pub fn finish(&mut self) {
+        if self.status.is_some() && self.printed > 0 {
+            eprint!("\r\x1b[K");
+            self.printed = 0;
+        }
+    }
+}
+
+/// One line describing the route to `index`, the best state found holding
+/// `target`.
+fn candidate(combinations: &Combinations, index: usize, target: &Candies) -> String {
+    let mut route: Vec<Trade> = Vec::new();
+    let mut current = index;
+    while let Some((parent, trade)) = combinations.parent(current) {
+        route.push(trade);
+        current = parent;
+    }
+    route.reverse();
+    let mut shown = route
+        .iter()
+        .take(SHOWN_TRADES)
+        .map(|trade| format!("{} -> {}", trade.give.compact(), trade.receive.compact()))
+        .collect::<Vec<_>>();
+    if route.len() > SHOWN_TRADES {
+        shown.push("...".to_string());
+    }
+    format!(
+        "  ({}) in {} trades, ending with {} candies: {}",
+        target.display(false).trim(),
+        route.len(),
+        combinations.state(index).total(),
+        shown.join(", ")
+    )
+}
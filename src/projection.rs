@@ -0,0 +1,89 @@
+use std::{collections::BTreeMap, fmt};
+
+use crate::{names, CandyWorks};
+
+/// The explored states seen through a few candies only: every combination
+/// of their counts some state holds, however much of the other candies it
+/// has.
+pub struct Projection {
+    candies: Vec<usize>,
+    /// For each combination, the number of states holding it and the
+    /// fewest trades any of them is reached in.
+    combinations: BTreeMap<Vec<i32>, (usize, usize)>,
+}
+
+impl CandyWorks {
+    /// Collapses the explored graph onto `candies`, ignoring the rest.
+    pub fn project(&self, candies: &[usize]) -> Projection {
+        let mut combinations = BTreeMap::new();
+        for (candies_held, depth) in self.combinations.states().zip(self.depths()) {
+            let key = candies
+                .iter()
+                .map(|&i| candies_held.get_by_index(i).get())
+                .collect();
+            let entry = combinations.entry(key).or_insert((0, depth));
+            entry.0 += 1;
+            entry.1 = entry.1.min(depth);
+        }
+        Projection {
+            candies: candies.to_vec(),
+            combinations,
+        }
+    }
+}
+
+impl fmt::Display for Projection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names = names::active();
+        let projected = self
+            .candies
+            .iter()
+            .map(|&i| names.plural(i))
+            .collect::<Vec<_>>();
+        writeln!(
+            f,
+            "Projected onto {}: {} combinations",
+            projected.join(", "),
+            self.combinations.len()
+        )?;
+        let headers = projected
+            .iter()
+            .copied()
+            .chain(["states", "trades"])
+            .collect::<Vec<_>>();
+        let rows = self
+            .combinations
+            .iter()
+            .map(|(counts, (states, trades))| {
+                counts
+                    .iter()
+                    .map(i32::to_string)
+                    .chain([states.to_string(), trades.to_string()])
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let widths = (0..headers.len())
+            .map(|c| {
+                rows.iter()
+                    .map(|row| row[c].len())
+                    .chain([headers[c].len()])
+                    .max()
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        let line = |cells: &[&str]| {
+            cells
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:>width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join("  ")
+        };
+        write!(f, "{}", line(&headers))?;
+        for row in &rows {
+            let cells = row.iter().map(String::as_str).collect::<Vec<_>>();
+            write!(f, "\n{}", line(&cells))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,124 @@
+#[cfg(not(feature = "readline"))]
+use std::io::{self, BufRead, Write};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Where the interactive questions are asked. The solver never touches the
+/// terminal itself; front ends other than the CLI implement this to supply
+/// the answers their own way.
+pub trait Prompt {
+    /// Shows a message that needs no answer.
+    fn say(&mut self, message: &str) {
+        println!("{}", message);
+    }
+
+    /// Shows `prompt` and reads one line, or `None` once input has ended.
+    fn ask(&mut self, prompt: &str) -> Option<String>;
+}
+
+#[cfg(feature = "readline")]
+impl Prompt for rustyline::DefaultEditor {
+    fn ask(&mut self, prompt: &str) -> Option<String> {
+        self.readline(prompt).ok()
+    }
+}
+
+/// Reads answers line by line from stdin, for builds without `readline`.
+#[cfg(not(feature = "readline"))]
+pub struct Stdin;
+
+#[cfg(not(feature = "readline"))]
+impl Prompt for Stdin {
+    fn ask(&mut self, prompt: &str) -> Option<String> {
+        print!("{}", prompt);
+        io::stdout().flush().ok()?;
+        let mut line = String::new();
+        match io::stdin().lock().read_line(&mut line) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => Some(line.trim_end_matches(['\n', '\r']).to_string()),
+        }
+    }
+}
+
+/// The prompt for the terminal this build supports.
+pub fn terminal() -> impl Prompt {
+    #[cfg(feature = "readline")]
+    return rustyline::DefaultEditor::new().unwrap();
+    #[cfg(not(feature = "readline"))]
+    return Stdin;
+}
+
+/// Offers the answers given in the previous session as defaults, accepted
+/// by pressing Enter, and records the new answers for the next one. The
+/// file holds one `question<TAB>answer` line per question asked, where the
+/// question is the message shown before the prompt; a default is only
+/// offered while the questions come in the same order as last time.
+/// Without a file it asks exactly like `inner`.
+pub struct Remembered<P> {
+    inner: P,
+    path: Option<PathBuf>,
+    previous: Vec<(String, String)>,
+    answers: Vec<(String, String)>,
+    said: String,
+}
+
+impl<P: Prompt> Remembered<P> {
+    pub fn new(inner: P, path: Option<&Path>) -> Self {
+        let previous = path
+            .and_then(|path| fs::read_to_string(path).ok())
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| line.split_once('\t'))
+            .map(|(question, answer)| (question.to_string(), answer.to_string()))
+            .collect();
+        Remembered {
+            inner,
+            path: path.map(Path::to_path_buf),
+            previous,
+            answers: Vec::new(),
+            said: String::new(),
+        }
+    }
+}
+
+impl<P: Prompt> Prompt for Remembered<P> {
+    fn say(&mut self, message: &str) {
+        self.said = message.to_string();
+        self.inner.say(message);
+    }
+
+    fn ask(&mut self, prompt: &str) -> Option<String> {
+        let question = format!("{} {}", self.said, prompt).replace(['\t', '\n'], " ");
+        let default = self
+            .previous
+            .get(self.answers.len())
+            .filter(|(previous, _)| *previous == question)
+            .map(|(_, answer)| answer.clone());
+        let answer = match &default {
+            Some(default) => {
+                let answer = self.inner.ask(&format!("{}[{}] ", prompt, default))?;
+                if answer.trim().is_empty() {
+                    default.clone()
+                } else {
+                    answer
+                }
+            }
+            None => self.inner.ask(prompt)?,
+        };
+        let Some(path) = &self.path else {
+            return Some(answer);
+        };
+        self.answers
+            .push((question, answer.replace(['\t', '\n'], " ")));
+        let saved = self
+            .answers
+            .iter()
+            .map(|(question, answer)| format!("{}\t{}\n", question, answer))
+            .collect::<String>();
+        // Losing the defaults only costs typing next time.
+        let _ = fs::write(path, saved);
+        Some(answer)
+    }
+}
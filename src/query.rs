@@ -0,0 +1,374 @@
+use std::{fmt::Write as _, fs, path::Path};
+
+use crate::{donate, kinds, names, Candies, CandyWorks};
+
+/// A number computed from a state: counts, the total and the trades it
+/// takes, combined with `+`, `-` and `*`.
+enum Value {
+    Number(i64),
+    Candy(usize),
+    Total,
+    Depth,
+    Add(Box<Value>, Box<Value>),
+    Sub(Box<Value>, Box<Value>),
+    Mul(Box<Value>, Box<Value>),
+}
+
+#[derive(Clone, Copy)]
+enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+/// A condition on explored states, such as
+/// `cakes >= 3 && total() <= 15 && depth < 6`.
+///
+/// Counts are named by candy letter or name, `total()` (or `total`) is
+/// the number of candies and `depth` the trades the state is reached in.
+/// Comparisons combine with `&&`, `||`, `!` and parentheses.
+pub struct Filter(Condition);
+
+enum Condition {
+    Compare(Value, Comparison, Value),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+    Not(Box<Condition>),
+}
+
+impl Value {
+    /// The value for `candies` reached in `depth` trades, or an error if
+    /// computing it counts past what an `i64` holds.
+    fn eval(&self, candies: &Candies, depth: usize) -> Result<i64, String> {
+        let checked = |a: &Value, b: &Value, op: fn(i64, i64) -> Option<i64>| {
+            op(a.eval(candies, depth)?, b.eval(candies, depth)?)
+                .ok_or_else(|| "the filter counts past what an i64 holds".to_string())
+        };
+        match self {
+            Value::Number(n) => Ok(*n),
+            Value::Candy(i) => Ok(candies.get_by_index(*i).get() as i64),
+            Value::Total => Ok(candies.total().get() as i64),
+            Value::Depth => Ok(depth as i64),
+            Value::Add(a, b) => checked(a, b, i64::checked_add),
+            Value::Sub(a, b) => checked(a, b, i64::checked_sub),
+            Value::Mul(a, b) => checked(a, b, i64::checked_mul),
+        }
+    }
+}
+
+impl Filter {
+    pub fn parse(input: &str) -> Result<Filter, String> {
+        let mut parser = Parser {
+            input: input.as_bytes(),
+            at: 0,
+        };
+        let filter = parser.or()?;
+        parser.skip_whitespace();
+        if parser.at != parser.input.len() {
+            return Err(parser.error("expected `&&`, `||` or the end of the filter"));
+        }
+        Ok(Filter(filter))
+    }
+
+    /// Whether `candies`, reached in `depth` trades, meets the filter; an
+    /// error if a value it compares counts past what an `i64` holds.
+    pub fn matches(&self, candies: &Candies, depth: usize) -> Result<bool, String> {
+        self.0.matches(candies, depth)
+    }
+}
+
+impl Condition {
+    fn matches(&self, candies: &Candies, depth: usize) -> Result<bool, String> {
+        Ok(match self {
+            Condition::Compare(a, comparison, b) => {
+                let (a, b) = (a.eval(candies, depth)?, b.eval(candies, depth)?);
+                match comparison {
+                    Comparison::Equal => a == b,
+                    Comparison::NotEqual => a != b,
+                    Comparison::Less => a < b,
+                    Comparison::LessOrEqual => a <= b,
+                    Comparison::Greater => a > b,
+                    Comparison::GreaterOrEqual => a >= b,
+                }
+            }
+            Condition::And(a, b) => a.matches(candies, depth)? && b.matches(candies, depth)?,
+            Condition::Or(a, b) => a.matches(candies, depth)? || b.matches(candies, depth)?,
+            Condition::Not(a) => !a.matches(candies, depth)?,
+        })
+    }
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    at: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> String {
+        format!("column {}: {}", self.at + 1, message)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.input.get(self.at).is_some_and(u8::is_ascii_whitespace) {
+            self.at += 1;
+        }
+    }
+
+    /// Skips `token` if it comes next.
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        let found = self.input[self.at..].starts_with(token.as_bytes());
+        if found {
+            self.at += token.len();
+        }
+        found
+    }
+
+    fn or(&mut self) -> Result<Condition, String> {
+        let mut filter = self.and()?;
+        while self.eat("||") {
+            filter = Condition::Or(Box::new(filter), Box::new(self.and()?));
+        }
+        Ok(filter)
+    }
+
+    fn and(&mut self) -> Result<Condition, String> {
+        let mut filter = self.not()?;
+        while self.eat("&&") {
+            filter = Condition::And(Box::new(filter), Box::new(self.not()?));
+        }
+        Ok(filter)
+    }
+
+    fn not(&mut self) -> Result<Condition, String> {
+        self.skip_whitespace();
+        let rest = &self.input[self.at..];
+        if rest.starts_with(b"!") && !rest.starts_with(b"!=") {
+            self.at += 1;
+            return Ok(Condition::Not(Box::new(self.not()?)));
+        }
+        // A parenthesis opens either a whole condition or a sum inside a
+        // comparison, as in `(eggs + worms) * 2 > 10`.
+        let start = self.at;
+        if self.eat("(") {
+            if let Ok(filter) = self.or() {
+                if self.eat(")") {
+                    return Ok(filter);
+                }
+            }
+            self.at = start;
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Condition, String> {
+        let left = self.sum()?;
+        let comparison = [
+            ("==", Comparison::Equal),
+            ("!=", Comparison::NotEqual),
+            ("<=", Comparison::LessOrEqual),
+            (">=", Comparison::GreaterOrEqual),
+            ("<", Comparison::Less),
+            (">", Comparison::Greater),
+        ]
+        .into_iter()
+        .find(|(token, _)| self.eat(token))
+        .map(|(_, comparison)| comparison)
+        .ok_or_else(|| self.error("expected a comparison such as `>=`"))?;
+        Ok(Condition::Compare(left, comparison, self.sum()?))
+    }
+
+    fn sum(&mut self) -> Result<Value, String> {
+        let mut value = self.product()?;
+        loop {
+            if self.eat("+") {
+                value = Value::Add(Box::new(value), Box::new(self.product()?));
+            } else if self.eat("-") {
+                value = Value::Sub(Box::new(value), Box::new(self.product()?));
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn product(&mut self) -> Result<Value, String> {
+        let mut value = self.atom()?;
+        while self.eat("*") {
+            value = Value::Mul(Box::new(value), Box::new(self.atom()?));
+        }
+        Ok(value)
+    }
+
+    fn atom(&mut self) -> Result<Value, String> {
+        if self.eat("(") {
+            let value = self.sum()?;
+            if !self.eat(")") {
+                return Err(self.error("expected `)`"));
+            }
+            return Ok(value);
+        }
+        self.skip_whitespace();
+        let start = self.at;
+        let input = self.input;
+        let rest = &input[start..];
+        let digits = rest.iter().take_while(|b| b.is_ascii_digit()).count();
+        if digits > 0 {
+            self.at += digits;
+            return std::str::from_utf8(&rest[..digits])
+                .unwrap()
+                .parse()
+                .map(Value::Number)
+                .map_err(|_| self.error("number too large"));
+        }
+        let length = rest
+            .iter()
+            .take_while(|b| b.is_ascii_alphanumeric() || **b == b'_')
+            .count();
+        if length == 0 {
+            return Err(self.error("expected a number, a candy, `total()` or `depth`"));
+        }
+        let word = std::str::from_utf8(&rest[..length]).unwrap();
+        self.at += length;
+        match word {
+            "total" => {
+                if self.eat("(") && !self.eat(")") {
+                    return Err(self.error("expected `)`"));
+                }
+                Ok(Value::Total)
+            }
+            "depth" => Ok(Value::Depth),
+            _ => donate::parse_candy(word).map(Value::Candy).ok_or_else(|| {
+                self.at = start;
+                self.error(&format!("unknown candy `{}`", word))
+            }),
+        }
+    }
+}
+
+impl CandyWorks {
+    /// The explored states matching `filter`, as indices in exploration
+    /// order, or why the filter cannot be computed for one of them.
+    pub fn matching(&self, filter: &Filter) -> Result<Vec<usize>, String> {
+        let mut matching = Vec::new();
+        for (i, (candies, depth)) in self.combinations.states().zip(self.depths()).enumerate() {
+            if filter.matches(&candies, depth)? {
+                matching.push(i);
+            }
+        }
+        Ok(matching)
+    }
+}
+
+/// Prints how many explored states match `filter` and, unless only the
+/// count is asked for, up to `limit` of them with their IDs and depths.
+pub fn list(
+    candy_works: &CandyWorks,
+    filter: &Filter,
+    limit: usize,
+    count: bool,
+) -> Result<(), String> {
+    let matching = candy_works.matching(filter)?;
+    println!(
+        "{} of {} states match",
+        matching.len(),
+        candy_works.combinations.len()
+    );
+    if count {
+        return Ok(());
+    }
+    let depths = candy_works.depths();
+    for &index in matching.iter().take(limit) {
+        let candies = candy_works.combinations.state(index);
+        println!(
+            "{:>16}  ({}) after {} trades",
+            candies.id_label(),
+            candies.display(true),
+            depths[index]
+        );
+    }
+    if matching.len() > limit {
+        println!("... and {} more", matching.len() - limit);
+    }
+    Ok(())
+}
+
+/// Writes the states matching `filter` to `path` as CSV: the state ID,
+/// the count of every candy, the total and the depth.
+pub fn export(candy_works: &CandyWorks, filter: &Filter, path: &Path) -> Result<usize, String> {
+    let names = names::active();
+    let mut out = String::from("id");
+    for i in 0..kinds::count() {
+        write!(out, ",{}", names.plural(i)).unwrap();
+    }
+    out.push_str(",total,depth\n");
+    let depths = candy_works.depths();
+    let matching = candy_works.matching(filter)?;
+    for &index in &matching {
+        let candies = candy_works.combinations.state(index);
+        out.push_str(&candies.id_label());
+        for i in 0..kinds::count() {
+            write!(out, ",{}", candies.get_by_index(i)).unwrap();
+        }
+        writeln!(out, ",{},{}", candies.total(), depths[index]).unwrap();
+    }
+    fs::write(path, out).map_err(|err| err.to_string())?;
+    Ok(matching.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::candies;
+
+    fn matches(filter: &str, candies: &str, depth: usize) -> Result<bool, String> {
+        Filter::parse(filter)
+            .unwrap()
+            .matches(&candies.parse().unwrap(), depth)
+    }
+
+    #[test]
+    fn compares_counts_totals_and_depths() {
+        let filter = "cakes >= 3 && total() <= 15 && depth < 6";
+        assert_eq!(matches(filter, "3c 2e", 2), Ok(true));
+        assert_eq!(matches(filter, "2c 2e", 2), Ok(false));
+        assert_eq!(matches(filter, "3c 2e", 6), Ok(false));
+        assert_eq!(matches("!(e == 0) || (e + w) * 2 > 10", "6w", 0), Ok(true));
+        assert_eq!(matches("e - w < 0", "1e 2w", 0), Ok(true));
+    }
+
+    #[test]
+    fn refuses_to_count_past_an_i64() {
+        let filter = "cakes * 9999999999 * 9999999999 > 0";
+        assert_eq!(
+            matches(filter, "1c", 0),
+            Err("the filter counts past what an i64 holds".to_string())
+        );
+        assert_eq!(matches(filter, "1e", 0), Ok(false));
+        assert!(matches("0 - 9223372036854775807 - 2 < 0", "1e", 0).is_err());
+    }
+
+    #[test]
+    fn points_at_what_does_not_parse() {
+        assert_eq!(
+            Filter::parse("cakes >").err(),
+            Some("column 8: expected a number, a candy, `total()` or `depth`".to_string())
+        );
+        assert!(Filter::parse("cakes + 1").is_err());
+        assert!(Filter::parse("99999999999999999999 > 0").is_err());
+    }
+
+    #[test]
+    fn lists_the_explored_states_matching() {
+        let mut candy_works = CandyWorks::new(candies("3e"), 20, Vec::new());
+        candy_works.explore();
+        let filter = Filter::parse("e == 0").unwrap();
+        let matching = candy_works.matching(&filter).unwrap();
+        assert_eq!(matching.len(), 4);
+        assert!(matching.iter().all(|&i| i > 0));
+        let huge = Filter::parse("w * 9999999999 * 9999999999 > 0").unwrap();
+        assert!(candy_works.matching(&huge).is_err());
+    }
+}
@@ -0,0 +1,316 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    path::Path,
+};
+
+use crate::{count::Count, display_side, json, json::Value, names, Candies, CandyWorks, Trade};
+
+pub struct Quest {
+    pub name: String,
+    pub requires: Candies,
+    /// Candies the quest gives back on completion.
+    pub produces: Candies,
+    /// What completing the quest is worth, in whatever unit the file uses.
+    pub reward: f64,
+}
+
+impl Quest {
+    /// The inventory after completing the quest from `candies`, if it can
+    /// be completed there.
+    pub fn complete(&self, candies: &Candies) -> Option<Candies> {
+        candies
+            .without(&self.requires)
+            .map(|left| left.with(&self.produces))
+    }
+}
+
+fn requirement(value: &Value) -> Result<Candies, String> {
+    match value {
+        Value::String(text) => text.parse().map_err(|err| format!("{}", err)),
+        Value::Object(fields) => {
+            let names = names::active();
+            let mut candies = Candies::none();
+            for (name, count) in fields {
+                let index = names
+                    .find(name)
+                    .ok_or_else(|| format!("unknown candy `{}`", name))?;
+                let count = count
+                    .as_f64()
+                    .filter(|count| count.fract() == 0.0 && *count >= 0.0)
+                    .ok_or_else(|| format!("the count of `{}` must be a whole number", name))?;
+                candies.add_by_index(index, Count::new(count as i32));
+            }
+            Ok(candies)
+        }
+        _ => Err("expected candies such as \"3c 1m\" or {\"cakes\": 3}".to_string()),
+    }
+}
+
+/// Reads quests from JSON: an array, or an object with a `quests` array,
+/// of objects with a `name`, the `requires` candies, written like "3c 1m"
+/// or as {"cakes": 3, "meats": 1}, and a numeric `reward`. Candies a quest
+/// gives back go in `produces`; a quest producing candies may leave out
+/// the reward.
+pub fn load(path: &Path) -> Result<Vec<Quest>, String> {
+    let input = crate::replay::read_to_string(path).map_err(|err| err.to_string())?;
+    let document = json::parse(&input)?;
+    let list = match document.get("quests").unwrap_or(&document) {
+        Value::Array(list) => list,
+        _ => return Err("expected an array of quests".to_string()),
+    };
+    list.iter()
+        .enumerate()
+        .map(|(n, quest)| {
+            let context = |message: String| format!("quest {}: {}", n + 1, message);
+            let name = quest
+                .get("name")
+                .and_then(Value::as_str)
+                .map_or_else(|| format!("quest {}", n + 1), str::to_string);
+            let requires = quest
+                .get("requires")
+                .ok_or_else(|| context("missing `requires`".to_string()))
+                .and_then(|value| requirement(value).map_err(context))?;
+            let produces = match quest.get("produces") {
+                Some(value) => requirement(value).map_err(context)?,
+                None => Candies::none(),
+            };
+            let reward = match quest.get("reward") {
+                Some(value) => value
+                    .as_f64()
+                    .ok_or_else(|| context("the `reward` must be a number".to_string()))?,
+                None if produces != Candies::none() => 0.0,
+                None => return Err(context("missing a numeric `reward`".to_string())),
+            };
+            Ok(Quest {
+                name,
+                requires,
+                produces,
+                reward,
+            })
+        })
+        .collect()
+}
+
+/// The quests to complete together with `inventory` for the largest total
+/// reward, as indices into `quests`, for quests that produce nothing: what
+/// one produces could fund another, which `plan` accounts for. A
+/// depth-first search over taking or
+/// skipping each quest, abandoning branches that cannot beat the best
+/// found even with every remaining reward.
+pub fn select(quests: &[Quest], inventory: Candies) -> Vec<usize> {
+    fn search(
+        quests: &[Quest],
+        next: usize,
+        left: Candies,
+        taken: &mut Vec<usize>,
+        reward: f64,
+        best: &mut (f64, Vec<usize>),
+    ) {
+        if reward > best.0 {
+            *best = (reward, taken.clone());
+        }
+        let remaining = quests[next..]
+            .iter()
+            .map(|quest| quest.reward.max(0.0))
+            .sum::<f64>();
+        if next == quests.len() || reward + remaining <= best.0 {
+            return;
+        }
+        if let Some(after) = left.without(&quests[next].requires) {
+            taken.push(next);
+            search(
+                quests,
+                next + 1,
+                after,
+                taken,
+                reward + quests[next].reward,
+                best,
+            );
+            taken.pop();
+        }
+        search(quests, next + 1, left, taken, reward, best);
+    }
+    let mut best = (0.0, Vec::new());
+    search(quests, 0, inventory, &mut Vec::new(), 0.0, &mut best);
+    best.1
+}
+
+/// Prints the selected quests, the reward they bring and what is left.
+pub fn print_plan(quests: &[Quest], selected: &[usize], inventory: Candies) {
+    if selected.is_empty() {
+        println!("No quest can be completed with the inventory");
+        return;
+    }
+    let mut left = inventory;
+    for &index in selected {
+        let quest = &quests[index];
+        left = left.without(&quest.requires).unwrap();
+        println!("Complete {}", describe(quest));
+    }
+    let total = selected.iter().map(|&i| quests[i].reward).sum::<f64>();
+    println!("Total reward: {}", total);
+    print_left(left);
+}
+
+#[derive(Clone, Copy)]
+pub enum Step {
+    Trade(Trade),
+    Quest(usize),
+}
+
+struct Node {
+    candies: Candies,
+    /// The quests completed, one bit each.
+    done: u32,
+    trades: usize,
+    parent: usize,
+    step: Option<Step>,
+}
+
+/// The most rewarding way to complete quests from the inventory of
+/// `candy_works` with at most `budget` trades, interleaving the trades
+/// that fund quests with the quests themselves: completing one frees room
+/// under the cap for the trades after it. Ties go to fewer trades.
+///
+/// Searches inventories together with the set of quests done, in order of
+/// trades made; completing a quest costs no trade.
+pub fn plan(
+    candy_works: &CandyWorks,
+    quests: &[Quest],
+    budget: usize,
+) -> Result<Vec<Step>, String> {
+    if quests.len() > 32 {
+        return Err("planning with trades handles at most 32 quests".to_string());
+    }
+    let reward = |done: u32| {
+        (0..quests.len())
+            .filter(|&i| done & (1 << i) != 0)
+            .map(|i| quests[i].reward)
+            .sum::<f64>()
+    };
+    let mut nodes = vec![Node {
+        candies: candy_works.candies,
+        done: 0,
+        trades: 0,
+        parent: 0,
+        step: None,
+    }];
+    let mut fewest = HashMap::from([((candy_works.candies, 0), 0)]);
+    let mut queue = VecDeque::from([0]);
+    let mut best = (0.0, 0, 0);
+    while let Some(index) = queue.pop_front() {
+        let (candies, done, trades) =
+            (nodes[index].candies, nodes[index].done, nodes[index].trades);
+        if fewest[&(candies, done)] < trades {
+            continue;
+        }
+        let value = reward(done);
+        if value > best.0 || value == best.0 && trades < best.1 {
+            best = (value, trades, index);
+        }
+        let mut next = Vec::new();
+        for (i, quest) in quests.iter().enumerate() {
+            if done & (1 << i) == 0 {
+                if let Some(after) = quest
+                    .complete(&candies)
+                    .filter(|after| candy_works.fits(after))
+                {
+                    next.push((after, done | (1 << i), trades, Step::Quest(i)));
+                }
+            }
+        }
+        if trades < budget {
+            for trade in &candy_works.trades {
+                if let Some(after) = candies.trade(trade).filter(|after| candy_works.fits(after)) {
+                    next.push((after, done, trades + 1, Step::Trade(*trade)));
+                }
+            }
+        }
+        for (candies, done, trades, step) in next {
+            if fewest
+                .get(&(candies, done))
+                .is_some_and(|&known| known <= trades)
+            {
+                continue;
+            }
+            fewest.insert((candies, done), trades);
+            nodes.push(Node {
+                candies,
+                done,
+                trades,
+                parent: index,
+                step: Some(step),
+            });
+            // Quests cost no trade, so they go ahead of the trades already
+            // queued, which keeps the queue ordered by trades made.
+            match step {
+                Step::Quest(_) => queue.push_front(nodes.len() - 1),
+                Step::Trade(_) => queue.push_back(nodes.len() - 1),
+            }
+        }
+    }
+    let mut steps = Vec::new();
+    let mut index = best.2;
+    while let Some(step) = nodes[index].step {
+        steps.push(step);
+        index = nodes[index].parent;
+    }
+    steps.reverse();
+    Ok(steps)
+}
+
+/// Prints a plan from `plan` in order, with the reward it brings, the
+/// trades it takes and what is left.
+pub fn print_steps(quests: &[Quest], steps: &[Step], inventory: Candies) {
+    if steps.is_empty() {
+        println!("No quest can be completed within the budget");
+        return;
+    }
+    let mut left = inventory;
+    let mut total = 0.0;
+    let mut trades = 0;
+    for (n, step) in steps.iter().enumerate() {
+        match step {
+            Step::Trade(trade) => {
+                left = left.trade(trade).unwrap();
+                trades += 1;
+                println!(
+                    "{:>3}. trade {} -> {}",
+                    n + 1,
+                    display_side(&trade.give),
+                    display_side(&trade.receive)
+                );
+            }
+            Step::Quest(index) => {
+                let quest = &quests[*index];
+                left = quest.complete(&left).unwrap();
+                total += quest.reward;
+                println!("{:>3}. complete {}", n + 1, describe(quest));
+            }
+        }
+    }
+    println!("Total reward: {} after {} trades", total, trades);
+    print_left(left);
+}
+
+fn print_left(left: Candies) {
+    if left == Candies::none() {
+        println!("Left over: nothing");
+    } else {
+        println!("Left over: ({})", left.display(false).trim());
+    }
+}
+
+/// A quest as plans show it, e.g. "Bakery: (3 cakes, 1 meat) for 10".
+pub fn describe(quest: &Quest) -> String {
+    let mut out = format!(
+        "{}: ({}) for {}",
+        quest.name,
+        quest.requires.display(false).trim(),
+        quest.reward
+    );
+    if quest.produces != Candies::none() {
+        out.push_str(&format!(" and ({})", quest.produces.display(false).trim()));
+    }
+    out
+}
@@ -0,0 +1,232 @@
+use std::{collections::HashMap, fmt};
+
+use crate::{display_side, value::worth, Candies, CandyWorks, Trade};
+
+/// A trade whose reward is drawn at random, as in `random = 3e -> 1m 40% |
+/// 1c 60%`: it gives `give` and receives one of the outcomes, each with
+/// its chance in percent, the chances adding up to 100.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RandomTrade {
+    pub give: Candies,
+    pub outcomes: Vec<(Candies, f64)>,
+}
+
+impl fmt::Display for RandomTrade {
+    /// Writes the trade as scenarios do.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let outcomes = self
+            .outcomes
+            .iter()
+            .map(|(receive, percent)| format!("{} {}%", receive.compact(), percent))
+            .collect::<Vec<_>>();
+        write!(f, "{} -> {}", self.give.compact(), outcomes.join(" | "))
+    }
+}
+
+impl RandomTrade {
+    /// The trade as routes show it, such as `3 eggs -> 1 meat 40% | 1 cake
+    /// 60%`.
+    pub fn display(&self) -> String {
+        let outcomes = self
+            .outcomes
+            .iter()
+            .map(|(receive, percent)| format!("{} {}%", display_side(receive), percent))
+            .collect::<Vec<_>>();
+        format!("{} -> {}", display_side(&self.give), outcomes.join(" | "))
+    }
+}
+
+/// What the planner under uncertainty is after.
+#[derive(Clone, Copy)]
+pub enum Goal {
+    /// The best chance of holding the target.
+    Reach(Candies),
+    /// The most the inventory is worth on average, each candy at its
+    /// weight.
+    Worth(Candies),
+}
+
+impl Goal {
+    fn score(&self, candies: &Candies) -> f64 {
+        match self {
+            Goal::Reach(target) => {
+                if candies.contains(target) {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            Goal::Worth(weights) => worth(weights, candies) as f64,
+        }
+    }
+}
+
+/// The first move of a plan under uncertainty.
+#[derive(Clone, Copy)]
+pub enum Move {
+    Trade(Trade),
+    /// The random trade at this index.
+    Random(usize),
+}
+
+/// Plans with `random` trades besides the trades of `candy_works`, apart
+/// from the deterministic planner: since what a random trade gives is
+/// only known once it is made, the plan is a choice of the next trade
+/// given what is held, each choice the best assuming the later ones are
+/// made as well. A random trade is only made when every outcome fits
+/// the cap.
+pub struct Chances<'a> {
+    pub candy_works: &'a CandyWorks,
+    pub random: &'a [RandomTrade],
+}
+
+impl Chances<'_> {
+    /// The moves that can be made from `candies`, each with what it may
+    /// leave and the chance of each.
+    fn moves(&self, candies: &Candies) -> Vec<(Move, Vec<(Candies, f64)>)> {
+        let mut moves = Vec::new();
+        for trade in &self.candy_works.trades {
+            if let Some(after) = candies
+                .trade(trade)
+                .filter(|after| self.candy_works.fits(after))
+            {
+                moves.push((Move::Trade(*trade), vec![(after, 1.0)]));
+            }
+        }
+        for (n, random) in self.random.iter().enumerate() {
+            let Some(left) = candies.without(&random.give) else {
+                continue;
+            };
+            let outcomes = random
+                .outcomes
+                .iter()
+                .map(|(receive, percent)| (left.with(receive), percent / 100.0))
+                .collect::<Vec<_>>();
+            if outcomes
+                .iter()
+                .all(|(after, _)| self.candy_works.fits(after))
+            {
+                moves.push((Move::Random(n), outcomes));
+            }
+        }
+        moves
+    }
+
+    /// The most `goal` scores on average from `candies` with at most
+    /// `left` trades more, stopping wherever going on would score less.
+    fn value(
+        &self,
+        goal: Goal,
+        candies: Candies,
+        left: usize,
+        memo: &mut HashMap<(Candies, usize), f64>,
+    ) -> f64 {
+        let now = goal.score(&candies);
+        if left == 0 || matches!(goal, Goal::Reach(_)) && now == 1.0 {
+            return now;
+        }
+        if let Some(value) = memo.get(&(candies, left)) {
+            return *value;
+        }
+        let mut best = now;
+        for (_, outcomes) in self.moves(&candies) {
+            let value = outcomes
+                .iter()
+                .map(|(after, chance)| chance * self.value(goal, *after, left - 1, memo))
+                .sum::<f64>();
+            best = best.max(value);
+        }
+        memo.insert((candies, left), best);
+        best
+    }
+
+    /// What `goal` scores on average from the inventory with at most
+    /// `within` trades, and the first move to get it; `None` when no move
+    /// does better than stopping.
+    pub fn plan(&self, goal: Goal, within: usize) -> (f64, Option<Move>) {
+        let start = self.candy_works.candies;
+        let mut best = (goal.score(&start), None);
+        if within == 0 {
+            return best;
+        }
+        let mut memo = HashMap::new();
+        for (first, outcomes) in self.moves(&start) {
+            let value = outcomes
+                .iter()
+                .map(|(after, chance)| chance * self.value(goal, *after, within - 1, &mut memo))
+                .sum::<f64>();
+            if value > best.0 {
+                best = (value, Some(first));
+            }
+        }
+        best
+    }
+
+    fn describe(&self, first: Move) -> String {
+        match first {
+            Move::Trade(trade) => format!(
+                "{} -> {}",
+                display_side(&trade.give),
+                display_side(&trade.receive)
+            ),
+            Move::Random(n) => format!("{} (random)", self.random[n].display()),
+        }
+    }
+
+    /// Prints the chance of holding `target` within `within` trades and the
+    /// trade to make first.
+    pub fn print_reach(&self, target: Candies, within: usize) {
+        if self.candy_works.candies.contains(&target) {
+            println!("Already holding ({})", display_side(&target));
+            return;
+        }
+        let (chance, first) = self.plan(Goal::Reach(target), within);
+        let Some(first) = first else {
+            println!(
+                "({}) cannot be held within {} trade{}, even with luck",
+                display_side(&target),
+                within,
+                if within == 1 { "" } else { "s" }
+            );
+            return;
+        };
+        println!(
+            "Chance of holding ({}) within {} trade{}: {:.1}%",
+            display_side(&target),
+            within,
+            if within == 1 { "" } else { "s" },
+            chance * 100.0
+        );
+        self.print_first(first);
+    }
+
+    /// Prints the most the inventory, `reserved` included, is worth on
+    /// average after at most `within` trades and the trade to make first.
+    pub fn print_worth(&self, weights: &Candies, within: usize, reserved: Candies) {
+        let (expected, first) = self.plan(Goal::Worth(*weights), within);
+        let start = worth(weights, &self.candy_works.candies.with(&reserved));
+        let Some(first) = first else {
+            println!(
+                "No trade adds value on average; the inventory is worth {}",
+                start
+            );
+            return;
+        };
+        let expected = expected + worth(weights, &reserved) as f64;
+        println!(
+            "Worth {:.2} on average after at most {} trade{}, up from {}",
+            expected,
+            within,
+            if within == 1 { "" } else { "s" },
+            start
+        );
+        self.print_first(first);
+    }
+
+    fn print_first(&self, first: Move) {
+        println!("First trade: {}", self.describe(first));
+        if matches!(first, Move::Random(_)) {
+            println!("Plan again from what it gives before the next trade");
+        }
+    }
+}
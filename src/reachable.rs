@@ -0,0 +1,48 @@
+use std::{cmp::Reverse, collections::HashMap};
+
+use crate::{compact::pareto_optimal, growth::shortest_depths, Candies, CandyWorks, Exploration};
+
+/// The best inventories the explored graph reaches, those no other
+/// reached state holds at least as many of every candy as, each with the
+/// fewest trades it takes. The fullest come first, and of those as full
+/// the ones reached sooner.
+pub fn best_reachable(candy_works: &CandyWorks) -> Vec<(Candies, usize)> {
+    let states = candy_works.combinations.states().collect::<Vec<_>>();
+    let shortest = shortest_depths(candy_works)
+        .into_iter()
+        .collect::<HashMap<_, _>>();
+    let mut best = pareto_optimal(&states)
+        .into_iter()
+        .zip(states.iter().zip(candy_works.depths()))
+        .filter(|(optimal, _)| *optimal)
+        .map(|(_, (candies, depth))| (*candies, shortest.get(candies).copied().unwrap_or(depth)))
+        .collect::<Vec<_>>();
+    best.sort_by_key(|(candies, depth)| (Reverse(candies.total()), *depth));
+    best
+}
+
+/// Prints up to `limit` of the best reachable inventories with the trades
+/// each takes.
+pub fn print(candy_works: &CandyWorks, limit: usize) {
+    let best = best_reachable(candy_works);
+    println!(
+        "{} inventor{} reachable that no other beats in every candy",
+        best.len(),
+        if best.len() == 1 { "y is" } else { "ies are" }
+    );
+    for (candies, depth) in best.iter().take(limit) {
+        println!(
+            "  #{} ({}) in {} trade{}",
+            candies.id_label(),
+            candies.display(true),
+            depth,
+            if *depth == 1 { "" } else { "s" }
+        );
+    }
+    if best.len() > limit {
+        println!("  ... and {} more", best.len() - limit);
+    }
+    if let Some(Exploration::Stopped(_)) = candy_works.exploration() {
+        println!("The exploration stopped early, so better inventories may be reachable");
+    }
+}
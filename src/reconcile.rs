@@ -0,0 +1,82 @@
+use std::{fs, path::Path};
+
+use crate::{
+    count::Count, error::CandyWorksError, kinds, names, prompt::Prompt, scenario::key_lines,
+    Candies,
+};
+
+/// Replaces the `inventory` line of a scenario file, keeping every other
+/// line (and comments) as written.
+fn replace_inventory(input: &str, inventory: &Candies) -> String {
+    let line = key_lines(input, "inventory").first().copied();
+    let mut out = String::new();
+    for (number, text) in input.lines().enumerate() {
+        if Some(number + 1) == line {
+            out.push_str(&format!("inventory = {}\n", inventory.compact()));
+        } else {
+            out.push_str(text);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Compares the tracked inventory with the game's and, if the player
+/// agrees, writes the game's into the scenario at `path`; fails only if
+/// the scenario cannot be read back or written.
+pub fn reconcile(
+    path: &Path,
+    tracked: Candies,
+    game: Candies,
+    rl: &mut impl Prompt,
+) -> Result<(), CandyWorksError> {
+    let names = names::active();
+    let width = names.plurals().iter().map(|n| n.len()).max().unwrap_or(0);
+    println!(
+        "{:<width$} {:>7} {:>7} {:>7}",
+        "",
+        "tracked",
+        "game",
+        "diff",
+        width = width
+    );
+    let mut discrepancies = 0;
+    for i in 0..kinds::count() {
+        let diff = game.get_by_index(i) - tracked.get_by_index(i);
+        let marker = if diff != Count::ZERO {
+            discrepancies += 1;
+            "  <- differs"
+        } else {
+            ""
+        };
+        println!(
+            "{:<width$} {:>7} {:>7} {:>+7}{}",
+            names.plural(i),
+            tracked.get_by_index(i),
+            game.get_by_index(i),
+            diff,
+            marker,
+            width = width
+        );
+    }
+    if discrepancies == 0 {
+        println!("Tracked inventory matches the game");
+        return Ok(());
+    }
+    println!("{} candy types differ", discrepancies);
+    let answer = rl
+        .ask(&format!(
+            "Adopt the game's numbers into {}? [y/N] ",
+            path.display()
+        ))
+        .unwrap_or_default();
+    if answer.trim().eq_ignore_ascii_case("y") {
+        let input = fs::read_to_string(path).map_err(|err| CandyWorksError::file(path, err))?;
+        fs::write(path, replace_inventory(&input, &game))
+            .map_err(|err| CandyWorksError::file(path, err))?;
+        println!("Updated the inventory in {}", path.display());
+    } else {
+        println!("Kept the tracked inventory");
+    }
+    Ok(())
+}
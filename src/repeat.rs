@@ -0,0 +1,118 @@
+use std::{collections::HashMap, sync::OnceLock};
+
+use crate::{count::Count, kinds, Candies, Trade};
+
+impl Trade {
+    /// The trade made `times` times over as one, as in `6e -> 2m` for
+    /// `3e -> 1m` twice; `None` if a count would overflow.
+    pub fn times(&self, times: u32) -> Option<Trade> {
+        let scale = |side: &Candies| {
+            let mut scaled = Candies::none();
+            for i in 0..kinds::count() {
+                let count = side.get_by_index(i).get().checked_mul(times as i32)?;
+                scaled.add_by_index(i, Count::new(count));
+            }
+            Some(scaled)
+        };
+        Some(Trade {
+            give: scale(&self.give)?,
+            receive: scale(&self.receive)?,
+        })
+    }
+}
+
+/// The multiples of a scenario's trades searched as single steps, so a run
+/// of the same trade made several times in a row is one step of the
+/// search and of the route found, and each multiple's trade and times for
+/// showing the step as the trades it stands for.
+#[derive(Default, PartialEq)]
+pub struct Repeats {
+    units: HashMap<Trade, (Trade, u32)>,
+}
+
+impl Repeats {
+    /// Each of `trades` made 2 up to `most` times over; a multiple that is
+    /// itself one of `trades` stays a trade of its own.
+    pub fn new(trades: &[Trade], most: u32) -> Repeats {
+        let mut units = HashMap::new();
+        for trade in trades {
+            for times in 2..=most {
+                let Some(multiple) = trade.times(times) else {
+                    break;
+                };
+                if !trades.contains(&multiple) {
+                    units.entry(multiple).or_insert((*trade, times));
+                }
+            }
+        }
+        Repeats { units }
+    }
+
+    /// The trade a step made of `trade` stands for and how many times, or
+    /// `trade` itself once if it is not a multiple.
+    pub fn unit(&self, trade: &Trade) -> (Trade, u32) {
+        self.units.get(trade).copied().unwrap_or((*trade, 1))
+    }
+
+    /// `trades` with the multiples of each added after them.
+    pub fn with_multiples(&self, trades: &[Trade]) -> Vec<Trade> {
+        let mut multiples = self
+            .units
+            .iter()
+            .filter(|(_, (unit, _))| trades.contains(unit))
+            .map(|(multiple, (unit, times))| (*unit, *times, *multiple))
+            .collect::<Vec<_>>();
+        multiples.sort_by_key(|(unit, times, _)| {
+            (trades.iter().position(|trade| trade == unit), *times)
+        });
+        let mut trades = trades.to_vec();
+        trades.extend(multiples.into_iter().map(|(_, _, multiple)| multiple));
+        trades
+    }
+}
+
+static ACTIVE: OnceLock<Repeats> = OnceLock::new();
+
+/// Sets the multiples searched and shown for the rest of the run,
+/// failing once other multiples are in use.
+pub fn configure(repeats: Repeats) -> Result<(), String> {
+    crate::configure_once(&ACTIVE, repeats, "the repeated trades are")
+}
+
+/// The multiples set by `configure`, or none.
+pub fn active() -> &'static Repeats {
+    ACTIVE.get_or_init(Repeats::default)
+}
+
+/// `route` with each multiple made as the trades it stands for.
+pub fn expand(route: &[Trade]) -> Vec<Trade> {
+    route
+        .iter()
+        .flat_map(|trade| {
+            let (unit, times) = active().unit(trade);
+            std::iter::repeat_n(unit, times as usize)
+        })
+        .collect()
+}
+
+/// Runs of the same trade in `route`, with how many times in a row it is
+/// made.
+pub fn runs(route: &[Trade]) -> Vec<(Trade, usize)> {
+    let mut runs: Vec<(Trade, usize)> = Vec::new();
+    for trade in route {
+        match runs.last_mut() {
+            Some((last, times)) if last == trade => *times += 1,
+            _ => runs.push((*trade, 1)),
+        }
+    }
+    runs
+}
+
+/// ` (x4)` for a trade made `times` times in a row, or nothing once.
+pub fn suffix(times: usize) -> String {
+    if times > 1 {
+        format!(" (x{})", times)
+    } else {
+        String::new()
+    }
+}
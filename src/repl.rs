@@ -0,0 +1,497 @@
+use std::{fs, path::Path};
+
+use crate::{
+    guard,
+    history::{Edit, History},
+    kind_caps::KindCaps,
+    objective::Objective,
+    preset::Preset,
+    prompt::Prompt,
+    route::Route,
+    Candies, CandyWorks, ParseCandiesError, Trade,
+};
+
+const HELP: &str = "\
+Commands:
+  set inventory CANDIES   start from these candies, e.g. `set inventory 3e 2w`
+  set cap N               hold at most N candies
+  add-trade TRADE         offer a trade, e.g. `add-trade 3e -> 1m`
+  remove-trade N          stop offering trade N of `show`
+  show                    list the inventory, cap and trades
+  explore                 explore now rather than at the next route
+  route [CANDIES]         the best route to these candies, or to the targets
+  stats                   statistics of the explored graph
+  make TRADE              note a trade made in the game, taking it out of the inventory
+  make route              make every step of the last route shown
+  undo                    undo the last change to the inventory, cap or trades
+  redo                    make the last change undone again
+  history                 list the changes made, oldest first
+  save FILE               save the changes made, to replay on the same problem
+  replay FILE             make the changes saved in a file
+  help                    this list
+  quit                    leave";
+
+/// Candies as a sentence, `nothing` for none.
+fn plain(candies: &Candies) -> String {
+    if *candies == Candies::none() {
+        return "nothing".to_string();
+    }
+    candies
+        .display(false)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A line typed at the prompt.
+enum Command {
+    SetInventory(Candies),
+    SetCap(u32),
+    AddTrade(Trade),
+    RemoveTrade(usize),
+    Show,
+    Explore,
+    Route(Option<Candies>),
+    Stats,
+    Make(Trade),
+    MakeRoute,
+    Undo,
+    Redo,
+    History,
+    Save(String),
+    Replay(String),
+    Help,
+    Quit,
+}
+
+fn parse_command(line: &str) -> Result<Command, String> {
+    let line = line.trim();
+    let (word, rest) = line.split_once(' ').unwrap_or((line, ""));
+    let rest = rest.trim();
+    let candies = |text: &str| text.parse::<Candies>().map_err(|err| err.to_string());
+    match (word, rest) {
+        ("set", rest) => match rest.split_once(' ').unwrap_or((rest, "")) {
+            ("inventory", candies_text) => candies(candies_text).map(Command::SetInventory),
+            ("cap", n) => n
+                .trim()
+                .parse()
+                .map(Command::SetCap)
+                .map_err(|_| format!("`{}` is not a cap, expected a whole number", n.trim())),
+            _ => Err("expected `set inventory CANDIES` or `set cap N`".to_string()),
+        },
+        ("add-trade", trade) => trade
+            .parse()
+            .map(Command::AddTrade)
+            .map_err(|err: ParseCandiesError| err.to_string()),
+        ("remove-trade", n) => match n.parse::<usize>() {
+            Ok(n) if n > 0 => Ok(Command::RemoveTrade(n)),
+            _ => Err(format!("`{}` is not a trade number of `show`", n)),
+        },
+        ("show", "") => Ok(Command::Show),
+        ("explore", "") => Ok(Command::Explore),
+        ("route", "") => Ok(Command::Route(None)),
+        ("route", target) => candies(target).map(|target| Command::Route(Some(target))),
+        ("stats", "") => Ok(Command::Stats),
+        ("make", "route") => Ok(Command::MakeRoute),
+        ("make", trade) => trade
+            .parse()
+            .map(Command::Make)
+            .map_err(|err: ParseCandiesError| err.to_string()),
+        ("undo", "") => Ok(Command::Undo),
+        ("redo", "") => Ok(Command::Redo),
+        ("history", "") => Ok(Command::History),
+        ("save", "") | ("replay", "") => Err(format!("expected `{} FILE`", word)),
+        ("save", path) => Ok(Command::Save(path.to_string())),
+        ("replay", path) => Ok(Command::Replay(path.to_string())),
+        ("help" | "?", "") => Ok(Command::Help),
+        ("quit" | "exit" | "q", "") => Ok(Command::Quit),
+        _ => Err(format!(
+            "unknown command `{}`, type `help` for the list",
+            line
+        )),
+    }
+}
+
+/// The problem being worked on at the prompt, with the graph explored for
+/// it. Adding a trade or changing the cap extends the graph in place;
+/// changing the inventory or removing a trade throws it away, and the next
+/// command needing one explores again. Every change is kept in a history
+/// to undo it, redo it or save it.
+pub struct Session {
+    pub inventory: Candies,
+    pub cap: u32,
+    /// The trades added besides the standard ones.
+    pub trades: Vec<Trade>,
+    /// The most of each kind a state may hold, if kinds are capped.
+    pub kind_caps: Option<KindCaps>,
+    /// Whether the standard trades are offered besides `trades`.
+    pub standard_trades: bool,
+    /// The preset whose standard trades those are.
+    pub preset: Preset,
+    /// Where `route` without candies plans to.
+    pub targets: Vec<Candies>,
+    pub objective: Objective,
+    /// Explore whatever size the cap makes the search space look.
+    pub force: bool,
+    explored: Option<CandyWorks>,
+    history: History,
+    /// The last route `route` printed, for `make route`.
+    last_route: Option<Route>,
+}
+
+impl Session {
+    pub fn new(inventory: Candies, cap: u32, trades: Vec<Trade>) -> Session {
+        Session {
+            inventory,
+            cap,
+            trades,
+            kind_caps: None,
+            standard_trades: true,
+            preset: Preset::builtin(),
+            targets: Vec::new(),
+            objective: Objective::default(),
+            force: false,
+            explored: None,
+            history: History::default(),
+            last_route: None,
+        }
+    }
+
+    /// The graph of the current problem, explored now unless it already
+    /// is, or `None` if the cap is refused as too large.
+    fn explored(&mut self) -> Option<&CandyWorks> {
+        if self.explored.is_none() {
+            if !self.force {
+                if let Err(err) = guard::check(self.cap.into()) {
+                    println!("{}", err);
+                    return None;
+                }
+            }
+            let mut builder = CandyWorks::builder(self.inventory)
+                .cap(self.cap)
+                .trades(self.trades.iter().copied())
+                .preset(self.preset.clone())
+                .standard_trades(self.standard_trades)
+                .objective(self.objective.clone());
+            if let Some(kind_caps) = self.kind_caps {
+                builder = builder.kind_caps(kind_caps);
+            }
+            let mut candy_works = match builder.build() {
+                Ok(candy_works) => candy_works,
+                Err(err) => {
+                    println!("{}", err);
+                    return None;
+                }
+            };
+            candy_works.explore();
+            println!(
+                "Explored {} combination{}",
+                candy_works.combinations.len(),
+                if candy_works.combinations.len() == 1 {
+                    ""
+                } else {
+                    "s"
+                }
+            );
+            self.explored = Some(candy_works);
+        }
+        self.explored.as_ref()
+    }
+
+    /// Brings the graph up to date with the cap and trades after `change`
+    /// made to it in place, as long as the cap is not refused, and says
+    /// how much more it holds.
+    fn update(&mut self, change: impl FnOnce(&mut CandyWorks)) {
+        if !self.force && guard::check(self.cap.into()).is_err() {
+            self.explored = None;
+            return;
+        }
+        let Some(candy_works) = self.explored.as_mut() else {
+            return;
+        };
+        let before = candy_works.combinations.len();
+        change(candy_works);
+        let after = candy_works.combinations.len();
+        if after >= before {
+            println!(
+                "Explored {} more combination{}, {} in all",
+                after - before,
+                if after - before == 1 { "" } else { "s" },
+                after
+            );
+        } else {
+            println!(
+                "Explored {} combination{}",
+                after,
+                if after == 1 { "" } else { "s" }
+            );
+        }
+    }
+
+    fn show(&self) {
+        println!("Inventory: {}", plain(&self.inventory));
+        println!("Cap: {}", self.cap);
+        if self.trades.is_empty() {
+            println!("No trades besides the standard ones");
+        }
+        for (n, trade) in self.trades.iter().enumerate() {
+            println!(
+                "  {}. {} -> {}",
+                n + 1,
+                plain(&trade.give),
+                plain(&trade.receive)
+            );
+        }
+        for target in &self.targets {
+            println!("Target: {}", plain(target));
+        }
+    }
+
+    fn route(&mut self, target: Candies) {
+        let Some(candy_works) = self.explored() else {
+            return;
+        };
+        let route = candy_works.find_optimal_route(target);
+        match &route {
+            Some(route) => print!(
+                "{}",
+                candy_works.format_route(route.start(), route.trades())
+            ),
+            None => println!("No route to {}", plain(&target)),
+        }
+        self.last_route = route;
+    }
+
+    /// Makes `edit` and keeps it in the history.
+    fn perform(&mut self, edit: Edit) {
+        self.make(edit);
+        self.history.record(edit);
+    }
+
+    fn make(&mut self, edit: Edit) {
+        match edit {
+            Edit::Inventory { after, .. } => {
+                self.explored = None;
+                self.inventory = after;
+            }
+            Edit::Cap { after, .. } => {
+                self.cap = after;
+                self.update(|candy_works| candy_works.set_cap(after));
+            }
+            Edit::AddTrade(trade) => {
+                self.trades.push(trade);
+                println!("Added trade {}", self.trades.len());
+                self.update(|candy_works| candy_works.add_trade(trade));
+            }
+            Edit::RemoveTrade { n, trade } => {
+                self.explored = None;
+                self.trades.remove(n - 1);
+                println!(
+                    "Removed {} -> {}",
+                    plain(&trade.give),
+                    plain(&trade.receive)
+                );
+            }
+            Edit::Make { trade, before } => {
+                self.explored = None;
+                self.inventory = before.trade(&trade).unwrap();
+                println!(
+                    "Made {} -> {}, leaving {}",
+                    plain(&trade.give),
+                    plain(&trade.receive),
+                    plain(&self.inventory)
+                );
+            }
+        }
+    }
+
+    /// Puts back what `edit` changed.
+    fn unmake(&mut self, edit: Edit) {
+        match edit {
+            Edit::Inventory { before, .. } | Edit::Make { before, .. } => {
+                self.explored = None;
+                self.inventory = before;
+            }
+            Edit::Cap { before, .. } => {
+                self.cap = before;
+                self.update(|candy_works| candy_works.set_cap(before));
+            }
+            Edit::AddTrade(_) => {
+                self.explored = None;
+                self.trades.pop();
+            }
+            Edit::RemoveTrade { n, trade } => {
+                self.explored = None;
+                self.trades.insert(n - 1, trade);
+            }
+        }
+    }
+
+    /// Makes every step of the last route printed, one edit each.
+    fn make_route(&mut self) {
+        let Some(route) = self.last_route.take() else {
+            println!("No route to make; plan one with `route`");
+            return;
+        };
+        if route.start() != self.inventory {
+            println!("The inventory changed since the route was planned; plan again with `route`");
+            return;
+        }
+        for trade in route.trades() {
+            self.perform(Edit::Make {
+                trade: *trade,
+                before: self.inventory,
+            });
+        }
+    }
+
+    /// Carries out the commands saved in the file at `path`, as if typed
+    /// at the prompt, stopping at the first that cannot be read.
+    pub fn replay(&mut self, path: &Path) {
+        let input = match fs::read_to_string(path) {
+            Ok(input) => input,
+            Err(err) => {
+                println!("Could not read {}: {}", path.display(), err);
+                return;
+            }
+        };
+        let mut replayed = 0;
+        for (number, line) in input.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match parse_command(line) {
+                Ok(command) => {
+                    println!("> {}", line);
+                    replayed += 1;
+                    if !self.apply(command) {
+                        break;
+                    }
+                }
+                Err(err) => {
+                    println!("{} line {}: {}", path.display(), number + 1, err);
+                    break;
+                }
+            }
+        }
+        println!(
+            "Replayed {} command{} from {}",
+            replayed,
+            if replayed == 1 { "" } else { "s" },
+            path.display()
+        );
+    }
+
+    /// Carries out `command`; false once it is time to leave.
+    fn apply(&mut self, command: Command) -> bool {
+        match command {
+            Command::SetInventory(inventory) => self.perform(Edit::Inventory {
+                before: self.inventory,
+                after: inventory,
+            }),
+            Command::SetCap(cap) => self.perform(Edit::Cap {
+                before: self.cap,
+                after: cap,
+            }),
+            Command::AddTrade(trade) => self.perform(Edit::AddTrade(trade)),
+            Command::RemoveTrade(n) if n > self.trades.len() => {
+                println!("There is no trade {}; `show` lists them", n);
+            }
+            Command::RemoveTrade(n) => self.perform(Edit::RemoveTrade {
+                n,
+                trade: self.trades[n - 1],
+            }),
+            Command::Show => self.show(),
+            Command::Explore => {
+                if self.explored.is_some() {
+                    println!("Already explored; nothing changed since");
+                }
+                self.explored();
+            }
+            Command::Route(Some(target)) => self.route(target),
+            Command::Route(None) if self.targets.is_empty() => {
+                println!("No targets to plan to; give one, e.g. `route 2c 1f`");
+            }
+            Command::Route(None) => {
+                for target in self.targets.clone() {
+                    self.route(target);
+                }
+            }
+            Command::Stats => {
+                if let Some(stats) = self.explored().and_then(CandyWorks::statistics) {
+                    println!("{}", stats);
+                }
+            }
+            Command::Make(trade) if self.inventory.trade(&trade).is_none() => println!(
+                "Cannot make {} -> {} with {}",
+                plain(&trade.give),
+                plain(&trade.receive),
+                plain(&self.inventory)
+            ),
+            Command::Make(trade) => self.perform(Edit::Make {
+                trade,
+                before: self.inventory,
+            }),
+            Command::MakeRoute => self.make_route(),
+            Command::Undo => match self.history.undo() {
+                Some(edit) => {
+                    println!("Undid `{}`", edit.command());
+                    self.unmake(edit);
+                }
+                None => println!("Nothing to undo"),
+            },
+            Command::Redo => match self.history.redo() {
+                Some(edit) => {
+                    println!("Redid `{}`", edit.command());
+                    self.make(edit);
+                }
+                None => println!("Nothing to redo"),
+            },
+            Command::History => {
+                if self.history.done().is_empty() {
+                    println!("No changes yet");
+                }
+                for (n, edit) in self.history.done().iter().enumerate() {
+                    println!("  {}. {}", n + 1, edit.command());
+                }
+            }
+            Command::Save(path) => match fs::write(&path, self.history.save()) {
+                Ok(()) => println!(
+                    "Saved {} change{} to {}",
+                    self.history.done().len(),
+                    if self.history.done().len() == 1 {
+                        ""
+                    } else {
+                        "s"
+                    },
+                    path
+                ),
+                Err(err) => println!("Could not save to {}: {}", path, err),
+            },
+            Command::Replay(path) => self.replay(Path::new(&path)),
+            Command::Help => println!("{}", HELP),
+            Command::Quit => return false,
+        }
+        true
+    }
+}
+
+/// Reads commands until `quit` or the end of input, exploring only when
+/// a command needs the graph and the problem changed since the last time.
+pub fn run(session: &mut Session, rl: &mut impl Prompt) {
+    println!("Type `help` for the commands");
+    while let Some(line) = rl.ask("> ") {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match parse_command(&line) {
+            Ok(command) => {
+                if !session.apply(command) {
+                    return;
+                }
+            }
+            Err(err) => println!("{}", err),
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use crate::{route::Route, Candies, CandyWorks};
+
+/// Plans again from the inventory before the failed step of `route`,
+/// counted from 1, without the trade that failed there. `candy_works`
+/// holds the problem, unexplored, and `route` has to be planned from its
+/// inventory. Returns the corrected route from the start: the steps
+/// already made followed by the new plan.
+pub fn replan(
+    candy_works: &CandyWorks,
+    route: &Route,
+    failed_step: usize,
+    reason: Option<&str>,
+    target: Candies,
+    reserved: Candies,
+) -> Result<Option<Route>, String> {
+    if failed_step == 0 || failed_step > route.len() {
+        return Err(format!(
+            "the route has {} steps, there is no step {}",
+            route.len(),
+            failed_step
+        ));
+    }
+    let route = route
+        .trades_for(candy_works.candies)
+        .map_err(|err| err.to_string())?;
+    let done = &route[..failed_step - 1];
+    let failed = route[failed_step - 1];
+    let current = candy_works.candies.trade_all(done).unwrap();
+    match reason {
+        Some(reason) => println!(
+            "Step {} (`{}`) failed: {}",
+            failed_step,
+            failed.to_string().trim(),
+            reason
+        ),
+        None => println!(
+            "Step {} (`{}`) failed",
+            failed_step,
+            failed.to_string().trim()
+        ),
+    }
+
+    let mut rest = candy_works.unexplored();
+    rest.candies = current;
+    rest.trades.retain(|trade| *trade != failed);
+    rest.explore();
+    let Some(plan) = rest.find_optimal_route(target) else {
+        println!("No route reaches the target without that trade");
+        return Ok(None);
+    };
+    println!("New plan from the inventory before step {}:", failed_step);
+    print!(
+        "{}",
+        candy_works.format_route(current.with(&reserved), plan.trades())
+    );
+    let mut corrected = done.to_vec();
+    corrected.extend(plan.trades());
+    Ok(Some(Route::new(candy_works.candies, corrected).unwrap()))
+}
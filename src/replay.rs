@@ -0,0 +1,167 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+};
+
+use crate::prompt::Prompt;
+
+const HEADER: &str = "candyworks-replay 1";
+
+/// A run is fully determined by its arguments, the files and environment
+/// variables it reads and the answers typed at its prompts: the solver
+/// uses no randomness. Recording captures those as they are used, one
+/// `kind value` line each, so a replay can serve them back without the
+/// original files or a terminal.
+enum Mode {
+    Record(Mutex<File>),
+    Replay {
+        files: HashMap<PathBuf, String>,
+        variables: HashMap<String, Option<String>>,
+        answers: Mutex<VecDeque<String>>,
+    },
+}
+
+static MODE: OnceLock<Mode> = OnceLock::new();
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+        .replace('\t', "\\t")
+}
+
+fn unescape(value: &str) -> String {
+    let mut out = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('t') => out.push('\t'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+fn append(line: String) {
+    if let Some(Mode::Record(file)) = MODE.get() {
+        // A recording that misses a line only fails to reproduce later.
+        let _ = writeln!(file.lock().unwrap(), "{}", line);
+    }
+}
+
+/// Starts recording the run into `path`, beginning with its arguments.
+pub fn record(path: &Path, args: &[String]) -> io::Result<()> {
+    let mut file = File::create(path)?;
+    writeln!(file, "{}", HEADER)?;
+    for arg in args {
+        writeln!(file, "arg {}", escape(arg))?;
+    }
+    let _ = MODE.set(Mode::Record(Mutex::new(file)));
+    Ok(())
+}
+
+/// Loads a recording to serve its files and answers from, returning the
+/// arguments of the recorded run.
+pub fn replay(path: &Path) -> Result<Vec<String>, String> {
+    let input = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let mut lines = input.lines();
+    if lines.next() != Some(HEADER) {
+        return Err("not a candyworks recording".to_string());
+    }
+    let mut args = Vec::new();
+    let mut files = HashMap::new();
+    let mut variables = HashMap::new();
+    let mut answers = VecDeque::new();
+    for (number, line) in lines.enumerate() {
+        let (kind, value) = line.split_once(' ').unwrap_or((line, ""));
+        match kind {
+            "arg" => args.push(unescape(value)),
+            "file" => {
+                let (path, content) = value.split_once('\t').ok_or_else(|| {
+                    format!("line {}: expected `file path<TAB>content`", number + 2)
+                })?;
+                files.insert(PathBuf::from(unescape(path)), unescape(content));
+            }
+            "env" => match value.split_once('\t') {
+                Some((name, content)) => {
+                    variables.insert(unescape(name), Some(unescape(content)));
+                }
+                None => {
+                    variables.insert(unescape(value), None);
+                }
+            },
+            "answer" => answers.push_back(unescape(value)),
+            kind => return Err(format!("line {}: unknown entry `{}`", number + 2, kind)),
+        }
+    }
+    let _ = MODE.set(Mode::Replay {
+        files,
+        variables,
+        answers: Mutex::new(answers),
+    });
+    Ok(args)
+}
+
+/// Reads an input file, from the recording when replaying one.
+pub fn read_to_string(path: &Path) -> io::Result<String> {
+    if let Some(Mode::Replay { files, .. }) = MODE.get() {
+        return files.get(path).cloned().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "file is missing from the recording",
+            )
+        });
+    }
+    let content = fs::read_to_string(path)?;
+    append(format!(
+        "file {}\t{}",
+        escape(&path.to_string_lossy()),
+        escape(&content)
+    ));
+    Ok(content)
+}
+
+/// Reads an environment variable, from the recording when replaying one.
+pub fn env_var(name: &str) -> Option<String> {
+    if let Some(Mode::Replay { variables, .. }) = MODE.get() {
+        return variables.get(name).cloned().flatten();
+    }
+    let value = std::env::var(name).ok();
+    match &value {
+        Some(value) => append(format!("env {}\t{}", escape(name), escape(value))),
+        None => append(format!("env {}", escape(name))),
+    }
+    value
+}
+
+/// Records the answers given to `inner`, or answers from the recording
+/// when replaying one.
+pub struct Replayable<P>(pub P);
+
+impl<P: Prompt> Prompt for Replayable<P> {
+    fn say(&mut self, message: &str) {
+        self.0.say(message);
+    }
+
+    fn ask(&mut self, prompt: &str) -> Option<String> {
+        if let Some(Mode::Replay { answers, .. }) = MODE.get() {
+            let answer = answers.lock().unwrap().pop_front()?;
+            println!("{}{}", prompt, answer);
+            return Some(answer);
+        }
+        let answer = self.0.ask(prompt)?;
+        append(format!("answer {}", escape(&answer)));
+        Some(answer)
+    }
+}
@@ -0,0 +1,406 @@
+use std::fmt::Write;
+
+use crate::{kinds, names, route::Route, Candies, CandyWorks, Trade};
+
+/// `text` as one Markdown table cell: a `|` would end the cell and a
+/// line break the row.
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|")
+        .replace("\r\n", "<br>")
+        .replace(['\r', '\n'], "<br>")
+}
+
+fn inventory_table(out: &mut String, candies: &Candies) {
+    out.push_str("| Candy | Count |\n|---|---:|\n");
+    for (name, count) in candies.rows() {
+        let _ = writeln!(out, "| {} | {} |", cell(name), count);
+    }
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | u32::from(b) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// A bar chart, as an SVG image, of how many states are first reached
+/// after each number of trades.
+fn depth_chart(depth_counts: &[usize]) -> String {
+    const BAR: usize = 32;
+    const HEIGHT: usize = 120;
+    let most = depth_counts.iter().copied().max().unwrap_or(0).max(1);
+    let width = BAR * depth_counts.len().max(1) + 16;
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+         font-family=\"sans-serif\" font-size=\"10\" text-anchor=\"middle\">",
+        width,
+        HEIGHT + 40
+    );
+    for (depth, &count) in depth_counts.iter().enumerate() {
+        let height = (count * HEIGHT).div_ceil(most);
+        let (x, y) = (8 + BAR * depth, 20 + HEIGHT - height);
+        let middle = x + BAR / 2;
+        let _ = write!(
+            svg,
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#e8a0bf\"/>\
+             <text x=\"{}\" y=\"{}\">{}</text><text x=\"{}\" y=\"{}\">{}</text>",
+            x + 2,
+            y,
+            BAR - 4,
+            height,
+            middle,
+            y - 4,
+            count,
+            middle,
+            HEIGHT + 34,
+            depth
+        );
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+/// The Markdown report. With `charts`, the statistics come with a chart of
+/// the states by trades, embedded as an image.
+pub fn markdown(
+    candy_works: &CandyWorks,
+    custom_trades: &[Trade],
+    targets: &[(Candies, Option<Route>)],
+    charts: bool,
+) -> String {
+    let mut out = String::new();
+    out.push_str("# CandyWorks report\n\n");
+
+    out.push_str("## Inventory\n\n");
+    inventory_table(&mut out, &candy_works.candies);
+    let _ = writeln!(out, "\nCandy cap: {}\n", candy_works.max_candies);
+
+    out.push_str("## Trades\n\n| # | Give | Receive | Kind |\n|---:|---|---|---|\n");
+    for (i, trade) in candy_works.trades.iter().enumerate() {
+        let kind = if custom_trades.contains(trade) {
+            "custom"
+        } else {
+            "standard"
+        };
+        let _ = writeln!(
+            out,
+            "| {} | {} | {} | {} |",
+            i + 1,
+            cell(trade.give.display(false).trim()),
+            cell(trade.receive.display(false).trim()),
+            kind
+        );
+    }
+
+    out.push_str("\n## Statistics\n\n");
+    match candy_works.statistics() {
+        Some(stats) => {
+            let _ = writeln!(out, "- Total combinations: {}", stats.combinations);
+            let _ = writeln!(out, "- Min candies: {}", stats.min_candies);
+            let _ = writeln!(out, "- Max candies: {}", stats.max_candies);
+            let _ = writeln!(out, "- Max trades: {}", stats.max_trades);
+            if let Some(exploration) = stats.exploration {
+                let _ = writeln!(out, "- Exploration: {}", exploration);
+            }
+            if charts {
+                let _ = writeln!(
+                    out,
+                    "\n![States by number of trades](data:image/svg+xml;base64,{})",
+                    base64(depth_chart(&stats.depth_counts).as_bytes())
+                );
+            }
+        }
+        None => out.push_str("No combinations found\n"),
+    }
+
+    for (n, (target, route)) in targets.iter().enumerate() {
+        let suffix = if targets.len() > 1 {
+            format!(" {}", n + 1)
+        } else {
+            String::new()
+        };
+        let _ = write!(out, "\n## Target{}\n\n", suffix);
+        inventory_table(&mut out, target);
+
+        let _ = write!(out, "\n## Route{}\n\n", suffix);
+        match route.as_ref().map(Route::trades) {
+            Some([]) => out.push_str("The target is already in the inventory.\n"),
+            Some(route) => {
+                out.push_str(
+                    "| Step | Trade | Inventory after | State ID |\n|---:|---|---|---:|\n",
+                );
+                let mut previous = candy_works.candies;
+                for (i, trade) in route.iter().enumerate() {
+                    previous = previous.trade(trade).unwrap();
+                    let _ = writeln!(
+                        out,
+                        "| {} | {} | {} | {} |",
+                        i + 1,
+                        cell(&trade.to_string()),
+                        cell(&previous.display(true)),
+                        previous.id_label()
+                    );
+                }
+            }
+            None => out.push_str("No route found.\n"),
+        }
+    }
+    out
+}
+
+fn js_candies(candies: &Candies) -> String {
+    let counts = (0..kinds::count())
+        .map(|i| candies.get_by_index(i).to_string())
+        .collect::<Vec<_>>();
+    format!("[{}]", counts.join(","))
+}
+
+const HTML_SCRIPT: &str = r##"
+const names = document.body.dataset.names.split(",");
+const show = (counts) => counts.map((c, i) => c + " " + names[i]).join(", ");
+for (const row of document.querySelectorAll(".route tr[data-after]")) {
+  const after = JSON.parse(row.dataset.after);
+  const inventory = row.closest("table").previousElementSibling;
+  row.addEventListener("mouseenter", () => { inventory.textContent = "After this step: " + show(after); });
+  row.addEventListener("mouseleave", () => { inventory.textContent = "Hover a step to see the inventory after it."; });
+}
+const filters = [...document.querySelectorAll("#filters input")];
+const body = document.querySelector("#states tbody");
+const summary = document.getElementById("summary");
+const LIMIT = 500;
+function render() {
+  const minimum = filters.map((input) => Number(input.value) || 0);
+  const matches = STATES.filter((s) => minimum.every((m, i) => s[i] >= m));
+  body.innerHTML = "";
+  for (const s of matches.slice(0, LIMIT)) {
+    const tr = document.createElement("tr");
+    for (const value of s) {
+      const td = document.createElement("td");
+      td.textContent = value;
+      tr.appendChild(td);
+    }
+    body.appendChild(tr);
+  }
+  summary.textContent = matches.length + " matching states" + (matches.length > LIMIT ? ", showing the first " + LIMIT : "");
+}
+filters.forEach((input) => input.addEventListener("input", render));
+render();
+"##;
+
+/// `text` with the characters HTML gives a meaning to written as
+/// entities, safe inside elements and quoted attributes alike.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// The HTML report; with `charts`, the statistics come with a chart of
+/// the states by trades.
+pub fn html(
+    candy_works: &CandyWorks,
+    custom_trades: &[Trade],
+    targets: &[(Candies, Option<Route>)],
+    charts: bool,
+) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>CandyWorks report</title>\n\
+         <style>body {{ font-family: sans-serif; margin: 2em; }} table {{ border-collapse: collapse; }} \
+         td, th {{ border: 1px solid #ccc; padding: 2px 8px; text-align: right; }} \
+         .route tr[data-after]:hover {{ background: #fec; }} .inventory {{ font-weight: bold; }}</style>\n\
+         </head>\n<body data-names=\"{}\">",
+        escape(&names::active().plurals().join(","))
+    );
+    out.push_str("<h1>CandyWorks report</h1>\n");
+
+    let _ = writeln!(
+        out,
+        "<h2>Inventory</h2>\n<p>{}</p>\n<p>Candy cap: {}</p>",
+        escape(&candy_works.candies.display(true)),
+        candy_works.max_candies
+    );
+
+    out.push_str(
+        "<h2>Trades</h2>\n<table>\n<tr><th>#</th><th>Give</th><th>Receive</th><th>Kind</th></tr>\n",
+    );
+    for (i, trade) in candy_works.trades.iter().enumerate() {
+        let kind = if custom_trades.contains(trade) {
+            "custom"
+        } else {
+            "standard"
+        };
+        let _ = writeln!(
+            out,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            i + 1,
+            escape(&trade.give.to_string()),
+            escape(&trade.receive.to_string()),
+            kind
+        );
+    }
+    out.push_str("</table>\n");
+
+    out.push_str("<h2>Statistics</h2>\n");
+    match candy_works.statistics() {
+        Some(stats) => {
+            let _ = writeln!(
+                out,
+                "<ul><li>Total combinations: {}</li><li>Min candies: {}</li>\
+                 <li>Max candies: {}</li><li>Max trades: {}</li>{}</ul>",
+                stats.combinations,
+                stats.min_candies,
+                stats.max_candies,
+                stats.max_trades,
+                stats
+                    .exploration
+                    .map_or(String::new(), |exploration| format!(
+                        "<li>Exploration: {}</li>",
+                        escape(&exploration.to_string())
+                    ))
+            );
+            if charts {
+                let _ = writeln!(out, "{}", depth_chart(&stats.depth_counts));
+            }
+        }
+        None => out.push_str("<p>No combinations found</p>\n"),
+    }
+
+    for (n, (target, route)) in targets.iter().enumerate() {
+        let suffix = if targets.len() > 1 {
+            format!(" {}", n + 1)
+        } else {
+            String::new()
+        };
+        let _ = writeln!(
+            out,
+            "<h2>Target{}</h2>\n<p>{}</p>",
+            suffix,
+            escape(&target.display(true))
+        );
+
+        let _ = writeln!(out, "<h2>Route{}</h2>", suffix);
+        match route.as_ref().map(Route::trades) {
+            Some([]) => out.push_str("<p>The target is already in the inventory.</p>\n"),
+            Some(route) => {
+                out.push_str(
+                    "<p class=\"inventory\">Hover a step to see the inventory after it.</p>\n",
+                );
+                out.push_str("<table class=\"route\">\n<tr><th>Step</th><th>Trade</th></tr>\n");
+                let mut previous = candy_works.candies;
+                for (i, trade) in route.iter().enumerate() {
+                    previous = previous.trade(trade).unwrap();
+                    let _ = writeln!(
+                        out,
+                        "<tr data-after=\"{}\"><td>{}</td><td>{}</td></tr>",
+                        js_candies(&previous),
+                        i + 1,
+                        escape(&trade.to_string())
+                    );
+                }
+                out.push_str("</table>\n");
+            }
+            None => out.push_str("<p>No route found.</p>\n"),
+        }
+    }
+
+    out.push_str("<h2>Reachable states</h2>\n<p id=\"filters\">At least: ");
+    for name in names::active().plurals() {
+        let _ = write!(
+            out,
+            "<label>{} <input type=\"number\" min=\"0\" size=\"3\"></label> ",
+            escape(name)
+        );
+    }
+    out.push_str("</p>\n<p id=\"summary\"></p>\n<table id=\"states\">\n<thead><tr>");
+    for name in names::active().plurals() {
+        let _ = write!(out, "<th>{}</th>", escape(name));
+    }
+    out.push_str("<th>trades</th><th>id</th></tr></thead>\n<tbody></tbody>\n</table>\n");
+
+    out.push_str("<script>\nconst STATES = [\n");
+    for (i, candies) in candy_works.combinations.states().enumerate() {
+        let counts = js_candies(&candies);
+        let _ = writeln!(
+            out,
+            "[{},{},\"{}\"],",
+            &counts[1..counts.len() - 1],
+            candy_works.len_from_combination(i) - 1,
+            candies.id_label()
+        );
+    }
+    out.push_str("];");
+    out.push_str(HTML_SCRIPT);
+    out.push_str("</script>\n</body>\n</html>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::candies;
+
+    #[test]
+    fn escapes_what_html_reads() {
+        assert_eq!(
+            escape(r#"<b class="x">Tom & Jerry's</b>"#),
+            "&lt;b class=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/b&gt;"
+        );
+        assert_eq!(escape("3 eggs"), "3 eggs");
+    }
+
+    #[test]
+    fn keeps_markdown_cells_whole() {
+        assert_eq!(cell("a | b\nc\r\nd"), "a \\| b<br>c<br>d");
+        assert_eq!(cell("3 eggs"), "3 eggs");
+    }
+
+    #[test]
+    fn encodes_images_as_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+    }
+
+    #[test]
+    fn charts_a_bar_per_number_of_trades() {
+        let chart = depth_chart(&[1, 4, 2]);
+        assert!(chart.starts_with("<svg "));
+        assert_eq!(chart.matches("<rect ").count(), 3);
+        assert!(chart.contains("height=\"120\" fill"));
+        let mut candy_works = CandyWorks::new(candies("3e"), 20, Vec::new());
+        candy_works.explore();
+        let plain = markdown(&candy_works, &[], &[], false);
+        let charted = markdown(&candy_works, &[], &[], true);
+        assert!(!plain.contains("data:image/svg+xml;base64,"));
+        assert!(charted.contains("](data:image/svg+xml;base64,"));
+        assert!(html(&candy_works, &[], &[], true).contains("<svg "));
+    }
+}
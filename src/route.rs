@@ -0,0 +1,138 @@
+use std::fmt;
+
+use crate::{Candies, Exploration, Trade};
+
+/// Trades planned from a known inventory. A route can only be made by
+/// checking that every trade can be made in turn from `start`, and it only
+/// hands its trades out for making against that same inventory, so a route
+/// planned before the inventory changed is refused rather than applied to
+/// candies it was never meant for.
+#[derive(Debug, Clone, Eq)]
+pub struct Route {
+    start: Candies,
+    trades: Vec<Trade>,
+    /// How far the exploration the route was found in got.
+    exploration: Option<Exploration>,
+}
+
+// Routes making the same trades from the same inventory are the same
+// route, whichever exploration found them.
+impl PartialEq for Route {
+    fn eq(&self, other: &Route) -> bool {
+        self.start == other.start && self.trades == other.trades
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RouteError {
+    /// The inventory is no longer the one the route was planned from.
+    Stale { planned: Candies, current: Candies },
+    /// Step `step`, counting from 1, cannot be made with `candies`.
+    Inapplicable { step: usize, candies: Candies },
+}
+
+impl fmt::Display for RouteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RouteError::Stale { planned, current } => write!(
+                f,
+                "the route was planned from ({}) but the inventory is ({})",
+                plain(planned),
+                plain(current)
+            ),
+            RouteError::Inapplicable { step, candies } => {
+                write!(f, "step {} cannot be made with ({})", step, plain(candies))
+            }
+        }
+    }
+}
+
+impl std::error::Error for RouteError {}
+
+/// Candies as a sentence, without the padding that lines them up in routes.
+fn plain(candies: &Candies) -> String {
+    candies
+        .display(false)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl Route {
+    /// The route making `trades` from `start`, if each of them can be made
+    /// after the ones before.
+    pub fn new(start: Candies, trades: Vec<Trade>) -> Result<Route, RouteError> {
+        let mut candies = start;
+        for (n, trade) in trades.iter().enumerate() {
+            candies = candies.trade(trade).ok_or(RouteError::Inapplicable {
+                step: n + 1,
+                candies,
+            })?;
+        }
+        Ok(Route {
+            start,
+            trades,
+            exploration: None,
+        })
+    }
+
+    /// The route of no trades, for a target already in hand.
+    pub fn empty(start: Candies) -> Route {
+        Route {
+            start,
+            trades: Vec::new(),
+            exploration: None,
+        }
+    }
+
+    /// The same route, found in an exploration that got as far as
+    /// `exploration`.
+    pub fn found_in(self, exploration: Option<Exploration>) -> Route {
+        Route {
+            exploration,
+            ..self
+        }
+    }
+
+    /// How far the exploration the route was found in got: a route found
+    /// by one that stopped early may not be the best. `None` for routes
+    /// read from files or not found by exploring.
+    pub fn exploration(&self) -> Option<Exploration> {
+        self.exploration
+    }
+
+    /// The inventory the route was planned from.
+    pub fn start(&self) -> Candies {
+        self.start
+    }
+
+    /// The inventory once every trade is made.
+    pub fn end(&self) -> Candies {
+        self.start.trade_all(&self.trades).unwrap()
+    }
+
+    /// The trades, to read; `trades_for` hands them out for making.
+    pub fn trades(&self) -> &[Trade] {
+        &self.trades
+    }
+
+    pub fn len(&self) -> usize {
+        self.trades.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.trades.is_empty()
+    }
+
+    /// The trades to make from `inventory`, which has to be the inventory
+    /// the route was planned from.
+    pub fn trades_for(&self, inventory: Candies) -> Result<&[Trade], RouteError> {
+        if inventory != self.start {
+            return Err(RouteError::Stale {
+                planned: self.start,
+                current: inventory,
+            });
+        }
+        Ok(&self.trades)
+    }
+}
@@ -0,0 +1,67 @@
+use std::{fs, path::Path};
+
+use crate::{notes::Notes, repeat, Candies, Trade};
+
+/// Reads a route written by `save`: one trade per line in the compact form,
+/// with `#` comments, such as the trades' notes, and blank lines ignored.
+pub fn load(path: &Path) -> Result<Vec<Trade>, String> {
+    let input = crate::replay::read_to_string(path).map_err(|err| err.to_string())?;
+    parse(&input)
+}
+
+pub fn parse(input: &str) -> Result<Vec<Trade>, String> {
+    Ok(parse_noted(input)?
+        .into_iter()
+        .map(|(trade, _)| trade)
+        .collect())
+}
+
+/// Like `parse`, keeping the comment after each trade, which `save` writes
+/// the trade's note in.
+pub fn parse_noted(input: &str) -> Result<Vec<(Trade, Option<String>)>, String> {
+    let mut route = Vec::new();
+    for (number, line) in input.lines().enumerate() {
+        let (line, comment) = line.split_once('#').unwrap_or((line, ""));
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let trade = line
+            .parse()
+            .map_err(|err| format!("line {}: {}", number + 1, err))?;
+        let comment = Some(comment.trim().to_string()).filter(|comment| !comment.is_empty());
+        route.push((trade, comment));
+    }
+    Ok(route)
+}
+
+/// Writes `route` to `path`, each trade with its note in `notes`.
+pub fn save(path: &Path, route: &[Trade], notes: &Notes) -> std::io::Result<()> {
+    let noted = repeat::expand(route)
+        .iter()
+        .map(|trade| (*trade, notes.get(trade).map(str::to_string)))
+        .collect::<Vec<_>>();
+    fs::write(path, format(&noted))
+}
+
+pub fn format(route: &[(Trade, Option<String>)]) -> String {
+    let mut out = String::new();
+    for (trade, note) in route {
+        out.push_str(&format!(
+            "{} -> {}{}\n",
+            trade.give.compact(),
+            trade.receive.compact(),
+            note.as_ref()
+                .map_or(String::new(), |note| format!("  # {}", note))
+        ));
+    }
+    out
+}
+
+/// The previous route as an incumbent for `target`, if it can still be
+/// executed from `start` and still reaches the target.
+pub fn incumbent(start: &Candies, route: &[Trade], target: &Candies) -> Option<Candies> {
+    start
+        .trade_all(route)
+        .filter(|candies| candies.contains(target))
+}
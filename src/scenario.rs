@@ -0,0 +1,922 @@
+use std::{fmt, path::Path};
+
+use crate::{
+    charges::Tax,
+    count::{Count, Counts, Integer, Overflow, Width},
+    kind_caps::KindCaps,
+    kinds,
+    names::CandyNames,
+    preset::Preset,
+    random::RandomTrade,
+    target_spec::TargetSpec,
+    template::{self, Variables},
+    Candies, Trade,
+};
+
+pub const VERSION: u32 = 1;
+
+/// The scenario run by `--demo`: a few vendor trades on top of the
+/// standard ones and two targets, solved in well under a second.
+pub const DEMO: &str = "\
+version = 1
+cap = 20
+inventory = 6e 4w 2c 1f
+trade = 2w -> 1m
+trade = 1c 1f -> 3e
+trade = 4e -> 1f 1c
+target = 2m
+target = 1m 2f
+";
+
+pub struct Scenario {
+    pub names: CandyNames,
+    pub inventory: Candies,
+    /// Part of the inventory kept out of planning.
+    pub reserved: Candies,
+    pub cap: u32,
+    /// The most of each kind a state may hold besides the cap, as in
+    /// `kind_cap = 10` or `kind_cap = 10e 5m`.
+    pub kind_caps: Option<KindCaps>,
+    pub trades: Vec<Trade>,
+    /// The vendor location each of `trades` is tagged with; untagged trades
+    /// can be made anywhere.
+    pub locations: Vec<Option<String>>,
+    /// How many more candies than written each of `trades` may receive,
+    /// for trades whose receive is only known within a range such as
+    /// `3e -> 1..3m`; `trades` holds the least.
+    pub uncertain: Vec<Candies>,
+    /// The lot size each of `trades` is made in, as in `1e -> 1m; lot 2`;
+    /// `trades` holds the whole lot, so searching can only make it whole.
+    pub lots: Vec<u32>,
+    /// What a vendor takes for every trade made with it, as in `fee = 1c`;
+    /// the standard trades are made without a vendor and cost nothing.
+    pub fee: Candies,
+    /// Fees set on one of `trades`, as in `3e -> 1m; fee none`, in place
+    /// of `fee`. `trades` gives the fee on top of the lot.
+    pub fees: Vec<Option<Candies>>,
+    /// The share of the give side a vendor adds for every trade made with
+    /// it, as in `tax = 10% ceil`; like `fee`, the standard trades are free.
+    pub tax: Tax,
+    /// Taxes set on one of `trades`, in place of `tax`.
+    pub taxes: Vec<Option<Tax>>,
+    /// The tax owed on each of `trades`, which `trades` gives on top of the
+    /// lot and before the fee.
+    pub taxed: Vec<Candies>,
+    /// Free-text notes on `trades`, as in `3e -> 1m; note after quest 3`,
+    /// shown with the trade in routes and exports.
+    pub notes: Vec<Option<String>>,
+    /// How many times each of `trades` can be made, as in `3e -> 1m; uses
+    /// 2`; `None` for as often as wanted.
+    pub uses: Vec<Option<u32>>,
+    /// What each of `trades` costs besides its candies, as in `3e -> 1m;
+    /// cost 5`; `None` for nothing.
+    pub costs: Vec<Option<f64>>,
+    /// Trades with a random reward, left to the `chance` command since
+    /// the other planners only make trades whose reward is known.
+    pub random: Vec<RandomTrade>,
+    /// Travel times between locations, in the order written.
+    pub travel: Vec<(String, String, f64)>,
+    /// Where the player stands before the first trade.
+    pub start: Option<String>,
+    pub targets: Vec<Candies>,
+    /// Targets asking for more than holding at least some candies, as in
+    /// `target = =2m 5*`, kept apart from `targets` and planned to after
+    /// them.
+    pub target_specs: Vec<TargetSpec>,
+    /// What to do with counts too large for an `i32`, as in
+    /// `overflow = saturate`; saturating also lowers the cap until no trade
+    /// can take a count past what an `i32` holds.
+    pub overflow: Overflow,
+    /// Whether its problems offer the standard trades besides `trades`,
+    /// as they do unless left out for trade lists holding every trade on
+    /// offer.
+    pub standard_trades: bool,
+    /// The preset of the game the scenario is played in, whose standard
+    /// trades and specials those are.
+    pub preset: Preset,
+}
+
+#[derive(Debug)]
+pub struct ScenarioError {
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for ScenarioError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}", line, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Candies written in a scenario, kept in an `i32` as `overflow` allows;
+/// `invalid` explains candies that cannot be read at all.
+fn parse_candies(
+    value: &str,
+    names: &CandyNames,
+    overflow: Overflow,
+    invalid: impl FnOnce(String) -> String,
+) -> Result<Candies, String> {
+    Counts::<i64>::parse_with(value, names)
+        .map_err(|err| invalid(err.to_string()))?
+        .narrow::<i32>(overflow)
+        .map(Candies::from)
+        .ok_or_else(|| TOO_LARGE.to_string())
+}
+
+const TOO_LARGE: &str =
+    "a count does not fit in an i32; use `count = i64` or `overflow = saturate`";
+
+pub fn key_lines(input: &str, key: &str) -> Vec<usize> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            let line = line.split('#').next().unwrap();
+            line.split_once('=').is_some_and(|(k, _)| k.trim() == key)
+        })
+        .map(|(number, _)| number + 1)
+        .collect()
+}
+
+impl Scenario {
+    pub fn parse(input: &str) -> Result<Scenario, ScenarioError> {
+        Scenario::parse_with(input, template::active(), &Preset::builtin())
+    }
+
+    /// Parses a scenario of a game played with `preset`, whose cap the
+    /// scenario has unless it sets one, filling its `${NAME}` variables
+    /// from `variables`.
+    pub fn parse_with(
+        input: &str,
+        variables: &Variables,
+        preset: &Preset,
+    ) -> Result<Scenario, ScenarioError> {
+        let mut version = None;
+        let mut names = CandyNames::builtin();
+        let mut inventory = None;
+        let mut reserved = None;
+        let mut cap = None;
+        let mut kind_caps = None;
+        let mut trades = Vec::new();
+        let mut locations = Vec::new();
+        let mut uncertain = Vec::new();
+        let mut lots = Vec::new();
+        let mut fee = None;
+        let mut fees = Vec::new();
+        let mut tax = None;
+        let mut taxes = Vec::new();
+        let mut notes = Vec::new();
+        let mut uses = Vec::new();
+        let mut costs = Vec::new();
+        let mut random = Vec::new();
+        let mut travel = Vec::new();
+        let mut start = None;
+        let mut targets = Vec::new();
+        let mut target_specs = Vec::new();
+        let mut overflow = None;
+        let mut trade_lines = Vec::new();
+        for (number, line) in input.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let error = |message: &str| ScenarioError {
+                line: Some(number + 1),
+                message: message.to_string(),
+            };
+            let line = variables.expand(line).map_err(|message| error(&message))?;
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(error("expected `key = value`"));
+            };
+            let value = value.trim();
+            match key.trim() {
+                "version" => {
+                    if version.is_some()
+                        || inventory.is_some()
+                        || cap.is_some()
+                        || !trades.is_empty()
+                        || !targets.is_empty()
+                    {
+                        return Err(error("version must come first and only once"));
+                    }
+                    let number = value
+                        .parse::<u32>()
+                        .map_err(|_| error("version must be a number"))?;
+                    if number > VERSION {
+                        return Err(error(&format!(
+                            "scenario version {} is newer than this candyworks supports ({})",
+                            number, VERSION
+                        )));
+                    }
+                    if number == 0 {
+                        return Err(error("unknown scenario version 0"));
+                    }
+                    version = Some(number);
+                }
+                "count" | "overflow" => {
+                    if inventory.is_some()
+                        || reserved.is_some()
+                        || cap.is_some()
+                        || fee.is_some()
+                        || !trades.is_empty()
+                        || !targets.is_empty()
+                    {
+                        return Err(error(&format!(
+                            "{} must come before the candies and the cap",
+                            key.trim()
+                        )));
+                    }
+                    if key.trim() == "overflow" {
+                        if overflow.is_some() {
+                            return Err(error("overflow is defined twice"));
+                        }
+                        overflow = Some(Overflow::parse(value).map_err(|err| error(&err))?);
+                    } else if Width::parse(value).map_err(|err| error(&err))? == Width::I64 {
+                        return Err(error(
+                            "`count = i64` scenarios can only be planned, checked, formatted and routed with `route --direct`",
+                        ));
+                    }
+                }
+                "name" => {
+                    let (candy, display) = value
+                        .split_once(':')
+                        .ok_or_else(|| error("expected `name = f: Fish / Fishes`"))?;
+                    let candy = candy.trim();
+                    let index = kinds::active()
+                        .letters()
+                        .position(|l| candy.len() == 1 && candy.starts_with(l))
+                        .or_else(|| CandyNames::builtin().find(candy))
+                        .ok_or_else(|| error(&format!("unknown candy `{}`", candy)))?;
+                    let (singular, plural) = match display.split_once('/') {
+                        Some((singular, plural)) => (singular.trim(), Some(plural.trim())),
+                        None => (display.trim(), None),
+                    };
+                    if singular.is_empty() || plural == Some("") {
+                        return Err(error("candy names cannot be empty"));
+                    }
+                    names.rename(index, singular, plural);
+                }
+                "alias" => {
+                    let (candy, aliases) = value
+                        .split_once(':')
+                        .ok_or_else(|| error("expected `alias = f: trout, tuna`"))?;
+                    let candy = candy.trim();
+                    let index = kinds::active()
+                        .letters()
+                        .position(|l| candy.len() == 1 && candy.starts_with(l))
+                        .or_else(|| names.find(candy))
+                        .ok_or_else(|| error(&format!("unknown candy `{}`", candy)))?;
+                    for alias in aliases.split(',') {
+                        let alias = alias.trim();
+                        if alias.is_empty() {
+                            return Err(error("aliases cannot be empty"));
+                        }
+                        names.add_alias(index, alias);
+                    }
+                }
+                "inventory" => {
+                    if inventory.is_some() {
+                        return Err(error("inventory is defined twice"));
+                    }
+                    let parsed =
+                        parse_candies(value, &names, overflow.unwrap_or_default(), |err| {
+                            format!("invalid candies: {}, expected e.g. `3e 2w`", err)
+                        })
+                        .map_err(|err| error(&err))?;
+                    if parsed.counts().total(Overflow::Error).is_none() {
+                        return Err(error(
+                            "the inventory holds more candies than an i32 counts; use `count = i64`",
+                        ));
+                    }
+                    inventory = Some(parsed);
+                }
+                "reserve" => {
+                    if reserved.is_some() {
+                        return Err(error("reserve is defined twice"));
+                    }
+                    reserved = Some(
+                        parse_candies(value, &names, overflow.unwrap_or_default(), |err| {
+                            format!("invalid candies: {}, expected e.g. `5f`", err)
+                        })
+                        .map_err(|err| error(&err))?,
+                    );
+                }
+                "cap" => {
+                    if cap.is_some() {
+                        return Err(error("cap is defined twice"));
+                    }
+                    let number = value
+                        .parse::<u64>()
+                        .map_err(|_| error("cap must be a non-negative number"))?;
+                    let fits = i64::try_from(number).ok().and_then(i32::from_i64);
+                    cap = Some(
+                        overflow
+                            .unwrap_or_default()
+                            .apply(fits)
+                            .ok_or_else(|| error(TOO_LARGE))? as u32,
+                    );
+                }
+                "kind_cap" => {
+                    if kind_caps.is_some() {
+                        return Err(error("kind_cap is defined twice"));
+                    }
+                    let caps = match value.parse::<Count>() {
+                        Ok(most) => KindCaps::all(most),
+                        Err(_) => {
+                            parse_candies(value, &names, overflow.unwrap_or_default(), |err| {
+                                format!("invalid candies: {}, expected e.g. `10` or `10e 5m`", err)
+                            })
+                            .and_then(|listed| KindCaps::of(&listed))
+                        }
+                    };
+                    kind_caps = Some(caps.map_err(|err| error(&err))?);
+                }
+                "trade" => {
+                    let (trade, location) = match value.split_once('@') {
+                        Some((trade, location)) => (trade, Some(location.trim())),
+                        None => (value, None),
+                    };
+                    if location == Some("") {
+                        return Err(error("expected a location after `@`"));
+                    }
+                    let explain =
+                        |err: String| format!("invalid trade: {}, expected e.g. `3e -> 1m`", err);
+                    let invalid = |err: String| error(&explain(err));
+                    let mut options = trade.split(';');
+                    let trade = options.next().unwrap();
+                    let mut lot = None;
+                    let mut own_fee = None;
+                    let mut own_tax = None;
+                    let mut note = None;
+                    let mut own_uses = None;
+                    let mut cost = None;
+                    for option in options {
+                        let option = option.trim();
+                        let (key, argument) = option
+                            .split_once(' ')
+                            .map_or((option, ""), |(key, argument)| (key, argument.trim()));
+                        match key {
+                            "lot" => {
+                                if lot.is_some() {
+                                    return Err(error("lot size is given twice"));
+                                }
+                                lot = Some(
+                                    argument
+                                        .parse::<u32>()
+                                        .ok()
+                                        .filter(|&size| size > 0)
+                                        .ok_or_else(|| {
+                                            error("lot size must be a positive number")
+                                        })?,
+                                );
+                            }
+                            "fee" => {
+                                if own_fee.is_some() {
+                                    return Err(error("fee is given twice"));
+                                }
+                                let explain = |err| {
+                                    format!("invalid fee: {}, expected e.g. `fee 1c`", err)
+                                };
+                                let overflow = overflow.unwrap_or_default();
+                                own_fee = Some(
+                                    parse_candies(argument, &names, overflow, explain)
+                                        .map_err(|err| error(&err))?,
+                                );
+                            }
+                            "tax" => {
+                                if own_tax.is_some() {
+                                    return Err(error("tax is given twice"));
+                                }
+                                own_tax = Some(Tax::parse(argument).map_err(|err| error(&err))?);
+                            }
+                            "note" => {
+                                if note.is_some() {
+                                    return Err(error("note is given twice"));
+                                }
+                                if argument.is_empty() {
+                                    return Err(error("expected text after `note`"));
+                                }
+                                note = Some(argument.to_string());
+                            }
+                            "uses" => {
+                                if own_uses.is_some() {
+                                    return Err(error("uses are given twice"));
+                                }
+                                own_uses = Some(
+                                    argument
+                                        .parse::<u32>()
+                                        .ok()
+                                        .filter(|&uses| uses > 0)
+                                        .ok_or_else(|| {
+                                            error("uses must be a positive number")
+                                        })?,
+                                );
+                            }
+                            "cost" => {
+                                if cost.is_some() {
+                                    return Err(error("cost is given twice"));
+                                }
+                                cost = Some(
+                                    argument
+                                        .parse::<f64>()
+                                        .ok()
+                                        .filter(|cost| cost.is_finite() && *cost >= 0.0)
+                                        .ok_or_else(|| {
+                                            error("cost must be a non-negative number")
+                                        })?,
+                                );
+                            }
+                            _ => {
+                                return Err(error(&format!(
+                                    "unknown trade option `{}`, expected e.g. `lot 2`, `fee 1c`, `tax 10% ceil`, `uses 2`, `cost 5` or `note after quest 3`",
+                                    option
+                                )))
+                            }
+                        }
+                    }
+                    let lot = lot.unwrap_or(1);
+                    let (give, receive) = trade.split_once("->").ok_or_else(|| {
+                        invalid("missing `->` between give and receive".to_string())
+                    })?;
+                    if give.contains("..") {
+                        return Err(invalid("only the receive side can be a range".to_string()));
+                    }
+                    let (least, most) = split_range(receive).map_err(invalid)?;
+                    let overflow = overflow.unwrap_or_default();
+                    let side = |side: &str| {
+                        parse_candies(side, &names, overflow, explain).map_err(|err| error(&err))
+                    };
+                    let parsed = Trade {
+                        give: side(give)?,
+                        receive: side(&least)?,
+                    };
+                    let most = side(&most)?;
+                    let times = |candies: Candies| {
+                        overflow
+                            .apply(i32::from_i64(lot.into()))
+                            .and_then(|lot| candies.counts().times(lot, overflow))
+                            .map(Candies::from)
+                            .ok_or_else(|| error(TOO_LARGE))
+                    };
+                    uncertain.push(times(most.without(&parsed.receive).unwrap())?);
+                    trades.push(Trade {
+                        give: times(parsed.give)?,
+                        receive: times(parsed.receive)?,
+                    });
+                    trade_lines.push(number + 1);
+                    lots.push(lot);
+                    fees.push(own_fee);
+                    taxes.push(own_tax);
+                    notes.push(note);
+                    uses.push(own_uses);
+                    costs.push(cost);
+                    locations.push(location.map(str::to_string));
+                }
+                "fee" => {
+                    if fee.is_some() {
+                        return Err(error("fee is defined twice"));
+                    }
+                    fee = Some(
+                        parse_candies(value, &names, overflow.unwrap_or_default(), |err| {
+                            format!("invalid candies: {}, expected e.g. `1c`", err)
+                        })
+                        .map_err(|err| error(&err))?,
+                    );
+                }
+                "tax" => {
+                    if tax.is_some() {
+                        return Err(error("tax is defined twice"));
+                    }
+                    tax = Some(Tax::parse(value).map_err(|err| error(&err))?);
+                }
+                "random" => {
+                    let expected = "expected `random = 3e -> 1m 40% | 1c 60%`";
+                    let (give, outcomes) = value.split_once("->").ok_or_else(|| error(expected))?;
+                    let candies = |side: &str| {
+                        parse_candies(side, &names, overflow.unwrap_or_default(), |err| {
+                            format!("invalid candies: {}, {}", err, expected)
+                        })
+                        .map_err(|err| error(&err))
+                    };
+                    let give = candies(give)?;
+                    let mut parsed = Vec::new();
+                    for outcome in outcomes.split('|') {
+                        let (receive, percent) = outcome
+                            .trim()
+                            .rsplit_once(' ')
+                            .ok_or_else(|| error(expected))?;
+                        let percent = percent
+                            .strip_suffix('%')
+                            .and_then(|percent| percent.parse::<f64>().ok())
+                            .filter(|percent| *percent > 0.0 && *percent <= 100.0)
+                            .ok_or_else(|| {
+                                error(&format!(
+                                    "invalid chance `{}`, expected a percentage such as `40%`",
+                                    percent
+                                ))
+                            })?;
+                        parsed.push((candies(receive)?, percent));
+                    }
+                    let total = parsed.iter().map(|(_, percent)| percent).sum::<f64>();
+                    if (total - 100.0).abs() > 1e-6 {
+                        return Err(error(&format!(
+                            "the chances add up to {}%, not 100%",
+                            total
+                        )));
+                    }
+                    random.push(RandomTrade {
+                        give,
+                        outcomes: parsed,
+                    });
+                }
+                "travel" => {
+                    let expected = "expected `travel = market -> docks: 30`";
+                    let (path, time) = value.split_once(':').ok_or_else(|| error(expected))?;
+                    let (from, to) = path.split_once("->").ok_or_else(|| error(expected))?;
+                    let (from, to) = (from.trim(), to.trim());
+                    if from.is_empty() || to.is_empty() {
+                        return Err(error(expected));
+                    }
+                    let time = time
+                        .trim()
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|time| *time >= 0.0)
+                        .ok_or_else(|| error("travel time must be a non-negative number"))?;
+                    travel.push((from.to_string(), to.to_string(), time));
+                }
+                "start" => {
+                    if start.is_some() {
+                        return Err(error("start is defined twice"));
+                    }
+                    if value.is_empty() {
+                        return Err(error("expected a location"));
+                    }
+                    start = Some((value.to_string(), number + 1));
+                }
+                "target" if value.contains(['=', '*']) => target_specs.push(
+                    TargetSpec::parse_with(value, &names)
+                        .map_err(|err| error(&format!("invalid target: {}", err)))?,
+                ),
+                "target" => targets.push(
+                    parse_candies(value, &names, overflow.unwrap_or_default(), |err| {
+                        format!("invalid candies: {}, expected e.g. `1m 2c`", err)
+                    })
+                    .map_err(|err| error(&err))?,
+                ),
+                key => return Err(error(&format!("unknown key `{}`", key))),
+            }
+        }
+        if let Some((location, line)) = &start {
+            let known = locations.iter().flatten().any(|l| l == location)
+                || travel
+                    .iter()
+                    .any(|(from, to, _)| from == location || to == location);
+            if !known {
+                return Err(ScenarioError {
+                    line: Some(*line),
+                    message: format!("unknown location `{}`", location),
+                });
+            }
+        }
+        let fee = fee.unwrap_or_else(Candies::none);
+        let tax = tax.unwrap_or_else(Tax::none);
+        let overflow = overflow.unwrap_or_default();
+        let mut cap = cap.unwrap_or_else(|| preset.default_cap());
+        let too_large = |line: usize| ScenarioError {
+            line: Some(line),
+            message: TOO_LARGE.to_string(),
+        };
+        let mut taxed = Vec::new();
+        for (n, trade) in trades.iter_mut().enumerate() {
+            let owed = taxes[n].unwrap_or(tax).on(&trade.give);
+            trade.give = trade
+                .give
+                .counts()
+                .with(&owed.counts(), overflow)
+                .and_then(|give| give.with(&fees[n].unwrap_or(fee).counts(), overflow))
+                .map(Candies::from)
+                .ok_or_else(|| too_large(trade_lines[n]))?;
+            taxed.push(owed);
+            // States stay within the cap, so a trade can only take a count
+            // past what an i32 holds by receiving more than is left above it.
+            let room = trade
+                .receive
+                .counts()
+                .total(Overflow::Error)
+                .map(|most| i32::MAX - most);
+            let message = match room {
+                Some(room) if cap as i32 <= room => continue,
+                Some(room) if overflow == Overflow::Saturate => {
+                    cap = room as u32;
+                    continue;
+                }
+                Some(_) => {
+                    "this trade can take a count past what an i32 holds under the cap; \
+                            use `count = i64` or `overflow = saturate` to lower the cap"
+                }
+                None => "this trade receives more candies than an i32 counts; use `count = i64`",
+            };
+            return Err(ScenarioError {
+                line: Some(trade_lines[n]),
+                message: message.to_string(),
+            });
+        }
+        // Files without a version line predate versioning and share the
+        // version 1 layout, so they are read as-is; `fmt` adds the line.
+        Ok(Scenario {
+            names,
+            inventory: inventory.ok_or_else(|| ScenarioError {
+                line: None,
+                message: "missing `inventory`".to_string(),
+            })?,
+            reserved: reserved.unwrap_or_else(Candies::none),
+            cap,
+            kind_caps,
+            trades,
+            locations,
+            uncertain,
+            lots,
+            fee,
+            fees,
+            tax,
+            taxes,
+            taxed,
+            notes,
+            uses,
+            costs,
+            random,
+            travel,
+            start: start.map(|(location, _)| location),
+            targets,
+            target_specs,
+            overflow,
+            standard_trades: true,
+            preset: preset.clone(),
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Scenario, ScenarioError> {
+        Scenario::load_with(path, &Preset::builtin())
+    }
+
+    /// Loads a scenario of a game played with `preset`; see `parse_with`.
+    pub fn load_with(path: &Path, preset: &Preset) -> Result<Scenario, ScenarioError> {
+        let input = crate::replay::read_to_string(path).map_err(|err| ScenarioError {
+            line: None,
+            message: err.to_string(),
+        })?;
+        Scenario::parse_with(&input, template::active(), preset)
+    }
+
+    /// The fee included in the give side of `trades[n]`.
+    pub fn fee_of(&self, n: usize) -> Candies {
+        self.fees[n].unwrap_or(self.fee)
+    }
+
+    pub fn format(&self) -> String {
+        let mut out = format!("version = {}\n", VERSION);
+        if self.overflow != Overflow::default() {
+            out.push_str(&format!("overflow = {}\n", self.overflow));
+        }
+        out.push_str(&format!("cap = {}\n", self.cap));
+        if let Some(kind_caps) = &self.kind_caps {
+            out.push_str(&format!("kind_cap = {}\n", kind_caps));
+        }
+        for (i, letter) in kinds::active().letters().enumerate() {
+            if !self.names.is_builtin(i) {
+                out.push_str(&format!(
+                    "name = {}: {} / {}\n",
+                    letter,
+                    self.names.singular(i),
+                    self.names.plural(i)
+                ));
+            }
+        }
+        for (i, letter) in kinds::active().letters().enumerate() {
+            if !self.names.aliases(i).is_empty() {
+                out.push_str(&format!(
+                    "alias = {}: {}\n",
+                    letter,
+                    self.names.aliases(i).join(", ")
+                ));
+            }
+        }
+        out.push_str(&format!("inventory = {}\n", self.inventory.compact()));
+        if self.reserved != Candies::none() {
+            out.push_str(&format!("reserve = {}\n", self.reserved.compact()));
+        }
+        if let Some(start) = &self.start {
+            out.push_str(&format!("start = {}\n", start));
+        }
+        if self.fee != Candies::none() {
+            out.push_str(&format!("fee = {}\n", self.fee.compact()));
+        }
+        if self.tax != Tax::none() {
+            out.push_str(&format!("tax = {}\n", self.tax));
+        }
+        for (n, trade) in self.trades.iter().enumerate() {
+            let lot = self.lots[n];
+            let give = trade
+                .give
+                .without(&self.fee_of(n).with(&self.taxed[n]))
+                .unwrap();
+            out.push_str(&format!(
+                "trade = {} -> {}",
+                per_lot(&give, lot).compact(),
+                receive_range(
+                    &per_lot(&trade.receive, lot),
+                    &per_lot(&self.uncertain[n], lot)
+                )
+            ));
+            if lot > 1 {
+                out.push_str(&format!("; lot {}", lot));
+            }
+            if let Some(fee) = &self.fees[n] {
+                out.push_str(&format!("; fee {}", fee.compact()));
+            }
+            if let Some(tax) = &self.taxes[n] {
+                out.push_str(&format!("; tax {}", tax));
+            }
+            if let Some(uses) = self.uses[n] {
+                out.push_str(&format!("; uses {}", uses));
+            }
+            if let Some(cost) = self.costs[n] {
+                out.push_str(&format!("; cost {}", cost));
+            }
+            if let Some(note) = &self.notes[n] {
+                out.push_str(&format!("; note {}", note));
+            }
+            if let Some(location) = &self.locations[n] {
+                out.push_str(&format!(" @ {}", location));
+            }
+            out.push('\n');
+        }
+        for random in &self.random {
+            out.push_str(&format!("random = {}\n", random));
+        }
+        for (from, to, time) in &self.travel {
+            out.push_str(&format!("travel = {} -> {}: {}\n", from, to, time));
+        }
+        for target in &self.targets {
+            out.push_str(&format!("target = {}\n", target.compact()));
+        }
+        for spec in &self.target_specs {
+            out.push_str(&format!("target = {}\n", spec));
+        }
+        out
+    }
+}
+
+/// Splits a receive side with ranges such as `1..3m 1c` into the least
+/// and the most it may be, `1m 1c` and `3m 1c`.
+fn split_range(side: &str) -> Result<(String, String), String> {
+    let (mut least, mut most) = (String::new(), String::new());
+    let mut rest = side;
+    while let Some(at) = rest.find("..") {
+        let before = &rest[..at];
+        let low = &before[before.trim_end_matches(|c: char| c.is_ascii_digit()).len()..];
+        let after = &rest[at + 2..];
+        let high =
+            &after[..after.len() - after.trim_start_matches(|c: char| c.is_ascii_digit()).len()];
+        if low.is_empty() || high.is_empty() {
+            return Err("a range needs a count on both sides of `..`".to_string());
+        }
+        match (low.parse::<u64>(), high.parse::<u64>()) {
+            (Ok(l), Ok(h)) if h < l => {
+                return Err(format!("range `{}..{}` ends below its start", low, high))
+            }
+            (Ok(_), Ok(_)) => {}
+            _ => return Err(format!("count in `{}..{}` is too large", low, high)),
+        }
+        least.push_str(before);
+        most.push_str(&before[..before.len() - low.len()]);
+        most.push_str(high);
+        rest = &after[high.len()..];
+    }
+    least.push_str(rest);
+    most.push_str(rest);
+    Ok((least, most))
+}
+
+/// One lot's worth of `candies`, which hold a whole number of lots.
+fn per_lot(candies: &Candies, lot: u32) -> Candies {
+    let mut one = Candies::none();
+    for i in 0..kinds::count() {
+        one.add_by_index(i, Count::new(candies.get_by_index(i).get() / lot as i32));
+    }
+    one
+}
+
+/// Writes a receive side back in the form `split_range` reads.
+fn receive_range(least: &Candies, spread: &Candies) -> String {
+    if *spread == Candies::none() {
+        return least.compact();
+    }
+    kinds::active()
+        .letters()
+        .enumerate()
+        .filter(|(i, _)| {
+            least.get_by_index(*i) != Count::ZERO || spread.get_by_index(*i) != Count::ZERO
+        })
+        .map(|(i, c)| match spread.get_by_index(i) {
+            Count::ZERO => format!("{}{}", least.get_by_index(i), c),
+            extra => format!(
+                "{}..{}{}",
+                least.get_by_index(i),
+                least.get_by_index(i) + extra,
+                c
+            ),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{candies, trade};
+
+    /// The line and message `input` fails to parse with.
+    fn error(input: &str) -> (Option<usize>, String) {
+        let err = Scenario::parse(input).err().unwrap();
+        (err.line, err.message)
+    }
+
+    #[test]
+    fn reads_every_line() {
+        let scenario = Scenario::parse(
+            "# A comment alone\n\
+             inventory = 6e 2w\n\
+             \n\
+             cap = 30  # and one after a value\n\
+             trade = 2e -> 1c; uses 2; cost 1.5\n\
+             trade = 1w -> 2m\n\
+             target = 3c\n\
+             target = 2m\n",
+        )
+        .unwrap();
+        assert_eq!(scenario.inventory, candies("6e 2w"));
+        assert_eq!(scenario.cap, 30);
+        assert_eq!(scenario.trades, [trade("2e -> 1c"), trade("1w -> 2m")]);
+        assert_eq!(scenario.uses, [Some(2), None]);
+        assert_eq!(scenario.costs, [Some(1.5), None]);
+        assert_eq!(scenario.targets, [candies("3c"), candies("2m")]);
+        assert!(scenario.target_specs.is_empty());
+        assert!(scenario.standard_trades);
+    }
+
+    #[test]
+    fn keeps_the_preset_cap_when_none_is_set() {
+        let scenario = Scenario::parse("inventory = 1e\n").unwrap();
+        assert_eq!(scenario.cap, crate::DEFAULT_CAP);
+        assert_eq!(scenario.reserved, Candies::none());
+        let preset = Preset {
+            cap: Some(30),
+            ..Preset::builtin()
+        };
+        let scenario =
+            Scenario::parse_with("inventory = 1e\n", &Variables::default(), &preset).unwrap();
+        assert_eq!(scenario.cap, 30);
+        assert_eq!(scenario.preset, preset);
+    }
+
+    #[test]
+    fn points_at_the_line_in_error() {
+        assert_eq!(
+            error("inventory = 1e\ncap = 10\ncap = 20\n"),
+            (Some(3), "cap is defined twice".to_string())
+        );
+        assert_eq!(
+            error("inventory = 1e\n\ncolour = red\n"),
+            (Some(3), "unknown key `colour`".to_string())
+        );
+        assert_eq!(
+            error("inventory = 1e\nversion = 1\n"),
+            (Some(2), "version must come first and only once".to_string())
+        );
+        assert_eq!(error("inventory 1e\n").0, Some(1));
+        assert_eq!(error("inventory = 1e\ntrade = 2e to 1c\n").0, Some(2));
+    }
+
+    #[test]
+    fn needs_an_inventory() {
+        assert_eq!(
+            error("cap = 10\ntarget = 1m\n"),
+            (None, "missing `inventory`".to_string())
+        );
+    }
+
+    #[test]
+    fn formats_to_what_it_reads_back() {
+        let input = "inventory = 6e 2w\ncap = 30\ntrade = 2e -> 1c; uses 2\ntarget = 3c\n";
+        let formatted = Scenario::parse(input).unwrap().format();
+        assert_eq!(Scenario::parse(&formatted).unwrap().format(), formatted);
+        let scenario = Scenario::parse(&formatted).unwrap();
+        assert_eq!(scenario.inventory, candies("6e 2w"));
+        assert_eq!(scenario.uses, [Some(2)]);
+    }
+}
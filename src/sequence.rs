@@ -0,0 +1,92 @@
+use std::fmt::Write;
+
+use crate::{display_side, route::Route, Candies, CandyWorks};
+
+impl CandyWorks {
+    /// A route for each of `targets` in turn: every leg starts from what the
+    /// last one ended with once its target was handed over, and is the best
+    /// route `find_optimal_route` finds from there. Legs are chosen one at
+    /// a time, so an earlier leg can use up candies a later one would have
+    /// needed, and the uses of limited trades: each leg keeps to the uses
+    /// the legs before it left. `Err` with the index of the first target no
+    /// route reaches.
+    pub fn plan_sequence(&self, targets: &[Candies]) -> Result<Vec<Route>, usize> {
+        let mut legs = Vec::new();
+        let mut candies = self.candies;
+        let mut use_limits = self.use_limits.clone();
+        for (n, target) in targets.iter().enumerate() {
+            let mut leg = CandyWorks {
+                candies,
+                use_limits,
+                ..self.unexplored()
+            };
+            leg.explore();
+            let route = leg.find_optimal_route(*target).ok_or(n)?;
+            candies = route.end().without(target).unwrap();
+            use_limits = leg.use_limits.less(route.trades());
+            legs.push(route);
+        }
+        Ok(legs)
+    }
+}
+
+/// The legs of `plan_sequence` as text, each followed by the target handed
+/// over, with the reserved candies shown but left alone.
+pub fn format(candy_works: &CandyWorks, targets: &[Candies], reserved: Candies) -> String {
+    let mut out = String::new();
+    let legs = match candy_works.plan_sequence(targets) {
+        Ok(legs) => legs,
+        Err(n) => {
+            writeln!(
+                out,
+                "No route to order {} ({}) after the ones before it",
+                n + 1,
+                display_side(&targets[n])
+            )
+            .unwrap();
+            return out;
+        }
+    };
+    for (n, (leg, target)) in legs.iter().zip(targets).enumerate() {
+        writeln!(out, "Order {}: ({})", n + 1, display_side(target)).unwrap();
+        out.push_str(&candy_works.format_route(leg.start().with(&reserved), leg.trades()));
+        writeln!(out, "Hand over ({})", display_side(target)).unwrap();
+    }
+    let trades = legs.iter().map(Route::len).sum::<usize>();
+    let left = legs.last().map_or(candy_works.candies, |leg| {
+        leg.end().without(targets.last().unwrap()).unwrap()
+    });
+    writeln!(
+        out,
+        "Filled {} order{} in {} trade{}, keeping ({})",
+        legs.len(),
+        if legs.len() == 1 { "" } else { "s" },
+        trades,
+        if trades == 1 { "" } else { "s" },
+        display_side(&left.with(&reserved))
+    )
+    .unwrap();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{scenario::Scenario, test_support::candies};
+
+    #[test]
+    fn legs_share_the_uses() {
+        let scenario = Scenario::parse(
+            "cap = 20\ninventory = 9e\ntrade = 1e -> 1m; uses 1\ntarget = 1m\ntarget = 1m\n",
+        )
+        .unwrap();
+        let candy_works = scenario.problem().unwrap();
+        let legs = candy_works.plan_sequence(&scenario.targets).unwrap();
+        let trades = legs
+            .iter()
+            .flat_map(|leg| leg.trades().iter().copied())
+            .collect::<Vec<_>>();
+        assert!(candy_works.use_limits.allow(&trades));
+        let last = legs.last().unwrap().end();
+        assert_eq!(last, candies("5e 1m"));
+    }
+}
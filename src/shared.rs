@@ -0,0 +1,113 @@
+use std::fmt::Write;
+
+use crate::{Candies, CandyWorks};
+
+/// Routes to several targets chosen to start with as many of the same
+/// trades as possible, so the choice between the targets can wait.
+pub struct SharedRoutes {
+    /// The state each shared route ends in, by target; `None` for targets
+    /// that are unreachable or already held.
+    pub ends: Vec<Option<usize>>,
+    /// The state the routes part at, after the trades they share.
+    pub fork: usize,
+}
+
+impl CandyWorks {
+    /// Picks for every target one of its best end states, holding the most
+    /// candies, so that the routes to them share the longest prefix. Routes
+    /// follow the explored tree, so the shared prefix is the route to the
+    /// deepest state whose descendants include a best end of every target.
+    /// Handles up to 64 targets; a target already held is left out, since
+    /// its route is empty.
+    pub fn shared_routes(&self, targets: &[Candies]) -> Option<SharedRoutes> {
+        if targets.len() > 64 {
+            return None;
+        }
+        let states = self.combinations.states().collect::<Vec<_>>();
+        let best = targets
+            .iter()
+            .map(|target| {
+                states
+                    .iter()
+                    .filter(|candies| candies.contains(target))
+                    .map(|candies| candies.total())
+                    .max()
+            })
+            .collect::<Vec<_>>();
+        let wanted = (0..targets.len())
+            .filter(|&t| best[t].is_some() && !self.candies.contains(&targets[t]))
+            .fold(0u64, |mask, t| mask | 1 << t);
+        if wanted.count_ones() < 2 {
+            return None;
+        }
+        // The targets with a best end among each state's descendants, built
+        // from the leaves up: parents are stored before their children.
+        let parents = self
+            .combinations
+            .iter()
+            .map(|(_, parent)| parent.map(|(index, _)| index))
+            .collect::<Vec<_>>();
+        let mut covered = states
+            .iter()
+            .map(|candies| {
+                (0..targets.len())
+                    .filter(|&t| wanted & 1 << t != 0)
+                    .filter(|&t| candies.contains(&targets[t]) && Some(candies.total()) == best[t])
+                    .fold(0u64, |mask, t| mask | 1 << t)
+            })
+            .collect::<Vec<_>>();
+        for index in (0..states.len()).rev() {
+            if let Some(parent) = parents[index] {
+                covered[parent] |= covered[index];
+            }
+        }
+        let depths = self.depths();
+        let fork = (0..states.len())
+            .filter(|&i| covered[i] == wanted)
+            .max_by_key(|&i| (depths[i], std::cmp::Reverse(i)))?;
+        let descends = |mut index: usize| loop {
+            if index == fork {
+                return true;
+            }
+            match parents[index] {
+                Some(parent) => index = parent,
+                None => return false,
+            }
+        };
+        let ends = (0..targets.len())
+            .map(|t| {
+                if wanted & 1 << t == 0 {
+                    return None;
+                }
+                (0..states.len()).find(|&i| {
+                    states[i].contains(&targets[t])
+                        && Some(states[i].total()) == best[t]
+                        && descends(i)
+                })
+            })
+            .collect();
+        Some(SharedRoutes { ends, fork })
+    }
+}
+
+/// The trades the routes share, or that they share none, as text.
+pub fn format_prefix(candy_works: &CandyWorks, shared: &SharedRoutes, start: Candies) -> String {
+    let mut out = String::new();
+    let prefix = candy_works.route_to(shared.fork);
+    if prefix.is_empty() {
+        writeln!(
+            out,
+            "The routes share no trades; choose a target before starting"
+        )
+        .unwrap();
+        return out;
+    }
+    writeln!(
+        out,
+        "The routes share their first {} trades; choose a target after them:",
+        prefix.len()
+    )
+    .unwrap();
+    out.push_str(&candy_works.format_route(start, &prefix));
+    out
+}
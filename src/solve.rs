@@ -0,0 +1,484 @@
+use std::{
+    fmt::{self, Write},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    charges::Charges,
+    conservation::{self, Law},
+    cost::TradeCosts,
+    count::Count,
+    display,
+    effort::EffortModel,
+    fallbacks,
+    flow::{Flow, FlowFormat},
+    interrupt, min_cap,
+    progress::Progress,
+    route::Route,
+    route_file,
+    scenario::Scenario,
+    travel::{self, Step, TravelMap},
+    uncertain::{self, Uncertainty},
+    Candies, CandyWorks, Exploration, Trade,
+};
+
+/// Routes compared for effort before settling on one.
+const EFFORT_CANDIDATES: usize = 50;
+
+/// How the route to each target is planned once the graph is explored, the
+/// same wherever a scenario is solved: its costs ask for the cheapest
+/// route, its uncertain trades for one within the cap whatever they
+/// receive, and its vendors for the one taking the least time to walk. The
+/// public fields are the options the CLI adds on top.
+pub struct Planner {
+    costs: TradeCosts,
+    uncertainty: Uncertainty,
+    travel: Option<TravelMap>,
+    charges: Charges,
+    /// The candies routes are shown with, kept out of planning.
+    reserved: Candies,
+    /// The cap as the scenario sets it, counting the reserved candies.
+    shown_cap: u32,
+    /// The effort of trades and walking; without vendors, the route kept is
+    /// the least effort of those as good.
+    pub effort: Option<EffortModel>,
+    /// The problem under a cap lowered by the headroom wanted, explored,
+    /// for targets whose route fills the inventory to the cap.
+    pub roomy: Option<CandyWorks>,
+    /// Stay as far below the cap as the route allows.
+    pub most_headroom: bool,
+    /// A route planned before, kept unless the new one is better.
+    pub previous: Option<Vec<Trade>>,
+    /// The graph was not explored, so the previous route is the plan.
+    pub settled: bool,
+    /// Show where the candies of each route go, in this format.
+    pub flow: Option<FlowFormat>,
+    /// Show the best other trade at every step of each route.
+    pub fallbacks: bool,
+}
+
+/// The route `Planner::plan` settled on for a target.
+pub struct Planned {
+    pub route: Option<Route>,
+    /// Each step of the route with where it is made, and the time taken,
+    /// when the scenario has vendors.
+    pub itinerary: Option<(Vec<Step>, f64)>,
+    /// What planning changed about the route, a line each, to show before
+    /// it.
+    pub notes: Vec<String>,
+}
+
+impl Planner {
+    /// Plans to the targets with the costs, uncertain trades and vendors
+    /// of `scenario`, and no options besides.
+    pub fn new(scenario: &Scenario) -> Planner {
+        Planner {
+            costs: TradeCosts::new(&scenario.trades, &scenario.costs),
+            uncertainty: Uncertainty::new(&scenario.trades, &scenario.uncertain),
+            travel: TravelMap::from_scenario(scenario),
+            charges: Charges::new(scenario),
+            reserved: scenario.reserved,
+            shown_cap: scenario.cap,
+            effort: None,
+            roomy: None,
+            most_headroom: false,
+            previous: None,
+            settled: false,
+            flow: None,
+            fallbacks: false,
+        }
+    }
+
+    /// Explores `candy_works` again `headroom` candies below its cap, for
+    /// `plan` to stay that far below it, for at most `limit`.
+    pub fn keep_headroom(
+        &mut self,
+        candy_works: &CandyWorks,
+        headroom: u32,
+        limit: Option<Duration>,
+    ) {
+        let mut roomy = candy_works.unexplored();
+        roomy.max_candies = candy_works.max_candies.saturating_sub(headroom);
+        roomy.explore_within(limit);
+        self.roomy = Some(roomy);
+    }
+
+    pub fn costs(&self) -> &TradeCosts {
+        &self.costs
+    }
+
+    /// `planned`, the route to `target` on `candy_works`, as the CLI
+    /// prints it: what planning changed about it, then the route with the
+    /// reserved candies added back and what it changes, what its trades
+    /// charge and cost, where it fills the cap, which steps depend on
+    /// uncertain trades and where to walk, with the flow and fallbacks the
+    /// options ask for; or why there is no route, see `no_route`.
+    pub fn describe<'a>(
+        &self,
+        candy_works: &CandyWorks,
+        target: Candies,
+        planned: &Planned,
+        laws: impl FnOnce() -> &'a [Law],
+    ) -> String {
+        let mut out = String::new();
+        for note in &planned.notes {
+            writeln!(out, "{}", note).unwrap();
+        }
+        let Some(route) = &planned.route else {
+            writeln!(out, "{}", no_route(candy_works, &target, laws)).unwrap();
+            return out;
+        };
+        let trades = route.trades();
+        let start = candy_works.candies.with(&self.reserved);
+        out.push_str(&candy_works.format_route(start, trades));
+        if !trades.is_empty() {
+            let end = start.trade_all(trades).unwrap();
+            writeln!(out, "{}", display::change_table(&start, &end)).unwrap();
+        }
+        out.push_str(&self.charges.paid(trades));
+        if !self.costs.is_empty() {
+            writeln!(out, "Total cost: {}", self.costs.total(trades)).unwrap();
+        }
+        let cap = candy_works.max_candies;
+        if let Some(warning) = cap_warning(candy_works.candies, trades, cap, self.shown_cap) {
+            writeln!(out, "{}", warning).unwrap();
+        }
+        out.push_str(&uncertain::dependencies(&self.uncertainty, trades));
+        if let Some((steps, time)) = &planned.itinerary {
+            out.push_str(&travel::itinerary(steps, *time, &candy_works.notes));
+        } else if let Some(model) = &self.effort {
+            writeln!(out, "Estimated effort: {}", model.effort(trades)).unwrap();
+        }
+        if let Some(format) = self.flow {
+            out.push_str(&Flow::of(candy_works.candies, trades, target).render(format));
+        }
+        if self.fallbacks && !self.settled {
+            out.push_str(&fallbacks::format(candy_works, trades, target));
+        }
+        out
+    }
+
+    /// The route to `target` in the explored `candy_works`: `found` if
+    /// given, and otherwise the cheapest if trades have costs or the best
+    /// by the objective, then kept below the cap, safe from uncertain
+    /// trades, weighed against the previous route, and walked in the least
+    /// time or with the least effort, as the options and scenario ask.
+    pub fn plan(
+        &self,
+        candy_works: &CandyWorks,
+        target: Candies,
+        found: Option<Option<Route>>,
+    ) -> Planned {
+        let cap = candy_works.max_candies;
+        let mut notes = Vec::new();
+        let mut route = match found {
+            Some(found) => found,
+            None if !self.costs.is_empty() => candy_works.cheapest_route(&self.costs, target),
+            None => candy_works.find_optimal_route(target),
+        };
+        if let (Some(roomy), Some(found)) = (&self.roomy, &route) {
+            if fills_cap(found, cap) {
+                if let Some(roomier) = roomy.find_optimal_route(target) {
+                    notes.push(format!(
+                        "Staying {} below the cap after every step",
+                        cap - roomy.max_candies
+                    ));
+                    route = Some(roomier);
+                }
+            }
+        }
+        if self.most_headroom && route.is_some() && !self.settled {
+            // The smallest cap that still reaches the target is the fullest
+            // the inventory has to get.
+            if let Some(lowest) = min_cap::within(candy_works, target).filter(|&c| c < cap) {
+                let mut roomy = candy_works.unexplored();
+                roomy.max_candies = lowest;
+                roomy.explore();
+                if let Some(roomier) = roomy.find_optimal_route(target) {
+                    notes.push(format!(
+                        "Staying {} below the cap after every step",
+                        cap - lowest
+                    ));
+                    route = Some(roomier);
+                }
+            }
+        }
+        if let (false, Some(found)) = (self.uncertainty.is_empty() || self.settled, &route) {
+            match uncertain::worst_case_route(candy_works, &self.uncertainty, target, found.clone())
+            {
+                Some(safe) => {
+                    if safe != *found {
+                        notes.push(
+                            "Planned to stay within the cap whatever the uncertain trades receive"
+                                .to_string(),
+                        );
+                    }
+                    route = Some(safe);
+                }
+                None => notes.push(format!(
+                    "Warning: no route stays within the cap whatever the uncertain trades \
+                     receive; this one can go {} over it",
+                    self.uncertainty.excess(found, cap)
+                )),
+            }
+        }
+        let incumbent = self
+            .previous
+            .as_ref()
+            .filter(|previous| candy_works.use_limits.allow(previous))
+            .and_then(|previous| route_file::incumbent(&candy_works.candies, previous, &target));
+        if let (Some(previous), Some(end)) = (&self.previous, incumbent) {
+            let objective = &candy_works.objective;
+            let found = route
+                .as_ref()
+                .map(|route| objective.rank(&route.end(), route.len()));
+            if found.is_some_and(|found| found > objective.rank(&end, previous.len())) {
+                notes.push("Improved on the previous route".to_string());
+            } else {
+                notes.push("Keeping the previous route".to_string());
+                // Kept for being no worse than what this exploration found.
+                let kept = Route::new(candy_works.candies, previous.clone()).unwrap();
+                route = Some(kept.found_in(candy_works.exploration()));
+            }
+        } else if self.previous.is_some() {
+            notes.push("The previous route no longer reaches this target".to_string());
+        }
+        let mut itinerary = None;
+        if let (Some(map), Some(_), false) = (&self.travel, &route, self.settled) {
+            let model = self.effort.clone().unwrap_or_default();
+            if let Some((steps, time)) = candy_works.route_with_travel(map, &model, target) {
+                let trades = steps.iter().map(|step| step.trade).collect();
+                let found = Route::new(candy_works.candies, trades).unwrap();
+                route = Some(found.found_in(candy_works.exploration()));
+                itinerary = Some((steps, time));
+            }
+        } else if let (Some(model), false) = (&self.effort, self.settled) {
+            route = route.map(|route| least_effort_route(candy_works, target, &route, model));
+        }
+        Planned {
+            route,
+            itinerary,
+            notes,
+        }
+    }
+}
+
+/// Explores `candy_works` for at most `limit` on `threads` threads,
+/// reporting progress toward `targets` every `every` if given and else on
+/// a status line when stderr is a terminal; Ctrl-C stops it early, keeping
+/// what was reached. Returns whether the exploration completed.
+pub fn explore(
+    candy_works: &mut CandyWorks,
+    limit: Option<Duration>,
+    threads: usize,
+    every: Option<Duration>,
+    targets: &[Candies],
+) -> bool {
+    interrupt::catch();
+    let complete = match every {
+        Some(every) => {
+            let mut progress = Progress::new(targets, every);
+            candy_works.explore_reporting(limit, &mut progress)
+        }
+        None if threads > 1 => {
+            let deadline = limit.map(|limit| Instant::now() + limit);
+            candy_works
+                .explore_parallel_bounded(threads, deadline, None)
+                .is_ok()
+        }
+        None => match Progress::status() {
+            Some(mut status) => candy_works.explore_reporting(limit, &mut status),
+            None => candy_works.explore_within(limit),
+        },
+    };
+    interrupt::release();
+    complete
+}
+
+/// Whether `previous` reaches every target of `scenario` from the
+/// inventory of `candy_works` by a route its objective proves cannot be
+/// beaten, which makes exploring unnecessary altogether.
+pub fn settles(candy_works: &CandyWorks, scenario: &Scenario, previous: &[Trade]) -> bool {
+    let (inventory, cap) = (candy_works.candies, candy_works.max_candies);
+    !scenario.targets.is_empty()
+        && scenario.target_specs.is_empty()
+        && candy_works.use_limits.allow(previous)
+        && scenario.targets.iter().all(|target| {
+            route_file::incumbent(&inventory, previous, target).is_some()
+                && Route::new(inventory, previous.to_vec())
+                    .is_ok_and(|route| candy_works.objective.proves_optimal(&route, target, cap))
+        })
+}
+
+/// Why no route to `target` was found on `candy_works`, for the line
+/// saying so: the cap, or one of the conservation laws `laws` works out
+/// only when needed.
+pub fn no_route<'a>(
+    candy_works: &CandyWorks,
+    target: &Candies,
+    laws: impl FnOnce() -> &'a [Law],
+) -> String {
+    if let Some(err) = candy_works.cap_error(*target) {
+        return format!("No route found: {}", err);
+    }
+    let (inventory, cap) = (candy_works.candies, candy_works.max_candies);
+    match conservation::ruling_out(laws(), &inventory, target, cap) {
+        Some(law) => format!(
+            "No route found: {}, and no inventory holding the target within the cap matches that",
+            law.describe(&inventory)
+        ),
+        None => "No route found".to_string(),
+    }
+}
+
+/// How sure a run stopped early, or settled without exploring, is of the
+/// route it planned to a target.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Status {
+    Optimal,
+    BestFound,
+    NoneFound,
+}
+
+impl Status {
+    /// The status of `route` to `target` on `candy_works`, whose
+    /// exploration was `complete` or not.
+    pub fn of(
+        candy_works: &CandyWorks,
+        route: Option<&Route>,
+        target: &Candies,
+        complete: bool,
+    ) -> Status {
+        // A route found by an exploration that stopped early is only the
+        // best so far, unless nothing could beat it anyway.
+        let proven = match route {
+            Some(route) => {
+                route.exploration().is_some_and(Exploration::is_complete)
+                    || candy_works
+                        .objective
+                        .proves_optimal(route, target, candy_works.max_candies)
+            }
+            None => complete,
+        };
+        match (proven, route) {
+            (true, _) => Status::Optimal,
+            (false, Some(_)) => Status::BestFound,
+            (false, None) => Status::NoneFound,
+        }
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Status::Optimal => "optimal",
+            Status::BestFound => "best found within the time limit, may not be optimal",
+            Status::NoneFound => "no route found within the time limit, one may still exist",
+        })
+    }
+}
+
+/// Warns about the steps of `route` from `start` that fill the inventory
+/// to `cap`, since anything picked up in the game right then would be
+/// lost; `None` if none does. The cap is shown as `shown_cap`, which
+/// counts the reserved candies.
+pub fn cap_warning(start: Candies, route: &[Trade], cap: u32, shown_cap: u32) -> Option<String> {
+    let mut candies = start;
+    let mut steps = Vec::new();
+    for (n, trade) in route.iter().enumerate() {
+        candies = candies.trade(trade).unwrap();
+        if candies.total() == Count::new(cap as i32) {
+            steps.push(n + 1);
+        }
+    }
+    // Long routes can touch the cap many times; the first few are enough to
+    // see where.
+    const LISTED: usize = 10;
+    let numbers = steps
+        .iter()
+        .take(LISTED)
+        .map(usize::to_string)
+        .collect::<Vec<_>>();
+    let listed = match (numbers.as_slice(), steps.len()) {
+        ([], _) => return None,
+        ([only], 1) => format!("Step {} fills", only),
+        (numbers, count) if count > LISTED => format!(
+            "Steps {} and {} more fill",
+            numbers.join(", "),
+            count - LISTED
+        ),
+        ([rest @ .., last], _) => format!("Steps {} and {} fill", rest.join(", "), last),
+    };
+    Some(format!(
+        "Warning: {} the inventory to the cap of {}; pickups right after would be wasted",
+        listed, shown_cap
+    ))
+}
+
+/// Whether some step of `route` fills the inventory to `cap`.
+fn fills_cap(route: &Route, cap: u32) -> bool {
+    let mut candies = route.start();
+    route.trades().iter().any(|trade| {
+        candies = candies.trade(trade).unwrap();
+        candies.total() == Count::new(cap as i32)
+    })
+}
+
+/// The route needing the least effort among those with the same length
+/// and final inventory size as `route`.
+fn least_effort_route(
+    candy_works: &CandyWorks,
+    target: Candies,
+    route: &Route,
+    model: &EffortModel,
+) -> Route {
+    let total = route.end().total();
+    let length = route.len();
+    candy_works
+        .alternative_routes(target, EFFORT_CANDIDATES, Some(model))
+        .into_iter()
+        .map(|alternative| alternative.route)
+        .filter(|candidate| {
+            candidate.len() == length
+                && candy_works.candies.trade_all(candidate).unwrap().total() == total
+        })
+        .chain([route.trades().to_vec()])
+        .min_by(|a, b| model.effort(a).partial_cmp(&model.effort(b)).unwrap())
+        .map(|trades| {
+            Route::new(route.start(), trades)
+                .unwrap()
+                .found_in(route.exploration())
+        })
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{candies, explored, trade};
+
+    #[test]
+    fn warns_about_the_steps_filling_the_cap() {
+        let route = [trade("1w -> 1m"), trade("1e -> 1w"), trade("1w -> 2m")];
+        assert_eq!(
+            cap_warning(candies("2w 1e"), &route, 3, 5).as_deref(),
+            Some(
+                "Warning: Steps 1 and 2 fill the inventory to the cap of 5; pickups right after \
+                 would be wasted"
+            )
+        );
+        assert_eq!(cap_warning(candies("2w 1e"), &route, 5, 5), None);
+    }
+
+    #[test]
+    fn proves_routes_found_by_a_complete_exploration_optimal() {
+        let candy_works = explored("6e", 20, &[]);
+        let route = candy_works.find_optimal_route(candies("2w")).unwrap();
+        let status = Status::of(&candy_works, Some(&route), &candies("2w"), true);
+        assert_eq!(status, Status::Optimal);
+        assert_eq!(
+            Status::of(&candy_works, None, &candies("9w"), false),
+            Status::NoneFound
+        );
+    }
+}
@@ -0,0 +1,133 @@
+use std::time::{Duration, Instant};
+
+use crate::{frontier::Frontier, route::Route, Candies, CandyWorks};
+
+struct Outcome {
+    name: String,
+    route: Option<Route>,
+    states: usize,
+    elapsed: Duration,
+}
+
+fn breadth_first(
+    candy_works: &CandyWorks,
+    prefilter: bool,
+    prune_dominated: bool,
+    frontier: Frontier,
+    name: &str,
+    target: Candies,
+) -> Outcome {
+    let mut candy_works = candy_works.unexplored();
+    candy_works.prefilter = prefilter;
+    candy_works.prune_dominated = prune_dominated;
+    candy_works.frontier = frontier;
+    let started = Instant::now();
+    candy_works.explore();
+    let route = candy_works.find_optimal_route(target);
+    Outcome {
+        name: name.to_string(),
+        route,
+        states: candy_works.combinations.len(),
+        elapsed: started.elapsed(),
+    }
+}
+
+/// Solves each target with every strategy and prints the results side by
+/// side. `candy_works` is only the problem; each strategy explores on its
+/// own. With `time_limit` the time-limited search is compared as well.
+pub fn compare(candy_works: &CandyWorks, targets: &[Candies], time_limit: Option<Duration>) {
+    for target in targets {
+        println!("Target: ({})", target.display(false).trim());
+        let mut outcomes = vec![
+            breadth_first(
+                candy_works,
+                false,
+                false,
+                Frontier::Lifo,
+                "breadth-first",
+                *target,
+            ),
+            breadth_first(
+                candy_works,
+                true,
+                false,
+                Frontier::Lifo,
+                "bloom prefilter",
+                *target,
+            ),
+            breadth_first(
+                candy_works,
+                false,
+                true,
+                Frontier::Lifo,
+                "dominance pruning",
+                *target,
+            ),
+            breadth_first(
+                candy_works,
+                false,
+                false,
+                Frontier::Fifo,
+                "fifo frontier",
+                *target,
+            ),
+            breadth_first(
+                candy_works,
+                false,
+                false,
+                Frontier::Most,
+                "most frontier",
+                *target,
+            ),
+            breadth_first(
+                candy_works,
+                false,
+                false,
+                Frontier::Nearest(vec![*target]),
+                "nearest frontier",
+                *target,
+            ),
+        ];
+
+        let started = Instant::now();
+        let reachable = candy_works.reachable_set();
+        outcomes.push(Outcome {
+            name: "two-phase".to_string(),
+            route: candy_works.route_backward(&reachable, *target),
+            states: reachable.len(),
+            elapsed: started.elapsed(),
+        });
+
+        if let Some(limit) = time_limit {
+            let mut limited = candy_works.unexplored();
+            let started = Instant::now();
+            limited.explore_within(Some(limit));
+            outcomes.push(Outcome {
+                name: format!("time limit {:?}", limit),
+                route: limited.find_optimal_route(*target),
+                states: limited.combinations.len(),
+                elapsed: started.elapsed(),
+            });
+        }
+
+        println!(
+            "{:<20}{:>8}{:>13}{:>10}{:>12}",
+            "strategy", "trades", "final total", "states", "ms"
+        );
+        for outcome in &outcomes {
+            let (trades, total) = match &outcome.route {
+                Some(route) => (route.len().to_string(), route.end().total().to_string()),
+                None => ("-".to_string(), "-".to_string()),
+            };
+            println!(
+                "{:<20}{:>8}{:>13}{:>10}{:>12.3}",
+                outcome.name,
+                trades,
+                total,
+                outcome.states,
+                outcome.elapsed.as_secs_f64() * 1000.0
+            );
+        }
+        println!();
+    }
+}
@@ -0,0 +1,17 @@
+use crate::{kinds, preset::Preset, Trade};
+
+/// The trades problems offer besides their own unless built without them:
+/// `preset`'s ratio of each candy for one of every other, then its
+/// specials.
+pub fn trades(preset: &Preset) -> Vec<Trade> {
+    let mut trades = Vec::new();
+    for i in 0..kinds::count() {
+        for j in 0..kinds::count() {
+            if i != j {
+                trades.push(Trade::standard_trade(i, j, preset.ratio));
+            }
+        }
+    }
+    trades.extend(preset.specials.iter().copied());
+    trades
+}
@@ -0,0 +1,115 @@
+use std::collections::HashSet;
+
+use crate::{count::Count, display_side, kinds, names, Candies, CandyWorks};
+
+/// Enumerating more inventories than this takes too long to be useful.
+pub const MAX_INVENTORIES: u64 = 5_000_000;
+
+/// The number of inventories of the run's kinds of candy holding at most `cap`.
+pub fn inventories_within(cap: u32) -> u64 {
+    // Choosing 5 counts summing to at most `cap` is choosing 5 of
+    // `cap + 5` positions.
+    (1..=kinds::count() as u64).fold(1, |count, k| count * (cap as u64 + k) / k)
+}
+
+/// Every inventory holding at most `cap` candies.
+pub fn all_within(cap: u32) -> Vec<Candies> {
+    let mut all = vec![Candies::none()];
+    for i in 0..kinds::count() {
+        all = all
+            .into_iter()
+            .flat_map(|candies| {
+                (0..=cap as i32 - candies.total().get()).map(move |count| {
+                    let mut more = candies;
+                    more.add_by_index(i, Count::new(count));
+                    more
+                })
+            })
+            .collect();
+    }
+    all
+}
+
+/// The inventories within the cap from which `target` can be reached with
+/// the trades of `candy_works`, found by undoing trades from every
+/// inventory that holds it.
+pub fn reaching(candy_works: &CandyWorks, target: Candies) -> HashSet<Candies> {
+    let mut reaching = all_within(candy_works.max_candies)
+        .into_iter()
+        .filter(|candies| candies.contains(&target))
+        .collect::<HashSet<_>>();
+    let mut queue = reaching.iter().copied().collect::<Vec<_>>();
+    while let Some(candies) = queue.pop() {
+        for trade in &candy_works.trades {
+            if let Some(before) = candies.untrade(trade) {
+                if candy_works.fits(&before) && reaching.insert(before) {
+                    queue.push(before);
+                }
+            }
+        }
+    }
+    reaching
+}
+
+/// Prints which starting inventories within the cap can reach `target`:
+/// how many, the fewest of each candy that reach it alone, and the
+/// smallest inventories that reach it, up to `limit` of them.
+pub fn print(candy_works: &CandyWorks, target: Candies, limit: usize) {
+    let cap = candy_works.max_candies;
+    let all = inventories_within(cap);
+    if all > MAX_INVENTORIES {
+        println!(
+            "The cap of {} allows {} inventories, too many to check; try a lower cap",
+            cap, all
+        );
+        return;
+    }
+    let reaching = reaching(candy_works, target);
+    println!(
+        "{} of {} inventories within the cap of {} can reach ({})",
+        reaching.len(),
+        all,
+        cap,
+        display_side(&target)
+    );
+    if reaching.is_empty() {
+        return;
+    }
+    let names = names::active();
+    println!("Holding one candy only:");
+    for i in 0..kinds::count() {
+        let fewest = (0..=cap as i32).find(|&count| {
+            let mut alone = Candies::none();
+            alone.add_by_index(i, Count::new(count));
+            reaching.contains(&alone)
+        });
+        match fewest {
+            Some(count) => println!("  {} {} or more", count, names.plural(i)),
+            None => println!("  no amount of {} is enough", names.plural(i)),
+        }
+    }
+    // An inventory is among the smallest when taking away any one candy
+    // leaves one that cannot reach the target.
+    let mut smallest = reaching
+        .iter()
+        .filter(|candies| {
+            (0..kinds::count()).all(|i| {
+                let mut less = **candies;
+                less.add_by_index(i, Count::new(-1));
+                candies.get_by_index(i) == Count::ZERO || !reaching.contains(&less)
+            })
+        })
+        .copied()
+        .collect::<Vec<_>>();
+    smallest.sort_by_key(|candies| (candies.total(), candies.compact()));
+    println!(
+        "{} smallest inventories reach it, none of them with a candy to spare:",
+        smallest.len()
+    );
+    for candies in smallest.iter().take(limit) {
+        println!("  {}", display_side(candies));
+    }
+    if smallest.len() > limit {
+        println!("  ... and {} more", smallest.len() - limit);
+    }
+}
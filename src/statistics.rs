@@ -0,0 +1,197 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use crate::{
+    count::Count, display_side, export::stats_json, json::quote, output::candies_json,
+    query::Filter, CandyWorks, Statistics, Trade,
+};
+
+/// How many characters the longest bar of a histogram takes.
+const BAR: usize = 40;
+
+/// What an explored graph holds beyond `Statistics`, counted over the
+/// shortest routes rather than the first ones the graph keeps.
+pub struct Detailed {
+    /// The states taking each number of trades at fewest, from none up.
+    pub depths: Vec<usize>,
+    /// The states holding each total of candies, from the fewest up.
+    pub totals: Vec<(Count, usize)>,
+    /// Each trade with how many times it appears on the shortest routes
+    /// to every state, the most used first.
+    pub trade_use: Vec<(Trade, usize)>,
+}
+
+impl CandyWorks {
+    /// The detailed statistics of the explored graph, or `None` before
+    /// exploring. The shortest routes are searched breadth first from the
+    /// graph's starts among the explored states, and each state counts the
+    /// trades of the first one found.
+    pub fn detailed_statistics(&self) -> Option<Detailed> {
+        if self.combinations.is_empty() {
+            return None;
+        }
+        let explored = self.combinations.states().collect::<HashSet<_>>();
+        let roots = self.roots();
+        let mut seen = roots.iter().copied().collect::<HashSet<_>>();
+        // Every state in the order reached, with the index of the state and
+        // the trade it was reached from.
+        let mut order = roots.iter().map(|root| (*root, None)).collect::<Vec<_>>();
+        let mut depth = vec![0; order.len()];
+        let mut next = 0;
+        while next < order.len() {
+            let (candies, _) = order[next];
+            for trade in &self.trades {
+                if let Some(after) = candies.trade(trade) {
+                    if explored.contains(&after) && seen.insert(after) {
+                        order.push((after, Some((next, *trade))));
+                        depth.push(depth[next] + 1);
+                    }
+                }
+            }
+            next += 1;
+        }
+        let mut depths = vec![0; depth.iter().max().map_or(0, |most| most + 1)];
+        for &d in &depth {
+            depths[d] += 1;
+        }
+        let mut totals = HashMap::new();
+        for (candies, _) in &order {
+            *totals.entry(candies.total()).or_insert(0) += 1;
+        }
+        let mut totals = totals.into_iter().collect::<Vec<_>>();
+        totals.sort();
+        // A trade reaching a state is on the routes to it and to every state
+        // reached through it, so the sizes add up from the last reached.
+        let mut below = vec![1; order.len()];
+        let mut uses = HashMap::new();
+        for i in (0..order.len()).rev() {
+            if let Some((parent, trade)) = order[i].1 {
+                *uses.entry(trade).or_insert(0) += below[i];
+                below[parent] += below[i];
+            }
+        }
+        let mut trade_use = uses.into_iter().collect::<Vec<_>>();
+        trade_use.sort_by_key(|(trade, uses)| {
+            (
+                std::cmp::Reverse(*uses),
+                self.trades.iter().position(|t| t == trade),
+            )
+        });
+        Some(Detailed {
+            depths,
+            totals,
+            trade_use,
+        })
+    }
+}
+
+/// `count` as a bar of `#`, `BAR` long for `most`.
+fn bar(count: usize, most: usize) -> String {
+    "#".repeat((count * BAR).div_ceil(most.max(1)))
+}
+
+impl fmt::Display for Detailed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let most = self.depths.iter().copied().max().unwrap_or(0);
+        writeln!(f, "States by fewest trades:")?;
+        for (trades, count) in self.depths.iter().enumerate() {
+            writeln!(f, "{:>4} {:>8}  {}", trades, count, bar(*count, most))?;
+        }
+        let most = self
+            .totals
+            .iter()
+            .map(|(_, count)| *count)
+            .max()
+            .unwrap_or(0);
+        writeln!(f, "States by candies held:")?;
+        for (total, count) in &self.totals {
+            writeln!(f, "{:>4} {:>8}  {}", total, count, bar(*count, most))?;
+        }
+        write!(f, "Trades on the shortest routes to every state:")?;
+        if self.trade_use.is_empty() {
+            write!(f, "\n  none")?;
+        }
+        for (trade, uses) in &self.trade_use {
+            write!(
+                f,
+                "\n{:>8}  {} -> {}",
+                uses,
+                display_side(&trade.give),
+                display_side(&trade.receive)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// States of `candy_works` matching each of `queries`, with the query
+/// as written.
+pub fn count_matching<'a>(
+    candy_works: &CandyWorks,
+    queries: &'a [(String, Filter)],
+) -> Result<Vec<(&'a str, usize)>, String> {
+    queries
+        .iter()
+        .map(|(query, filter)| Ok((query.as_str(), candy_works.matching(filter)?.len())))
+        .collect()
+}
+
+/// The statistics, their detailed part and how many states match each
+/// query, as `stats --detailed` prints them.
+pub fn detailed_text(stats: &Statistics, detailed: &Detailed, queries: &[(&str, usize)]) -> String {
+    let mut text = format!("{}\n{}\n", stats, detailed);
+    for (query, states) in queries {
+        text.push_str(&format!(
+            "{} state{} match `{}`\n",
+            states,
+            if *states == 1 { "" } else { "s" },
+            query
+        ));
+    }
+    text
+}
+
+/// The statistics, the detailed ones and the counts of `queries` as one
+/// JSON document.
+pub fn detailed_json(stats: &Statistics, detailed: &Detailed, queries: &[(&str, usize)]) -> String {
+    let list = |items: Vec<String>| format!("[{}]", items.join(","));
+    let depths = list(detailed.depths.iter().map(usize::to_string).collect());
+    let totals = list(
+        detailed
+            .totals
+            .iter()
+            .map(|(total, states)| format!("{{\"total\":{},\"states\":{}}}", total, states))
+            .collect(),
+    );
+    let trades = list(
+        detailed
+            .trade_use
+            .iter()
+            .map(|(trade, uses)| {
+                format!(
+                    "{{\"give\":{},\"receive\":{},\"uses\":{}}}",
+                    candies_json(&trade.give),
+                    candies_json(&trade.receive),
+                    uses
+                )
+            })
+            .collect(),
+    );
+    let queries = list(
+        queries
+            .iter()
+            .map(|(query, states)| format!("{{\"query\":{},\"states\":{}}}", quote(query), states))
+            .collect(),
+    );
+    format!(
+        "{{\"statistics\":{},\"states_by_fewest_trades\":{},\"states_by_total\":{},\
+         \"trades_on_shortest_routes\":{},\"queries\":{}}}",
+        stats_json(stats),
+        depths,
+        totals,
+        trades,
+        queries
+    )
+}
@@ -0,0 +1,219 @@
+use std::mem;
+
+use crate::{Candies, Trade};
+
+/// States per chunk. Every chunk after the first is allocated full size,
+/// so only the first one ever grows and small graphs stay small.
+const CHUNK: usize = 1 << 16;
+/// Bits of a link holding the trade index; the parent index gets the rest.
+const TRADE_BITS: u32 = 24;
+const NO_PARENT: u64 = u64::MAX;
+
+/// The states of a chunk: packed into their IDs while every one has an
+/// ID, and as they are once one does not.
+#[derive(Clone)]
+enum States {
+    Packed(Vec<u64>),
+    Wide(Vec<Candies>),
+}
+
+impl States {
+    fn get(&self, index: usize) -> Candies {
+        match self {
+            States::Packed(ids) => Candies::from_id(ids[index]).unwrap(),
+            States::Wide(states) => states[index],
+        }
+    }
+
+    fn push(&mut self, candies: Candies) {
+        if let States::Packed(ids) = self {
+            match candies.id() {
+                Some(id) => return ids.push(id),
+                None => {
+                    let mut states = Vec::with_capacity(ids.capacity());
+                    states.extend(ids.iter().map(|id| Candies::from_id(*id).unwrap()));
+                    *self = States::Wide(states);
+                }
+            }
+        }
+        if let States::Wide(states) = self {
+            states.push(candies);
+        }
+    }
+
+    fn memory_bytes(&self) -> usize {
+        match self {
+            States::Packed(ids) => ids.capacity() * mem::size_of::<u64>(),
+            States::Wide(states) => states.capacity() * mem::size_of::<Candies>(),
+        }
+    }
+}
+
+/// A chunk of states with, for each, its parent index and trade index
+/// packed into one link. Stored this way a state takes 16 bytes.
+#[derive(Clone)]
+struct Chunk {
+    states: States,
+    links: Vec<u64>,
+}
+
+impl Chunk {
+    fn with_capacity(capacity: usize) -> Chunk {
+        Chunk {
+            states: States::Packed(Vec::with_capacity(capacity)),
+            links: Vec::with_capacity(capacity),
+        }
+    }
+
+    fn memory_bytes(&self) -> usize {
+        self.states.memory_bytes() + self.links.capacity() * mem::size_of::<u64>()
+    }
+}
+
+/// The explored states, each with the parent and trade it was reached by.
+/// States and their links to parents live in separate arrays, split into
+/// fixed-size chunks, so passes over just the states or just the parents
+/// stay contiguous and growing only copies the last chunk.
+#[derive(Clone, Default)]
+pub struct Combinations {
+    chunks: Vec<Chunk>,
+    /// Trades seen so far; the chunks store indices into this.
+    trades: Vec<Trade>,
+    len: usize,
+}
+
+impl Combinations {
+    pub fn new() -> Self {
+        Combinations::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn push(&mut self, (candies, parent): (Candies, Option<(usize, Trade)>)) {
+        match parent {
+            Some((parent, trade)) => {
+                let trade = self.trade_index(trade);
+                self.push_child(candies, parent, trade);
+            }
+            None => self.push_link(candies, NO_PARENT),
+        }
+    }
+
+    /// The index `push_child` takes for `trade`. Exploring looks these up
+    /// once per trade rather than once per state stored. There can be 2^24
+    /// different trades, far more than any game offers.
+    pub fn trade_index(&mut self, trade: Trade) -> u32 {
+        let index = match self.trades.iter().position(|t| *t == trade) {
+            Some(index) => index,
+            None => {
+                assert!(self.trades.len() < 1 << TRADE_BITS, "too many trades");
+                self.trades.push(trade);
+                self.trades.len() - 1
+            }
+        };
+        index as u32
+    }
+
+    /// Stores a state reached from `parent` by the trade `trade_index`
+    /// returned.
+    pub fn push_child(&mut self, candies: Candies, parent: usize, trade: u32) {
+        self.push_link(candies, (parent as u64) << TRADE_BITS | u64::from(trade));
+    }
+
+    fn push_link(&mut self, candies: Candies, link: u64) {
+        if self.len.is_multiple_of(CHUNK) {
+            self.chunks.push(if self.chunks.is_empty() {
+                Chunk::with_capacity(0)
+            } else {
+                Chunk::with_capacity(CHUNK)
+            });
+        }
+        let chunk = self.chunks.last_mut().unwrap();
+        chunk.states.push(candies);
+        chunk.links.push(link);
+        self.len += 1;
+    }
+
+    pub fn state(&self, index: usize) -> Candies {
+        self.chunks[index / CHUNK].states.get(index % CHUNK)
+    }
+
+    pub fn parent(&self, index: usize) -> Option<(usize, Trade)> {
+        match self.chunks[index / CHUNK].links[index % CHUNK] {
+            NO_PARENT => None,
+            link => Some((
+                (link >> TRADE_BITS) as usize,
+                self.trades[(link & ((1 << TRADE_BITS) - 1)) as usize],
+            )),
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Option<(Candies, Option<(usize, Trade)>)> {
+        (index < self.len).then(|| (self.state(index), self.parent(index)))
+    }
+
+    pub fn states(&self) -> impl Iterator<Item = Candies> + '_ {
+        (0..self.len).map(|index| self.state(index))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Candies, Option<(usize, Trade)>)> + '_ {
+        (0..self.len).map(|index| (self.state(index), self.parent(index)))
+    }
+
+    /// Bytes reserved by the chunks, including unused room in the last one.
+    pub fn memory_bytes(&self) -> usize {
+        self.chunks.iter().map(Chunk::memory_bytes).sum()
+    }
+}
+
+impl FromIterator<(Candies, Option<(usize, Trade)>)> for Combinations {
+    fn from_iter<I: IntoIterator<Item = (Candies, Option<(usize, Trade)>)>>(iter: I) -> Self {
+        let mut combinations = Combinations::new();
+        for combination in iter {
+            combinations.push(combination);
+        }
+        combinations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{candies, trade};
+
+    #[test]
+    fn keeps_states_and_parents_across_chunks() {
+        let cake = trade("2e -> 1c");
+        let mut combinations = Combinations::new();
+        combinations.push((candies("0e"), None));
+        let index = combinations.trade_index(cake);
+        for n in 1..CHUNK + 10 {
+            combinations.push_child(candies(&format!("{}e", n % 4096)), n - 1, index);
+        }
+        assert_eq!(combinations.len(), CHUNK + 10);
+        assert_eq!(combinations.state(CHUNK + 3), candies("3e"));
+        assert_eq!(combinations.parent(CHUNK + 3), Some((CHUNK + 2, cake)));
+        assert_eq!(combinations.parent(0), None);
+        assert_eq!(combinations.get(CHUNK + 10), None);
+        assert_eq!(combinations.trade_index(cake), index);
+        assert_ne!(combinations.trade_index(trade("2w -> 1c")), index);
+    }
+
+    #[test]
+    fn keeps_states_too_large_for_an_id() {
+        let states = [candies("3e"), candies("5000e"), candies("1w")];
+        let combinations = states
+            .iter()
+            .map(|candies| (*candies, None))
+            .collect::<Combinations>();
+        assert!(candies("5000e").id().is_none());
+        assert!(combinations.states().eq(states));
+        assert!(combinations.iter().all(|(_, parent)| parent.is_none()));
+    }
+}
@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+
+use crate::{
+    display_side,
+    quests::{self, Quest, Step},
+    Candies, CandyWorks,
+};
+
+/// The graph of inventories reachable under the cap when trades can be
+/// made and quests completed any number of times, with the reward of
+/// every move.
+struct Graph {
+    states: Vec<Candies>,
+    moves: Vec<Vec<(usize, f64, Step)>>,
+}
+
+fn explore(candy_works: &CandyWorks, quests: &[Quest]) -> Graph {
+    let mut index = HashMap::from([(candy_works.candies, 0)]);
+    let mut graph = Graph {
+        states: vec![candy_works.candies],
+        moves: Vec::new(),
+    };
+    let mut next = 0;
+    while next < graph.states.len() {
+        let candies = graph.states[next];
+        let trades = candy_works
+            .trades
+            .iter()
+            .filter_map(|trade| Some((candies.trade(trade)?, 0.0, Step::Trade(*trade))));
+        let completions = quests.iter().enumerate().filter_map(|(i, quest)| {
+            Some((quest.complete(&candies)?, quest.reward, Step::Quest(i)))
+        });
+        let mut moves = Vec::new();
+        for (after, reward, step) in trades.chain(completions) {
+            if !candy_works.fits(&after) {
+                continue;
+            }
+            let target = *index.entry(after).or_insert_with(|| {
+                graph.states.push(after);
+                graph.states.len() - 1
+            });
+            moves.push((target, reward, step));
+        }
+        graph.moves.push(moves);
+        next += 1;
+    }
+    graph
+}
+
+/// Looks `horizon` moves ahead for the most reward from the inventory of
+/// `candy_works`, then follows the best move from each inventory until one
+/// repeats, which is the loop the strategy settles into. Prints what leads
+/// into the loop, the loop, and the reward it earns per lap and per move.
+pub fn simulate(candy_works: &CandyWorks, quests: &[Quest], horizon: usize) {
+    let graph = explore(candy_works, quests);
+    // The most reward each inventory can still earn with so many moves
+    // left; doing nothing is always a move, worth nothing.
+    let mut value = vec![0.0; graph.states.len()];
+    let mut previous = value.clone();
+    for _ in 0..horizon {
+        previous.clone_from(&value);
+        for (state, moves) in graph.moves.iter().enumerate() {
+            value[state] = moves
+                .iter()
+                .map(|(target, reward, _)| reward + previous[*target])
+                .fold(previous[state], f64::max);
+        }
+    }
+    println!(
+        "Explored {} inventories; the best {} moves earn {}",
+        graph.states.len(),
+        horizon,
+        value[0]
+    );
+
+    let best_move = |state: usize| {
+        graph.moves[state]
+            .iter()
+            .map(|&(target, reward, step)| (reward + previous[target], target, reward, step))
+            .fold(
+                None,
+                |best: Option<(f64, usize, f64, Step)>, candidate| match best {
+                    Some(best) if best.0 >= candidate.0 => Some(best),
+                    _ => Some(candidate),
+                },
+            )
+    };
+    let mut path = Vec::new();
+    let mut visited = HashMap::from([(0, 0)]);
+    let mut state = 0;
+    let entry = loop {
+        let Some((_, target, reward, step)) = best_move(state) else {
+            println!("No loop keeps earning rewards from this inventory");
+            return;
+        };
+        path.push((state, reward, step));
+        state = target;
+        if let Some(&at) = visited.get(&state) {
+            break at;
+        }
+        visited.insert(state, path.len());
+    };
+
+    let (lead, cycle) = path.split_at(entry);
+    let lap = cycle.iter().map(|(_, reward, _)| reward).sum::<f64>();
+    if lap <= 0.0 {
+        println!("No loop keeps earning rewards from this inventory");
+        return;
+    }
+    let print = |steps: &[(usize, f64, Step)]| {
+        for (state, _, step) in steps {
+            let candies = graph.states[*state].display(true);
+            match step {
+                Step::Trade(trade) => println!(
+                    "({}) trade {} -> {}",
+                    candies,
+                    display_side(&trade.give),
+                    display_side(&trade.receive)
+                ),
+                Step::Quest(i) => {
+                    println!("({}) complete {}", candies, quests::describe(&quests[*i]))
+                }
+            }
+        }
+    };
+    if !lead.is_empty() {
+        println!("To get into the loop:");
+        print(lead);
+    }
+    println!(
+        "Then repeat, earning {} per lap of {} moves ({:.3} per move):",
+        lap,
+        cycle.len(),
+        lap / cycle.len() as f64
+    );
+    print(cycle);
+}
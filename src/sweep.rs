@@ -0,0 +1,176 @@
+use std::{path::Path, str::FromStr};
+
+use crate::{guard, preset::Preset, scenario::Scenario, template::Variables};
+
+/// One axis of a sweep: the scenario's `cap`, or a `${NAME}` variable of
+/// the scenario file, and the values it takes.
+#[derive(Clone)]
+pub struct Param {
+    name: String,
+    values: Vec<String>,
+}
+
+impl FromStr for Param {
+    type Err = String;
+
+    /// Reads `name=from..to` with an optional `:step`, both ends included,
+    /// or `name=a,b,c` for values that are not a range.
+    fn from_str(input: &str) -> Result<Param, String> {
+        let (name, values) = input
+            .split_once('=')
+            .ok_or_else(|| format!("expected `name=from..to:step`, got {:?}", input))?;
+        let values = match values.split_once("..") {
+            Some((from, rest)) => {
+                let (to, step) = rest.split_once(':').unwrap_or((rest, "1"));
+                let number = |text: &str| {
+                    text.trim()
+                        .parse::<u32>()
+                        .map_err(|_| format!("`{}` is not a whole number", text))
+                };
+                let (from, to, step) = (number(from)?, number(to)?, number(step)?);
+                if step == 0 || from > to {
+                    return Err(format!("{}..{}:{} is an empty range", from, to, step));
+                }
+                (from..=to)
+                    .step_by(step as usize)
+                    .map(|value| value.to_string())
+                    .collect()
+            }
+            None => values
+                .split(',')
+                .map(|value| value.trim().to_string())
+                .collect(),
+        };
+        Ok(Param {
+            name: name.trim().to_string(),
+            values,
+        })
+    }
+}
+
+impl Param {
+    /// The values the parameter takes, in order.
+    pub fn values(&self) -> &[String] {
+        &self.values
+    }
+}
+
+/// Every combination of the values of `params`, the last one varying
+/// fastest.
+fn grid(params: &[Param]) -> Vec<Vec<&str>> {
+    params.iter().fold(vec![Vec::new()], |points, param| {
+        points
+            .iter()
+            .flat_map(|point| {
+                param.values.iter().map(move |value| {
+                    let mut point = point.clone();
+                    point.push(value.as_str());
+                    point
+                })
+            })
+            .collect()
+    })
+}
+
+/// Solves the scenario in `path` at every point of the grid of `params`
+/// and prints a row per point: how many states are reachable and how many
+/// trades each target takes. `variables` and `preset` are the ones set for
+/// the whole run; points too large to explore are skipped unless `force`.
+pub fn run(
+    path: &Path,
+    params: &[Param],
+    variables: &Variables,
+    preset: &Preset,
+    force: bool,
+) -> Result<(), String> {
+    let input = crate::replay::read_to_string(path).map_err(|err| err.to_string())?;
+    let mut header = params.iter().map(|p| p.name.clone()).collect::<Vec<_>>();
+    header.push("states".to_string());
+    let mut targets_shown = false;
+    let mut rows = Vec::new();
+    for point in grid(params) {
+        let mut variables = variables.clone();
+        let mut cap = None;
+        for (param, value) in params.iter().zip(&point) {
+            if param.name == "cap" {
+                cap = Some(
+                    value
+                        .parse::<u32>()
+                        .map_err(|_| format!("cap `{}` is not a whole number", value))?,
+                );
+            } else {
+                variables.insert(&param.name, value);
+            }
+        }
+        let mut scenario = Scenario::parse_with(&input, &variables, preset)
+            .map_err(|err| format!("at {}: {}", point.join(", "), err))?;
+        if let Some(cap) = cap {
+            scenario.cap = cap;
+        }
+        if scenario.targets.is_empty() {
+            return Err("the scenario has no targets to sweep".to_string());
+        }
+        if !targets_shown {
+            header.extend(scenario.targets.iter().map(|target| target.compact()));
+            targets_shown = true;
+        }
+
+        let mut row = point
+            .iter()
+            .map(|value| value.to_string())
+            .collect::<Vec<_>>();
+        let mut candy_works = match scenario.problem() {
+            Ok(candy_works) => candy_works,
+            Err(err) => {
+                rows.push((row, Some(err.to_string())));
+                continue;
+            }
+        };
+        if !force && guard::check(candy_works.max_candies.into()).is_err() {
+            let note = format!(
+                "skipped, up to {} states",
+                guard::estimate_states(candy_works.max_candies.into())
+            );
+            rows.push((row, Some(note)));
+            continue;
+        }
+        candy_works.explore();
+        row.push(candy_works.combinations.len().to_string());
+        for target in &scenario.targets {
+            row.push(match candy_works.find_optimal_route(*target) {
+                Some(route) => format!("{} trades", route.len()),
+                None => "unreachable".to_string(),
+            });
+        }
+        rows.push((row, None));
+    }
+
+    // Rows of skipped points end in a note instead of their results, which
+    // is left out of the column widths.
+    let widths = (0..header.len())
+        .map(|column| {
+            rows.iter()
+                .filter_map(|(row, _)| row.get(column))
+                .chain([&header[column]])
+                .map(|cell| cell.len())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect::<Vec<_>>();
+    let line = |cells: &[String]| {
+        cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:>width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ")
+    };
+    println!("{}", line(&header));
+    for (row, note) in &rows {
+        match note {
+            Some(note) => println!("{}  {}", line(row), note),
+            None => println!("{}", line(row)),
+        }
+    }
+    Ok(())
+}
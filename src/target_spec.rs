@@ -0,0 +1,194 @@
+use std::{fmt, str::FromStr};
+
+use crate::{
+    count::Count,
+    kinds,
+    names::{self, CandyNames},
+    objective::Objective,
+    route::Route,
+    uses::Ranking,
+    Candies, CandyWorks,
+};
+
+/// How many of one candy a target asks for.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Bound {
+    /// Any number, as for the candies a target does not name.
+    #[default]
+    Any,
+    AtLeast(Count),
+    Exactly(Count),
+}
+
+/// A target with a bound on each candy and on the total, written as in
+/// `2m =1c 5*`: at least 2 meats, exactly 1 cake and at least 5 candies
+/// of any kinds in all. Candies written plainly are lower bounds, as
+/// targets given as `Candies` are.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TargetSpec {
+    bounds: Vec<Bound>,
+    total: Option<Count>,
+}
+
+impl From<Candies> for TargetSpec {
+    fn from(target: Candies) -> TargetSpec {
+        TargetSpec {
+            bounds: (0..kinds::count())
+                .map(|i| match target.get_by_index(i) {
+                    Count::ZERO => Bound::Any,
+                    count => Bound::AtLeast(count),
+                })
+                .collect(),
+            total: None,
+        }
+    }
+}
+
+impl TargetSpec {
+    /// Reads a target such as `2m =1c 5*`: `=` before a count and a candy
+    /// asks for exactly that many, `=0m` for none, and a count before `*`
+    /// for at least that many candies in all; the rest is read as candies,
+    /// each at least as many as written.
+    pub fn parse_with(input: &str, names: &CandyNames) -> Result<TargetSpec, String> {
+        let mut exactly = Vec::new();
+        let mut total = None;
+        let mut rest = Vec::new();
+        for token in input.split([' ', ',']).filter(|token| !token.is_empty()) {
+            if let Some(count) = token.strip_suffix('*') {
+                let count = count.parse::<u32>().map_err(|_| {
+                    format!("`{}` is not a total, expected a count such as `5*`", token)
+                })?;
+                total = Some(Count::new(count as i32));
+            } else if let Some(exact) = token.strip_prefix('=') {
+                let digits =
+                    exact.len() - exact.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+                let (count, name) = exact.split_at(digits);
+                let count = count
+                    .parse::<u32>()
+                    .map_err(|_| format!("`{}` has no count, expected e.g. `=2m`", token))?;
+                let index = names
+                    .find(name)
+                    .ok_or_else(|| format!("unknown candy `{}` in `{}`", name, token))?;
+                exactly.push((index, Count::new(count as i32)));
+            } else {
+                rest.push(token);
+            }
+        }
+        let at_least =
+            Candies::parse_with(&rest.join(" "), names).map_err(|err| err.to_string())?;
+        let mut spec = TargetSpec::from(at_least);
+        for (index, count) in exactly {
+            if spec.bounds[index] != Bound::Any {
+                return Err(format!("{} are asked for twice", names.plural(index)));
+            }
+            spec.bounds[index] = Bound::Exactly(count);
+        }
+        spec.total = total;
+        Ok(spec)
+    }
+
+    /// Whether `candies` meet every bound.
+    pub fn matches(&self, candies: &Candies) -> bool {
+        self.bounds
+            .iter()
+            .enumerate()
+            .all(|(i, bound)| match bound {
+                Bound::Any => true,
+                Bound::AtLeast(count) => candies.get_by_index(i) >= *count,
+                Bound::Exactly(count) => candies.get_by_index(i) == *count,
+            })
+            && self.total.is_none_or(|total| candies.total() >= total)
+    }
+
+    /// The target as candies to hold at least, when it asks for nothing
+    /// more than that.
+    pub fn lower_bound(&self) -> Option<Candies> {
+        if self.total.is_some() {
+            return None;
+        }
+        let mut target = Candies::none();
+        for (i, bound) in self.bounds.iter().enumerate() {
+            match bound {
+                Bound::Any => (),
+                Bound::AtLeast(count) => target.add_by_index(i, *count),
+                Bound::Exactly(_) => return None,
+            }
+        }
+        Some(target)
+    }
+}
+
+impl FromStr for TargetSpec {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<TargetSpec, String> {
+        TargetSpec::parse_with(input, names::active())
+    }
+}
+
+impl fmt::Display for TargetSpec {
+    /// Writes the target as `parse_with` reads it.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut parts = Vec::new();
+        for (letter, bound) in kinds::active().letters().zip(&self.bounds) {
+            match bound {
+                Bound::Any => (),
+                Bound::AtLeast(count) => parts.push(format!("{}{}", count, letter)),
+                Bound::Exactly(count) => parts.push(format!("={}{}", count, letter)),
+            }
+        }
+        if let Some(total) = self.total {
+            parts.push(format!("{}*", total));
+        }
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+impl CandyWorks {
+    /// The best route by the objective to an explored state meeting
+    /// `spec`, as `find_optimal_route` finds for targets that are only
+    /// lower bounds, keeping to the use limits as it does.
+    pub fn find_spec_route(&self, spec: &TargetSpec) -> Option<Route> {
+        if let Some(target) = spec.lower_bound() {
+            return self.find_optimal_route(target);
+        }
+        if let Objective::MinTrades = self.objective {
+            return self.fewest_trades_matching(|candies| spec.matches(candies));
+        }
+        let max = self
+            .combinations
+            .states()
+            .filter(|candies| spec.matches(candies))
+            .map(|candies| self.objective.score(&candies))
+            .max()?;
+        let index = self
+            .combinations
+            .states()
+            .position(|candies| spec.matches(&candies) && self.objective.score(&candies) == max)?;
+        self.keep_to_uses(
+            self.route(index),
+            |candies| spec.matches(candies),
+            Ranking::Objective,
+        )
+    }
+
+    /// The best route by the objective to an explored state meeting
+    /// `spec` in a loaded graph. Merged graphs have several starts, and
+    /// the route is planned from the one its state was first reached from.
+    pub fn graph_spec_route(&self, spec: &TargetSpec) -> Option<Route> {
+        match (spec.lower_bound(), &self.objective) {
+            (Some(target), Objective::MinTrades) => self.fewest_trades_route(target),
+            (Some(target), _) => self
+                .find_optimal_combination(target)
+                .map(|index| self.route(index))
+                .and_then(|route| {
+                    self.keep_to_uses(
+                        route,
+                        |candies| candies.contains(&target),
+                        Ranking::Objective,
+                    )
+                }),
+            (None, _) => self.find_spec_route(spec),
+        }
+    }
+}
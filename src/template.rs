@@ -0,0 +1,81 @@
+use std::sync::OnceLock;
+
+/// Values for the `${NAME}` variables of scenario files, given with
+/// `--set`; variables not set there are read from the environment.
+#[derive(Clone, Default, PartialEq)]
+pub struct Variables(Vec<(String, String)>);
+
+impl Variables {
+    /// Adds a `name=value` assignment; a later one for the same name wins.
+    pub fn set(&mut self, assignment: &str) -> Result<(), String> {
+        let (name, value) = assignment
+            .split_once('=')
+            .filter(|(name, _)| is_name(name))
+            .ok_or_else(|| format!("expected `NAME=value`, got {:?}", assignment))?;
+        self.insert(name, value);
+        Ok(())
+    }
+
+    pub fn insert(&mut self, name: &str, value: &str) {
+        self.0.push((name.to_string(), value.to_string()));
+    }
+
+    fn get(&self, name: &str) -> Option<String> {
+        self.0
+            .iter()
+            .rev()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.clone())
+            .or_else(|| crate::replay::env_var(name))
+    }
+
+    /// Replaces every `${NAME}` and `${NAME:-default}` in `line`.
+    pub fn expand(&self, line: &str) -> Result<String, String> {
+        let mut out = String::new();
+        let mut rest = line;
+        while let Some(start) = rest.find("${") {
+            out.push_str(&rest[..start]);
+            let end = rest[start..]
+                .find('}')
+                .ok_or("`${` without a closing `}`")?;
+            let inner = &rest[start + 2..start + end];
+            let (name, default) = match inner.split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (inner, None),
+            };
+            if !is_name(name) {
+                return Err(format!("invalid variable name `{}`", name));
+            }
+            let value = self.get(name).or(default.map(str::to_string)).ok_or_else(|| {
+                format!(
+                    "variable `{}` is not set, pass --set {}=... or give a default as `${{{}:-...}}`",
+                    name, name, name
+                )
+            })?;
+            out.push_str(&value);
+            rest = &rest[start + end + 1..];
+        }
+        out.push_str(rest);
+        Ok(out)
+    }
+}
+
+fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+static ACTIVE: OnceLock<Variables> = OnceLock::new();
+
+/// Sets the variables scenario files are read with for the rest of the
+/// run, failing once other variables are in use.
+pub fn configure(variables: Variables) -> Result<(), String> {
+    crate::configure_once(&ACTIVE, variables, "the scenario variables are")
+}
+
+pub fn active() -> &'static Variables {
+    ACTIVE.get_or_init(Variables::default)
+}
@@ -0,0 +1,24 @@
+//! Helpers the unit tests share for writing candies and trades as the
+//! scenario file does.
+
+use crate::{Candies, CandyWorks, Trade};
+
+pub fn candies(input: &str) -> Candies {
+    input.parse().unwrap()
+}
+
+pub fn trade(input: &str) -> Trade {
+    input.parse().unwrap()
+}
+
+/// The problem of `inventory` within `cap` offering only `trades`,
+/// explored.
+pub fn explored(inventory: &str, cap: u32, trades: &[&str]) -> CandyWorks {
+    let mut candy_works = CandyWorks::builder(candies(inventory))
+        .cap(cap)
+        .trades(trades.iter().map(|input| trade(input)))
+        .build()
+        .unwrap();
+    candy_works.explore();
+    candy_works
+}
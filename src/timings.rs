@@ -0,0 +1,82 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+#[derive(Clone, Copy)]
+pub enum Phase {
+    /// Exploring the graph, including the two phases below.
+    Exploration,
+    /// Checking explored states for new ones.
+    Hashing,
+    /// Storing new states and queueing them.
+    Storing,
+    /// Finding the best state holding a target.
+    Search,
+    /// Following parents back from a state to the route reaching it.
+    Reconstruction,
+}
+
+/// How long each phase took, added up over the problems sharing it, as
+/// `CandyWorks::timings` does; problems without one are not timed.
+#[derive(Default)]
+pub struct Timings {
+    nanos: [AtomicU64; 5],
+}
+
+impl Timings {
+    pub fn new() -> Self {
+        Timings::default()
+    }
+
+    pub fn record(&self, phase: Phase, duration: Duration) {
+        self.nanos[phase as usize].fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    fn get(&self, phase: Phase) -> Duration {
+        Duration::from_nanos(self.nanos[phase as usize].load(Ordering::Relaxed))
+    }
+}
+
+/// Records `duration` in `timings`, if there are any.
+pub fn record(timings: Option<&Timings>, phase: Phase, duration: Duration) {
+    if let Some(timings) = timings {
+        timings.record(phase, duration);
+    }
+}
+
+/// Runs `f`, recording how long it took in `timings`, if there are any.
+pub fn time<T>(timings: Option<&Timings>, phase: Phase, f: impl FnOnce() -> T) -> T {
+    let Some(timings) = timings else {
+        return f();
+    };
+    let started = Instant::now();
+    let result = f();
+    timings.record(phase, started.elapsed());
+    result
+}
+
+/// Prints the collected timings to stderr, so they stay out of the regular
+/// output. Expansion is what exploring took beyond hashing and storing:
+/// applying trades and walking the queue. Timing every state slows the
+/// search down, most of all hashing, so the phases are best compared with
+/// each other rather than with untimed runs.
+pub fn report(timings: &Timings, started: Instant) {
+    let get = |phase: Phase| timings.get(phase);
+    let exploration = get(Phase::Exploration);
+    let hashing = get(Phase::Hashing);
+    let storing = get(Phase::Storing);
+    let rows = [
+        ("exploration", exploration),
+        ("  expansion", exploration.saturating_sub(hashing + storing)),
+        ("  hashing", hashing),
+        ("  storing", storing),
+        ("route search", get(Phase::Search)),
+        ("reconstruction", get(Phase::Reconstruction)),
+        ("total", started.elapsed()),
+    ];
+    eprintln!("Timings:");
+    for (name, duration) in rows {
+        eprintln!("  {:<16}{:>12.3} ms", name, duration.as_secs_f64() * 1000.0);
+    }
+}
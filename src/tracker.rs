@@ -0,0 +1,102 @@
+use std::{fs, path::Path};
+
+use crate::{display_side, route_file, Trade};
+
+/// A route being made in the game and how many of its steps are done, kept
+/// in a file so the plan survives closing the terminal: a `done = N` line
+/// followed by the route as `--save-route` writes it, notes included.
+pub struct Tracker {
+    route: Vec<(Trade, Option<String>)>,
+    done: usize,
+}
+
+/// `  # note` for a step with a note, or nothing.
+fn suffix(note: &Option<String>) -> String {
+    note.as_ref()
+        .map_or(String::new(), |note| format!("  # {}", note))
+}
+
+impl Tracker {
+    pub fn start(route: Vec<(Trade, Option<String>)>) -> Tracker {
+        Tracker { route, done: 0 }
+    }
+
+    pub fn load(path: &Path) -> Result<Tracker, String> {
+        let input = crate::replay::read_to_string(path).map_err(|err| err.to_string())?;
+        let mut done = None;
+        // The `done` line is blanked rather than dropped so the route's
+        // errors keep their line numbers.
+        let rest = input
+            .lines()
+            .map(|line| match line.trim().strip_prefix("done =") {
+                Some(count) => {
+                    done = Some(count.trim().parse::<usize>().ok());
+                    ""
+                }
+                None => line,
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let done = done
+            .ok_or("missing `done = N`; start tracking with `route start`")?
+            .ok_or("`done` must be a number of steps")?;
+        let route = route_file::parse_noted(&rest)?;
+        if done > route.len() {
+            return Err(format!(
+                "{} steps are marked done but the route has {}",
+                done,
+                route.len()
+            ));
+        }
+        Ok(Tracker { route, done })
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let out = format!("done = {}\n{}", self.done, route_file::format(&self.route));
+        fs::write(path, out)
+    }
+
+    /// Marks every step up to and including `step`, counted from 1, as
+    /// done; a smaller step than before takes back the later ones.
+    pub fn mark_done(&mut self, step: usize) -> Result<(), String> {
+        if step == 0 || step > self.route.len() {
+            return Err(format!(
+                "step {} is not in the route, which has steps 1 to {}",
+                step,
+                self.route.len()
+            ));
+        }
+        self.done = step;
+        Ok(())
+    }
+
+    /// Prints how far the route is and the step to make next.
+    pub fn print(&self) {
+        let Some((next, note)) = self.route.get(self.done) else {
+            println!("All {} steps are done", self.route.len());
+            return;
+        };
+        println!("{} of {} steps done", self.done, self.route.len());
+        println!(
+            "Next: step {}, trade {} -> {}{}",
+            self.done + 1,
+            display_side(&next.give),
+            display_side(&next.receive),
+            suffix(note)
+        );
+    }
+
+    /// Prints every step not done yet.
+    pub fn print_remaining(&self) {
+        self.print();
+        for (n, (trade, note)) in self.route.iter().enumerate().skip(self.done + 1) {
+            println!(
+                "  {}. {} -> {}{}",
+                n + 1,
+                display_side(&trade.give),
+                display_side(&trade.receive),
+                suffix(note)
+            );
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use crate::{names::CandyNames, Candies};
+
+/// What a column of the header holds.
+enum Column {
+    /// The count of one candy given, as in `give_eggs`.
+    Give(usize),
+    /// The count of one candy received, as in `recv_meats`.
+    Receive(usize),
+    /// Every candy given, written as in scenarios, such as `3e 1w`.
+    Gives,
+    /// Every candy received, such as `1m`.
+    Receives,
+    Vendor,
+    Note,
+}
+
+/// Trades kept in a spreadsheet and exported as CSV, as catalog `trade`
+/// lines written with `names`, one for each row, so that line numbers
+/// stay those of the rows. The header names the columns: `give_eggs` to
+/// `recv_meats` (or `receive_meats`) hold the count of one candy each,
+/// and `give` and `receive` both sides written as in scenarios, such as
+/// `3e` and `1m`; `vendor` and `note` are optional. Blank cells count as
+/// nothing, and blank rows are skipped.
+pub fn catalog(input: &str, names: &CandyNames) -> Result<String, String> {
+    let mut lines = input.lines().enumerate();
+    let Some((header_number, header)) = lines.find(|(_, line)| !line.trim().is_empty()) else {
+        return Err("no header row".to_string());
+    };
+    let columns = cells(header)
+        .map_err(|err| format!("line {}: {}", header_number + 1, err))?
+        .iter()
+        .map(|name| column(name, names))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| format!("header: {}", err))?;
+    let sides = (
+        columns
+            .iter()
+            .any(|column| matches!(column, Column::Give(_) | Column::Gives)),
+        columns
+            .iter()
+            .any(|column| matches!(column, Column::Receive(_) | Column::Receives)),
+    );
+    if sides != (true, true) {
+        return Err(
+            "header: expected columns such as give_eggs and recv_meats, or give and receive"
+                .to_string(),
+        );
+    }
+    let mut catalog = "\n".repeat(header_number + 1);
+    for (number, line) in lines {
+        if line.trim().is_empty() {
+            catalog.push('\n');
+            continue;
+        }
+        let row = cells(line)
+            .and_then(|row| trade_line(&columns, &row, names))
+            .map_err(|err| format!("line {}: {}", number + 1, err))?;
+        catalog.push_str(&row);
+        catalog.push('\n');
+    }
+    Ok(catalog)
+}
+
+fn column(name: &str, names: &CandyNames) -> Result<Column, String> {
+    let name = name.trim().to_lowercase();
+    let candy = |candy: &str| {
+        names
+            .find(candy)
+            .ok_or_else(|| format!("unknown candy `{}` in column `{}`", candy, name))
+    };
+    Ok(match name.as_str() {
+        "give" => Column::Gives,
+        "receive" | "recv" => Column::Receives,
+        "vendor" | "location" => Column::Vendor,
+        "note" => Column::Note,
+        _ => {
+            if let Some(candy_name) = name.strip_prefix("give_") {
+                Column::Give(candy(candy_name)?)
+            } else if let Some(candy_name) = name
+                .strip_prefix("recv_")
+                .or_else(|| name.strip_prefix("receive_"))
+            {
+                Column::Receive(candy(candy_name)?)
+            } else {
+                return Err(format!("unknown column `{}`", name));
+            }
+        }
+    })
+}
+
+/// The `trade = ...` line for one row under `columns`.
+fn trade_line(columns: &[Column], row: &[String], names: &CandyNames) -> Result<String, String> {
+    if row.len() > columns.len() {
+        return Err(format!("{} cells for {} columns", row.len(), columns.len()));
+    }
+    let (mut give, mut receive) = (Vec::new(), Vec::new());
+    let (mut vendor, mut note) = (None, None);
+    for (column, cell) in columns.iter().zip(row) {
+        let cell = cell.trim();
+        if cell.is_empty() {
+            continue;
+        }
+        let count = |index: usize| match cell.parse::<u32>() {
+            Ok(0) => Ok(None),
+            Ok(1) => Ok(Some(format!("1 {}", names.singular(index)))),
+            Ok(count) => Ok(Some(format!("{} {}", count, names.plural(index)))),
+            Err(_) => Err(format!(
+                "invalid count `{}` of {}, expected a whole number",
+                cell,
+                names.plural(index)
+            )),
+        };
+        let candies = || {
+            Candies::parse_with(cell, names)
+                .map(|_| cell.to_string())
+                .map_err(|err| format!("invalid candies `{}`: {}", cell, err))
+        };
+        match column {
+            Column::Give(index) => give.extend(count(*index)?),
+            Column::Receive(index) => receive.extend(count(*index)?),
+            Column::Gives => give.push(candies()?),
+            Column::Receives => receive.push(candies()?),
+            Column::Vendor => vendor = Some(cell),
+            Column::Note => note = Some(cell),
+        }
+    }
+    if give.is_empty() {
+        return Err("the trade gives nothing".to_string());
+    }
+    if receive.is_empty() {
+        return Err("the trade receives nothing".to_string());
+    }
+    let mut line = format!("trade = {} -> {}", give.join(" "), receive.join(" "));
+    if let Some(note) = note {
+        if note.contains(['@', ';', '#']) {
+            return Err(format!("the note `{}` cannot hold `@`, `;` or `#`", note));
+        }
+        line.push_str(&format!("; note {}", note));
+    }
+    if let Some(vendor) = vendor {
+        if vendor.contains('#') {
+            return Err(format!("the vendor `{}` cannot hold `#`", vendor));
+        }
+        line.push_str(&format!(" @ {}", vendor));
+    }
+    Ok(line)
+}
+
+/// The cells of one CSV row; a cell in double quotes may hold commas, and
+/// `""` for a quote.
+fn cells(line: &str) -> Result<Vec<String>, String> {
+    let mut cells = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        let cell = cells.last_mut().unwrap();
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                cell.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => cells.push(String::new()),
+            c => cell.push(c),
+        }
+    }
+    if quoted {
+        return Err("unterminated quote".to_string());
+    }
+    Ok(cells)
+}
@@ -0,0 +1,225 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BinaryHeap, HashMap},
+    fmt::Write,
+};
+
+use crate::{effort::EffortModel, notes::Notes, scenario::Scenario, Candies, CandyWorks, Trade};
+
+/// Where each trade can be made and how long it takes to walk between
+/// places, from the `@ location`, `travel` and `start` lines of a scenario.
+pub struct TravelMap {
+    locations: Vec<String>,
+    /// Locations offering each tagged trade; trades not listed here (and
+    /// untagged ones) can be made wherever the player stands.
+    offers: HashMap<Trade, Vec<usize>>,
+    anywhere: Vec<Trade>,
+    times: HashMap<(usize, usize), f64>,
+    start: Option<usize>,
+}
+
+pub struct Step {
+    pub trade: Trade,
+    /// Where the trade is made, `None` if it can be made anywhere.
+    pub location: Option<String>,
+    /// Time spent walking there from the previous step.
+    pub travel: f64,
+}
+
+/// The itinerary of `steps` taking `time` as text, a line a step saying
+/// where to walk and the trade to make there with its note in `notes`.
+pub fn itinerary(steps: &[Step], time: f64, notes: &Notes) -> String {
+    let mut out = String::new();
+    writeln!(out, "Itinerary:").unwrap();
+    for (n, step) in steps.iter().enumerate() {
+        let trade = format!(
+            "{} -> {}{}",
+            step.trade.give.to_string().trim(),
+            step.trade.receive.to_string().trim(),
+            notes.suffix(&step.trade)
+        );
+        match &step.location {
+            Some(location) if step.travel > 0.0 => writeln!(
+                out,
+                "  {}. walk to {} ({}), {}",
+                n + 1,
+                location,
+                step.travel,
+                trade
+            )
+            .unwrap(),
+            Some(location) => writeln!(out, "  {}. at {}, {}", n + 1, location, trade).unwrap(),
+            None => writeln!(out, "  {}. anywhere, {}", n + 1, trade).unwrap(),
+        }
+    }
+    writeln!(out, "Estimated time: {}", time).unwrap();
+    out
+}
+
+/// A cost ordered by `f64::total_cmp`, for keeping costs in a heap.
+#[derive(PartialEq)]
+pub struct Cost(pub f64);
+
+impl Eq for Cost {}
+
+impl PartialOrd for Cost {
+    fn partial_cmp(&self, other: &Cost) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Cost {
+    fn cmp(&self, other: &Cost) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+fn intern(locations: &mut Vec<String>, name: &str) -> usize {
+    match locations.iter().position(|l| l == name) {
+        Some(i) => i,
+        None => {
+            locations.push(name.to_string());
+            locations.len() - 1
+        }
+    }
+}
+
+/// A state index and where the player stands, if anywhere yet.
+type Node = (usize, Option<usize>);
+
+impl TravelMap {
+    /// Returns `None` when the scenario tags no trade with a location.
+    pub fn from_scenario(scenario: &Scenario) -> Option<TravelMap> {
+        if scenario.locations.iter().all(Option::is_none) {
+            return None;
+        }
+        let mut locations = Vec::new();
+        let mut offers = HashMap::<Trade, Vec<usize>>::new();
+        let mut anywhere = Vec::new();
+        for (trade, location) in scenario.trades.iter().zip(&scenario.locations) {
+            match location {
+                Some(location) => offers
+                    .entry(*trade)
+                    .or_default()
+                    .push(intern(&mut locations, location)),
+                None => anywhere.push(*trade),
+            }
+        }
+        let mut times = HashMap::new();
+        for (from, to, time) in &scenario.travel {
+            let (from, to) = (intern(&mut locations, from), intern(&mut locations, to));
+            times.insert((from, to), *time);
+            // A path written once is assumed to take as long both ways.
+            times.entry((to, from)).or_insert(*time);
+        }
+        let start = scenario
+            .start
+            .as_deref()
+            .map(|start| intern(&mut locations, start));
+        Some(TravelMap {
+            locations,
+            offers,
+            anywhere,
+            times,
+            start,
+        })
+    }
+
+    fn travel(&self, from: Option<usize>, to: usize) -> Option<f64> {
+        match from {
+            None => Some(0.0),
+            Some(from) if from == to => Some(0.0),
+            Some(from) => self.times.get(&(from, to)).copied(),
+        }
+    }
+
+    /// The places `trade` can be made at, `None` standing for wherever the
+    /// player already is.
+    fn places(&self, trade: &Trade) -> Vec<Option<usize>> {
+        if self.anywhere.contains(trade) {
+            return vec![None];
+        }
+        match self.offers.get(trade) {
+            Some(places) => places.iter().map(|&p| Some(p)).collect(),
+            None => vec![None],
+        }
+    }
+}
+
+impl CandyWorks {
+    /// Among the explored routes to a best state for `target`, finds the
+    /// one that takes the least time counting `model.trade` per trade plus
+    /// the walking between vendors, with a shortest-path search over
+    /// (state, location) pairs. `None` as well if that route breaks the use
+    /// limits.
+    pub fn route_with_travel(
+        &self,
+        map: &TravelMap,
+        model: &EffortModel,
+        target: Candies,
+    ) -> Option<(Vec<Step>, f64)> {
+        let best = self
+            .combinations
+            .state(self.find_optimal_combination(target)?)
+            .total();
+        let index = self
+            .combinations
+            .states()
+            .enumerate()
+            .map(|(i, candies)| (candies, i))
+            .collect::<HashMap<_, _>>();
+        let places = self
+            .trades
+            .iter()
+            .map(|trade| map.places(trade))
+            .collect::<Vec<_>>();
+        let start = (index[&self.candies], map.start);
+        let mut costs = HashMap::from([(start, 0.0)]);
+        let mut previous = HashMap::<Node, (Node, Trade, Option<usize>, f64)>::new();
+        let mut heap = BinaryHeap::from([Reverse((Cost(0.0), start))]);
+        while let Some(Reverse((Cost(cost), node))) = heap.pop() {
+            if costs.get(&node).is_some_and(|&known| known < cost) {
+                continue;
+            }
+            let (state, location) = node;
+            let candies = self.combinations.state(state);
+            if candies.contains(&target) && candies.total() == best {
+                let mut steps = Vec::new();
+                let mut current = node;
+                while let Some(&(before, trade, place, travel)) = previous.get(&current) {
+                    steps.push(Step {
+                        trade,
+                        location: place.map(|p| map.locations[p].clone()),
+                        travel,
+                    });
+                    current = before;
+                }
+                steps.reverse();
+                let trades = steps.iter().map(|step| step.trade).collect::<Vec<_>>();
+                return self.use_limits.allow(&trades).then_some((steps, cost));
+            }
+            for (trade, places) in self.trades.iter().zip(&places) {
+                let Some(&next) = candies.trade(trade).and_then(|next| index.get(&next)) else {
+                    continue;
+                };
+                for place in places {
+                    let (travel, arrived) = match place {
+                        None => (0.0, location),
+                        Some(place) => match map.travel(location, *place) {
+                            Some(travel) => (travel, Some(*place)),
+                            None => continue,
+                        },
+                    };
+                    let cost = cost + model.trade + travel;
+                    let node_next = (next, arrived);
+                    if costs.get(&node_next).is_none_or(|&known| cost < known) {
+                        costs.insert(node_next, cost);
+                        previous.insert(node_next, (node, *trade, *place, travel));
+                        heap.push(Reverse((Cost(cost), node_next)));
+                    }
+                }
+            }
+        }
+        None
+    }
+}
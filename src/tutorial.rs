@@ -0,0 +1,109 @@
+use crate::{names, parse_letters, print_statistics, Candies, CandyWorks, Prompt, Trade};
+
+/// Asks `question` until the answer passes `check`, repeating `hint` after
+/// each wrong one. Returns `None` once input has ended.
+fn ask_until(
+    rl: &mut impl Prompt,
+    question: &str,
+    hint: &str,
+    check: impl Fn(&str) -> bool,
+) -> Option<()> {
+    rl.say(question);
+    loop {
+        let answer = rl.ask(">> ")?;
+        if check(answer.trim()) {
+            return Some(());
+        }
+        rl.say(hint);
+    }
+}
+
+fn ask_number(rl: &mut impl Prompt, question: &str, expected: usize) -> Option<()> {
+    ask_until(
+        rl,
+        question,
+        &format!("Not quite, look again. (The answer is {}.)", expected),
+        |answer| answer.parse() == Ok(expected),
+    )
+}
+
+/// Walks through the interactive flow on a small built-in scenario,
+/// checking each answer before moving on.
+pub fn run(rl: &mut impl Prompt) {
+    if steps(rl).is_none() {
+        rl.say("\nTutorial stopped.");
+    }
+}
+
+fn steps(rl: &mut impl Prompt) -> Option<()> {
+    let inventory = "4e 2w".parse::<Candies>().unwrap();
+    let trade = "1w -> 2c".parse::<Trade>().unwrap();
+    let target = "1m".parse::<Candies>().unwrap();
+
+    rl.say("Welcome to candyworks! This tutorial solves a small scenario with you.\n");
+    rl.say("Step 1 of 4: the inventory");
+    rl.say(&format!(
+        "Candyworks first asks how many of each candy you have. Say your bag holds {}.",
+        inventory.display(false).trim()
+    ));
+    for (i, name) in names::active().plurals().iter().enumerate() {
+        let expected = inventory.get_by_index(i).get() as usize;
+        ask_number(rl, &format!("How many {} do you have?", name), expected)?;
+    }
+
+    rl.say("\nStep 2 of 4: trades");
+    rl.say(
+        "Next come the trades vendors offer, typed as one letter per candy: \
+         E for eggs, W for worms, C for cakes, F for fishes and M for meats.",
+    );
+    rl.say(&format!(
+        "A vendor gives {} for {}. Enter this trade, first what you give, then what you receive.",
+        trade.receive.display(false).trim(),
+        trade.give.display(false).trim()
+    ));
+    let hint = "Type one letter per candy, for example EEE for three eggs.";
+    ask_until(rl, "What do you give?", hint, |answer| {
+        parse_letters(answer) == trade.give
+    })?;
+    ask_until(rl, "What do you receive?", hint, |answer| {
+        parse_letters(answer) == trade.receive
+    })?;
+    rl.say("Any three candies can also always be traded for one of another kind.");
+
+    rl.say("\nStep 3 of 4: exploring");
+    rl.say("Candyworks now tries every sequence of trades and counts what it reached:");
+    let mut candy_works = CandyWorks::new(inventory, 20, vec![trade]);
+    candy_works.explore();
+    print_statistics(&candy_works);
+    ask_number(
+        rl,
+        "How many different inventories can you reach?",
+        candy_works.combinations.len(),
+    )?;
+
+    rl.say("\nStep 4 of 4: reading a route");
+    rl.say(&format!(
+        "Finally you say what you want, here {}. Candyworks finds the route that \
+         gets it while keeping as many candies as possible:",
+        target.display(false).trim()
+    ));
+    let route = candy_works.find_optimal_route(target).unwrap();
+    print!(
+        "{}",
+        candy_works.format_route(candy_works.candies, route.trades())
+    );
+    rl.say(
+        "Each line shows your inventory and the trade to make next; the last line is the result.",
+    );
+    ask_number(rl, "How many trades does the route take?", route.len())?;
+    let end = route.end();
+    ask_number(
+        rl,
+        "How many candies do you have at the end?",
+        end.total().get() as usize,
+    )?;
+
+    rl.say("\nWell done! Run candyworks without a command to solve your own inventory,");
+    rl.say("or see candyworks --help for scenario files and everything else.");
+    Some(())
+}
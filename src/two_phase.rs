@@ -0,0 +1,71 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::{route::Route, uses::Ranking, Candies, CandyWorks};
+
+impl CandyWorks {
+    /// Computes every reachable state without parent links, which is enough
+    /// to tell whether a target can be reached at all.
+    pub fn reachable_set(&self) -> HashSet<Candies> {
+        let mut known = HashSet::from([self.candies]);
+        let mut queue = VecDeque::from([self.candies]);
+        while let Some(candies) = queue.pop_front() {
+            for trade in &self.trades {
+                if let Some(new_candies) = candies.trade(trade) {
+                    if self.fits(&new_candies) && known.insert(new_candies) {
+                        queue.push_back(new_candies);
+                    }
+                }
+            }
+        }
+        known
+    }
+
+    /// Finds a route as good as `find_optimal_route` (same final total and
+    /// length, ties may break differently) from a set computed by
+    /// `reachable_set`: the reachable states with the most candies that
+    /// contain the target are searched backwards, staying inside the set,
+    /// until the starting inventory is met. The route keeps to the use
+    /// limits, as `find_optimal_route`'s does.
+    pub fn route_backward(&self, reachable: &HashSet<Candies>, target: Candies) -> Option<Route> {
+        if self.candies.contains(&target) {
+            return Some(Route::empty(self.candies));
+        }
+        let best = reachable
+            .iter()
+            .filter(|candies| candies.contains(&target))
+            .map(|candies| candies.total())
+            .max()?;
+        let mut next = HashMap::new();
+        let mut queue = VecDeque::new();
+        for candies in reachable {
+            if candies.total() == best && candies.contains(&target) {
+                next.insert(*candies, None);
+                queue.push_back(*candies);
+            }
+        }
+        while let Some(candies) = queue.pop_front() {
+            if candies == self.candies {
+                let mut route = Vec::new();
+                let mut current = candies;
+                while let Some(Some((trade, after))) = next.get(&current).copied() {
+                    route.push(trade);
+                    current = after;
+                }
+                return self.keep_to_uses(
+                    Route::new(self.candies, route).unwrap(),
+                    |candies| candies.contains(&target),
+                    Ranking::Objective,
+                );
+            }
+            for trade in &self.trades {
+                if let Some(before) = candies.untrade(trade) {
+                    if reachable.contains(&before) && !next.contains_key(&before) {
+                        next.insert(before, Some((*trade, candies)));
+                        queue.push_back(before);
+                    }
+                }
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,120 @@
+use std::{collections::HashMap, fmt::Write};
+
+use crate::{count::Count, display_side, kinds, route::Route, Candies, CandyWorks, Trade};
+
+/// Trades whose receive is only known within a range, keyed by the least
+/// they receive, with how many more candies they may give out.
+pub struct Uncertainty {
+    spreads: HashMap<Trade, Candies>,
+}
+
+impl Uncertainty {
+    /// Collects the trades with a non-zero spread; `spreads` runs parallel
+    /// to `trades`, as in a scenario.
+    pub fn new(trades: &[Trade], spreads: &[Candies]) -> Uncertainty {
+        let mut uncertainty = Uncertainty {
+            spreads: HashMap::new(),
+        };
+        for (trade, spread) in trades.iter().zip(spreads) {
+            if *spread == Candies::none() {
+                continue;
+            }
+            let known = uncertainty
+                .spreads
+                .entry(*trade)
+                .or_insert_with(Candies::none);
+            for i in 0..kinds::count() {
+                let more = spread.get_by_index(i) - known.get_by_index(i);
+                if more > Count::ZERO {
+                    known.add_by_index(i, more);
+                }
+            }
+        }
+        uncertainty
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spreads.is_empty()
+    }
+
+    /// How far over `cap` the inventory can get along `route` if every
+    /// uncertain trade receives the most it may, or 0 if it never does.
+    pub fn excess(&self, route: &Route, cap: u32) -> u32 {
+        let mut candies = route.start();
+        let mut extra = Count::ZERO;
+        let mut excess = 0;
+        for trade in route.trades() {
+            candies = candies.trade(trade).unwrap();
+            extra += self.spreads.get(trade).map_or(Count::ZERO, Candies::total);
+            excess = excess.max((candies.total() + extra).get() - cap as i32);
+        }
+        excess as u32
+    }
+}
+
+/// A route to `target` that stays within the cap however much the
+/// uncertain trades receive. Planning counts on the least they receive,
+/// which is always enough to make the later trades, so only the cap can be
+/// broken; while it is, the route is planned again under a cap lowered by
+/// the overshoot. `None` if no route is left once the cap is that low.
+pub fn worst_case_route(
+    candy_works: &CandyWorks,
+    uncertainty: &Uncertainty,
+    target: Candies,
+    mut route: Route,
+) -> Option<Route> {
+    let cap = candy_works.max_candies;
+    let mut lowered = cap;
+    loop {
+        let excess = uncertainty.excess(&route, cap);
+        if excess == 0 {
+            return Some(route);
+        }
+        lowered = lowered.checked_sub(excess)?;
+        let mut lower = candy_works.unexplored();
+        lower.max_candies = lowered;
+        lower.explore();
+        route = lower.find_optimal_route(target)?;
+    }
+}
+
+/// The steps of `route` that make an uncertain trade and the later steps
+/// giving candies such a trade may receive more of, which the plan counts
+/// on only the least of, as text.
+pub fn dependencies(uncertainty: &Uncertainty, route: &[Trade]) -> String {
+    let mut out = String::new();
+    let mut first = true;
+    for (n, trade) in route.iter().enumerate() {
+        let Some(spread) = uncertainty.spreads.get(trade) else {
+            continue;
+        };
+        if first {
+            writeln!(out, "Steps that depend on uncertain trades:").unwrap();
+            first = false;
+        }
+        let later = route
+            .iter()
+            .enumerate()
+            .skip(n + 1)
+            .filter(|(_, later)| {
+                (0..kinds::count()).any(|i| {
+                    spread.get_by_index(i) > Count::ZERO && later.give.get_by_index(i) > Count::ZERO
+                })
+            })
+            .map(|(m, _)| (m + 1).to_string())
+            .collect::<Vec<_>>();
+        writeln!(
+            out,
+            "  Step {} may receive up to {} more than planned{}",
+            n + 1,
+            display_side(spread),
+            match later.len() {
+                0 => String::new(),
+                1 => format!("; step {} uses what it makes", later[0]),
+                _ => format!("; steps {} use what it makes", later.join(", ")),
+            }
+        )
+        .unwrap();
+    }
+    out
+}
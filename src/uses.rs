@@ -0,0 +1,404 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet, VecDeque},
+};
+
+use crate::{
+    cost::TradeCosts, count::Count, objective::Objective, route::Route, scenario::Scenario,
+    standard, travel::Cost, Candies, CandyWorks, Trade,
+};
+
+/// Trades a vendor makes only so many times, as in `3e -> 1m; uses 2`,
+/// each with how many times it can be made, in the order first written.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct UseLimits {
+    limits: Vec<(Trade, u32)>,
+}
+
+impl UseLimits {
+    /// Collects the limits; `uses` runs parallel to `trades`, as in a
+    /// scenario. A trade written more than once can be made as many times
+    /// as its lines add up to, and without a limit on one of them, or as
+    /// one of the `standard` trades offered besides, as often as wanted.
+    pub fn new(trades: &[Trade], uses: &[Option<u32>], standard: &[Trade]) -> UseLimits {
+        let mut unlimited = standard.iter().copied().collect::<HashSet<_>>();
+        let mut limits = Vec::<(Trade, u32)>::new();
+        for (trade, uses) in trades.iter().zip(uses) {
+            match uses {
+                Some(uses) => match limits.iter_mut().find(|(known, _)| known == trade) {
+                    Some((_, limit)) => *limit += uses,
+                    None => limits.push((*trade, *uses)),
+                },
+                None => {
+                    unlimited.insert(*trade);
+                }
+            }
+        }
+        limits.retain(|(trade, _)| !unlimited.contains(trade));
+        UseLimits { limits }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.limits.is_empty()
+    }
+
+    /// How many times `trade` can be made, if it is limited.
+    pub fn limit(&self, trade: &Trade) -> Option<u32> {
+        self.limits
+            .iter()
+            .find(|(known, _)| known == trade)
+            .map(|&(_, limit)| limit)
+    }
+
+    /// Whether `trades` make no trade more often than its limit.
+    pub fn allow(&self, trades: &[Trade]) -> bool {
+        if self.limits.is_empty() {
+            return true;
+        }
+        let mut used = HashMap::<Trade, u32>::new();
+        trades.iter().all(|trade| {
+            let used = used.entry(*trade).or_insert(0);
+            *used += 1;
+            self.limit(trade).is_none_or(|limit| *used <= limit)
+        })
+    }
+
+    /// The limits left once `trades` have been made.
+    pub fn less(&self, trades: &[Trade]) -> UseLimits {
+        let mut limits = self.limits.clone();
+        for trade in trades {
+            if let Some((_, left)) = limits.iter_mut().find(|(known, _)| known == trade) {
+                *left = left.saturating_sub(1);
+            }
+        }
+        UseLimits { limits }
+    }
+
+    /// No use made yet of any limited trade, as `after` counts them.
+    pub fn unused(&self) -> Vec<u32> {
+        vec![0; self.limits.len()]
+    }
+
+    /// The uses made of each limited trade once `trade` is made after
+    /// `used`, or `None` if that passes its limit.
+    pub fn after(&self, used: &[u32], trade: &Trade) -> Option<Vec<u32>> {
+        let mut used = used.to_vec();
+        if let Some(slot) = self.limits.iter().position(|(known, _)| known == trade) {
+            if used[slot] == self.limits[slot].1 {
+                return None;
+            }
+            used[slot] += 1;
+        }
+        Some(used)
+    }
+}
+
+impl UseLimits {
+    /// `  (1 use left)` for a limited `trade` made for the `used`th time,
+    /// or nothing, for the end of a line showing it. A step making the last
+    /// use is flagged as a single point of failure: with no use to spare,
+    /// the plan falls through if the vendor turns that trade down.
+    pub fn suffix(&self, trade: &Trade, used: u32) -> String {
+        match self.limit(trade) {
+            Some(limit) => {
+                let left = limit.saturating_sub(used);
+                match left {
+                    0 => "  (no uses left, single point of failure)".to_string(),
+                    1 => "  (1 use left)".to_string(),
+                    left => format!("  ({} uses left)", left),
+                }
+            }
+            None => String::new(),
+        }
+    }
+}
+
+/// How `CandyWorks::keep_to_uses` ranks the routes keeping to the limits.
+pub enum Ranking<'a> {
+    /// Best by the objective of the problem.
+    Objective,
+    /// Fewest trades, whatever the objective.
+    FewestTrades,
+    /// Costing the least by these costs, then in the fewest trades.
+    Cheapest(&'a TradeCosts),
+}
+
+/// A state of a search over the candies together with the uses made of
+/// each limited trade, as `UseLimits::after` counts them.
+pub type Node = (Candies, Vec<u32>);
+
+/// The states a search over nodes reached, each with the one it was reached
+/// from by which trade, and in how many trades.
+type Reached = Vec<(Node, Option<(usize, Trade)>, usize)>;
+
+impl CandyWorks {
+    /// `route` itself if it makes no trade more often than the use limits
+    /// of the problem allow, and otherwise the best route from the same
+    /// start to a state `goal` accepts that keeps to them, as `ranking`
+    /// ranks them, searched over the states together with the uses made of
+    /// every limited trade. `None` if every such route breaks a limit.
+    pub fn keep_to_uses(
+        &self,
+        route: Route,
+        goal: impl Fn(&Candies) -> bool,
+        ranking: Ranking,
+    ) -> Option<Route> {
+        if self.use_limits.allow(route.trades()) {
+            return Some(route);
+        }
+        let start = (route.start(), self.use_limits.unused());
+        let (nodes, end) = match ranking {
+            Ranking::Cheapest(costs) => self.cheapest_within_uses(start, &goal, costs),
+            Ranking::FewestTrades => self.best_within_uses(start, &goal, true),
+            Ranking::Objective => {
+                let fewest = self.objective == Objective::MinTrades;
+                self.best_within_uses(start, &goal, fewest)
+            }
+        };
+        let mut index = end?;
+        let mut trades = Vec::new();
+        while let Some((parent, trade)) = nodes[index].1 {
+            trades.push(trade);
+            index = parent;
+        }
+        trades.reverse();
+        let found = Route::new(route.start(), trades).unwrap();
+        Some(found.found_in(self.exploration()))
+    }
+
+    /// The state `goal` accepts ranked best by the objective, or reached
+    /// first if `fewest`, searched breadth first so the first state of a
+    /// score is the one in the fewest trades; with every state reached and
+    /// the one it was reached from by which trade.
+    fn best_within_uses(
+        &self,
+        start: Node,
+        goal: &impl Fn(&Candies) -> bool,
+        fewest: bool,
+    ) -> (Reached, Option<usize>) {
+        let mut nodes = vec![(start.clone(), None, 0_usize)];
+        let mut seen = HashSet::from([start]);
+        let mut queue = VecDeque::from([0]);
+        let mut best: Option<(usize, (i64, Count), usize)> = None;
+        while let Some(index) = queue.pop_front() {
+            let ((candies, used), _, depth) = nodes[index].clone();
+            if fewest && best.is_some_and(|(_, _, best_depth)| depth > best_depth) {
+                break;
+            }
+            if goal(&candies) {
+                let score = self.objective.score(&candies);
+                if best.is_none_or(|(_, best_score, _)| score > best_score) {
+                    best = Some((index, score, depth));
+                }
+            }
+            for trade in &self.trades {
+                let Some(next) = self.within_uses(&candies, &used, trade) else {
+                    continue;
+                };
+                if seen.insert(next.clone()) {
+                    queue.push_back(nodes.len());
+                    nodes.push((next, Some((index, *trade)), depth + 1));
+                }
+            }
+        }
+        (nodes, best.map(|(index, _, _)| index))
+    }
+
+    /// The state `goal` accepts reached at the least cost, then in the
+    /// fewest trades, searched with Dijkstra's algorithm.
+    fn cheapest_within_uses(
+        &self,
+        start: Node,
+        goal: &impl Fn(&Candies) -> bool,
+        costs: &TradeCosts,
+    ) -> (Reached, Option<usize>) {
+        let mut nodes = vec![(start.clone(), None, 0_usize)];
+        let mut best = HashMap::from([(start, (0.0, 0))]);
+        let mut heap = BinaryHeap::from([Reverse((Cost(0.0), 0, 0))]);
+        while let Some(Reverse((Cost(cost), length, index))) = heap.pop() {
+            let ((candies, used), _, _) = nodes[index].clone();
+            if best
+                .get(&(candies, used.clone()))
+                .is_some_and(|&known| known < (cost, length))
+            {
+                continue;
+            }
+            if goal(&candies) {
+                return (nodes, Some(index));
+            }
+            for trade in &self.trades {
+                let Some(next) = self.within_uses(&candies, &used, trade) else {
+                    continue;
+                };
+                let reached = (cost + costs.of(trade), length + 1);
+                if best.get(&next).is_none_or(|&known| reached < known) {
+                    best.insert(next.clone(), reached);
+                    heap.push(Reverse((Cost(reached.0), reached.1, nodes.len())));
+                    nodes.push((next, Some((index, *trade)), reached.1));
+                }
+            }
+        }
+        (nodes, None)
+    }
+
+    /// Where making `trade` from `candies` with `used` uses made leads,
+    /// unless the trade cannot be made, passes a cap or uses up its limit.
+    fn within_uses(&self, candies: &Candies, used: &[u32], trade: &Trade) -> Option<Node> {
+        let next = candies.trade(trade).filter(|next| self.fits(next))?;
+        Some((next, self.use_limits.after(used, trade)?))
+    }
+}
+
+impl Scenario {
+    /// The limits on the uses of the scenario's trades.
+    pub fn use_limits(&self) -> UseLimits {
+        UseLimits::new(&self.trades, &self.uses, &self.standard())
+    }
+
+    /// The standard trades the scenario's problems offer besides its own;
+    /// none when they are left out.
+    pub fn standard(&self) -> Vec<Trade> {
+        if self.standard_trades {
+            standard::trades(&self.preset)
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{candies, trade};
+    use crate::{advisor::Advisor, format_route_with, min_cap, notes::Notes};
+
+    /// Two meats are one egg each by the vendor, but only once.
+    const SCENARIO: &str = "\
+cap = 20
+inventory = 9e
+trade = 1e -> 1m; uses 1
+target = 2m
+";
+
+    fn problem(objective: Objective) -> CandyWorks {
+        let mut candy_works = Scenario::parse(SCENARIO).unwrap().problem().unwrap();
+        candy_works.objective = objective;
+        candy_works.explore();
+        candy_works
+    }
+
+    /// Asserts `route` reaches two meats keeping to the one use.
+    fn keeps(candy_works: &CandyWorks, route: Option<Route>) {
+        let route = route.expect("a route keeping to the uses");
+        assert!(
+            candy_works.use_limits.allow(route.trades()),
+            "{:?}",
+            route.trades()
+        );
+        assert!(route.end().contains(&candies("2m")));
+    }
+
+    #[test]
+    fn limits_come_from_the_scenario() {
+        let candy_works = problem(Objective::default());
+        assert_eq!(candy_works.use_limits.limit(&trade("1e -> 1m")), Some(1));
+        assert_eq!(candy_works.use_limits.limit(&trade("3e -> 1m")), None);
+    }
+
+    #[test]
+    fn steps_show_the_uses_left() {
+        let meat = trade("1e -> 1m");
+        let limits = UseLimits::new(&[meat], &[Some(3)], &[]);
+        assert_eq!(limits.suffix(&meat, 1), "  (2 uses left)");
+        assert_eq!(limits.suffix(&meat, 2), "  (1 use left)");
+        assert_eq!(
+            limits.suffix(&meat, 3),
+            "  (no uses left, single point of failure)"
+        );
+        assert_eq!(limits.suffix(&trade("3e -> 1m"), 1), "");
+        let route = format_route_with(candies("3e"), &[meat, meat], &limits, &Notes::default());
+        assert!(route.contains("1 egg ->  1 meat (x2)  (1 use left)\n"));
+    }
+
+    #[test]
+    fn less_and_after_count_uses() {
+        let limits = problem(Objective::default()).use_limits;
+        let trade = trade("1e -> 1m");
+        let used = limits.after(&limits.unused(), &trade).unwrap();
+        assert_eq!(limits.after(&used, &trade), None);
+        assert_eq!(limits.less(&[trade]).limit(&trade), Some(0));
+    }
+
+    #[test]
+    fn forward_routes_keep_to_uses() {
+        for objective in [
+            Objective::MaxLeftoverTotal,
+            Objective::MinTrades,
+            Objective::MaxWeightedValue(candies("1e 1m")),
+        ] {
+            let candy_works = problem(objective);
+            keeps(&candy_works, candy_works.find_optimal_route(candies("2m")));
+            keeps(&candy_works, candy_works.try_route(candies("2m")).ok());
+            keeps(
+                &candy_works,
+                candy_works.route_from(candies("9e"), candies("2m")),
+            );
+        }
+        let candy_works = problem(Objective::default());
+        let route = candy_works.find_optimal_route(candies("2m")).unwrap();
+        assert_eq!(route.end(), candies("5e 2m"));
+    }
+
+    #[test]
+    fn other_searches_keep_to_uses() {
+        let candy_works = problem(Objective::default());
+        let target = candies("2m");
+        keeps(
+            &candy_works,
+            candy_works.cheapest_route(&TradeCosts::default(), target),
+        );
+        keeps(&candy_works, candy_works.find_route_direct(target));
+        keeps(
+            &candy_works,
+            candy_works.shortest_route(candies("9e"), candies("5e 2m")),
+        );
+        assert_eq!(
+            candy_works.shortest_route(candies("9e"), candies("7e 2m")),
+            None
+        );
+        let reachable = candy_works.reachable_set();
+        keeps(&candy_works, candy_works.route_backward(&reachable, target));
+        assert!(candy_works.backward_route(target).is_err());
+        let value = candy_works.best_value_route(&candies("1m"), Some(2));
+        assert!(candy_works.use_limits.allow(value.trades()));
+        for alternative in candy_works.alternative_routes(target, 10, None) {
+            assert!(candy_works.use_limits.allow(&alternative.route));
+        }
+    }
+
+    #[test]
+    fn min_cap_keeps_to_uses() {
+        let mut candy_works = problem(Objective::default());
+        let cap = min_cap::find(&candy_works, candies("2m"), true).unwrap();
+        assert_eq!(cap, 7);
+        candy_works = candy_works.unexplored();
+        candy_works.max_candies = cap;
+        candy_works.explore();
+        keeps(&candy_works, candy_works.find_optimal_route(candies("2m")));
+    }
+
+    #[test]
+    fn advice_keeps_to_uses() {
+        let candy_works = problem(Objective::default());
+        let mut advisor = Advisor::new(&candy_works, candies("2m"));
+        let mut current = candies("9e");
+        let mut made = Vec::new();
+        while let Some(trade) = advisor.advise(current) {
+            advisor.made(&trade);
+            made.push(trade);
+            current = current.trade(&trade).unwrap();
+        }
+        assert!(advisor.done(&current));
+        assert!(candy_works.use_limits.allow(&made));
+    }
+}
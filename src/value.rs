@@ -0,0 +1,96 @@
+use std::{collections::HashMap, fmt::Write};
+
+use crate::{objective::Objective, route::Route, uses::Node, Candies, CandyWorks, Trade};
+
+/// What `candies` are worth, each kind at its weight in `weights`.
+pub fn worth(weights: &Candies, candies: &Candies) -> i64 {
+    Objective::MaxWeightedValue(*weights).score(candies).0
+}
+
+impl CandyWorks {
+    /// The route of at most `max_trades` trades, or of any number without
+    /// a budget, leaving the inventory worth the most with each candy at
+    /// its weight in `weights`. The states within the cap are searched
+    /// breadth first from the start, keeping the value of each; of states
+    /// worth as much, the one with the most candies wins, then the one
+    /// reached in the fewest trades. The start itself is the answer when
+    /// no trade adds value. With use limits, states are searched together
+    /// with the uses made of each limited trade, so the route keeps to them.
+    pub fn best_value_route(&self, weights: &Candies, max_trades: Option<usize>) -> Route {
+        let objective = Objective::MaxWeightedValue(*weights);
+        let start = (self.candies, self.use_limits.unused());
+        let mut previous: HashMap<Node, Option<(Node, Trade)>> =
+            HashMap::from([(start.clone(), None)]);
+        let mut best = (objective.score(&self.candies), start.clone());
+        let mut level = vec![start];
+        let mut depth = 0;
+        while !level.is_empty() && max_trades.is_none_or(|max_trades| depth < max_trades) {
+            let mut next = Vec::new();
+            for (candies, used) in &level {
+                for trade in &self.trades {
+                    let Some(after) = candies.trade(trade) else {
+                        continue;
+                    };
+                    let Some(after_used) = self.use_limits.after(used, trade) else {
+                        continue;
+                    };
+                    let after = (after, after_used);
+                    if !self.fits(&after.0) || previous.contains_key(&after) {
+                        continue;
+                    }
+                    previous.insert(after.clone(), Some(((*candies, used.clone()), *trade)));
+                    let score = objective.score(&after.0);
+                    if score > best.0 {
+                        best = (score, after.clone());
+                    }
+                    next.push(after);
+                }
+            }
+            level = next;
+            depth += 1;
+        }
+        let mut trades = Vec::new();
+        let mut current = best.1;
+        while let Some(Some((before, trade))) = previous.get(&current).cloned() {
+            trades.push(trade);
+            current = before;
+        }
+        trades.reverse();
+        Route::new(self.candies, trades).unwrap()
+    }
+}
+
+/// The route of `best_value_route` as text, with what the inventory is
+/// worth before and after, the reserved candies shown and counted but left
+/// alone.
+pub fn plan(
+    candy_works: &CandyWorks,
+    weights: &Candies,
+    max_trades: Option<usize>,
+    reserved: Candies,
+) -> String {
+    let mut out = String::new();
+    let route = candy_works.best_value_route(weights, max_trades);
+    let start = route.start().with(&reserved);
+    let end = route.end().with(&reserved);
+    out.push_str(&candy_works.format_route(start, route.trades()));
+    if route.is_empty() {
+        writeln!(
+            out,
+            "No trade adds value; the inventory is worth {}",
+            worth(weights, &start)
+        )
+        .unwrap();
+        return out;
+    }
+    writeln!(
+        out,
+        "Worth {} after {} trade{}, up from {}",
+        worth(weights, &end),
+        route.len(),
+        if route.len() == 1 { "" } else { "s" },
+        worth(weights, &start)
+    )
+    .unwrap();
+    out
+}
@@ -0,0 +1,131 @@
+use clap::ValueEnum;
+
+use crate::{names::CandyNames, Candies, ParseCandiesError, Trade};
+
+/// Which side a description without a verb, like "3 Eggs for 1 Cake",
+/// names first. Shops tend to list what they sell first, swap boards what
+/// is handed over.
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Notation {
+    GiveFirst,
+    ReceiveFirst,
+}
+
+/// Phrasings vendors and wikis describe trades with: what the text starts
+/// with, what separates the two sides and whether the receive side comes
+/// first. Longer separators come before ones they contain.
+const PHRASINGS: &[(&str, &str, bool)] = &[
+    ("give ", " receive ", false),
+    ("give ", " get ", false),
+    ("give ", " for ", false),
+    ("pay ", " receive ", false),
+    ("pay ", " get ", false),
+    ("pay ", " for ", false),
+    ("trade ", " for ", false),
+    ("exchange ", " for ", false),
+    ("swap ", " for ", false),
+    ("sell ", " for ", false),
+    ("receive ", " in exchange for ", true),
+    ("get ", " in exchange for ", true),
+    ("receive ", " for ", true),
+    ("get ", " for ", true),
+    ("buy ", " for ", true),
+];
+
+/// Separators of descriptions that are only the two sides, and whether
+/// they are arrows, which always point from give to receive; the others
+/// are read in the configured `Notation`.
+const BARE: &[(&str, bool)] = &[
+    ("=>", true),
+    ("→", true),
+    (" for ", false),
+    (" = ", false),
+    (": ", false),
+];
+
+/// Reads a trade pasted from a vendor description such as "Give 3 Eggs,
+/// Receive 1 Cake" or "Buy 1 cake for 3 eggs". Returns `None` when the text
+/// is in none of the known phrasings.
+pub fn parse(
+    text: &str,
+    notation: Notation,
+    names: &CandyNames,
+) -> Option<Result<Trade, ParseCandiesError>> {
+    let text = text
+        .trim()
+        .trim_end_matches(['.', '!'])
+        .to_lowercase()
+        .replace(['\t', '\u{a0}'], " ");
+    if text.contains("->") {
+        return Some(Trade::parse_with(&text, names));
+    }
+    let (first, second, reversed) = PHRASINGS
+        .iter()
+        .find_map(|(start, separator, reversed)| {
+            let (first, second) = text.strip_prefix(start)?.split_once(separator)?;
+            Some((first, second, *reversed))
+        })
+        .or_else(|| {
+            BARE.iter().find_map(|(separator, arrow)| {
+                let (first, second) = text.split_once(separator)?;
+                let reversed = !arrow && matches!(notation, Notation::ReceiveFirst);
+                Some((first, second, reversed))
+            })
+        })?;
+    let sides = side(first, names).and_then(|first| Ok((first, side(second, names)?)));
+    Some(sides.map(|(first, second)| match reversed {
+        false => Trade {
+            give: first,
+            receive: second,
+        },
+        true => Trade {
+            give: second,
+            receive: first,
+        },
+    }))
+}
+
+/// Reads one side of a description, a list like "3 Eggs and 2 Worms" whose
+/// items may also be written "3x Eggs", "Eggs x3" or "an Egg".
+fn side(text: &str, names: &CandyNames) -> Result<Candies, ParseCandiesError> {
+    let text = text
+        .trim()
+        .trim_start_matches(':')
+        .trim_end_matches([',', ';', ':', ' '])
+        .trim_end_matches(" and")
+        .trim_end_matches(" then")
+        .trim_end_matches(',');
+    let items = text
+        .replace(" and ", ",")
+        .replace(['+', '&', ';'], ",")
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(item)
+        .collect::<Vec<_>>();
+    Candies::parse_with(&items.join(", "), names)
+}
+
+/// Puts an item into the `count name` form candies are parsed from.
+fn item(item: &str) -> String {
+    let words = item
+        .split_whitespace()
+        .filter(|word| !matches!(*word, "x" | "×"))
+        .collect::<Vec<_>>();
+    let count = |word: &str| {
+        word.trim_start_matches(['x', '×'])
+            .trim_end_matches(['x', '×'])
+            .parse::<i32>()
+            .ok()
+    };
+    match words[..] {
+        ["a" | "an", ref name @ ..] => format!("1 {}", name.join(" ")),
+        [first, ref name @ ..] if count(first).is_some() && !name.is_empty() => {
+            format!("{} {}", count(first).unwrap(), name.join(" "))
+        }
+        [ref name @ .., last] if count(last).is_some() && !name.is_empty() => {
+            format!("{} {}", count(last).unwrap(), name.join(" "))
+        }
+        _ => item.to_string(),
+    }
+}
@@ -0,0 +1,85 @@
+use std::fmt;
+
+use crate::{count::Count, display_side, kinds, Candies, CandyWorks, Trade};
+
+/// Why a step of a plan cannot be made, counting steps from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StepError {
+    /// No vendor offers the trade, and it is not a standard one.
+    NotOffered { step: usize, trade: Trade },
+    /// The inventory is short of `missing` for the trade.
+    Short {
+        step: usize,
+        trade: Trade,
+        missing: Candies,
+    },
+    /// The trade would take the inventory past the cap, or a kind past
+    /// its own.
+    OverCap {
+        step: usize,
+        trade: Trade,
+        after: Candies,
+    },
+}
+
+impl fmt::Display for StepError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StepError::NotOffered { step, trade } => {
+                write!(f, "step {} ({}) is not a trade on offer", step, trade)
+            }
+            StepError::Short {
+                step,
+                trade,
+                missing,
+            } => write!(
+                f,
+                "step {} ({}) cannot be made, short of ({})",
+                step,
+                trade,
+                display_side(missing)
+            ),
+            StepError::OverCap { step, trade, after } => write!(
+                f,
+                "step {} ({}) would leave ({}), over the cap",
+                step,
+                trade,
+                display_side(after)
+            ),
+        }
+    }
+}
+
+impl std::error::Error for StepError {}
+
+/// The inventory after each of `trades` made in turn from the start of
+/// `candy_works`, beginning with the start itself, or why the first step
+/// that cannot be made fails.
+pub fn verify(candy_works: &CandyWorks, trades: &[Trade]) -> Result<Vec<Candies>, StepError> {
+    let mut inventories = vec![candy_works.candies];
+    for (n, trade) in trades.iter().enumerate() {
+        let step = n + 1;
+        let trade = *trade;
+        if !candy_works.trades.contains(&trade) {
+            return Err(StepError::NotOffered { step, trade });
+        }
+        let candies = *inventories.last().unwrap();
+        let Some(after) = candies.trade(&trade) else {
+            let mut missing = Candies::none();
+            for i in 0..kinds::count() {
+                let short = trade.give.get_by_index(i) - candies.get_by_index(i);
+                missing.add_by_index(i, short.max(Count::ZERO));
+            }
+            return Err(StepError::Short {
+                step,
+                trade,
+                missing,
+            });
+        };
+        if !candy_works.fits(&after) {
+            return Err(StepError::OverCap { step, trade, after });
+        }
+        inventories.push(after);
+    }
+    Ok(inventories)
+}
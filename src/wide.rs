@@ -0,0 +1,554 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    fmt::Write,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    count::{Counts, Integer, Overflow, Width},
+    kinds,
+    names::CandyNames,
+    preset::Preset,
+    scenario::{ScenarioError, VERSION},
+    template::{self, Variables},
+    Candies,
+};
+
+/// A scenario whose counts are kept in `C`. Only inventories, the cap,
+/// fees, trades with their lots, fees and notes, and targets can be
+/// written; the rest of the scenario format needs the `i32` counts the
+/// explorer is built around.
+pub struct WideScenario<C> {
+    width: Width,
+    inventory: Counts<C>,
+    cap: C,
+    /// What a vendor takes for every trade made with it, as in `fee = 1c`.
+    fee: Counts<C>,
+    /// The trades as written, per lot.
+    written: Vec<WideTrade<C>>,
+    /// The trades as made: `written` in whole lots with their fees on
+    /// top, then the preset's standard trades.
+    trades: Vec<(Counts<C>, Counts<C>)>,
+    targets: Vec<Counts<C>>,
+    overflow: Overflow,
+    /// Whether the preset's standard trades are offered besides the
+    /// written ones.
+    pub standard_trades: bool,
+}
+
+/// A trade as written in a `count = i64` scenario.
+struct WideTrade<C> {
+    give: Counts<C>,
+    receive: Counts<C>,
+    lot: C,
+    /// The fee set on this trade, in place of the scenario's.
+    fee: Option<Counts<C>>,
+    note: Option<String>,
+}
+
+/// Every state reached, with the index of the state and of the trade it
+/// was reached by.
+type Explored<C> = Vec<(Counts<C>, Option<(usize, usize)>)>;
+
+/// Whether `input` asks for `count = i64`, which `Scenario::parse` refuses.
+pub fn is_wide(input: &str) -> bool {
+    input.lines().any(|line| {
+        let line = line.split('#').next().unwrap();
+        line.split_once('=').is_some_and(|(key, value)| {
+            key.trim() == "count" && Width::parse(value.trim()) == Ok(Width::I64)
+        })
+    })
+}
+
+impl<C: Integer> WideScenario<C> {
+    /// Parses `input` with the run's variables and the built-in preset.
+    pub fn parse(input: &str) -> Result<WideScenario<C>, ScenarioError> {
+        WideScenario::parse_with(input, template::active(), &Preset::builtin())
+    }
+
+    /// Parses `input` with its `${name}` variables from `variables`, and
+    /// the standard trades and the cap of scenarios setting none from
+    /// `preset`.
+    pub fn parse_with(
+        input: &str,
+        variables: &Variables,
+        preset: &Preset,
+    ) -> Result<WideScenario<C>, ScenarioError> {
+        let names = CandyNames::builtin();
+        let mut width = Width::default();
+        let mut overflow = Overflow::default();
+        let mut inventory = None;
+        let mut cap = None;
+        let mut fee = None;
+        let mut written = Vec::new();
+        let mut targets = Vec::new();
+        for (number, line) in input.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let error = |message: &str| ScenarioError {
+                line: Some(number + 1),
+                message: message.to_string(),
+            };
+            let line = variables.expand(line).map_err(|message| error(&message))?;
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(error("expected `key = value`"));
+            };
+            let value = value.trim();
+            let candies = |value: &str| {
+                Counts::<C>::parse_with(value, &names)
+                    .map_err(|err| error(&format!("invalid candies: {}", err)))
+            };
+            match key.trim() {
+                "version" => {
+                    if value
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|v| (1..=VERSION).contains(v))
+                        .is_none()
+                    {
+                        return Err(error("unknown scenario version"));
+                    }
+                }
+                "count" => width = Width::parse(value).map_err(|err| error(&err))?,
+                "overflow" => overflow = Overflow::parse(value).map_err(|err| error(&err))?,
+                "inventory" => inventory = Some(candies(value)?),
+                "cap" => {
+                    cap = Some(
+                        value
+                            .parse::<C>()
+                            .ok()
+                            .filter(|cap| *cap >= C::ZERO)
+                            .ok_or_else(|| error("cap must be a non-negative number"))?,
+                    )
+                }
+                "fee" => {
+                    if fee.is_some() {
+                        return Err(error("fee is defined twice"));
+                    }
+                    fee = Some(candies(value)?);
+                }
+                "trade" => {
+                    let mut options = value.split(';');
+                    let trade = options.next().unwrap();
+                    let mut lot = None;
+                    let mut own_fee = None;
+                    let mut note = None;
+                    for option in options {
+                        let option = option.trim();
+                        let (key, argument) = option
+                            .split_once(' ')
+                            .map_or((option, ""), |(key, argument)| (key, argument.trim()));
+                        match key {
+                            "lot" => {
+                                if lot.is_some() {
+                                    return Err(error("lot size is given twice"));
+                                }
+                                lot = Some(
+                                    argument
+                                        .parse::<C>()
+                                        .ok()
+                                        .filter(|lot| *lot > C::ZERO)
+                                        .ok_or_else(|| {
+                                            error("lot size must be a positive number")
+                                        })?,
+                                );
+                            }
+                            "fee" => {
+                                if own_fee.is_some() {
+                                    return Err(error("fee is given twice"));
+                                }
+                                own_fee = Some(candies(argument)?);
+                            }
+                            "note" => {
+                                if note.is_some() {
+                                    return Err(error("note is given twice"));
+                                }
+                                if argument.is_empty() {
+                                    return Err(error("expected text after `note`"));
+                                }
+                                note = Some(argument.to_string());
+                            }
+                            _ => {
+                                return Err(error(&format!(
+                                    "`{}` is not supported with `count = i64`; only `lot`, `fee` and `note` can follow a trade",
+                                    key
+                                )))
+                            }
+                        }
+                    }
+                    let (give, receive) = trade
+                        .split_once("->")
+                        .ok_or_else(|| error("missing `->` between give and receive"))?;
+                    let trade = WideTrade {
+                        give: candies(give)?,
+                        receive: candies(receive)?,
+                        lot: lot.unwrap_or_else(|| C::from_i64(1).unwrap()),
+                        fee: own_fee,
+                        note,
+                    };
+                    if trade.made(&Counts::none(), overflow).is_none() {
+                        return Err(error("the lot does not fit in an i64 count"));
+                    }
+                    written.push((number + 1, trade));
+                }
+                "target" => targets.push(candies(value)?),
+                key => {
+                    return Err(error(&format!(
+                        "`{}` is not supported with `count = i64`",
+                        key
+                    )))
+                }
+            }
+        }
+        let fee = fee.unwrap_or_else(Counts::none);
+        let mut trades = Vec::new();
+        for (line, trade) in &written {
+            trades.push(trade.made(&fee, overflow).ok_or_else(|| ScenarioError {
+                line: Some(*line),
+                message: "the trade and its fee do not fit in an i64 count".to_string(),
+            })?);
+        }
+        let ratio = C::from_i64(preset.ratio.into()).ok_or_else(|| ScenarioError {
+            line: None,
+            message: "the preset's ratio does not fit the counts".to_string(),
+        })?;
+        for i in 0..kinds::count() {
+            for j in (0..kinds::count()).filter(|&j| j != i) {
+                let (mut give, mut receive) = (Counts::none(), Counts::none());
+                give.0[i] = ratio;
+                receive.0[j] = C::from_i64(1).unwrap();
+                trades.push((give, receive));
+            }
+        }
+        for special in &preset.specials {
+            // Trade counts are `i32`s, which every count type holds.
+            let side = |side: &Candies| side.counts().narrow(Overflow::Error).unwrap();
+            trades.push((side(&special.give), side(&special.receive)));
+        }
+        let cap = match cap {
+            Some(cap) => cap,
+            None => C::from_i64(preset.default_cap().into()).ok_or_else(|| ScenarioError {
+                line: None,
+                message: "the preset's cap does not fit the counts".to_string(),
+            })?,
+        };
+        Ok(WideScenario {
+            width,
+            inventory: inventory.ok_or_else(|| ScenarioError {
+                line: None,
+                message: "missing `inventory`".to_string(),
+            })?,
+            cap,
+            fee,
+            written: written.into_iter().map(|(_, trade)| trade).collect(),
+            trades,
+            targets,
+            overflow,
+            standard_trades: true,
+        })
+    }
+
+    pub fn cap(&self) -> C {
+        self.cap
+    }
+
+    /// The trades explored with: all of `trades`, or only the written ones
+    /// without `standard_trades`.
+    fn offered(&self) -> &[(Counts<C>, Counts<C>)] {
+        if self.standard_trades {
+            &self.trades
+        } else {
+            &self.trades[..self.written.len()]
+        }
+    }
+
+    /// Explores breadth first from the inventory for at most `limit`,
+    /// returning the states reached and whether the exploration completed.
+    /// With `until`, stops at the first state holding it, which has the
+    /// fewest trades to it.
+    fn explore(&self, limit: Option<Duration>, until: Option<&Counts<C>>) -> (Explored<C>, bool) {
+        let deadline = limit.map(|limit| Instant::now() + limit);
+        let mut states = vec![(self.inventory, None)];
+        if until.is_some_and(|until| self.inventory.contains(until)) {
+            return (states, true);
+        }
+        let mut known = HashSet::from([self.inventory]);
+        let mut queue = VecDeque::from([0]);
+        while let Some(index) = queue.pop_front() {
+            if index % 1024 == 0 && deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return (states, false);
+            }
+            let candies = states[index].0;
+            for (n, (give, receive)) in self.offered().iter().enumerate() {
+                let Some(next) = candies.trade(give, receive, self.overflow) else {
+                    continue;
+                };
+                if next
+                    .total(self.overflow)
+                    .is_none_or(|total| total > self.cap)
+                {
+                    continue;
+                }
+                if known.insert(next) {
+                    queue.push_back(states.len());
+                    states.push((next, Some((index, n))));
+                    if until.is_some_and(|until| next.contains(until)) {
+                        return (states, true);
+                    }
+                }
+            }
+        }
+        (states, true)
+    }
+
+    /// The best route to every target, as text: the one ending with the
+    /// most candies, as for `i32` scenarios, among the fewest trades.
+    pub fn plan(&self, limit: Option<Duration>) -> String {
+        let mut out = String::new();
+        let (states, complete) = self.explore(limit, None);
+        writeln!(out, "Total combinations: {}", states.len()).unwrap();
+        if !complete {
+            writeln!(
+                out,
+                "Stopped after the time limit; routes are the best found so far"
+            )
+            .unwrap();
+        }
+        for target in &self.targets {
+            let best = states
+                .iter()
+                .enumerate()
+                .filter(|(_, (candies, _))| candies.contains(target))
+                .max_by_key(|(index, (candies, _))| {
+                    (candies.total(self.overflow), std::cmp::Reverse(*index))
+                });
+            match best {
+                Some((index, _)) => out.push_str(&self.route(&states, index)),
+                None => writeln!(out, "No route found").unwrap(),
+            }
+        }
+        out
+    }
+
+    /// The route in the fewest trades to each of `targets`, or to the
+    /// scenario's targets if `targets` is empty, as text, and whether every
+    /// target has one.
+    pub fn direct(&self, targets: &[Counts<C>]) -> (String, bool) {
+        let mut out = String::new();
+        let mut reached = true;
+        let targets = if targets.is_empty() {
+            &self.targets
+        } else {
+            targets
+        };
+        for target in targets {
+            let (states, _) = self.explore(None, Some(target));
+            match states.len().checked_sub(1) {
+                Some(index) if states[index].0.contains(target) => {
+                    out.push_str(&self.route(&states, index))
+                }
+                _ => {
+                    writeln!(out, "No route found").unwrap();
+                    reached = false;
+                }
+            }
+        }
+        (out, reached)
+    }
+
+    /// The route to `states[index]`, as text: each state with the trade
+    /// made from it and its note, then the state reached.
+    fn route(&self, states: &Explored<C>, mut index: usize) -> String {
+        let mut out = String::new();
+        let end = states[index].0;
+        let mut route = Vec::new();
+        while let Some((parent, trade)) = states[index].1 {
+            route.push((states[parent].0, trade));
+            index = parent;
+        }
+        route.reverse();
+        let held = route
+            .iter()
+            .map(|(candies, _)| format!("({})", candies.compact()))
+            .collect::<Vec<_>>();
+        let gives = route
+            .iter()
+            .map(|(_, trade)| self.trades[*trade].0.compact())
+            .collect::<Vec<_>>();
+        let width = |column: &[String]| column.iter().map(String::len).max().unwrap_or(0);
+        for (n, (_, trade)) in route.iter().enumerate() {
+            let note = self
+                .written
+                .get(*trade)
+                .and_then(|written| written.note.as_ref())
+                .map_or(String::new(), |note| format!("  # {}", note));
+            writeln!(
+                out,
+                "{:<held$} {:>gives$} -> {}{}",
+                held[n],
+                gives[n],
+                self.trades[*trade].1.compact(),
+                note,
+                held = width(&held),
+                gives = width(&gives)
+            )
+            .unwrap();
+        }
+        writeln!(out, "({})", end.compact()).unwrap();
+        out
+    }
+
+    /// The scenario in canonical form, as `fmt` writes it back, with each
+    /// trade per lot and its own fee and note.
+    pub fn format(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "version = {}", VERSION).unwrap();
+        writeln!(out, "count = {}", self.width).unwrap();
+        if self.overflow != Overflow::default() {
+            writeln!(out, "overflow = {}", self.overflow).unwrap();
+        }
+        writeln!(out, "cap = {}", self.cap).unwrap();
+        writeln!(out, "inventory = {}", self.inventory.compact()).unwrap();
+        if self.fee != Counts::none() {
+            writeln!(out, "fee = {}", self.fee.compact()).unwrap();
+        }
+        for trade in &self.written {
+            write!(
+                out,
+                "trade = {} -> {}",
+                trade.give.compact(),
+                trade.receive.compact()
+            )
+            .unwrap();
+            if trade.lot != C::from_i64(1).unwrap() {
+                write!(out, "; lot {}", trade.lot).unwrap();
+            }
+            if let Some(fee) = &trade.fee {
+                write!(out, "; fee {}", fee.compact()).unwrap();
+            }
+            if let Some(note) = &trade.note {
+                write!(out, "; note {}", note).unwrap();
+            }
+            writeln!(out).unwrap();
+        }
+        for target in &self.targets {
+            writeln!(out, "target = {}", target.compact()).unwrap();
+        }
+        out
+    }
+}
+
+impl<C: Integer> WideTrade<C> {
+    /// The trade as made: in whole lots, with its own fee or else `fee` on
+    /// top of the give side.
+    fn made(&self, fee: &Counts<C>, overflow: Overflow) -> Option<(Counts<C>, Counts<C>)> {
+        let give = self.give.times(self.lot, overflow)?;
+        let give = give.with(self.fee.as_ref().unwrap_or(fee), overflow)?;
+        Some((give, self.receive.times(self.lot, overflow)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scenario(input: &str) -> WideScenario<i64> {
+        WideScenario::parse(input).unwrap()
+    }
+
+    fn error(input: &str) -> (Option<usize>, String) {
+        let err = WideScenario::<i64>::parse(input).err().unwrap();
+        (err.line, err.message)
+    }
+
+    #[test]
+    fn reads_counts_past_an_i32() {
+        assert!(is_wide("count = i64  # wide\ninventory = 1e\n"));
+        assert!(!is_wide("# count = i64\ninventory = 1e\n"));
+        let wide = scenario("count = i64\ninventory = 3000000000e\ncap = 5000000000\n");
+        assert_eq!(wide.cap(), 5_000_000_000);
+        assert_eq!(wide.inventory.0[0], 3_000_000_000);
+        let lots = scenario("count = i64\ninventory = 1e\ntrade = 1e -> 2c; lot 2000000000\n");
+        assert_eq!(lots.trades[0].1 .0[2], 4_000_000_000);
+    }
+
+    #[test]
+    fn refuses_what_it_cannot_hold() {
+        assert_eq!(
+            error("count = i64\ninventory = 1e\ntrade = 2e -> 1c; lot 9223372036854775807\n"),
+            (Some(3), "the lot does not fit in an i64 count".to_string())
+        );
+        assert_eq!(
+            error("count = i64\ninventory = 1e\ntrade = 2e -> 1c; uses 1\n"),
+            (
+                Some(3),
+                "`uses` is not supported with `count = i64`; only `lot`, `fee` and `note` can follow a trade".to_string()
+            )
+        );
+        assert_eq!(
+            error("count = i64\ninventory = 1e\nreserve = 1e\n"),
+            (
+                Some(3),
+                "`reserve` is not supported with `count = i64`".to_string()
+            )
+        );
+        assert_eq!(error("count = i64\n").1, "missing `inventory`");
+    }
+
+    #[test]
+    fn explores_within_the_cap() {
+        let wide = scenario("count = i64\ninventory = 6e\ncap = 6\ntrade = 2e -> 3c\n");
+        let (states, complete) = wide.explore(None, None);
+        assert!(complete);
+        assert!(states
+            .iter()
+            .all(|(candies, _)| candies.total(Overflow::Error) <= Some(6)));
+        let cakes = Counts::<i64>::parse_with("6c", &CandyNames::builtin()).unwrap();
+        assert!(!states.iter().any(|(candies, _)| candies.contains(&cakes)));
+        let cakes = Counts::<i64>::parse_with("3c", &CandyNames::builtin()).unwrap();
+        assert!(states.iter().any(|(candies, _)| candies.contains(&cakes)));
+    }
+
+    #[test]
+    fn offers_the_standard_trades_of_the_preset() {
+        let preset = Preset {
+            name: "double".to_string(),
+            ratio: 2,
+            cap: Some(7),
+            specials: vec!["1m -> 2c".parse().unwrap()],
+        };
+        let input = "count = i64\ninventory = 2e\n";
+        let wide = WideScenario::<i64>::parse_with(input, &Variables::default(), &preset).unwrap();
+        assert_eq!(wide.cap(), 7);
+        let meat = Counts::<i64>::parse_with("1m", &CandyNames::builtin()).unwrap();
+        let (routes, reached) = wide.direct(&[meat]);
+        assert!(reached);
+        assert_eq!(routes, "(2e) 2e -> 1m\n(1m)\n");
+        let mut wide = wide;
+        wide.standard_trades = false;
+        assert_eq!(wide.direct(&[meat]).0, "No route found\n");
+        assert_eq!(scenario("count = i64\ninventory = 1e\n").cap(), 20);
+    }
+
+    #[test]
+    fn charges_fees_and_shows_notes() {
+        let input = "count = i64\ninventory = 9e\nfee = 1e\ntrade = 2e -> 1c; lot 2; note at dawn\ntrade = 1e -> 1w; fee none\ntarget = 2c 1w\n";
+        let wide = scenario(input);
+        assert_eq!(
+            wide.plan(None).lines().skip(1).collect::<Vec<_>>(),
+            [
+                "(9e)    5e -> 2c  # at dawn",
+                "(4e 2c) 1e -> 1w",
+                "(3e 1w 2c)"
+            ]
+        );
+        let formatted = wide.format();
+        assert_eq!(
+            formatted,
+            "version = 1\ncount = i64\ncap = 20\ninventory = 9e\nfee = 1e\ntrade = 2e -> 1c; lot 2; note at dawn\ntrade = 1e -> 1w; fee none\ntarget = 1w 2c\n"
+        );
+        assert_eq!(scenario(&formatted).format(), formatted);
+    }
+}
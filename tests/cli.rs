@@ -0,0 +1,338 @@
+use std::{
+    env, fs,
+    path::PathBuf,
+    process::{Command, Output, Stdio},
+};
+
+/// A scenario written to a file of its own for the run to read.
+fn scenario(name: &str, input: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("candyworks-{}-{}.candy", name, std::process::id()));
+    fs::write(&path, input).unwrap();
+    path
+}
+
+/// Runs the CLI on `scenario` with `args`, answering no prompt.
+fn run(scenario: &PathBuf, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_candyworks"))
+        .arg("--scenario")
+        .arg(scenario)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+/// Five eggs pass the cap of four on eggs, though the trades reach them
+/// within the cap on the total.
+const KIND_CAPPED: &str = "\
+cap = 20
+inventory = 3e 3w
+kind_cap = 4e
+trade = 1w -> 2e
+target = 5e
+";
+
+#[test]
+fn route_direct_keeps_to_kind_caps() {
+    let path = scenario("direct-kind-caps", KIND_CAPPED);
+    let output = run(&path, &["route", "--direct"]);
+    assert_eq!(stdout(&output).trim(), "No route found");
+    assert_eq!(output.status.code(), Some(3));
+}
+
+#[test]
+fn export_route_keeps_to_kind_caps() {
+    let path = scenario("export-kind-caps", KIND_CAPPED);
+    let output = run(&path, &["export", "route"]);
+    assert!(stdout(&output).is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("(5 eggs) holds more eggs than their cap of 4 allows"));
+    assert_eq!(output.status.code(), Some(3));
+}
+
+#[test]
+fn solve_blames_the_kind_cap() {
+    let path = scenario("solve-kind-caps", KIND_CAPPED);
+    let output = run(&path, &[]);
+    assert!(stdout(&output)
+        .contains("No route found: (5 eggs) holds more eggs than their cap of 4 allows"));
+}
+
+#[test]
+fn follow_keeps_to_kind_caps() {
+    let path = scenario("follow-kind-caps", KIND_CAPPED);
+    let output = run(&path, &["follow"]);
+    assert!(stdout(&output).contains("No route reaches the target from here"));
+}
+
+#[test]
+fn min_cap_keeps_to_kind_caps() {
+    let path = scenario("min-cap-kind-caps", KIND_CAPPED);
+    let output = run(&path, &["min-cap"]);
+    assert_eq!(
+        stdout(&output).trim(),
+        "(5 eggs) is not reachable under any cap"
+    );
+}
+
+#[test]
+fn explore_exits_3_without_a_route_whatever_the_strategy() {
+    let explore = ["explore", "--inventory", "3e", "--target", "50e"];
+    for strategy in [None, Some("--two-phase")] {
+        let output = Command::new(env!("CARGO_BIN_EXE_candyworks"))
+            .args(explore)
+            .args(strategy)
+            .stdin(Stdio::null())
+            .output()
+            .unwrap();
+        assert!(stdout(&output).contains("No route found"));
+        assert_eq!(output.status.code(), Some(3), "{:?}", strategy);
+    }
+}
+
+/// One meat is one egg by the vendor, but only once; the other costs
+/// three eggs by the standard trade.
+const LIMITED: &str = "\
+cap = 20
+inventory = 9e
+trade = 1e -> 1m; uses 1
+target = 2m
+";
+
+/// How many times the route printed makes the limited trade.
+fn limited_uses(output: &Output) -> usize {
+    stdout(output).matches("1 egg ->").count()
+}
+
+#[test]
+fn solve_keeps_to_uses() {
+    let path = scenario("solve-uses", LIMITED);
+    let output = run(&path, &[]);
+    assert_eq!(limited_uses(&output), 1);
+    assert!(stdout(&output).contains("3 eggs ->  1 meat"));
+}
+
+#[test]
+fn json_keeps_to_uses() {
+    let path = scenario("json-uses", LIMITED);
+    let output = run(&path, &["--output", "json"]);
+    assert_eq!(stdout(&output).matches("\"give\":\"1e\"").count(), 1);
+}
+
+#[test]
+fn route_direct_keeps_to_uses() {
+    let path = scenario("direct-uses", LIMITED);
+    assert_eq!(limited_uses(&run(&path, &["route", "--direct"])), 1);
+}
+
+#[test]
+fn route_graph_keeps_to_uses() {
+    let path = scenario("graph-uses", LIMITED);
+    let graph = path.with_extension("graph");
+    let saved = run(&path, &["--save-graph", graph.to_str().unwrap()]);
+    assert!(saved.status.success());
+    let output = run(&path, &["route", "--graph", graph.to_str().unwrap()]);
+    assert_eq!(limited_uses(&output), 1);
+}
+
+#[test]
+fn export_route_keeps_to_uses() {
+    let path = scenario("export-uses", LIMITED);
+    assert_eq!(limited_uses(&run(&path, &["export", "route"])), 1);
+}
+
+#[test]
+fn sequence_keeps_to_uses() {
+    let path = scenario("sequence-uses", LIMITED);
+    assert_eq!(limited_uses(&run(&path, &["sequence"])), 1);
+}
+
+#[test]
+fn follow_keeps_to_uses() {
+    let path = scenario("follow-uses", LIMITED);
+    let output = run(&path, &["follow"]);
+    assert_eq!(stdout(&output).matches("1 egg ->  1 meat").count(), 1);
+}
+
+#[test]
+fn advise_keeps_to_uses() {
+    let path = scenario("advise-uses", LIMITED);
+    let output = run(&path, &["advise", "--current", "9e"]);
+    assert_eq!(stdout(&output).trim(), "Next: 1 egg ->  1 meat");
+}
+
+/// Solves `input` alone in a batch, returning the result written for it.
+fn batch(name: &str, input: &str) -> String {
+    let dir = env::temp_dir().join(format!("candyworks-batch-{}-{}", name, std::process::id()));
+    let out = dir.join("out");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join(format!("{}.candy", name)), input).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_candyworks"))
+        .arg("batch")
+        .arg(&dir)
+        .arg("--out")
+        .arg(&out)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    fs::read_to_string(out.join(format!("{}.txt", name))).unwrap()
+}
+
+#[test]
+fn batch_keeps_to_uses() {
+    let result = batch("limited", LIMITED);
+    assert_eq!(result.matches("1 egg ->").count(), 1);
+    assert!(result.contains("1 egg ->  1 meat  (no uses left, single point of failure)"));
+}
+
+#[test]
+fn batch_plans_the_cheapest_route() {
+    let input = "cap = 20\ninventory = 9e\ntrade = 1e -> 1m; cost 5\ntarget = 1m\n";
+    let result = batch("costs", input);
+    let path = scenario("solve-costs", input);
+    let solved = stdout(&run(&path, &[]));
+    assert!(result.contains("3 eggs ->  1 meat"));
+    assert!(result.contains("Total cost: 0"));
+    assert!(solved.contains("3 eggs ->  1 meat"));
+}
+
+#[test]
+fn sequence_legs_share_the_uses() {
+    let path = scenario(
+        "sequence-legs-uses",
+        "cap = 20\ninventory = 9e\ntrade = 1e -> 1m; uses 1\ntarget = 1m\ntarget = 1m\n",
+    );
+    let output = run(&path, &["sequence"]);
+    assert_eq!(limited_uses(&output), 1);
+    assert!(stdout(&output).contains("Filled 2 orders in 2 trades, keeping (5 eggs)"));
+}
+
+/// A route of two trades ends at the cap, where the standard trade
+/// reaches the meat in one.
+const WARM: &str = "\
+cap = 5
+inventory = 3e
+trade = 1e -> 2w
+trade = 1w -> 2m
+target = 1m
+";
+
+#[test]
+fn warm_start_ranks_by_the_objective() {
+    let path = scenario("warm-objective", WARM);
+    let previous = path.with_extension("route");
+    fs::write(&previous, "1e -> 2w\n1w -> 2m\n").unwrap();
+    let previous = previous.to_str().unwrap();
+    let fewest = stdout(&run(
+        &path,
+        &["--objective", "fewest-trades", "--warm-start", previous],
+    ));
+    assert!(!fewest.contains("Status: optimal"));
+    assert!(fewest.contains("Improved on the previous route"));
+    assert!(fewest.contains("3 eggs ->  1 meat"));
+    let most = stdout(&run(&path, &["--warm-start", previous]));
+    assert!(most.contains("The previous route is still optimal"));
+    assert!(most.contains("Status: optimal"));
+}
+
+#[test]
+fn conservation_skips_exploring_only_when_asked() {
+    let path = scenario(
+        "conserved",
+        "cap = 20\ninventory = 1e\ntrade = 2e -> 1c\ntarget = 1c\n",
+    );
+    let why = "No route found: eggs + 2·cakes is always 1";
+    let explored = stdout(&run(&path, &["--no-standard-trades"]));
+    assert!(explored.contains("Total combinations: 1"));
+    assert!(explored.contains(why));
+    let pruned = stdout(&run(&path, &["--no-standard-trades", "--prune-conserved"]));
+    assert!(pruned.starts_with("Conservation laws rule out every target, skipping exploration"));
+    assert!(pruned.contains(why));
+}
+
+#[test]
+fn unwritable_output_fails_in_one_line() {
+    let path = scenario("unwritable", LIMITED);
+    let missing = env::temp_dir()
+        .join(format!("candyworks-missing-{}", std::process::id()))
+        .join("out");
+    let missing = missing.to_str().unwrap();
+    for args in [
+        ["--save-graph", missing],
+        ["--save-route", missing],
+        ["--save-scenario", missing],
+    ] {
+        let output = run(&path, &args);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert_eq!(output.status.code(), Some(1), "{:?}: {}", args, stderr);
+        assert!(stderr.starts_with(&format!("Could not write {}: ", missing)));
+        assert_eq!(stderr.lines().count(), 1, "{}", stderr);
+    }
+}
+
+#[test]
+fn format_answers_as_output_does() {
+    let path = scenario("format-json", LIMITED);
+    let format = stdout(&run(&path, &["--format", "json"]));
+    assert!(format.starts_with("{\"statistics\""));
+    assert_eq!(format, stdout(&run(&path, &["--output", "json"])));
+}
+
+#[test]
+fn every_command_has_help() {
+    let help = Command::new(env!("CARGO_BIN_EXE_candyworks"))
+        .arg("--help")
+        .output()
+        .unwrap();
+    let help = stdout(&help);
+    let commands = help
+        .lines()
+        .skip_while(|line| *line != "Commands:")
+        .skip(1)
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.split_whitespace().next())
+        .filter(|command| *command != "help")
+        .collect::<Vec<_>>();
+    assert!(commands.contains(&"report"));
+    for command in commands {
+        let output = Command::new(env!("CARGO_BIN_EXE_candyworks"))
+            .args([command, "--help"])
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{} --help failed", command);
+    }
+}
+
+/// More eggs than an `i32` count holds, traded in lots as large.
+const WIDE: &str = "\
+version = 1
+count = i64
+cap = 5000000000
+inventory = 3000000000e
+trade = 1e -> 1w; lot 1000000000; note at the mill
+target = 1000000000w
+";
+
+#[test]
+fn every_wide_command_reads_i64_counts() {
+    let path = scenario("wide", WIDE);
+    let output = run(&path, &["route", "--direct"]);
+    assert_eq!(
+        stdout(&output),
+        "(3000000000e) 1000000000e -> 1000000000w  # at the mill\n(2000000000e 1000000000w)\n"
+    );
+    assert!(run(&path, &["check", path.to_str().unwrap()])
+        .status
+        .success());
+    assert!(run(&path, &["fmt", "--check", path.to_str().unwrap()])
+        .status
+        .success());
+    let output = run(&path, &["--reserve", "1e"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("--reserve is not supported with `count = i64` scenarios"));
+}